@@ -16,7 +16,7 @@ use axum::extract::Request;
 use axum::middleware::{self, Next};
 #[cfg(feature = "ffi")]
 use axum::response::Response;
-use axum::{routing::get, Json, Router};
+use axum::Router;
 use shared::chapter_storage::ChapterStorage;
 use shared::database::Database;
 use shared::settings::Settings;
@@ -24,9 +24,13 @@ use shared::source_manager::SourceManager;
 use shared::usecases::install_update::cleanup_update_backup;
 
 use crate::build_info::{get_build_info, DEFAULT_SETTINGS_JSON};
+use crate::download_queue::DownloadQueue;
 use crate::listener::{pick_listener, ResolvedListener};
 use crate::state::State;
-use crate::{cookie, job, manga, playlists, settings, source, system, update};
+use crate::{
+    arima, cookie, db, download_queue, job, manga, opds, playlists, settings, source, system,
+    update,
+};
 
 /// Initialize logging. Safe to call multiple times; only the first invocation
 /// installs the logger.
@@ -149,9 +153,12 @@ impl Log for StderrLogger {
 /// binary entry point and the JNI bridge can share it.
 pub fn build_router(state: State) -> Router {
     let router = Router::new()
-        .route("/health-check", get(health_check))
+        .merge(arima::routes())
         .merge(cookie::routes())
+        .merge(db::routes())
+        .merge(download_queue::routes())
         .merge(manga::routes())
+        .merge(opds::routes())
         .merge(playlists::routes())
         .merge(job::routes())
         .merge(settings::routes())
@@ -189,10 +196,6 @@ async fn request_logger(req: Request, next: Next) -> Response {
     response
 }
 
-async fn health_check() -> Json<()> {
-    Json(())
-}
-
 /// Construct the [`State`] from a home path. This performs all the
 /// filesystem bootstrapping (creating directories, writing default
 /// settings, opening the database) and source loading.
@@ -223,12 +226,14 @@ pub async fn build_state(home_path: PathBuf) -> Result<State> {
     let cookies_path = home_path.join("cookies.json");
     shared::cookie_store::init_cookie_store_with_path(&cookies_path)
         .context("couldn't initialize cookie store")?;
+    // The settings key must be loaded before the settings, as it decrypts their secure values.
+    shared::settings::secure::init(&home_path.join(".settings.key"));
     let settings = Settings::from_file(&settings_path)
         .with_context(|| format!("couldn't read settings file at {}", settings_path.display()))?;
 
     shared::tls::set_proxy_url(settings.proxy_url.clone());
 
-    let source_manager = SourceManager::from_folder(sources_path, settings.clone())
+    let source_manager = SourceManager::from_folder(sources_path.clone(), settings.clone())
         .context("couldn't create source manager")?;
 
     let downloads_folder_path = settings
@@ -245,6 +250,10 @@ pub async fn build_state(home_path: PathBuf) -> Result<State> {
     )
     .context("couldn't initialize chapter storage")?;
 
+    if let Err(e) = shared::usecases::shard_chapter_storage(&database, &chapter_storage).await {
+        warn!("couldn't move downloaded chapters into per-source folders: {e:#}");
+    }
+
     if settings.ram_storage_enabled {
         // Clean up old files on startup
         let _ = chapter_storage.clean_tmpfs().await;
@@ -267,15 +276,29 @@ pub async fn build_state(home_path: PathBuf) -> Result<State> {
         });
     }
 
+    let source_manager = Arc::new(Mutex::new(source_manager));
+    let database = Arc::new(database);
+    let chapter_storage = Arc::new(Mutex::new(chapter_storage));
+    let settings = Arc::new(Mutex::new(settings));
+    let download_semaphore = Arc::new(Semaphore::new(3));
+    let download_queue = DownloadQueue::spawn(
+        source_manager.clone(),
+        database.clone(),
+        chapter_storage.clone(),
+        settings.clone(),
+        download_semaphore.clone(),
+    );
+
     let state = State {
-        source_manager: Arc::new(Mutex::new(source_manager)),
-        database: Arc::new(database),
-        chapter_storage: Arc::new(Mutex::new(chapter_storage)),
-        settings: Arc::new(Mutex::new(settings)),
+        source_manager,
+        database,
+        chapter_storage,
+        settings,
         settings_path,
         job_state: Default::default(),
         cancel_token_store: Arc::new(Mutex::new(HashMap::new())),
-        download_semaphore: Arc::new(Semaphore::new(3)),
+        download_semaphore,
+        download_queue,
         startup_log,
     };
 
@@ -286,6 +309,12 @@ pub async fn build_state(home_path: PathBuf) -> Result<State> {
             .context("couldn't load sources")?;
     }
 
+    if let Err(err) =
+        crate::source::spawn_source_watcher(state.source_manager.clone(), sources_path)
+    {
+        warn!("sources won't be reloaded automatically: {err:?}");
+    }
+
     Ok(state)
 }
 
@@ -0,0 +1,24 @@
+use axum::extract::{Path, State as StateExtractor};
+use axum::routing::get;
+use axum::{Json, Router};
+use shared::model::MangaId;
+use shared::usecases::{self, get_arima_diagnostics::ArimaDiagnostics};
+
+use crate::state::State;
+use crate::AppError;
+
+pub fn routes() -> Router<State> {
+    Router::new().route(
+        "/arima/diagnostics/{source_id}/{manga_id}",
+        get(get_arima_diagnostics),
+    )
+}
+
+async fn get_arima_diagnostics(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(manga_id): Path<MangaId>,
+) -> Result<Json<ArimaDiagnostics>, AppError> {
+    Ok(Json(
+        usecases::get_arima_diagnostics(&database, &manga_id).await?,
+    ))
+}
@@ -0,0 +1,28 @@
+use axum::extract::State as StateExtractor;
+use axum::routing::get;
+use axum::{Json, Router};
+use shared::model::{DatabaseStats, SchemaVersion};
+use shared::usecases;
+
+use crate::state::State;
+use crate::AppError;
+
+pub fn routes() -> Router<State> {
+    Router::new()
+        .route("/db/schema-version", get(get_schema_version))
+        .route("/db/stats", get(get_stats))
+}
+
+async fn get_schema_version(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+) -> Result<Json<SchemaVersion>, AppError> {
+    Ok(Json(
+        usecases::get_database_schema_version(&database).await?,
+    ))
+}
+
+async fn get_stats(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+) -> Result<Json<DatabaseStats>, AppError> {
+    Ok(Json(usecases::get_database_stats(&database).await?))
+}
@@ -0,0 +1,5 @@
+mod routes;
+mod state;
+
+pub use routes::routes;
+pub use state::{DownloadQueue, ProgressEvent, Status};
@@ -0,0 +1,87 @@
+use std::convert::Infallible;
+
+use axum::extract::State as StateExtractor;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use futures::{stream, Stream};
+use serde::Deserialize;
+use shared::model::ChapterId;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::state::State;
+use crate::AppError;
+
+pub fn routes() -> Router<State> {
+    Router::new()
+        .route("/download-queue/enqueue", post(enqueue_chapters))
+        .route("/download-queue/cancel", delete(cancel_chapters))
+        .route("/download-queue/progress", get(download_progress))
+}
+
+#[derive(Deserialize)]
+struct QueuedChapterBody {
+    source_id: String,
+    manga_id: String,
+    chapter_id: String,
+}
+
+impl From<QueuedChapterBody> for ChapterId {
+    fn from(value: QueuedChapterBody) -> Self {
+        ChapterId::from_strings(value.source_id, value.manga_id, value.chapter_id)
+    }
+}
+
+async fn enqueue_chapters(
+    StateExtractor(State { download_queue, .. }): StateExtractor<State>,
+    Json(chapters): Json<Vec<QueuedChapterBody>>,
+) -> Result<Json<usize>, AppError> {
+    let chapter_ids = chapters.into_iter().map(ChapterId::from).collect();
+
+    Ok(Json(download_queue.enqueue(chapter_ids).await))
+}
+
+#[derive(Deserialize)]
+struct CancelChaptersBody {
+    /// The chapters to cancel. When omitted, the whole queue is cancelled.
+    chapters: Option<Vec<QueuedChapterBody>>,
+}
+
+async fn cancel_chapters(
+    StateExtractor(State { download_queue, .. }): StateExtractor<State>,
+    Json(body): Json<CancelChaptersBody>,
+) -> Result<Json<()>, AppError> {
+    let chapter_ids = body
+        .chapters
+        .map(|chapters| chapters.into_iter().map(ChapterId::from).collect());
+
+    download_queue.cancel(chapter_ids).await;
+
+    Ok(Json(()))
+}
+
+async fn download_progress(
+    StateExtractor(State { download_queue, .. }): StateExtractor<State>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = download_queue.subscribe();
+
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(progress) => {
+                    let event = Event::default()
+                        .json_data(&progress)
+                        .unwrap_or_else(|_| Event::default().comment("invalid progress event"));
+
+                    return Some((Ok(event), receiver));
+                }
+                // A slow subscriber only misses intermediate progress updates,
+                // later events still carry the latest status.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,374 @@
+use std::{collections::HashMap, sync::Arc};
+
+use log::warn;
+use serde::Serialize;
+use shared::{
+    chapter_storage::ChapterStorage, database::Database, model::ChapterId, settings::Settings,
+    source_collection::SourceCollection, source_manager::SourceManager, usecases,
+};
+use tokio::sync::{broadcast, mpsc, watch, Mutex, Semaphore};
+use tokio_util::sync::CancellationToken;
+
+/// How many progress events are buffered for slow SSE subscribers before
+/// they start lagging behind (and skipping events).
+const PROGRESS_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Status {
+    Queued,
+    Downloading,
+    Completed,
+    Errored,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub chapter_id: ChapterId,
+    pub status: Status,
+    pub progress_pct: f32,
+}
+
+type WorkItem = (ChapterId, CancellationToken);
+
+/// A queue of chapters to be downloaded in the background.
+///
+/// Work items are processed by a single dispatcher task, which runs up to
+/// `concurrent_requests_pages` downloads at the same time. The setting is read
+/// again for every item, and each download also holds a permit of the shared
+/// download semaphore, so it counts towards the same limit as the chapters
+/// opened from the reader. Progress for every item is published on a
+/// broadcast channel, so any number of listeners can follow it (see
+/// [`DownloadQueue::subscribe`]).
+#[derive(Clone)]
+pub struct DownloadQueue {
+    sender: mpsc::UnboundedSender<WorkItem>,
+    progress_tx: broadcast::Sender<ProgressEvent>,
+    tokens: Arc<Mutex<HashMap<ChapterId, CancellationToken>>>,
+}
+
+impl DownloadQueue {
+    pub fn spawn(
+        source_manager: Arc<Mutex<SourceManager>>,
+        database: Arc<Database>,
+        chapter_storage: Arc<Mutex<ChapterStorage>>,
+        settings: Arc<Mutex<Settings>>,
+        download_semaphore: Arc<Semaphore>,
+    ) -> Self {
+        let (queue, mut receiver) = Self::new();
+
+        let worker = queue.clone();
+        tokio::spawn(async move {
+            let running = Arc::new(watch::channel(0usize).0);
+            let mut running_rx = running.subscribe();
+
+            while let Some((chapter_id, token)) = receiver.recv().await {
+                // Read for every item, so a changed setting applies to the rest of the queue.
+                let limit = settings
+                    .lock()
+                    .await
+                    .concurrent_requests_pages_or_default()
+                    .max(1);
+                if running_rx
+                    .wait_for(|running| *running < limit)
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+
+                let Ok(permit) = download_semaphore.clone().acquire_owned().await else {
+                    break;
+                };
+                running.send_modify(|running| *running += 1);
+
+                let worker = worker.clone();
+                let source_manager = source_manager.clone();
+                let database = database.clone();
+                let chapter_storage = chapter_storage.lock().await.clone();
+                let settings = settings.clone();
+                let running = running.clone();
+
+                tokio::spawn(async move {
+                    let _permit = permit;
+
+                    worker
+                        .process(
+                            source_manager,
+                            database,
+                            chapter_storage,
+                            settings,
+                            chapter_id,
+                            token,
+                        )
+                        .await;
+
+                    running.send_modify(|running| *running -= 1);
+                });
+            }
+        });
+
+        queue
+    }
+
+    /// Creates a queue and the receiving end of its work items, leaving it to the caller to
+    /// process them.
+    fn new() -> (Self, mpsc::UnboundedReceiver<WorkItem>) {
+        let (sender, receiver) = mpsc::unbounded_channel::<WorkItem>();
+        let (progress_tx, _) = broadcast::channel(PROGRESS_CHANNEL_CAPACITY);
+
+        let queue = Self {
+            sender,
+            progress_tx,
+            tokens: Default::default(),
+        };
+
+        (queue, receiver)
+    }
+
+    /// Adds the given chapters to the end of the queue. Chapters that are
+    /// already queued (or being downloaded) are skipped. Returns how many
+    /// chapters were actually enqueued.
+    pub async fn enqueue(&self, chapter_ids: Vec<ChapterId>) -> usize {
+        let mut tokens = self.tokens.lock().await;
+        let mut enqueued = 0;
+
+        for chapter_id in chapter_ids {
+            if tokens.contains_key(&chapter_id) {
+                continue;
+            }
+
+            let token = CancellationToken::new();
+            if self
+                .sender
+                .send((chapter_id.clone(), token.clone()))
+                .is_err()
+            {
+                warn!("download queue dispatcher is not running");
+                break;
+            }
+
+            tokens.insert(chapter_id.clone(), token);
+            self.publish(chapter_id, Status::Queued, 0.0);
+            enqueued += 1;
+        }
+
+        enqueued
+    }
+
+    /// Cancels the given chapters, or every queued and running download when
+    /// `chapter_ids` is `None`.
+    pub async fn cancel(&self, chapter_ids: Option<Vec<ChapterId>>) {
+        let mut tokens = self.tokens.lock().await;
+
+        let cancelled: Vec<(ChapterId, CancellationToken)> = match chapter_ids {
+            Some(chapter_ids) => chapter_ids
+                .into_iter()
+                .filter_map(|id| tokens.remove(&id).map(|token| (id, token)))
+                .collect(),
+            None => tokens.drain().collect(),
+        };
+
+        for (_, token) in cancelled {
+            token.cancel();
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.progress_tx.subscribe()
+    }
+
+    fn publish(&self, chapter_id: ChapterId, status: Status, progress_pct: f32) {
+        // Sending only fails when nobody is listening, which is fine.
+        let _ = self.progress_tx.send(ProgressEvent {
+            chapter_id,
+            status,
+            progress_pct,
+        });
+    }
+
+    async fn process(
+        &self,
+        source_manager: Arc<Mutex<SourceManager>>,
+        database: Arc<Database>,
+        chapter_storage: ChapterStorage,
+        settings: Arc<Mutex<Settings>>,
+        chapter_id: ChapterId,
+        token: CancellationToken,
+    ) {
+        if token.is_cancelled() {
+            self.publish(chapter_id, Status::Cancelled, 0.0);
+            return;
+        }
+
+        let source = source_manager
+            .lock()
+            .await
+            .get_by_id(chapter_id.source_id())
+            .cloned();
+        let Some(source) = source else {
+            warn!(
+                "download queue: source {} not found",
+                chapter_id.source_id().value()
+            );
+            self.finish(chapter_id, &token, Status::Errored).await;
+            return;
+        };
+
+        let (concurrent_requests_pages, optimize_image, chapter_title_format) = {
+            let settings = settings.lock().await;
+            (
+                settings.concurrent_requests_pages_or_default(),
+                settings.optimize_image,
+                settings.chapter_title_format,
+            )
+        };
+
+        self.publish(chapter_id.clone(), Status::Downloading, 0.0);
+
+        let on_progress = {
+            let queue = self.clone();
+            let chapter_id = chapter_id.clone();
+            Arc::new(move |processed: f32, total: f32| {
+                let progress_pct = if total > 0.0 {
+                    (processed / total * 100.0).min(100.0)
+                } else {
+                    0.0
+                };
+                queue.publish(chapter_id.clone(), Status::Downloading, progress_pct);
+            })
+        };
+
+        let result = usecases::fetch_manga_chapter(
+            &token,
+            &database,
+            &source,
+            &chapter_storage,
+            &chapter_id,
+            concurrent_requests_pages,
+            optimize_image,
+            Some(on_progress),
+            false,
+            chapter_title_format,
+        )
+        .await;
+
+        let status = match result {
+            Ok(_) => Status::Completed,
+            Err(_) if token.is_cancelled() => Status::Cancelled,
+            Err(e) => {
+                warn!(
+                    "download queue: failed to download chapter {}: {e:#}",
+                    chapter_id.value()
+                );
+                Status::Errored
+            }
+        };
+
+        self.finish(chapter_id, &token, status).await;
+    }
+
+    async fn finish(&self, chapter_id: ChapterId, token: &CancellationToken, status: Status) {
+        // A cancelled item was already removed by `cancel`, and the chapter may
+        // have been enqueued again since then, so leave that new entry alone.
+        if !token.is_cancelled() {
+            self.tokens.lock().await.remove(&chapter_id);
+        }
+
+        let progress_pct = match status {
+            Status::Completed => 100.0,
+            _ => 0.0,
+        };
+        self.publish(chapter_id, status, progress_pct);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(id: &str) -> ChapterId {
+        ChapterId::from_strings("source".to_owned(), "manga".to_owned(), id.to_owned())
+    }
+
+    fn queued(receiver: &mut mpsc::UnboundedReceiver<WorkItem>) -> Vec<ChapterId> {
+        std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|(chapter_id, _)| chapter_id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn chapters_are_queued_in_order() {
+        let (queue, mut receiver) = DownloadQueue::new();
+        let mut progress = queue.subscribe();
+
+        let enqueued = queue
+            .enqueue(vec![chapter("1"), chapter("2"), chapter("3")])
+            .await;
+
+        assert_eq!(enqueued, 3);
+        assert_eq!(
+            queued(&mut receiver),
+            vec![chapter("1"), chapter("2"), chapter("3")]
+        );
+
+        let event = progress.try_recv().unwrap();
+        assert_eq!(event.chapter_id, chapter("1"));
+        assert!(matches!(event.status, Status::Queued));
+    }
+
+    #[tokio::test]
+    async fn chapters_already_in_the_queue_are_skipped() {
+        let (queue, mut receiver) = DownloadQueue::new();
+
+        queue.enqueue(vec![chapter("1"), chapter("2")]).await;
+        let enqueued = queue
+            .enqueue(vec![chapter("2"), chapter("3"), chapter("3")])
+            .await;
+
+        assert_eq!(enqueued, 1);
+        assert_eq!(
+            queued(&mut receiver),
+            vec![chapter("1"), chapter("2"), chapter("3")]
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelling_chapters_cancels_only_their_downloads() {
+        let (queue, mut receiver) = DownloadQueue::new();
+
+        queue.enqueue(vec![chapter("1"), chapter("2")]).await;
+        let tokens: HashMap<_, _> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+
+        queue.cancel(Some(vec![chapter("1")])).await;
+
+        assert!(tokens[&chapter("1")].is_cancelled());
+        assert!(!tokens[&chapter("2")].is_cancelled());
+
+        // A cancelled chapter can be queued again, with a fresh token.
+        assert_eq!(queue.enqueue(vec![chapter("1")]).await, 1);
+        let (chapter_id, token) = receiver.try_recv().unwrap();
+        assert_eq!(chapter_id, chapter("1"));
+        assert!(!token.is_cancelled());
+
+        // The cancelled download finishing doesn't drop the new entry.
+        queue
+            .finish(chapter("1"), &tokens[&chapter("1")], Status::Cancelled)
+            .await;
+        assert_eq!(queue.enqueue(vec![chapter("1")]).await, 0);
+    }
+
+    #[tokio::test]
+    async fn cancelling_without_chapters_cancels_the_whole_queue() {
+        let (queue, mut receiver) = DownloadQueue::new();
+
+        queue.enqueue(vec![chapter("1"), chapter("2")]).await;
+        queue.cancel(None).await;
+
+        assert!(
+            std::iter::from_fn(|| receiver.try_recv().ok()).all(|(_, token)| token.is_cancelled())
+        );
+        assert_eq!(queue.enqueue(vec![chapter("1"), chapter("2")]).await, 2);
+    }
+}
@@ -25,6 +25,7 @@ pub enum AppError {
     SourceNotFound,
     NotFound,
     DownloadAllChaptersProgressNotFound,
+    ChapterLocked,
     NetworkFailure(anyhow::Error),
     Other(anyhow::Error),
     MountTmpFs(anyhow::Error),
@@ -45,6 +46,7 @@ impl AppError {
     pub fn from_fetch_manga_chapters_error(value: FetchMangaChaptersError) -> Self {
         match value {
             FetchMangaChaptersError::DownloadError(e) => Self::NetworkFailure(e),
+            FetchMangaChaptersError::ChapterLocked => Self::ChapterLocked,
             FetchMangaChaptersError::Other(e) => Self::Other(e),
         }
     }
@@ -56,6 +58,7 @@ impl From<&AppError> for StatusCode {
             AppError::SourceNotFound
             | AppError::NotFound
             | AppError::DownloadAllChaptersProgressNotFound => StatusCode::NOT_FOUND,
+            AppError::ChapterLocked => StatusCode::FORBIDDEN,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -69,6 +72,7 @@ impl From<&AppError> for ErrorResponse {
             AppError::DownloadAllChaptersProgressNotFound => {
                 "No download is in progress.".to_string()
             }
+            AppError::ChapterLocked => "chapter is locked".to_string(),
             AppError::NetworkFailure(_) => {
                 "There was a network error. Check your connection and try again.".to_string()
             }
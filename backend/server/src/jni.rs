@@ -107,8 +107,8 @@ pub async fn bridge_net_send(
             .as_ref()
             .map(|m| m.to_string())
             .unwrap_or_else(|| "GET".to_string()),
-        headers: req.headers.clone(),
-        body: req.body.clone(),
+        headers: req.encoded_headers(),
+        body: req.encoded_body(),
     };
 
     let json = serde_json::to_string(&pending).unwrap_or_default();
@@ -149,6 +149,7 @@ impl DownloadChapterJob {
         let ((path, errors), chapter_use_ram) = match ensure_chapter_is_in_storage(
             &cancellation_token,
             &chapter_storage,
+            &db,
             &source,
             &manga,
             &chapter,
@@ -173,6 +174,7 @@ impl DownloadChapterJob {
                     ensure_chapter_is_in_storage(
                         &cancellation_token,
                         &chapter_storage,
+                        &db,
                         &source,
                         &manga,
                         &chapter,
@@ -94,7 +94,7 @@ async fn create_download_chapter_job(
         database,
         chapter_storage,
         body.into(),
-        settings.concurrent_requests_pages.unwrap_or(4),
+        settings.concurrent_requests_pages_or_default(),
         settings.optimize_image,
         download_semaphore,
         settings.ram_storage_enabled,
@@ -160,7 +160,7 @@ async fn create_download_unread_chapters_job(
         manga_id,
         filter,
         langs,
-        settings.concurrent_requests_pages.unwrap_or(4),
+        settings.concurrent_requests_pages_or_default(),
         settings.optimize_image,
         settings.chapter_title_format,
     );
@@ -224,7 +224,7 @@ async fn create_download_scanlator_chapters_job(
         manga_id,
         scanlator_filter,
         langs,
-        settings.concurrent_requests_pages.unwrap_or(4),
+        settings.concurrent_requests_pages_or_default(),
         settings.optimize_image,
         settings.chapter_title_format,
     );
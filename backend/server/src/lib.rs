@@ -12,13 +12,17 @@
 //!
 //! See [`listener::pick_listener`] for the exact resolution rules.
 
+pub mod arima;
 pub mod build_info;
 pub mod cookie;
+pub mod db;
+pub mod download_queue;
 pub mod error;
 pub mod job;
 pub mod listener;
 pub mod manga;
 pub mod model;
+pub mod opds;
 pub mod playlists;
 pub mod settings;
 pub mod source;
@@ -0,0 +1,114 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use shared::model::MangaId;
+
+/// How long a downloaded cover is served before it's fetched from the source again. Sources
+/// often hand out signed cover URLs that expire, so covers are always fetched on demand.
+const COVER_TTL: Duration = Duration::from_secs(60 * 60);
+/// How long a manga whose cover couldn't be fetched is answered without asking the source
+/// again, so that a library full of mangas without covers doesn't hammer it.
+const MISSING_COVER_TTL: Duration = Duration::from_secs(5 * 60);
+/// How many bytes of covers are kept in memory before the least recently used are dropped.
+const MAX_BYTES: usize = 32 * 1024 * 1024;
+
+/// Covers served recently, keyed by manga. `None` marks a cover that couldn't be fetched.
+#[derive(Default)]
+pub struct CoverCache {
+    entries: HashMap<MangaId, (Option<Vec<u8>>, Instant)>,
+    /// Mangas ordered from least to most recently used.
+    order: VecDeque<MangaId>,
+    bytes: usize,
+}
+
+impl CoverCache {
+    /// The cached cover of `manga_id`: `Some(None)` when it's known to be missing, `None`
+    /// when it has to be fetched.
+    pub fn get(&mut self, manga_id: &MangaId) -> Option<Option<Vec<u8>>> {
+        let (cover, fetched_at) = self.entries.get(manga_id)?;
+        let ttl = if cover.is_some() {
+            COVER_TTL
+        } else {
+            MISSING_COVER_TTL
+        };
+        if fetched_at.elapsed() >= ttl {
+            self.remove(manga_id);
+            return None;
+        }
+
+        let cover = cover.clone();
+        self.touch(manga_id);
+
+        Some(cover)
+    }
+
+    pub fn insert(&mut self, manga_id: MangaId, cover: Option<Vec<u8>>) {
+        self.remove(&manga_id);
+        if cover.as_ref().is_some_and(|cover| cover.len() > MAX_BYTES) {
+            return;
+        }
+
+        self.bytes += cover.as_ref().map_or(0, Vec::len);
+        self.entries
+            .insert(manga_id.clone(), (cover, Instant::now()));
+        self.order.push_back(manga_id);
+
+        while self.bytes > MAX_BYTES {
+            let Some(oldest) = self.order.front().cloned() else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+
+    fn remove(&mut self, manga_id: &MangaId) {
+        let Some((cover, _)) = self.entries.remove(manga_id) else {
+            return;
+        };
+        self.bytes -= cover.as_ref().map_or(0, Vec::len);
+        self.order.retain(|id| id != manga_id);
+    }
+
+    fn touch(&mut self, manga_id: &MangaId) {
+        if let Some(position) = self.order.iter().position(|id| id == manga_id) {
+            if let Some(id) = self.order.remove(position) {
+                self.order.push_back(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manga(id: &str) -> MangaId {
+        MangaId::from_strings("source".to_owned(), id.to_owned())
+    }
+
+    #[test]
+    fn remembers_missing_covers() {
+        let mut cache = CoverCache::default();
+        cache.insert(manga("a"), None);
+
+        assert_eq!(cache.get(&manga("a")), Some(None));
+        assert_eq!(cache.get(&manga("b")), None);
+    }
+
+    #[test]
+    fn drops_the_least_recently_used_covers_past_the_byte_limit() {
+        let mut cache = CoverCache::default();
+        let cover = vec![0; MAX_BYTES / 2];
+        cache.insert(manga("a"), Some(cover.clone()));
+        cache.insert(manga("b"), Some(cover.clone()));
+
+        assert!(cache.get(&manga("a")).is_some());
+
+        cache.insert(manga("c"), Some(cover.clone()));
+
+        assert_eq!(cache.get(&manga("b")), None);
+        assert!(cache.get(&manga("a")).is_some());
+        assert!(cache.get(&manga("c")).is_some());
+        assert!(cache.bytes <= MAX_BYTES);
+    }
+}
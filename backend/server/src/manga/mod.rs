@@ -1,3 +1,4 @@
+mod cover_cache;
 mod routes;
 
 pub use routes::routes;
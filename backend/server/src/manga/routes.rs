@@ -3,19 +3,23 @@ use std::sync::{Arc, LazyLock};
 use std::time::Duration;
 
 use axum::extract::{Path, Query, State as StateExtractor};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
 use axum::routing::{delete, get, patch, post};
 use axum::{Json, Router};
 use futures::Future;
 use log::warn;
 use serde::{Deserialize, Serialize};
+use shared::arima_light::ArimaSpecOverride;
 use shared::model::{
-    ChapterId, MangaId, NotificationInformation, TrackingCandidate, TrackingService,
+    ChapterId, DownloadProgress, MangaId, NotificationPage, TrackingCandidate, TrackingService,
     TrackingSyncDirection, TrackingSyncResult,
 };
 use shared::usecases;
 use tokio::sync::Mutex;
 use tokio_util::sync::CancellationToken;
 
+use super::cover_cache::CoverCache;
 use crate::model::{Chapter, Manga};
 use crate::source_extractor::SourceExtractor;
 use crate::state::State;
@@ -44,6 +48,9 @@ pub fn routes() -> Router<State> {
         .route("/check-mangas-update", post(check_mangas_update))
         .route("/count-notifications", get(get_count_notifications))
         .route("/notifications", get(get_notifications))
+        .route("/update-schedule", get(get_update_schedule))
+        .route("/tags", get(get_library_tags))
+        .route("/manga-cover/{source_id}/{manga_id}", get(get_manga_cover))
         .route("/notifications/{id}", delete(delete_notification))
         .route("/clear-notifications", post(clear_notifications))
         .route(
@@ -86,6 +93,10 @@ pub fn routes() -> Router<State> {
             "/mangas/{source_id}/{manga_id}/chapters/{chapter_id}/download",
             post(download_manga_chapter),
         )
+        .route(
+            "/mangas/{source_id}/{manga_id}/chapters/{chapter_id}/download-progress",
+            get(get_download_progress),
+        )
         .route(
             "/mangas/{source_id}/{manga_id}/chapters/{chapter_id}/revoke",
             post(revoke_manga_chapter),
@@ -99,12 +110,17 @@ pub fn routes() -> Router<State> {
             post(update_last_read),
         )
         .route(
-            "/mangas/{source_id}/{manga_id}/preferred-scanlator",
-            get(get_manga_preferred_scanlator),
+            "/mangas/{source_id}/{manga_id}/preferred-scanlators",
+            get(get_manga_preferred_scanlators),
+        )
+        .route("/mangas/{source_id}/{manga_id}/tags", get(get_manga_tags))
+        .route(
+            "/mangas/{source_id}/{manga_id}/disk-usage",
+            get(get_manga_disk_usage),
         )
         .route(
-            "/mangas/{source_id}/{manga_id}/preferred-scanlator",
-            post(set_manga_preferred_scanlator),
+            "/mangas/{source_id}/{manga_id}/preferred-scanlators",
+            post(set_manga_preferred_scanlators),
         )
         .route(
             "/mangas/{source_id}/{manga_id}/tracking",
@@ -134,6 +150,10 @@ pub fn routes() -> Router<State> {
             "/mangas/{source_id}/{manga_id}/viewer",
             post(set_manga_viewer),
         )
+        .route(
+            "/mangas/{source_id}/{manga_id}/arima-spec",
+            post(set_manga_arima_spec).delete(reset_manga_arima_spec),
+        )
 }
 
 async fn get_manga_library(
@@ -188,20 +208,14 @@ async fn find_orphan_or_read_files(
 ) -> Result<Json<FileSummary>, AppError> {
     let chapter_storage = chapter_storage.lock().await;
 
-    let paths =
+    let files =
         usecases::find_orphan_or_read_files(&database, &chapter_storage, invalid == "true").await?;
 
-    let filenames: Vec<String> = paths
+    let filenames: Vec<String> = files
         .iter()
-        .filter_map(|p| p.file_name()?.to_str().map(|s| s.to_string()))
+        .filter_map(|(p, _)| chapter_storage.relative_filename(p, false))
         .collect();
-
-    let mut total_size = 0u64;
-    for p in paths {
-        if let Ok(meta) = tokio::fs::metadata(p).await {
-            total_size += meta.len();
-        }
-    }
+    let total_size: u64 = files.iter().map(|(_, size)| size).sum();
 
     let total_text = humansize::format_size(total_size, humansize::DECIMAL);
 
@@ -258,16 +272,27 @@ async fn check_mangas_update(
         chapter_storage,
         source_manager,
         cancel_token_store,
+        settings,
         ..
     }): StateExtractor<State>,
     Query(GetCheckMangasUpdate { cancel_id }): Query<GetCheckMangasUpdate>,
 ) -> Result<Json<()>, AppError> {
+    let concurrent_source_operations = settings
+        .lock()
+        .await
+        .concurrent_source_operations_or_default();
     let chapter_storage = chapter_storage.lock().await;
     let source_manager = source_manager.lock().await;
     let token = create_token(cancel_token_store, cancel_id).await;
 
-    let _ = usecases::check_mangas_update(&token.0, &database, &chapter_storage, &*source_manager)
-        .await;
+    let _ = usecases::check_mangas_update(
+        &token.0,
+        &database,
+        &chapter_storage,
+        &*source_manager,
+        concurrent_source_operations,
+    )
+    .await;
 
     Ok(Json(()))
 }
@@ -332,7 +357,6 @@ async fn get_mangas(
                 q,
                 &exclude,
                 page,
-                30,
             )
         })
         .await
@@ -374,24 +398,12 @@ struct NotificationParams {
     id: i32,
 }
 
-#[derive(Deserialize)]
-struct MangaChaptersPathParams {
-    source_id: String,
-    manga_id: String,
-}
-
 #[derive(Deserialize)]
 struct MangaMarkChaptersAsRead {
     range: String,
     state: bool,
 }
 
-impl From<MangaChaptersPathParams> for MangaId {
-    fn from(value: MangaChaptersPathParams) -> Self {
-        MangaId::from_strings(value.source_id, value.manga_id)
-    }
-}
-
 async fn add_manga_to_library(
     StateExtractor(State {
         database,
@@ -400,10 +412,8 @@ async fn add_manga_to_library(
         settings,
         ..
     }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
 ) -> Result<Json<()>, AppError> {
-    let manga_id = MangaId::from(params);
-
     let settings = settings.lock().await;
 
     usecases::add_manga_to_library(&database, manga_id).await?;
@@ -430,18 +440,60 @@ async fn get_count_notifications(
     Ok(Json(count))
 }
 
+#[derive(Deserialize)]
+struct NotificationsQuery {
+    limit: Option<i64>,
+    before_id: Option<i64>,
+}
+
 async fn get_notifications(
     StateExtractor(State {
         database,
         chapter_storage,
         ..
     }): StateExtractor<State>,
-) -> Result<Json<Vec<NotificationInformation>>, AppError> {
+    Query(NotificationsQuery { limit, before_id }): Query<NotificationsQuery>,
+) -> Result<Json<NotificationPage>, AppError> {
     let chapter_storage = chapter_storage.lock().await;
 
-    let rows = usecases::get_notifications(&database, &chapter_storage).await?;
+    let page = usecases::get_notifications(&database, &chapter_storage, before_id, limit).await?;
+
+    Ok(Json(page))
+}
+
+async fn get_update_schedule(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+) -> Result<Json<Vec<usecases::get_update_schedule::UpdateScheduleEntry>>, AppError> {
+    let schedule = usecases::get_update_schedule(&database).await?;
+
+    Ok(Json(schedule))
+}
+
+static COVER_CACHE: LazyLock<Mutex<CoverCache>> = LazyLock::new(Default::default);
+
+async fn get_manga_cover(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    SourceExtractor(source): SourceExtractor,
+    Path(manga_id): Path<MangaId>,
+) -> Result<Response, AppError> {
+    let cached = COVER_CACHE.lock().await.get(&manga_id);
+
+    let bytes = match cached {
+        Some(bytes) => bytes.ok_or(AppError::NotFound)?,
+        None => {
+            let cover = usecases::fetch_manga_cover(&database, &source, &manga_id).await;
+            COVER_CACHE
+                .lock()
+                .await
+                .insert(manga_id, cover.as_ref().ok().cloned().flatten());
+
+            cover
+                .map_err(AppError::NetworkFailure)?
+                .ok_or(AppError::NotFound)?
+        }
+    };
 
-    Ok(Json(rows))
+    Ok(([(header::CONTENT_TYPE, "image/jpeg")], bytes).into_response())
 }
 
 async fn delete_notification(
@@ -490,9 +542,8 @@ async fn remove_manga_from_library(
         settings,
         ..
     }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
 ) -> Result<Json<()>, AppError> {
-    let manga_id = MangaId::from(params);
     // Copy the flag and release the settings lock before the long-running
     // removal work, so concurrent settings access isn't blocked.
     let delete_downloaded_on_remove = settings.lock().await.delete_downloaded_on_remove;
@@ -509,6 +560,12 @@ async fn remove_manga_from_library(
     Ok(Json(()))
 }
 
+#[derive(Deserialize)]
+struct GetCachedMangaChaptersQuery {
+    read: Option<bool>,
+    downloaded: Option<bool>,
+}
+
 async fn get_cached_manga_chapters(
     StateExtractor(State {
         database,
@@ -516,16 +573,16 @@ async fn get_cached_manga_chapters(
         settings,
         ..
     }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
+    Query(GetCachedMangaChaptersQuery { read, downloaded }): Query<GetCachedMangaChaptersQuery>,
 ) -> Result<Json<Vec<Chapter>>, AppError> {
-    let manga_id = MangaId::from(params);
-
     let chapter_storage = &*chapter_storage.lock().await;
     let chapters = usecases::get_cached_manga_chapters(
         &database,
         chapter_storage,
         &manga_id,
         settings.lock().await.ram_storage_enabled,
+        usecases::get_cached_manga_chapters::ChapterFilter { read, downloaded },
     )
     .await?;
 
@@ -541,11 +598,9 @@ async fn refresh_manga_chapters(
         ..
     }): StateExtractor<State>,
     SourceExtractor(source): SourceExtractor,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
     Json(cancel_id): Json<Option<usize>>,
 ) -> Result<Json<()>, AppError> {
-    let manga_id = MangaId::from(params);
-
     let token = create_token(cancel_token_store, cancel_id).await;
 
     let _ = usecases::refresh_manga_chapters(&token.0, &database, &source, &manga_id, 60).await;
@@ -561,11 +616,9 @@ async fn get_cached_manga_details(
         ..
     }): StateExtractor<State>,
     SourceExtractor(source): SourceExtractor,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
     Query(GetCheckMangasUpdate { cancel_id }): Query<GetCheckMangasUpdate>,
 ) -> Result<Json<(shared::source::model::Manga, f64)>, AppError> {
-    let manga_id = MangaId::from(params);
-
     let chapter_storage = &*chapter_storage.lock().await;
 
     let token = create_token(cancel_token_store, cancel_id).await;
@@ -589,11 +642,9 @@ async fn refresh_manga_details(
         ..
     }): StateExtractor<State>,
     SourceExtractor(source): SourceExtractor,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
     Json(cancel_id): Json<Option<usize>>,
 ) -> Result<Json<()>, AppError> {
-    let manga_id = MangaId::from(params);
-
     let chapter_storage = &*chapter_storage.lock().await;
     let token = create_token(cancel_token_store, cancel_id).await;
 
@@ -618,11 +669,9 @@ async fn mark_chapters_as_read(
         settings_path,
         ..
     }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
     Json(MangaMarkChaptersAsRead { range, state }): Json<MangaMarkChaptersAsRead>,
 ) -> Result<Json<Option<usize>>, AppError> {
-    let manga_id = MangaId::from(params);
-
     let (delete_downloaded_after_read, tracking_auto_sync) = {
         let settings = settings.lock().await;
         (
@@ -648,19 +697,6 @@ async fn mark_chapters_as_read(
     Ok(Json(count))
 }
 
-#[derive(Deserialize)]
-struct DownloadMangaChapterParams {
-    source_id: String,
-    manga_id: String,
-    chapter_id: String,
-}
-
-impl From<DownloadMangaChapterParams> for ChapterId {
-    fn from(value: DownloadMangaChapterParams) -> Self {
-        ChapterId::from_strings(value.source_id, value.manga_id, value.chapter_id)
-    }
-}
-
 #[derive(Deserialize, Default)]
 struct DownloadQuery {
     offline: Option<bool>,
@@ -675,7 +711,7 @@ async fn download_manga_chapter(
         ..
     }): StateExtractor<State>,
     SourceExtractor(source): SourceExtractor,
-    Path(params): Path<DownloadMangaChapterParams>,
+    Path(chapter_id): Path<ChapterId>,
     Query(query): Query<DownloadQuery>,
     Json(cancel_id): Json<Option<usize>>,
 ) -> Result<Json<(String, Vec<shared::chapter_downloader::DownloadError>)>, AppError> {
@@ -687,13 +723,12 @@ async fn download_manga_chapter(
             database.clone(),
             cs.clone(),
             !query.offline.unwrap_or_default() && settings.ram_storage_enabled,
-            settings.concurrent_requests_pages.unwrap_or(4),
+            settings.concurrent_requests_pages_or_default(),
             settings.optimize_image,
             settings.chapter_title_format,
         )
     };
 
-    let chapter_id = ChapterId::from(params);
     let output_path = usecases::fetch_manga_chapter(
         &token.0,
         &db,
@@ -715,6 +750,15 @@ async fn download_manga_chapter(
     )))
 }
 
+async fn get_download_progress(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(chapter_id): Path<ChapterId>,
+) -> Result<Json<Option<DownloadProgress>>, AppError> {
+    let progress = usecases::get_download_progress(&database, &chapter_id).await?;
+
+    Ok(Json(progress))
+}
+
 #[derive(Deserialize)]
 struct RevokeMangaChapterQuery {
     use_ram: Option<bool>,
@@ -723,10 +767,9 @@ async fn revoke_manga_chapter(
     StateExtractor(State {
         chapter_storage, ..
     }): StateExtractor<State>,
-    Path(params): Path<DownloadMangaChapterParams>,
+    Path(chapter_id): Path<ChapterId>,
     Query(query): Query<RevokeMangaChapterQuery>,
 ) -> Result<Json<bool>, AppError> {
-    let chapter_id = ChapterId::from(params);
     let chapter_storage = &*chapter_storage.lock().await;
 
     let result = usecases::revoke_manga_chapter(
@@ -751,11 +794,9 @@ async fn mark_chapter_as_read(
         chapter_storage,
         ..
     }): StateExtractor<State>,
-    Path(params): Path<DownloadMangaChapterParams>,
+    Path(chapter_id): Path<ChapterId>,
     Json(MarkChapterAsReadBody { state }): Json<MarkChapterAsReadBody>,
 ) -> Result<Json<()>, AppError> {
-    let chapter_id = ChapterId::from(params);
-
     let (delete_downloaded_after_read, tracking_auto_sync) = {
         let settings = settings.lock().await;
         (
@@ -785,6 +826,10 @@ async fn mark_chapter_as_read(
     Ok(Json(()))
 }
 
+#[derive(Deserialize)]
+struct UpdateLastReadBody {
+    last_read_page: Option<i32>,
+}
 async fn update_last_read(
     StateExtractor(State {
         database,
@@ -792,17 +837,18 @@ async fn update_last_read(
         settings_path,
         ..
     }): StateExtractor<State>,
-    Path(params): Path<DownloadMangaChapterParams>,
+    Path(chapter_id): Path<ChapterId>,
+    body: Option<Json<UpdateLastReadBody>>,
 ) -> Result<Json<()>, AppError> {
-    let chapter_id = ChapterId::from(params);
-
+    // Older clients send no body at all.
+    let last_read_page = body.and_then(|Json(body)| body.last_read_page);
     let tracking_auto_sync = {
         let settings = settings.lock().await;
 
         settings.tracking_auto_sync
     };
 
-    usecases::update_last_read_chapter(&database, &chapter_id).await?;
+    usecases::update_last_read_chapter(&database, &chapter_id, last_read_page).await?;
     if tracking_auto_sync {
         spawn_tracking_sync_after_local_update(
             database,
@@ -817,39 +863,68 @@ async fn update_last_read(
 
 // Scanlator preference handlers
 #[derive(Deserialize)]
-struct SetPreferredScanlatorBody {
-    preferred_scanlator: Option<String>,
+struct SetPreferredScanlatorsBody {
+    /// In order of preference; empty shows chapters from every scanlator.
+    #[serde(default)]
+    preferred_scanlators: Vec<String>,
 }
 
-async fn get_manga_preferred_scanlator(
+async fn get_manga_preferred_scanlators(
     StateExtractor(State { database, .. }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
-) -> Result<Json<Option<String>>, AppError> {
-    let manga_id = MangaId::from(params);
+    Path(manga_id): Path<MangaId>,
+) -> Result<Json<Vec<String>>, AppError> {
+    let preferred_scanlators =
+        usecases::get_manga_preferred_scanlators(&database, &manga_id).await?;
+
+    Ok(Json(preferred_scanlators))
+}
 
-    let preferred_scanlator = usecases::get_manga_preferred_scanlator(&database, &manga_id).await?;
+async fn get_manga_tags(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(manga_id): Path<MangaId>,
+) -> Result<Json<Vec<String>>, AppError> {
+    let tags = usecases::get_manga_tags(&database, &manga_id).await?;
 
-    Ok(Json(preferred_scanlator))
+    Ok(Json(tags))
 }
 
-async fn set_manga_preferred_scanlator(
+async fn get_manga_disk_usage(
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        ..
+    }): StateExtractor<State>,
+    Path(manga_id): Path<MangaId>,
+) -> Result<Json<usecases::get_manga_disk_usage::MangaDiskUsage>, AppError> {
+    let chapter_storage = chapter_storage.lock().await.clone();
+    let usage = usecases::get_manga_disk_usage(&database, &chapter_storage, &manga_id).await?;
+
+    Ok(Json(usage))
+}
+
+async fn get_library_tags(
     StateExtractor(State { database, .. }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
-    Json(body): Json<SetPreferredScanlatorBody>,
-) -> Result<Json<()>, AppError> {
-    let manga_id = MangaId::from(params);
+) -> Result<Json<Vec<String>>, AppError> {
+    let tags = usecases::get_library_tags(&database).await?;
 
-    usecases::set_manga_preferred_scanlator(&database, manga_id, body.preferred_scanlator).await?;
+    Ok(Json(tags))
+}
+
+async fn set_manga_preferred_scanlators(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(manga_id): Path<MangaId>,
+    Json(body): Json<SetPreferredScanlatorsBody>,
+) -> Result<Json<()>, AppError> {
+    usecases::set_manga_preferred_scanlators(&database, manga_id, body.preferred_scanlators)
+        .await?;
 
     Ok(Json(()))
 }
 
 async fn list_tracking_bindings(
     StateExtractor(State { database, .. }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
 ) -> Result<Json<Vec<shared::model::TrackingBinding>>, AppError> {
-    let manga_id = MangaId::from(params);
-
     Ok(Json(
         usecases::list_tracking_bindings(&database, &manga_id).await?,
     ))
@@ -886,11 +961,9 @@ async fn link_tracking_binding(
         settings_path,
         ..
     }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
     Json(candidate): Json<TrackingCandidate>,
 ) -> Result<Json<()>, AppError> {
-    let manga_id = MangaId::from(params);
-
     usecases::link_tracking_binding(&database, &manga_id, &candidate).await?;
 
     // Automatically pull progress from the newly linked service.
@@ -918,11 +991,9 @@ struct SetViewerBody {
 
 async fn set_manga_viewer(
     StateExtractor(State { database, .. }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
     Json(body): Json<SetViewerBody>,
 ) -> Result<Json<()>, AppError> {
-    let manga_id = MangaId::from(params);
-
     if let Some(viewer) = body.viewer {
         if viewer < 0 || viewer > 4 {
             return Err(AppError::Other(anyhow::anyhow!(
@@ -937,6 +1008,26 @@ async fn set_manga_viewer(
     Ok(Json(()))
 }
 
+// Update forecast spec handlers
+async fn set_manga_arima_spec(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(manga_id): Path<MangaId>,
+    Json(body): Json<ArimaSpecOverride>,
+) -> Result<Json<()>, AppError> {
+    usecases::set_manga_arima_spec(&database, manga_id, Some(body)).await?;
+
+    Ok(Json(()))
+}
+
+async fn reset_manga_arima_spec(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    Path(manga_id): Path<MangaId>,
+) -> Result<Json<()>, AppError> {
+    usecases::set_manga_arima_spec(&database, manga_id, None).await?;
+
+    Ok(Json(()))
+}
+
 #[derive(Deserialize)]
 struct SyncTrackingBindingsBody {
     service: Option<TrackingService>,
@@ -951,10 +1042,9 @@ async fn sync_tracking_bindings(
         settings_path,
         ..
     }): StateExtractor<State>,
-    Path(params): Path<MangaChaptersPathParams>,
+    Path(manga_id): Path<MangaId>,
     Json(body): Json<SyncTrackingBindingsBody>,
 ) -> Result<Json<Vec<TrackingSyncResult>>, AppError> {
-    let manga_id = MangaId::from(params);
     let mut settings = settings.lock().await;
     let chapter_storage = chapter_storage.lock().await;
 
@@ -975,8 +1065,8 @@ async fn sync_tracking_bindings(
 
 #[derive(Deserialize)]
 struct TrackingBindingPathParams {
-    source_id: String,
-    manga_id: String,
+    #[serde(flatten)]
+    manga_id: MangaId,
     service: String,
 }
 
@@ -985,7 +1075,7 @@ async fn unlink_tracking_binding(
     Path(params): Path<TrackingBindingPathParams>,
 ) -> Result<Json<()>, AppError> {
     let service = TrackingService::try_from(params.service.as_str())?;
-    let manga_id = MangaId::from_strings(params.source_id, params.manga_id);
+    let manga_id = params.manga_id;
 
     usecases::unlink_tracking_binding(&database, &manga_id, service).await?;
 
@@ -1004,7 +1094,7 @@ async fn set_tracking_dates(
     Json(body): Json<SetTrackingDatesBody>,
 ) -> Result<Json<()>, AppError> {
     let service = TrackingService::try_from(params.service.as_str())?;
-    let manga_id = MangaId::from_strings(params.source_id, params.manga_id);
+    let manga_id = params.manga_id;
 
     database
         .set_tracking_dates(&manga_id, service, body.started_at, body.completed_at)
@@ -1068,16 +1158,26 @@ type CancelTokenStore =
     std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<usize, CancellationToken>>>;
 struct TokenGuard(CancellationToken, CancelTokenStore, Option<usize>);
 
+/// Removes the token from the store once the request is over, whether it completed normally,
+/// failed or was cancelled, so that the store doesn't grow with every `cancel_id` ever used.
 impl Drop for TokenGuard {
     fn drop(&mut self) {
-        if let Some(cancel_id) = self.2 {
-            let store = self.1.clone();
+        let Some(cancel_id) = self.2 else {
+            return;
+        };
 
-            tokio::spawn(async move {
-                let mut store = store.lock().await;
-                store.remove(&cancel_id);
-            });
+        // Remove it right away when possible, and only fall back to a task when someone else
+        // (e.g. `post_cancel_request`) is holding the lock.
+        if let Ok(mut store) = self.1.try_lock() {
+            store.remove(&cancel_id);
+            return;
         }
+
+        let store = self.1.clone();
+        tokio::spawn(async move {
+            let mut store = store.lock().await;
+            store.remove(&cancel_id);
+        });
     }
 }
 
@@ -1121,3 +1221,34 @@ where
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn removes_token_after_request_completes() {
+        let store = CancelTokenStore::default();
+
+        let token = create_token(store.clone(), Some(1)).await;
+        let result = cancel_after(&token.0, Duration::from_secs(60), |_| async { 42 }).await;
+        assert_eq!(result, 42);
+        assert_eq!(store.lock().await.len(), 1);
+
+        drop(token);
+        assert!(store.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn removes_token_when_store_is_busy() {
+        let store = CancelTokenStore::default();
+        let token = create_token(store.clone(), Some(1)).await;
+
+        let busy = store.lock().await;
+        drop(token);
+        drop(busy);
+
+        tokio::task::yield_now().await;
+        assert!(store.lock().await.is_empty());
+    }
+}
@@ -5,7 +5,7 @@ use shared::{
         Chapter as DomainChapter, Manga as DomainManga,
         SourceInformation as DomainSourceInformation,
     },
-    source::model::MangaViewer,
+    source::{model::MangaViewer, Source},
 };
 
 #[derive(Serialize)]
@@ -27,6 +27,59 @@ impl From<DomainSourceInformation> for SourceInformation {
     }
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SdkMode {
+    Legacy,
+    Next,
+}
+
+#[derive(Serialize)]
+pub struct SourceFeatures {
+    process_page_image: bool,
+    has_handle_notification: bool,
+}
+
+/// An installed source, with everything read from its manifest and the SDK mode it
+/// was loaded with.
+#[derive(Serialize)]
+pub struct SourceDetails {
+    pub id: String,
+    pub name: String,
+    lang: Option<String>,
+    version: usize,
+    url: Option<String>,
+    min_app_version: Option<String>,
+    source_of_source: Option<String>,
+    sdk_mode: SdkMode,
+    features: SourceFeatures,
+}
+
+impl From<&Source> for SourceDetails {
+    fn from(value: &Source) -> Self {
+        let manifest = value.manifest();
+
+        Self {
+            id: manifest.info.id,
+            name: manifest.info.name,
+            lang: manifest.info.lang,
+            version: manifest.info.version,
+            url: manifest.info.url,
+            min_app_version: manifest.info.min_app_version,
+            source_of_source: manifest.source_of_source,
+            sdk_mode: if value.is_next_sdk() {
+                SdkMode::Next
+            } else {
+                SdkMode::Legacy
+            },
+            features: SourceFeatures {
+                process_page_image: value.1.features.process_page_image,
+                has_handle_notification: value.1.features.has_handle_notification,
+            },
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct Manga {
     // FIXME maybe both `id` and `source_id` should be encoded into a single field
@@ -69,9 +122,11 @@ pub struct Chapter {
     volume_num: Option<f32>,
     read: bool,
     last_read: Option<i64>,
+    last_read_page: Option<i32>,
     downloaded: bool,
     locked: bool,
     lang: Option<String>,
+    url: Option<String>,
     on_tmpfs: bool,
 }
 
@@ -95,9 +150,11 @@ impl From<DomainChapter> for Chapter {
             volume_num: chapter_information.volume_number,
             read: state.read,
             last_read: state.last_read,
+            last_read_page: state.last_read_page,
             downloaded,
             locked: chapter_information.locked.unwrap_or_default(),
             lang: chapter_information.lang,
+            url: chapter_information.url.map(|url| url.to_string()),
             on_tmpfs,
         }
     }
@@ -0,0 +1,195 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+
+pub const NAVIGATION_FEED_TYPE: &str = "application/atom+xml;profile=opds-catalog;kind=navigation";
+pub const ACQUISITION_FEED_TYPE: &str =
+    "application/atom+xml;profile=opds-catalog;kind=acquisition";
+
+pub const ACQUISITION_REL: &str = "http://opds-spec.org/acquisition";
+pub const IMAGE_REL: &str = "http://opds-spec.org/image";
+pub const THUMBNAIL_REL: &str = "http://opds-spec.org/image/thumbnail";
+
+/// How many entries a single page of a feed holds.
+pub const PAGE_SIZE: usize = 50;
+
+pub struct Link {
+    pub rel: &'static str,
+    pub href: String,
+    pub mime_type: String,
+}
+
+impl Link {
+    pub fn new(rel: &'static str, href: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            rel,
+            href: href.into(),
+            mime_type: mime_type.into(),
+        }
+    }
+}
+
+pub struct Entry {
+    pub id: String,
+    pub title: String,
+    pub updated: DateTime<Utc>,
+    pub author: Option<String>,
+    pub content: Option<String>,
+    pub links: Vec<Link>,
+}
+
+pub struct Feed {
+    pub id: String,
+    pub title: String,
+    pub updated: DateTime<Utc>,
+    pub links: Vec<Link>,
+    pub entries: Vec<Entry>,
+}
+
+impl Feed {
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:opds="http://opds-spec.org/2010/catalog">"#,
+            "\n",
+        ));
+
+        push_element(&mut xml, 1, "id", &self.id);
+        push_element(&mut xml, 1, "title", &self.title);
+        push_element(&mut xml, 1, "updated", &format_date(&self.updated));
+        for link in &self.links {
+            push_link(&mut xml, 1, link);
+        }
+
+        for entry in &self.entries {
+            xml.push_str("  <entry>\n");
+            push_element(&mut xml, 2, "id", &entry.id);
+            push_element(&mut xml, 2, "title", &entry.title);
+            push_element(&mut xml, 2, "updated", &format_date(&entry.updated));
+            if let Some(author) = &entry.author {
+                xml.push_str("    <author>\n");
+                push_element(&mut xml, 3, "name", author);
+                xml.push_str("    </author>\n");
+            }
+            if let Some(content) = &entry.content {
+                indent(&mut xml, 2);
+                xml.push_str(&format!(
+                    r#"<content type="text">{}</content>"#,
+                    escape(content)
+                ));
+                xml.push('\n');
+            }
+            for link in &entry.links {
+                push_link(&mut xml, 2, link);
+            }
+            xml.push_str("  </entry>\n");
+        }
+
+        xml.push_str("</feed>\n");
+        xml
+    }
+}
+
+/// Returns the items on the 1-based `page`, and whether there is a page after it.
+pub fn paginate<T>(items: &[T], page: usize) -> (&[T], bool) {
+    let start = page.saturating_sub(1).saturating_mul(PAGE_SIZE);
+    if start >= items.len() {
+        return (&[], false);
+    }
+
+    let end = (start + PAGE_SIZE).min(items.len());
+
+    (&items[start..end], end < items.len())
+}
+
+/// Builds an absolute path out of `segments`, percent-encoding each of them, since manga and
+/// chapter IDs are free-form and may contain slashes.
+pub fn encode_path<'a>(segments: impl IntoIterator<Item = &'a str>) -> String {
+    let mut url = url::Url::parse("http://localhost/").unwrap();
+    url.path_segments_mut().unwrap().extend(segments);
+
+    url.path().to_owned()
+}
+
+fn format_date(date: &DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn indent(xml: &mut String, level: usize) {
+    xml.push_str(&"  ".repeat(level));
+}
+
+fn push_element(xml: &mut String, level: usize, name: &str, value: &str) {
+    indent(xml, level);
+    xml.push_str(&format!("<{name}>{}</{name}>\n", escape(value)));
+}
+
+fn push_link(xml: &mut String, level: usize, link: &Link) {
+    indent(xml, level);
+    xml.push_str(&format!(
+        r#"<link rel="{}" href="{}" type="{}"/>"#,
+        escape(link.rel),
+        escape(&link.href),
+        escape(&link.mime_type)
+    ));
+    xml.push('\n');
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paginates_with_one_based_pages() {
+        let items: Vec<_> = (0..PAGE_SIZE + 1).collect();
+
+        let (first, has_next) = paginate(&items, 1);
+        assert_eq!(first.len(), PAGE_SIZE);
+        assert!(has_next);
+
+        let (second, has_next) = paginate(&items, 2);
+        assert_eq!(second, &[PAGE_SIZE]);
+        assert!(!has_next);
+
+        assert!(paginate(&items, 3).0.is_empty());
+    }
+
+    #[test]
+    fn encodes_each_path_segment() {
+        assert_eq!(
+            encode_path(["opds", "manga", "source", "series/1 2"]),
+            "/opds/manga/source/series%2F1%202"
+        );
+    }
+
+    #[test]
+    fn escapes_text_and_attributes() {
+        let feed = Feed {
+            id: "urn:test".to_owned(),
+            title: "Tom & Jerry <3".to_owned(),
+            updated: DateTime::from_timestamp(0, 0).unwrap(),
+            links: vec![Link::new("self", "/opds?page=1&x=\"y\"", "text/plain")],
+            entries: vec![],
+        };
+        let xml = feed.to_xml();
+
+        assert!(xml.contains("<title>Tom &amp; Jerry &lt;3</title>"));
+        assert!(xml.contains(r#"href="/opds?page=1&amp;x=&quot;y&quot;""#));
+        assert!(xml.contains("<updated>1970-01-01T00:00:00Z</updated>"));
+    }
+}
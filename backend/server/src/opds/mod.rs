@@ -0,0 +1,4 @@
+mod feed;
+mod routes;
+
+pub use routes::routes;
@@ -0,0 +1,298 @@
+use axum::extract::{Path, Query, State as StateExtractor};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use shared::model::{Chapter, ChapterId, Manga, MangaId};
+use shared::usecases;
+use tokio_util::sync::CancellationToken;
+
+use super::feed::{
+    encode_path, paginate, Entry, Feed, Link, ACQUISITION_FEED_TYPE, ACQUISITION_REL, IMAGE_REL,
+    NAVIGATION_FEED_TYPE, THUMBNAIL_REL,
+};
+use crate::source_extractor::SourceExtractor;
+use crate::state::State;
+use crate::AppError;
+
+const CBZ_TYPE: &str = "application/vnd.comicbook+zip";
+const EPUB_TYPE: &str = "application/epub+zip";
+
+pub fn routes() -> Router<State> {
+    Router::new()
+        .route("/opds", get(get_library_feed))
+        .route("/opds/manga/{source_id}/{manga_id}", get(get_manga_feed))
+        .route(
+            "/opds/manga/{source_id}/{manga_id}/{chapter_id}/download",
+            get(download_chapter),
+        )
+}
+
+#[derive(Deserialize)]
+struct PageQuery {
+    page: Option<usize>,
+}
+
+async fn get_library_feed(
+    StateExtractor(State {
+        database,
+        source_manager,
+        settings,
+        ..
+    }): StateExtractor<State>,
+    Query(PageQuery { page }): Query<PageQuery>,
+) -> Result<Response, AppError> {
+    let page = page.unwrap_or(1).max(1);
+    let mangas = {
+        let settings = settings.lock().await;
+        let source_manager = source_manager.lock().await;
+
+        usecases::get_manga_library(&database, &*source_manager, &settings.library_sorting_mode)
+            .await?
+    };
+
+    let now = Utc::now();
+    let (mangas_on_page, has_next_page) = paginate(&mangas, page);
+    let feed = Feed {
+        id: "urn:rakuyomi:library".to_owned(),
+        title: "Library".to_owned(),
+        updated: now,
+        links: page_links("/opds", NAVIGATION_FEED_TYPE, page, has_next_page),
+        entries: mangas_on_page
+            .iter()
+            .map(|manga| manga_entry(manga, now))
+            .collect(),
+    };
+
+    Ok(atom_response(feed, NAVIGATION_FEED_TYPE))
+}
+
+async fn get_manga_feed(
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        settings,
+        ..
+    }): StateExtractor<State>,
+    Path(manga_id): Path<MangaId>,
+    Query(PageQuery { page }): Query<PageQuery>,
+) -> Result<Response, AppError> {
+    let page = page.unwrap_or(1).max(1);
+    let chapters = usecases::get_cached_manga_chapters(
+        &database,
+        &*chapter_storage.lock().await,
+        &manga_id,
+        settings.lock().await.ram_storage_enabled,
+        Default::default(),
+    )
+    .await?;
+    let title = database
+        .find_cached_manga_details(&manga_id)
+        .await?
+        .and_then(|(details, _)| details.title)
+        .unwrap_or_else(|| manga_id.value().clone());
+
+    let now = Utc::now();
+    let self_href = manga_href(&manga_id);
+    let (chapters_on_page, has_next_page) = paginate(&chapters, page);
+    let feed = Feed {
+        id: manga_urn(&manga_id),
+        title,
+        updated: now,
+        links: page_links(&self_href, ACQUISITION_FEED_TYPE, page, has_next_page),
+        entries: chapters_on_page
+            .iter()
+            .map(|chapter| chapter_entry(chapter, now))
+            .collect(),
+    };
+
+    Ok(atom_response(feed, ACQUISITION_FEED_TYPE))
+}
+
+async fn download_chapter(
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        settings,
+        ..
+    }): StateExtractor<State>,
+    SourceExtractor(source): SourceExtractor,
+    Path(chapter_id): Path<ChapterId>,
+) -> Result<Response, AppError> {
+    let (cs, use_ram, concurrent_requests_pages, optimize_image, chapter_title_format) = {
+        let cs = chapter_storage.lock().await;
+        let settings = settings.lock().await;
+        (
+            cs.clone(),
+            settings.ram_storage_enabled,
+            settings.concurrent_requests_pages_or_default(),
+            settings.optimize_image,
+            settings.chapter_title_format,
+        )
+    };
+
+    let (path, _) = usecases::fetch_manga_chapter(
+        &CancellationToken::new(),
+        &database,
+        &source,
+        &cs,
+        &chapter_id,
+        concurrent_requests_pages,
+        optimize_image,
+        None,
+        use_ram,
+        chapter_title_format,
+    )
+    .await
+    .map_err(AppError::from_fetch_manga_chapters_error)?;
+
+    let is_epub = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("epub"));
+    let file_name = format!(
+        "{}.{}",
+        sanitize_file_name(chapter_id.value()),
+        if is_epub { "epub" } else { "cbz" }
+    );
+    let contents = tokio::fs::read(&path)
+        .await
+        .map_err(|err| AppError::Other(err.into()))?;
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                if is_epub { EPUB_TYPE } else { CBZ_TYPE }.to_owned(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        contents,
+    )
+        .into_response())
+}
+
+fn atom_response(feed: Feed, content_type: &'static str) -> Response {
+    ([(header::CONTENT_TYPE, content_type)], feed.to_xml()).into_response()
+}
+
+fn page_links(href: &str, feed_type: &'static str, page: usize, has_next_page: bool) -> Vec<Link> {
+    let page_href = |page: usize| format!("{href}?page={page}");
+
+    let mut links = vec![
+        Link::new("self", page_href(page), feed_type),
+        Link::new("start", "/opds", NAVIGATION_FEED_TYPE),
+    ];
+    if page > 1 {
+        links.push(Link::new("previous", page_href(page - 1), feed_type));
+    }
+    if has_next_page {
+        links.push(Link::new("next", page_href(page + 1), feed_type));
+    }
+
+    links
+}
+
+fn manga_entry(manga: &Manga, now: DateTime<Utc>) -> Entry {
+    let information = &manga.information;
+    let mut links = vec![Link::new(
+        "subsection",
+        manga_href(&information.id),
+        ACQUISITION_FEED_TYPE,
+    )];
+    // Covers are only linked when they're on the web, as local files can't be fetched by a
+    // reader on another device.
+    if let Some(cover_url) = information
+        .cover_url
+        .as_ref()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+    {
+        links.push(Link::new(IMAGE_REL, cover_url.as_str(), "image/jpeg"));
+        links.push(Link::new(THUMBNAIL_REL, cover_url.as_str(), "image/jpeg"));
+    }
+
+    Entry {
+        id: manga_urn(&information.id),
+        title: information
+            .title
+            .clone()
+            .unwrap_or_else(|| information.id.value().clone()),
+        updated: manga
+            .last_read
+            .and_then(|last_read| DateTime::from_timestamp(last_read, 0))
+            .unwrap_or(now),
+        author: information.author.clone(),
+        content: Some(manga.source_information.name.clone()),
+        links,
+    }
+}
+
+fn chapter_entry(chapter: &Chapter, now: DateTime<Utc>) -> Entry {
+    let information = &chapter.information;
+    let id = &information.id;
+    let download_href = encode_path([
+        "opds",
+        "manga",
+        id.source_id().value().as_str(),
+        id.manga_id().value().as_str(),
+        id.value().as_str(),
+        "download",
+    ]);
+
+    Entry {
+        id: format!("{}:{}", manga_urn(id.manga_id()), id.value()),
+        title: chapter_title(chapter),
+        updated: information
+            .last_updated
+            .and_then(|last_updated| DateTime::from_timestamp(last_updated, 0))
+            .unwrap_or(now),
+        author: information.scanlator.clone(),
+        content: None,
+        // The real format is only known once the chapter is downloaded; almost every source
+        // gives comics, so that's what is advertised.
+        links: vec![Link::new(ACQUISITION_REL, download_href, CBZ_TYPE)],
+    }
+}
+
+fn chapter_title(chapter: &Chapter) -> String {
+    let information = &chapter.information;
+    match (information.chapter_number, &information.title) {
+        (Some(number), Some(title)) => format!("Chapter {number}: {title}"),
+        (Some(number), None) => format!("Chapter {number}"),
+        (None, Some(title)) => title.clone(),
+        (None, None) => information.id.value().clone(),
+    }
+}
+
+fn manga_href(manga_id: &MangaId) -> String {
+    encode_path([
+        "opds",
+        "manga",
+        manga_id.source_id().value().as_str(),
+        manga_id.value().as_str(),
+    ])
+}
+
+fn manga_urn(manga_id: &MangaId) -> String {
+    format!(
+        "urn:rakuyomi:manga:{}:{}",
+        manga_id.source_id().value(),
+        manga_id.value()
+    )
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -1,3 +1,5 @@
 mod routes;
+mod watcher;
 
 pub use routes::routes;
+pub use watcher::spawn_source_watcher;
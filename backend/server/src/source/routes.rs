@@ -1,15 +1,19 @@
 use std::collections::HashMap;
 
-use axum::extract::{Path, State as StateExtractor};
+use axum::extract::{Path, Query, State as StateExtractor};
 use axum::routing::{delete, get, post};
 use axum::{Json, Router};
-use serde::Deserialize;
+use log::warn;
+use serde::{Deserialize, Serialize};
 use shared::model::SourceId;
 use shared::settings::SourceSettingValue;
 use shared::source::model::SettingDefinition;
+use shared::source::wasm_store::BandwidthStats;
+use shared::source_collection::SourceCollection;
 use shared::usecases;
+use tokio_util::sync::CancellationToken;
 
-use crate::model::SourceInformation;
+use crate::model::{Manga, SourceDetails, SourceInformation};
 use crate::source_extractor::{SourceExtractor, SourceParams};
 use crate::state::State;
 use crate::AppError;
@@ -35,6 +39,25 @@ pub fn routes() -> Router<State> {
             "/installed-sources/{source_id}/stored-settings",
             post(set_source_stored_settings),
         )
+        .route("/sources", get(list_sources))
+        .route("/sources/reload", post(reload_source))
+        .route("/sources/{source_id}", delete(unload_source))
+        .route("/sources/{source_id}/login", post(login_to_source))
+        .route(
+            "/sources/{source_id}/handle-deep-link",
+            post(handle_deep_link),
+        )
+        .route("/sources/{source_id}/listings", get(get_source_listings))
+        .route("/sources/{source_id}/mangas", get(get_source_manga_list))
+        .route("/sources/{source_id}/tags", get(get_source_tags))
+        .route(
+            "/sources/{source_id}/tags/mangas",
+            get(get_source_mangas_by_tag),
+        )
+        .route(
+            "/sources/{source_id}/bandwidth-stats",
+            get(get_source_bandwidth_stats),
+        )
 }
 
 async fn list_available_sources(
@@ -58,21 +81,44 @@ struct InstallSourceParams {
 async fn install_source(
     StateExtractor(State {
         source_manager,
+        database,
+        chapter_storage,
         settings,
         ..
     }): StateExtractor<State>,
     Path(InstallSourceParams { source_id }): Path<InstallSourceParams>,
     Json(source_of_source): Json<String>,
 ) -> Result<Json<()>, AppError> {
+    let source_id = SourceId::new(source_id);
     usecases::install_source(
         &mut *source_manager.lock().await,
         &source_manager,
         &settings.lock().await.source_lists,
-        SourceId::new(source_id),
+        source_id.clone(),
         source_of_source,
     )
     .await?;
 
+    // Updates may rename the source's manga IDs. The source was still installed if
+    // migrating them fails, so that isn't reported as an error.
+    let source = source_manager.lock().await.get_by_id(&source_id).cloned();
+    if let Some(source) = source {
+        let chapter_storage = chapter_storage.lock().await.clone();
+        if let Err(e) = usecases::migrate_manga_ids(
+            &database,
+            &chapter_storage,
+            &source,
+            CancellationToken::new(),
+        )
+        .await
+        {
+            warn!(
+                "failed to migrate the manga IDs of {}: {e:?}",
+                source_id.value()
+            );
+        }
+    }
+
     Ok(Json(()))
 }
 
@@ -96,6 +142,172 @@ async fn uninstall_source(
     Ok(Json(()))
 }
 
+async fn list_sources(
+    StateExtractor(State { source_manager, .. }): StateExtractor<State>,
+) -> Json<Vec<SourceDetails>> {
+    let source_manager = source_manager.lock().await;
+    let mut sources: Vec<SourceDetails> = source_manager
+        .sources()
+        .into_iter()
+        .map(SourceDetails::from)
+        .collect();
+
+    sources.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Json(sources)
+}
+
+#[derive(Deserialize)]
+struct ReloadSourceBody {
+    path: String,
+}
+
+async fn reload_source(
+    StateExtractor(State { source_manager, .. }): StateExtractor<State>,
+    Json(ReloadSourceBody { path }): Json<ReloadSourceBody>,
+) -> Result<Json<String>, AppError> {
+    let source_id = usecases::reload_source(
+        &mut *source_manager.lock().await,
+        &source_manager,
+        std::path::Path::new(&path),
+    )?;
+
+    Ok(Json(source_id.value().clone()))
+}
+
+async fn unload_source(
+    StateExtractor(State { source_manager, .. }): StateExtractor<State>,
+    Path(SourceParams { source_id }): Path<SourceParams>,
+) -> Result<Json<()>, AppError> {
+    if !usecases::unload_source(&mut *source_manager.lock().await, &SourceId::new(source_id)) {
+        return Err(AppError::SourceNotFound);
+    }
+
+    Ok(Json(()))
+}
+
+#[derive(Deserialize)]
+struct LoginBody {
+    username: String,
+    password: String,
+}
+
+#[derive(Serialize)]
+struct LoginResponse {
+    success: bool,
+    message: Option<String>,
+}
+
+async fn login_to_source(
+    SourceExtractor(source): SourceExtractor,
+    Json(LoginBody { username, password }): Json<LoginBody>,
+) -> Json<LoginResponse> {
+    let response = match usecases::login_to_source(
+        CancellationToken::new(),
+        &source,
+        username,
+        password,
+    )
+    .await
+    {
+        Ok(success) => LoginResponse {
+            success,
+            message: None,
+        },
+        Err(err) => LoginResponse {
+            success: false,
+            message: Some(format!("{err:#}")),
+        },
+    };
+
+    Json(response)
+}
+
+#[derive(Deserialize)]
+struct DeepLinkBody {
+    url: url::Url,
+}
+
+#[derive(Serialize)]
+struct DeepLinkResponse {
+    manga_id: String,
+    chapter_id: Option<String>,
+}
+
+async fn handle_deep_link(
+    SourceExtractor(source): SourceExtractor,
+    Json(DeepLinkBody { url }): Json<DeepLinkBody>,
+) -> Result<Json<DeepLinkResponse>, AppError> {
+    let target = usecases::handle_deep_link(&source, url).await?;
+
+    Ok(Json(DeepLinkResponse {
+        manga_id: target.manga_id.value().clone(),
+        chapter_id: target.chapter_id.map(|id| id.value().clone()),
+    }))
+}
+
+async fn get_source_listings(
+    SourceExtractor(source): SourceExtractor,
+) -> Result<Json<Vec<String>>, AppError> {
+    let listings = usecases::get_source_listings(&source, CancellationToken::new()).await?;
+
+    Ok(Json(listings))
+}
+
+#[derive(Deserialize)]
+struct MangaListQuery {
+    listing: Option<String>,
+}
+
+async fn get_source_manga_list(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    SourceExtractor(source): SourceExtractor,
+    Query(MangaListQuery { listing }): Query<MangaListQuery>,
+) -> Result<Json<Vec<Manga>>, AppError> {
+    let mangas =
+        usecases::get_source_manga_list(&database, &source, CancellationToken::new(), listing)
+            .await?;
+
+    Ok(Json(mangas.into_iter().map(Manga::from).collect()))
+}
+
+async fn get_source_bandwidth_stats(
+    SourceExtractor(source): SourceExtractor,
+) -> Result<Json<BandwidthStats>, AppError> {
+    Ok(Json(source.bandwidth_stats()))
+}
+
+async fn get_source_tags(
+    SourceExtractor(source): SourceExtractor,
+) -> Result<Json<Vec<String>>, AppError> {
+    let tags = usecases::get_source_tags(&source, CancellationToken::new()).await?;
+
+    Ok(Json(tags))
+}
+
+#[derive(Deserialize)]
+struct MangasByTagQuery {
+    tag: String,
+    page: Option<u32>,
+}
+
+async fn get_source_mangas_by_tag(
+    StateExtractor(State { database, .. }): StateExtractor<State>,
+    SourceExtractor(source): SourceExtractor,
+    Query(MangasByTagQuery { tag, page }): Query<MangasByTagQuery>,
+) -> Result<Json<Vec<Manga>>, AppError> {
+    let mangas = usecases::get_source_mangas_by_tag(
+        &database,
+        &source,
+        CancellationToken::new(),
+        tag,
+        page.unwrap_or(1).max(1),
+    )
+    .await?;
+
+    Ok(Json(mangas.into_iter().map(Manga::from).collect()))
+}
+
 async fn get_source_setting_definitions(
     SourceExtractor(source): SourceExtractor,
 ) -> Json<Vec<SettingDefinition>> {
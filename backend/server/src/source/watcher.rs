@@ -0,0 +1,186 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use shared::{model::SourceId, source_manager::SourceManager, usecases};
+use tokio::sync::{mpsc, Mutex};
+
+/// How long the sources folder has to stay quiet before pending changes are applied, so that
+/// a source still being written is not loaded half-way.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watches `sources_path` in the background, reloading a source whenever its `.aix` file is
+/// created or modified and unloading it when the file is deleted.
+pub fn spawn_source_watcher(
+    source_manager: Arc<Mutex<SourceManager>>,
+    sources_path: PathBuf,
+) -> Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) => {
+                let _ = tx.send(event);
+            }
+            Err(err) => warn!("source watcher error: {err:?}"),
+        })
+        .context("couldn't create the sources folder watcher")?;
+
+    watcher
+        .watch(&sources_path, RecursiveMode::NonRecursive)
+        .with_context(|| format!("couldn't watch {}", sources_path.display()))?;
+
+    tokio::spawn(async move {
+        // The watcher stops as soon as it's dropped, so keep it alive with the task.
+        let _watcher: RecommendedWatcher = watcher;
+        // The ID each file was last loaded as, for files whose name isn't `<id>.aix`.
+        let mut loaded_ids: HashMap<PathBuf, SourceId> = HashMap::new();
+
+        while let Some(event) = rx.recv().await {
+            let mut pending = HashSet::new();
+            collect_aix_paths(&event, &mut pending);
+
+            while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                collect_aix_paths(&event, &mut pending);
+            }
+
+            for path in pending {
+                apply_change(&source_manager, &mut loaded_ids, &path).await;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn collect_aix_paths(event: &Event, pending: &mut HashSet<PathBuf>) {
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return;
+    }
+
+    pending.extend(
+        event
+            .paths
+            .iter()
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("aix"))
+            })
+            .cloned(),
+    );
+}
+
+async fn apply_change(
+    source_manager: &Arc<Mutex<SourceManager>>,
+    loaded_ids: &mut HashMap<PathBuf, SourceId>,
+    path: &Path,
+) {
+    // Events only say what happened to the file at some point; whether it's still there is
+    // what decides between reloading and unloading.
+    if path.exists() {
+        let mut manager = source_manager.lock().await;
+        // Installing a source loads it right away, so the events its write caused have
+        // nothing left to do.
+        if manager.is_up_to_date(path) {
+            return;
+        }
+
+        match usecases::reload_source(&mut manager, source_manager, path) {
+            Ok(id) => {
+                info!(
+                    "source watcher: {} changed, reloaded source {}",
+                    path.display(),
+                    id.value()
+                );
+                loaded_ids.insert(path.to_owned(), id);
+            }
+            Err(err) => warn!(
+                "source watcher: {} changed, but couldn't be loaded: {err:?}",
+                path.display()
+            ),
+        }
+
+        return;
+    }
+
+    let Some(id) = loaded_ids.remove(path).or_else(|| {
+        path.file_stem()
+            .map(|stem| SourceId::new(stem.to_string_lossy().into_owned()))
+    }) else {
+        return;
+    };
+
+    if usecases::unload_source(&mut *source_manager.lock().await, &id) {
+        info!(
+            "source watcher: {} deleted, unloaded source {}",
+            path.display(),
+            id.value()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{AccessKind, CreateKind, ModifyKind, RemoveKind};
+
+    use super::*;
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event {
+        paths
+            .iter()
+            .fold(Event::new(kind), |event, path| event.add_path(path.into()))
+    }
+
+    #[test]
+    fn collects_only_aix_files() {
+        let mut pending = HashSet::new();
+        collect_aix_paths(
+            &event(
+                EventKind::Create(CreateKind::File),
+                &["/sources/a.aix", "/sources/a.source", "/sources/B.AIX"],
+            ),
+            &mut pending,
+        );
+
+        assert_eq!(
+            pending,
+            HashSet::from([
+                PathBuf::from("/sources/a.aix"),
+                PathBuf::from("/sources/B.AIX")
+            ])
+        );
+    }
+
+    #[test]
+    fn ignores_events_that_dont_change_files() {
+        let mut pending = HashSet::new();
+        collect_aix_paths(
+            &event(EventKind::Access(AccessKind::Any), &["/sources/a.aix"]),
+            &mut pending,
+        );
+
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn merges_repeated_changes_to_a_file() {
+        let mut pending = HashSet::new();
+        for kind in [
+            EventKind::Create(CreateKind::File),
+            EventKind::Modify(ModifyKind::Any),
+            EventKind::Remove(RemoveKind::File),
+        ] {
+            collect_aix_paths(&event(kind, &["/sources/a.aix"]), &mut pending);
+        }
+
+        assert_eq!(pending, HashSet::from([PathBuf::from("/sources/a.aix")]));
+    }
+}
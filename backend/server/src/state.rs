@@ -9,6 +9,7 @@ use tokio_util::sync::CancellationToken;
 
 use axum::extract::FromRef;
 
+use crate::download_queue::DownloadQueue;
 use crate::job::State as JobState;
 
 /// A shared log of startup warnings/errors to be displayed to the user via Lua.
@@ -49,6 +50,7 @@ pub struct State {
     pub job_state: JobState,
     pub cancel_token_store: Arc<Mutex<HashMap<usize, CancellationToken>>>,
     pub download_semaphore: Arc<Semaphore>,
+    pub download_queue: DownloadQueue,
     pub startup_log: StartupLog,
 }
 
@@ -2,18 +2,209 @@ use std::fs;
 use std::path::Path;
 
 use axum::extract::State as StateExtractor;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
 use axum::routing::get;
 use axum::{Json, Router};
 use log::warn;
 use serde::Serialize;
-use tokio::time::{sleep, Duration};
+use shared::source::processed_image_cache::{self, ProcessedImageCacheMetrics};
+use shared::usecases;
+use tokio::time::{sleep, timeout, Duration};
 
 use crate::state::State;
 
 pub fn routes() -> Router<State> {
     Router::<State>::new()
+        .route("/health-check", get(health_check))
         .route("/system/stats", get(system_stats))
         .route("/system/startup-log", get(startup_log))
+        .route("/metrics", get(metrics))
+}
+
+#[derive(Serialize)]
+struct MetricsResponse {
+    processed_image_cache: ProcessedImageCacheMetrics,
+}
+
+async fn metrics() -> Json<MetricsResponse> {
+    Json(MetricsResponse {
+        processed_image_cache: processed_image_cache::metrics(),
+    })
+}
+
+/// How long a health check waits on the database or a lock before giving up. The checks run
+/// concurrently, so this also bounds the whole request.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_millis(500);
+/// Below this much free space, downloads are likely to start failing.
+const LOW_DISK_SPACE_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+enum HealthStatus {
+    Ok,
+    Degraded,
+    Error,
+}
+
+#[derive(Serialize)]
+struct DatabaseCheck {
+    status: HealthStatus,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DiskCheck {
+    status: HealthStatus,
+    free_bytes: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SourcesCheck {
+    status: HealthStatus,
+    /// `None` when the sources couldn't be counted because they're being reloaded.
+    loaded: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ArimaCheck {
+    status: HealthStatus,
+    /// `None` when the settings couldn't be read because they're being changed.
+    enabled: Option<bool>,
+    running: bool,
+}
+
+#[derive(Serialize)]
+struct HealthChecks {
+    db: DatabaseCheck,
+    disk: DiskCheck,
+    sources: SourcesCheck,
+    arima: ArimaCheck,
+}
+
+#[derive(Serialize)]
+struct HealthCheckResponse {
+    status: HealthStatus,
+    checks: HealthChecks,
+}
+
+/// Reports whether the server and what it depends on are usable. Only an unreachable
+/// database is an error (503), everything else merely degrades the server, so the frontend,
+/// which polls this while the server starts, still gets a 200 when a lock is busy.
+async fn health_check(
+    StateExtractor(State {
+        database,
+        chapter_storage,
+        source_manager,
+        settings,
+        ..
+    }): StateExtractor<State>,
+) -> Response {
+    let (db, disk, sources, arima) = tokio::join!(
+        async {
+            match timeout(HEALTH_CHECK_TIMEOUT, database.ping()).await {
+                Ok(Ok(())) => DatabaseCheck {
+                    status: HealthStatus::Ok,
+                    error: None,
+                },
+                Ok(Err(e)) => DatabaseCheck {
+                    status: HealthStatus::Error,
+                    error: Some(format!("{e:#}")),
+                },
+                Err(_) => DatabaseCheck {
+                    status: HealthStatus::Error,
+                    error: Some("the database didn't answer in time".to_owned()),
+                },
+            }
+        },
+        async {
+            let Ok(cs) = timeout(HEALTH_CHECK_TIMEOUT, chapter_storage.lock()).await else {
+                return DiskCheck {
+                    status: HealthStatus::Degraded,
+                    free_bytes: None,
+                    error: Some("the chapter storage is busy".to_owned()),
+                };
+            };
+            let downloads_path = cs.downloads_path().clone();
+            drop(cs);
+
+            match read_filesystem_info(&downloads_path) {
+                Ok(info) => DiskCheck {
+                    status: if info.free_bytes < LOW_DISK_SPACE_BYTES {
+                        HealthStatus::Degraded
+                    } else {
+                        HealthStatus::Ok
+                    },
+                    free_bytes: Some(info.free_bytes),
+                    error: None,
+                },
+                Err(e) => DiskCheck {
+                    status: HealthStatus::Degraded,
+                    free_bytes: None,
+                    error: Some(e),
+                },
+            }
+        },
+        async {
+            match timeout(HEALTH_CHECK_TIMEOUT, source_manager.lock()).await {
+                Ok(source_manager) => SourcesCheck {
+                    status: HealthStatus::Ok,
+                    loaded: Some(source_manager.sources_by_id.len()),
+                },
+                Err(_) => SourcesCheck {
+                    status: HealthStatus::Degraded,
+                    loaded: None,
+                },
+            }
+        },
+        async {
+            let running = usecases::is_manga_cron_running();
+            match timeout(HEALTH_CHECK_TIMEOUT, settings.lock()).await {
+                Ok(settings) => {
+                    let enabled = settings.enabled_cron_check_mangas_update;
+                    ArimaCheck {
+                        status: if enabled && !running {
+                            HealthStatus::Degraded
+                        } else {
+                            HealthStatus::Ok
+                        },
+                        enabled: Some(enabled),
+                        running,
+                    }
+                }
+                Err(_) => ArimaCheck {
+                    status: HealthStatus::Degraded,
+                    enabled: None,
+                    running,
+                },
+            }
+        },
+    );
+
+    let status = [db.status, disk.status, sources.status, arima.status]
+        .into_iter()
+        .max()
+        .unwrap_or(HealthStatus::Ok);
+    let status_code = if status == HealthStatus::Error {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    (
+        status_code,
+        Json(HealthCheckResponse {
+            status,
+            checks: HealthChecks {
+                db,
+                disk,
+                sources,
+                arima,
+            },
+        }),
+    )
+        .into_response()
 }
 
 #[derive(Serialize)]
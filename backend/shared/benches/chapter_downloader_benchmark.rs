@@ -7,8 +7,12 @@ use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use futures::executor;
 use pprof::criterion::{Output, PProfProfiler};
 use shared::{
-    cbz_metadata::ComicInfo, chapter_downloader::download_chapter_pages_as_cbz, model::ChapterId,
-    settings::Settings, source::Source, source_manager::SourceManager,
+    cbz_metadata::ComicInfo,
+    chapter_downloader::download_chapter_pages_as_cbz,
+    model::ChapterId,
+    settings::Settings,
+    source::{model::Chapter, Source},
+    source_manager::SourceManager,
 };
 use std::{collections::HashMap, env, io, path::PathBuf, sync::Arc};
 use tokio::sync::Mutex;
@@ -33,9 +37,13 @@ pub fn chapter_downloader_benchmark(c: &mut Criterion) {
     let source = Source::from_aix_file(source_path.as_ref(), &manager, &arc_manager).unwrap();
     let pages = executor::block_on(source.get_page_list(
         CancellationToken::new(),
-        manga_id,
-        chapter_id,
-        Some(0.0),
+        Chapter {
+            source_id: source.manifest().info.id,
+            id: chapter_id,
+            manga_id,
+            chapter_num: Some(0.0),
+            ..Default::default()
+        },
     ))
     .unwrap();
 
@@ -52,11 +60,14 @@ pub fn chapter_downloader_benchmark(c: &mut Criterion) {
                 4,
                 false,
                 None,
+                None,
+                &env::temp_dir(),
                 &ChapterId::from_strings(
                     "benchmark_source".into(),
                     "benchmark_manga".into(),
                     "benchmark_chapter".into(),
                 ),
+                None,
             )
             .await;
         })
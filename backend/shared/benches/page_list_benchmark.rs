@@ -0,0 +1,52 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use futures::executor;
+use pprof::criterion::{Output, PProfProfiler};
+use shared::{
+    settings::Settings,
+    source::{model::Chapter, Source},
+    source_manager::SourceManager,
+};
+use std::{collections::HashMap, env, path::PathBuf, sync::Arc};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+// Mostly measures the HTML parsing and selecting a source does, so point it at a long
+// chapter (a couple hundred pages) from a source that scrapes its page list.
+pub fn page_list_benchmark(c: &mut Criterion) {
+    let source_path: PathBuf = env::var("BENCHMARK_SOURCE_PATH").unwrap().into();
+    let manga_id = env::var("BENCHMARK_MANGA_ID").unwrap();
+    let chapter_id = env::var("BENCHMARK_CHAPTER_ID").unwrap();
+    let settings = Settings::default();
+
+    let arc_manager = Arc::new(Mutex::new(SourceManager::new(
+        PathBuf::new(),
+        HashMap::new(),
+        settings,
+    )));
+    let manager = arc_manager.blocking_lock();
+    let source = Source::from_aix_file(source_path.as_ref(), &manager, &arc_manager).unwrap();
+    let chapter = Chapter {
+        source_id: source.manifest().info.id,
+        id: chapter_id,
+        manga_id,
+        chapter_num: Some(0.0),
+        ..Default::default()
+    };
+
+    c.bench_function("get_page_list", |b| {
+        b.iter(|| {
+            let pages =
+                executor::block_on(source.get_page_list(CancellationToken::new(), chapter.clone()))
+                    .unwrap();
+
+            black_box(pages);
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10).with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+    targets = page_list_benchmark
+}
+criterion_main!(benches);
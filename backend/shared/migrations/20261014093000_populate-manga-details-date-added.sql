@@ -0,0 +1,30 @@
+-- Keep manga_details.date_added populated for mangas in the library, so the
+-- library can be sorted by the date a manga was added. Sources may still
+-- provide their own value, in which case it is left untouched.
+UPDATE manga_details
+SET date_added = strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')
+WHERE date_added IS NULL
+AND EXISTS (
+    SELECT 1 FROM manga_library ml
+    WHERE ml.source_id = manga_details.source_id AND ml.manga_id = manga_details.id
+);
+
+CREATE TRIGGER manga_library_populate_date_added
+AFTER INSERT ON manga_library
+BEGIN
+    UPDATE manga_details
+    SET date_added = strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')
+    WHERE source_id = NEW.source_id AND id = NEW.manga_id AND date_added IS NULL;
+END;
+
+CREATE TRIGGER manga_details_populate_date_added
+AFTER INSERT ON manga_details
+WHEN NEW.date_added IS NULL AND EXISTS (
+    SELECT 1 FROM manga_library ml
+    WHERE ml.source_id = NEW.source_id AND ml.manga_id = NEW.id
+)
+BEGIN
+    UPDATE manga_details
+    SET date_added = strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')
+    WHERE source_id = NEW.source_id AND id = NEW.id;
+END;
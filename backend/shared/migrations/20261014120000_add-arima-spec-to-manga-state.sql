@@ -0,0 +1,3 @@
+-- Add per-manga ARIMA spec override to manga_state, stored as JSON.
+-- NULL means "use the default spec".
+ALTER TABLE manga_state ADD COLUMN arima_spec TEXT NULL;
@@ -0,0 +1,11 @@
+-- Progress of chapter downloads that are still running, rows are deleted once they end.
+CREATE TABLE download_progress (
+    source_id TEXT NOT NULL,
+    manga_id TEXT NOT NULL,
+    chapter_id TEXT NOT NULL,
+    pages_total INTEGER NOT NULL,
+    pages_done INTEGER NOT NULL,
+    started_at INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    PRIMARY KEY (source_id, manga_id, chapter_id)
+) STRICT;
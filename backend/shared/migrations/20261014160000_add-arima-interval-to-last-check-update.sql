@@ -0,0 +1,4 @@
+-- Bounds of the prediction interval of next_ts_arima. NULL when the model could not
+-- produce one, in which case next_ts_arima alone is used.
+ALTER TABLE last_check_update ADD COLUMN next_ts_arima_lower INTEGER NULL;
+ALTER TABLE last_check_update ADD COLUMN next_ts_arima_upper INTEGER NULL;
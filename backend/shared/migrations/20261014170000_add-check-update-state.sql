@@ -0,0 +1,7 @@
+-- Where the last run of `check_mangas_update` stopped, so an interrupted run can be resumed.
+-- There is at most one row, deleted once a run gets through the whole library.
+CREATE TABLE check_update_state (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    last_check_batch_source_id TEXT NOT NULL,
+    last_check_batch_cursor TEXT NOT NULL
+) STRICT;
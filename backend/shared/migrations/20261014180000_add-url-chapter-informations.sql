@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE chapter_informations
+ADD COLUMN url TEXT;
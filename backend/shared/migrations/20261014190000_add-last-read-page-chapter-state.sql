@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE chapter_state
+ADD COLUMN last_read_page INTEGER;
@@ -0,0 +1,10 @@
+-- Add migration script here
+ALTER TABLE manga_state
+ADD COLUMN preferred_scanlators TEXT NULL;
+
+UPDATE manga_state
+SET preferred_scanlators = json_array(preferred_scanlator)
+WHERE preferred_scanlator IS NOT NULL;
+
+ALTER TABLE manga_state
+DROP COLUMN preferred_scanlator;
@@ -0,0 +1,45 @@
+-- One row per tag of every cached manga, kept in sync with manga_details.tags
+-- (a JSON array of strings), so mangas can be looked up by tag through an index.
+CREATE TABLE manga_tags (
+    source_id TEXT NOT NULL,
+    manga_id  TEXT NOT NULL,
+    tag       TEXT NOT NULL,
+
+    PRIMARY KEY (source_id, manga_id, tag)
+);
+
+CREATE INDEX manga_tags_tag ON manga_tags (tag);
+
+INSERT OR IGNORE INTO manga_tags (source_id, manga_id, tag)
+SELECT md.source_id, md.id, tags.value
+FROM manga_details md, json_each(md.tags) tags
+WHERE md.tags IS NOT NULL AND json_valid(md.tags) AND tags.type = 'text';
+
+CREATE TRIGGER manga_details_insert_tags
+AFTER INSERT ON manga_details
+WHEN NEW.tags IS NOT NULL AND json_valid(NEW.tags)
+BEGIN
+    INSERT OR IGNORE INTO manga_tags (source_id, manga_id, tag)
+    SELECT NEW.source_id, NEW.id, tags.value
+    FROM json_each(NEW.tags) tags
+    WHERE tags.type = 'text';
+END;
+
+CREATE TRIGGER manga_details_update_tags
+AFTER UPDATE OF tags ON manga_details
+BEGIN
+    DELETE FROM manga_tags
+    WHERE source_id = NEW.source_id AND manga_id = NEW.id;
+
+    INSERT OR IGNORE INTO manga_tags (source_id, manga_id, tag)
+    SELECT NEW.source_id, NEW.id, tags.value
+    FROM json_each(CASE WHEN json_valid(NEW.tags) THEN NEW.tags ELSE '[]' END) tags
+    WHERE tags.type = 'text';
+END;
+
+CREATE TRIGGER manga_details_delete_tags
+AFTER DELETE ON manga_details
+BEGIN
+    DELETE FROM manga_tags
+    WHERE source_id = OLD.source_id AND manga_id = OLD.id;
+END;
@@ -0,0 +1,6 @@
+-- The version of each source whose manga ID migration last ran, so that it only runs again once
+-- the source is updated.
+CREATE TABLE source_key_migrations (
+    source_id TEXT NOT NULL PRIMARY KEY,
+    version INTEGER NOT NULL
+) STRICT;
@@ -20,12 +20,13 @@
 //
 
 use anyhow::bail;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::cmp::Ordering;
 
 use crate::model::ChapterInformation;
 
 /// ARIMA specification
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct ArimaSpec {
     pub p: usize,
     pub d: usize,
@@ -35,6 +36,12 @@ pub struct ArimaSpec {
     pub rolling_window: Option<usize>,
     /// Minimum points required to attempt fit (after differencing).
     pub min_points: usize,
+    /// Gaps further than this many interquartile ranges outside the quartiles of all gaps are
+    /// dropped before fitting, see [`remove_outliers`]. `f64::INFINITY` keeps every gap.
+    pub iqr_multiplier: f64,
+    /// Seasonal period in data points, for releases that repeat every few chapters. Below 2,
+    /// [`fit_model_from_chapters`] fits a non-seasonal model.
+    pub seasonal_period: usize,
 }
 
 impl Default for ArimaSpec {
@@ -45,12 +52,145 @@ impl Default for ArimaSpec {
             q: 1,
             rolling_window: Some(30), // <-- default changed to 30 as requested
             min_points: 6,
+            iqr_multiplier: 3.0,
+            seasonal_period: 0,
+        }
+    }
+}
+
+/// Per-manga changes to the default [`ArimaSpec`]. Fields left as `None` keep their
+/// default value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArimaSpecOverride {
+    pub p: Option<usize>,
+    pub d: Option<usize>,
+    pub q: Option<usize>,
+    /// `Some(None)`, sent as an explicit `null`, fits the whole history instead of the
+    /// default window.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rolling_window: Option<Option<usize>>,
+    pub min_points: Option<usize>,
+    pub iqr_multiplier: Option<f64>,
+    pub seasonal_period: Option<usize>,
+}
+
+impl ArimaSpecOverride {
+    /// Checks the override describes a model the lightweight fitter can handle.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let spec = ArimaSpec::from(*self);
+
+        if spec.p > 2 || spec.d > 2 || spec.q > 2 {
+            bail!(
+                "p, d and q must be at most 2 (got p={}, d={}, q={})",
+                spec.p,
+                spec.d,
+                spec.q
+            );
+        }
+        if spec.min_points < 3 {
+            bail!("min_points must be at least 3 (got {})", spec.min_points);
+        }
+        if spec
+            .rolling_window
+            .is_some_and(|window| window < spec.min_points)
+        {
+            bail!("rolling_window must not be smaller than min_points");
+        }
+        if spec.seasonal_period == 1 {
+            bail!("seasonal_period must be 0 or at least 2");
+        }
+        if spec.iqr_multiplier.is_nan() || spec.iqr_multiplier <= 0.0 {
+            bail!(
+                "iqr_multiplier must be positive (got {})",
+                spec.iqr_multiplier
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl From<ArimaSpecOverride> for ArimaSpec {
+    fn from(value: ArimaSpecOverride) -> Self {
+        let default = ArimaSpec::default();
+
+        Self {
+            p: value.p.unwrap_or(default.p),
+            d: value.d.unwrap_or(default.d),
+            q: value.q.unwrap_or(default.q),
+            rolling_window: value.rolling_window.unwrap_or(default.rolling_window),
+            min_points: value.min_points.unwrap_or(default.min_points),
+            iqr_multiplier: value.iqr_multiplier.unwrap_or(default.iqr_multiplier),
+            seasonal_period: value.seasonal_period.unwrap_or(default.seasonal_period),
+        }
+    }
+}
+
+/// Deserializes a field that is present, even as `null`, into `Some`, so that a missing
+/// field (`None`, through `#[serde(default)]`) can be told apart from a `null` one.
+fn deserialize_present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Seasonal ARIMA(p,d,q)(P,D,Q)m specification, for release schedules that repeat every
+/// `m` chapters (e.g. a weekly series with one extra chapter each month).
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SarimaSpec {
+    pub p: usize,
+    pub d: usize,
+    pub q: usize,
+    /// Seasonal AR order.
+    pub P: usize,
+    /// Seasonal differencing order.
+    pub D: usize,
+    /// Seasonal MA order.
+    pub Q: usize,
+    /// Seasonal period in data points; below 2 the model is not seasonal.
+    pub m: usize,
+    pub rolling_window: Option<usize>,
+    pub min_points: usize,
+}
+
+impl SarimaSpec {
+    /// The same specification with every seasonal term dropped.
+    fn without_seasonal(self) -> Self {
+        Self {
+            P: 0,
+            D: 0,
+            Q: 0,
+            m: 0,
+            ..self
+        }
+    }
+}
+
+impl From<ArimaSpec> for SarimaSpec {
+    fn from(value: ArimaSpec) -> Self {
+        Self {
+            p: value.p,
+            d: value.d,
+            q: value.q,
+            P: 0,
+            D: 0,
+            Q: 0,
+            m: 0,
+            rolling_window: value.rolling_window,
+            min_points: value.min_points,
         }
     }
 }
 
 /// Fitted model returned by fit function
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ArimaModel {
     pub spec: ArimaSpec,
     pub ar: Vec<f64>, // length p
@@ -77,21 +217,43 @@ impl ArimaModel {
         let ts_f64: Vec<f64> = ts.iter().map(|x| *x as f64).collect();
         let raw_forecast = self.forecast_1(&ts_f64)?;
 
-        let mut predicted_ts = raw_forecast.round() as i64;
+        Some(push_past_last_check(
+            raw_forecast,
+            self.mu,
+            &ts_f64,
+            last_check_no_update,
+        ))
+    }
 
-        if let Some(last_check) = last_check_no_update {
-            if predicted_ts <= last_check {
-                let interval = if self.mu > 3600.0 {
-                    self.mu
-                } else {
-                    calculate_recent_interval(&ts_f64, 5).unwrap_or(86400.0)
-                };
+    /// Prediction interval of the next timestamp, from the same chapters as
+    /// `forecast_1_from_chapters`. Returns unix timestamp seconds rounded to i64.
+    pub fn forecast_interval_from_chapters(
+        &self,
+        chapters: &[ChapterInformation],
+        alpha: f64,
+    ) -> Option<(i64, i64)> {
+        let ts = timestamps_from_chapters(chapters, self.spec.rolling_window)?;
+        if ts.is_empty() {
+            return None;
+        }
 
-                predicted_ts = last_check + interval.round() as i64;
-            }
+        let ts_f64: Vec<f64> = ts.iter().map(|x| *x as f64).collect();
+        let (lower, upper) = self.forecast_interval(&ts_f64, alpha)?;
+
+        Some((lower.round() as i64, upper.round() as i64))
+    }
+
+    /// `1 - alpha` prediction interval around `forecast_1`, using a normal approximation of
+    /// the one-step-ahead error, whose variance is the residual variance `sigma2`.
+    pub fn forecast_interval(&self, original_ts: &[f64], alpha: f64) -> Option<(f64, f64)> {
+        if alpha.is_nan() || alpha <= 0.0 || alpha >= 1.0 {
+            return None;
         }
 
-        Some(predicted_ts)
+        let forecast = self.forecast_1(original_ts)?;
+        let half_width = qnorm(1.0 - alpha / 2.0) * self.sigma2.max(0.0).sqrt();
+
+        Some((forecast - half_width, forecast + half_width))
     }
 
     /// Forecast next timestamp from a numeric timestamp slice (ascending)
@@ -154,6 +316,145 @@ impl ArimaModel {
         Some(next_ts)
     }
 }
+
+/// Fitted seasonal model returned by [`fit_sarima_from_chapters`].
+#[derive(Debug, Clone, Serialize)]
+pub struct SarimaModel {
+    pub spec: SarimaSpec,
+    pub ar: Vec<f64>,  // length p
+    pub ma: Vec<f64>,  // length q
+    pub sar: Vec<f64>, // length P
+    pub sma: Vec<f64>, // length Q
+    pub mu: f64,       // drift in differenced series
+    pub sigma2: f64,   // residual variance
+    pub aic: f64,
+    pub bic: f64,
+}
+
+impl SarimaModel {
+    /// Seasonal counterpart of [`ArimaModel::forecast_1_from_chapters`].
+    pub fn forecast_1_from_chapters(
+        &self,
+        chapters: &[ChapterInformation],
+        last_check_no_update: Option<i64>,
+    ) -> Option<i64> {
+        let ts = timestamps_from_chapters(chapters, self.spec.rolling_window)?;
+        let ts_f64: Vec<f64> = ts.iter().map(|x| *x as f64).collect();
+        let raw_forecast = self.forecast_1(&ts_f64)?;
+
+        Some(push_past_last_check(
+            raw_forecast,
+            self.mu,
+            &ts_f64,
+            last_check_no_update,
+        ))
+    }
+
+    /// Seasonal counterpart of [`ArimaModel::forecast_interval_from_chapters`].
+    pub fn forecast_interval_from_chapters(
+        &self,
+        chapters: &[ChapterInformation],
+        alpha: f64,
+    ) -> Option<(i64, i64)> {
+        let ts = timestamps_from_chapters(chapters, self.spec.rolling_window)?;
+        let ts_f64: Vec<f64> = ts.iter().map(|x| *x as f64).collect();
+        let (lower, upper) = self.forecast_interval(&ts_f64, alpha)?;
+
+        Some((lower.round() as i64, upper.round() as i64))
+    }
+
+    /// `1 - alpha` prediction interval around `forecast_1`, see
+    /// [`ArimaModel::forecast_interval`].
+    pub fn forecast_interval(&self, original_ts: &[f64], alpha: f64) -> Option<(f64, f64)> {
+        if alpha.is_nan() || alpha <= 0.0 || alpha >= 1.0 {
+            return None;
+        }
+
+        let forecast = self.forecast_1(original_ts)?;
+        let half_width = qnorm(1.0 - alpha / 2.0) * self.sigma2.max(0.0).sqrt();
+
+        Some((forecast - half_width, forecast + half_width))
+    }
+
+    /// Forecast next timestamp from a numeric timestamp slice (ascending)
+    pub fn forecast_1(&self, original_ts: &[f64]) -> Option<f64> {
+        let SarimaSpec {
+            d,
+            D: seasonal_d,
+            m,
+            ..
+        } = self.spec;
+
+        let diff = seasonal_difference_series(original_ts, d, seasonal_d, m);
+        if diff.is_empty() {
+            return None;
+        }
+
+        let (resids, _fitted) = compute_seasonal_residuals_and_fitted(
+            &diff, &self.ar, &self.ma, &self.sar, &self.sma, m, self.mu,
+        );
+
+        // Value `lag` steps back from the next one, or zero before the start of the series.
+        let lagged =
+            |series: &[f64], lag: usize| series.len().checked_sub(lag).map_or(0.0, |i| series[i]);
+
+        let mut next_diff = self.mu;
+        for (i, phi) in self.ar.iter().enumerate() {
+            next_diff += phi * lagged(&diff, i + 1);
+        }
+        for (i, theta) in self.ma.iter().enumerate() {
+            next_diff += theta * lagged(&resids, i + 1);
+        }
+        for (j, phi) in self.sar.iter().enumerate() {
+            next_diff += phi * lagged(&diff, m * (j + 1));
+        }
+        for (j, theta) in self.sma.iter().enumerate() {
+            next_diff += theta * lagged(&resids, m * (j + 1));
+        }
+
+        // Undo the differencing: applying (1 - B)^d (1 - B^m)^D to the series extended with
+        // the next timestamp must give `next_diff`.
+        let poly = differencing_polynomial(d, seasonal_d, m);
+        if original_ts.len() < poly.len() - 1 {
+            return None;
+        }
+
+        let n = original_ts.len();
+        let next_ts = poly
+            .iter()
+            .enumerate()
+            .skip(1)
+            .fold(next_diff, |acc, (k, c)| acc - c * original_ts[n - k]);
+
+        Some(next_ts)
+    }
+}
+
+/// Rounds a forecast, moving it one interval past `last_check_no_update` if a check at
+/// that time already found no new chapter.
+fn push_past_last_check(
+    raw_forecast: f64,
+    mu: f64,
+    ts: &[f64],
+    last_check_no_update: Option<i64>,
+) -> i64 {
+    let mut predicted_ts = raw_forecast.round() as i64;
+
+    if let Some(last_check) = last_check_no_update {
+        if predicted_ts <= last_check {
+            let interval = if mu > 3600.0 {
+                mu
+            } else {
+                calculate_recent_interval(ts, 5).unwrap_or(86400.0)
+            };
+
+            predicted_ts = last_check + interval.round() as i64;
+        }
+    }
+
+    predicted_ts
+}
+
 fn calculate_recent_interval(ts: &[f64], n_last: usize) -> Option<f64> {
     if ts.len() < 2 {
         return None;
@@ -218,6 +519,14 @@ pub fn fit_arima_from_chapters(
 
     // convert to f64
     let ts_f64: Vec<f64> = ts.iter().map(|x| *x as f64).collect();
+    let ts_f64 = remove_outliers(&ts_f64, spec.iqr_multiplier);
+    if ts_f64.len() < spec.min_points {
+        bail!(
+            "Warning: Not enough points ({} < {}) left to fit ARIMA after removing outliers",
+            ts_f64.len(),
+            spec.min_points
+        )
+    }
 
     // @2 differenced series
     let diff = difference_series(&ts_f64, spec.d);
@@ -275,7 +584,12 @@ pub fn fit_arima_from_chapters(
 
     // Optimize: AR step default, MA step slightly larger
     let optimized = coordinate_descent_optimize_weighted(
-        &diff, spec.p, spec.q, params, &lower, &upper, 350,  // max_iter
+        |params| sse_loss_weighted(&diff, spec.p, spec.q, params),
+        spec.p,
+        params,
+        &lower,
+        &upper,
+        350,  // max_iter
         1e-7, // tol
         1.5,  // MA step multiplier (increase movement for MA)
     );
@@ -290,25 +604,7 @@ pub fn fit_arima_from_chapters(
 
     // residuals
     let (resids, _fitted_vals) = compute_residuals_and_fitted(&diff, &ar_opt, &ma_opt, mu_opt);
-    let n = resids.len() as f64;
-    let sigma2 = if n > 0.0 {
-        resids.iter().map(|r| r * r).sum::<f64>() / n
-    } else {
-        0.0
-    };
-
-    let sse = resids.iter().map(|r| r * r).sum::<f64>();
-    let k = optimized.len() as f64;
-    let aic = if n > 0.0 {
-        n * (sse / n).ln() + 2.0 * k
-    } else {
-        f64::INFINITY
-    };
-    let bic = if n > 0.0 {
-        n * (sse / n).ln() + k * n.ln()
-    } else {
-        f64::INFINITY
-    };
+    let (sigma2, aic, bic) = fit_statistics(&resids, optimized.len());
 
     Ok(ArimaModel {
         spec,
@@ -321,12 +617,166 @@ pub fn fit_arima_from_chapters(
     })
 }
 
-/// Weighted coordinate descent for ARIMA (recent residuals have more weight)
+/// Fit a seasonal ARIMA model from ChapterInformation Vec.
+///
+/// The seasonal terms are only used when `spec.m >= 2` and the series is long enough to
+/// cover them; otherwise the non-seasonal part of `spec` is fitted on its own.
+pub fn fit_sarima_from_chapters(
+    chapters: &[ChapterInformation],
+    spec: SarimaSpec,
+) -> Result<SarimaModel, anyhow::Error> {
+    let ts = timestamps_from_chapters(chapters, spec.rolling_window).unwrap_or([].into());
+
+    if ts.len() < spec.min_points {
+        bail!(
+            "Warning: Not enough points ({} < {}) to fit SARIMA",
+            ts.len(),
+            spec.min_points
+        )
+    }
+
+    let ts_f64: Vec<f64> = ts.iter().map(|x| *x as f64).collect();
+
+    // The seasonal lags need a few points of differenced data past them to be estimated.
+    let seasonal_lags = spec.m * spec.P.max(spec.Q);
+    let spec = if spec.m >= 2
+        && seasonal_difference_series(&ts_f64, spec.d, spec.D, spec.m).len() >= seasonal_lags + 3
+    {
+        spec
+    } else {
+        spec.without_seasonal()
+    };
+
+    let diff = seasonal_difference_series(&ts_f64, spec.d, spec.D, spec.m);
+    if diff.len() < 3 {
+        bail!(
+            "Differenced series too short ({} points) to fit SARIMA",
+            diff.len()
+        )
+    }
+
+    // param vector layout: [ar..., ma..., sar..., sma..., mu]
+    let mut params = vec![0.0; spec.p + spec.q + spec.P + spec.Q + 1];
+    if spec.p >= 1 {
+        params[0] = simple_autoregressive_initial(&diff);
+    }
+    params[spec.p + spec.q + spec.P + spec.Q] = mean(&diff) * 0.01;
+
+    let lower = vec![-0.99_f64; params.len()];
+    let upper = vec![0.99_f64; params.len()];
+
+    let optimized = coordinate_descent_optimize_weighted(
+        |params| sse_loss_seasonal_weighted(&diff, &spec, params),
+        spec.p,
+        params,
+        &lower,
+        &upper,
+        350,  // max_iter
+        1e-7, // tol
+        1.5,  // MA step multiplier (increase movement for MA)
+    );
+
+    let (ar, ma, sar, sma, mu) = split_seasonal_params(&spec, &optimized);
+    let (resids, _fitted_vals) =
+        compute_seasonal_residuals_and_fitted(&diff, ar, ma, sar, sma, spec.m, mu);
+    let (sigma2, aic, bic) = fit_statistics(&resids, optimized.len());
+
+    Ok(SarimaModel {
+        spec,
+        ar: ar.to_vec(),
+        ma: ma.to_vec(),
+        sar: sar.to_vec(),
+        sma: sma.to_vec(),
+        mu,
+        sigma2,
+        aic,
+        bic,
+    })
+}
+
+/// A model fitted by [`fit_model_from_chapters`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum UpdateModel {
+    Arima(ArimaModel),
+    Sarima(SarimaModel),
+}
+
+impl UpdateModel {
+    pub fn forecast_1_from_chapters(
+        &self,
+        chapters: &[ChapterInformation],
+        last_check_no_update: Option<i64>,
+    ) -> Option<i64> {
+        match self {
+            Self::Arima(model) => model.forecast_1_from_chapters(chapters, last_check_no_update),
+            Self::Sarima(model) => model.forecast_1_from_chapters(chapters, last_check_no_update),
+        }
+    }
+
+    pub fn forecast_interval_from_chapters(
+        &self,
+        chapters: &[ChapterInformation],
+        alpha: f64,
+    ) -> Option<(i64, i64)> {
+        match self {
+            Self::Arima(model) => model.forecast_interval_from_chapters(chapters, alpha),
+            Self::Sarima(model) => model.forecast_interval_from_chapters(chapters, alpha),
+        }
+    }
+
+    pub fn sigma2(&self) -> f64 {
+        match self {
+            Self::Arima(model) => model.sigma2,
+            Self::Sarima(model) => model.sigma2,
+        }
+    }
+}
+
+/// Fits the update-time model of a manga: a SARIMA(p,d,q)(1,1,0)m one when `spec` has a
+/// seasonal period, see [`fit_sarima_from_chapters`], and a plain ARIMA one otherwise.
+pub fn fit_model_from_chapters(
+    chapters: &[ChapterInformation],
+    spec: ArimaSpec,
+) -> Result<UpdateModel, anyhow::Error> {
+    if spec.seasonal_period < 2 {
+        return fit_arima_from_chapters(chapters, spec).map(UpdateModel::Arima);
+    }
+
+    let spec = SarimaSpec {
+        P: 1,
+        D: 1,
+        Q: 0,
+        m: spec.seasonal_period,
+        ..spec.into()
+    };
+
+    fit_sarima_from_chapters(chapters, spec).map(UpdateModel::Sarima)
+}
+
+/// Residual variance, AIC and BIC of a fit with `k` parameters.
+fn fit_statistics(resids: &[f64], k: usize) -> (f64, f64, f64) {
+    let n = resids.len() as f64;
+    let sse = resids.iter().map(|r| r * r).sum::<f64>();
+    let k = k as f64;
+
+    if n > 0.0 {
+        (
+            sse / n,
+            n * (sse / n).ln() + 2.0 * k,
+            n * (sse / n).ln() + k * n.ln(),
+        )
+    } else {
+        (0.0, f64::INFINITY, f64::INFINITY)
+    }
+}
+
+/// Weighted coordinate descent for ARIMA (recent residuals have more weight).
+/// Parameters from `ar_len` on (the MA terms and after) move with larger steps.
 #[allow(clippy::too_many_arguments)]
 fn coordinate_descent_optimize_weighted(
-    diff: &[f64],
-    p: usize,
-    q: usize,
+    loss: impl Fn(&[f64]) -> f64,
+    ar_len: usize,
     init_params: Vec<f64>,
     lower: &[f64],
     upper: &[f64],
@@ -336,14 +786,14 @@ fn coordinate_descent_optimize_weighted(
 ) -> Vec<f64> {
     let mut params = init_params;
     let nparam = params.len();
-    let mut last_loss = sse_loss_weighted(diff, p, q, &params);
+    let mut last_loss = loss(&params);
 
     for _it in 0..max_iter {
         let mut improved = false;
 
         for j in 0..nparam {
             let cur = params[j];
-            let step = if j >= p {
+            let step = if j >= ar_len {
                 0.05_f64 * ma_step_multiplier
             } else {
                 0.05_f64
@@ -358,9 +808,9 @@ fn coordinate_descent_optimize_weighted(
             p_left[j] = left;
             p_right[j] = right;
 
-            let f_left = sse_loss_weighted(diff, p, q, &p_left);
+            let f_left = loss(&p_left);
             let f_cur = last_loss;
-            let f_right = sse_loss_weighted(diff, p, q, &p_right);
+            let f_right = loss(&p_right);
 
             // parabolic interpolation
             let x1 = left;
@@ -386,7 +836,7 @@ fn coordinate_descent_optimize_weighted(
 
             let mut p_cand = params.clone();
             p_cand[j] = x_vertex;
-            let f_cand = sse_loss_weighted(diff, p, q, &p_cand);
+            let f_cand = loss(&p_cand);
 
             // choose best
             let mut best_x = cur;
@@ -429,6 +879,32 @@ fn sse_loss_weighted(diff: &[f64], p: usize, q: usize, params: &[f64]) -> f64 {
     let mu = params[idx];
 
     let (resids, _fitted) = compute_residuals_and_fitted(diff, ar, ma, mu);
+
+    weighted_sse(&resids)
+}
+
+/// SSE loss of a seasonal model, with the parameter layout `[ar..., ma..., sar..., sma..., mu]`.
+fn sse_loss_seasonal_weighted(diff: &[f64], spec: &SarimaSpec, params: &[f64]) -> f64 {
+    let (ar, ma, sar, sma, mu) = split_seasonal_params(spec, params);
+    let (resids, _fitted) =
+        compute_seasonal_residuals_and_fitted(diff, ar, ma, sar, sma, spec.m, mu);
+
+    weighted_sse(&resids)
+}
+
+fn split_seasonal_params<'a>(
+    spec: &SarimaSpec,
+    params: &'a [f64],
+) -> (&'a [f64], &'a [f64], &'a [f64], &'a [f64], f64) {
+    let (ar, rest) = params.split_at(spec.p);
+    let (ma, rest) = rest.split_at(spec.q);
+    let (sar, rest) = rest.split_at(spec.P);
+    let (sma, rest) = rest.split_at(spec.Q);
+
+    (ar, ma, sar, sma, rest[0])
+}
+
+fn weighted_sse(resids: &[f64]) -> f64 {
     let n = resids.len() as f64;
 
     resids
@@ -448,7 +924,7 @@ fn sse_loss_weighted(diff: &[f64], p: usize, q: usize, params: &[f64]) -> f64 {
 /// - sorts by chapter_number (if available) ascending; if chapter_number missing uses last_updated ascending
 /// - deduplicates identical timestamps (keeps last occurrence)
 /// - optionally applies rolling window (keeps last N)
-fn timestamps_from_chapters(
+pub fn timestamps_from_chapters(
     chapters: &[ChapterInformation],
     rolling_window: Option<usize>,
 ) -> Option<Vec<i64>> {
@@ -510,6 +986,112 @@ fn timestamps_from_chapters(
     Some(ts_vec)
 }
 
+/// Quantile function of the standard normal distribution, using Acklam's rational
+/// approximation (relative error below 1.15e-9). `p` must be in (0, 1).
+fn qnorm(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Smallest IQR [`remove_outliers`] uses, as a fraction of the median gap.
+const MIN_RELATIVE_IQR: f64 = 0.5;
+
+/// Drops the outlying gaps of an ascending timestamp series, like a single 400-day hiatus in
+/// an otherwise daily series. A gap is an outlier when it lies more than `iqr_multiplier`
+/// interquartile ranges below the first or above the third quartile of all gaps (Tukey's
+/// fences).
+///
+/// The points before a dropped gap are shifted forward to close it, so the series keeps its
+/// last timestamp, which forecasts are anchored on. Series with fewer than 4 gaps are
+/// returned as is, as their quartiles say little.
+///
+/// Regular series have most of their gaps equal, which makes the IQR zero and every slightly
+/// late chapter an outlier, so the IQR is never taken smaller than `MIN_RELATIVE_IQR` times
+/// the median gap. When even that is zero, nothing is dropped.
+pub fn remove_outliers(ts: &[f64], iqr_multiplier: f64) -> Vec<f64> {
+    let gaps: Vec<f64> = ts.windows(2).map(|w| w[1] - w[0]).collect();
+    if gaps.len() < 4 {
+        return ts.to_vec();
+    }
+
+    let mut sorted = gaps.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let q1 = quantile(&sorted, 0.25);
+    let q3 = quantile(&sorted, 0.75);
+    let iqr = (q3 - q1).max(quantile(&sorted, 0.5) * MIN_RELATIVE_IQR);
+    if iqr <= 0.0 {
+        return ts.to_vec();
+    }
+    let (low, high) = (q1 - iqr_multiplier * iqr, q3 + iqr_multiplier * iqr);
+
+    let Some(&last) = ts.last() else {
+        return Vec::new();
+    };
+    let mut kept = vec![last];
+    let mut current = last;
+    for &gap in gaps.iter().rev() {
+        if gap >= low && gap <= high {
+            current -= gap;
+            kept.push(current);
+        }
+    }
+    kept.reverse();
+
+    kept
+}
+
+/// Linearly interpolated quantile `q` of an ascending, non-empty slice.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
 /// compute simple mean
 fn mean(xs: &[f64]) -> f64 {
     if xs.is_empty() {
@@ -523,32 +1105,57 @@ fn mean(xs: &[f64]) -> f64 {
 /// This function is intentionally defensive because ARIMA(d) on short
 /// or highly-collapsed timestamps may quickly reduce to length < 1.
 fn difference_series(series: &[f64], d: usize) -> Vec<f64> {
-    // If no differencing, simply clone
-    if d == 0 {
-        return series.to_vec();
+    difference_series_at_lag(series, d, 1)
+}
+
+/// Regular differencing `d` times, then seasonal differencing at lag `m` `seasonal_d`
+/// times. Returns an empty Vec if the data collapses, like [`difference_series`].
+fn seasonal_difference_series(series: &[f64], d: usize, seasonal_d: usize, m: usize) -> Vec<f64> {
+    let diff = difference_series(series, d);
+    if seasonal_d == 0 {
+        return diff;
     }
 
+    difference_series_at_lag(&diff, seasonal_d, m)
+}
+
+fn difference_series_at_lag(series: &[f64], times: usize, lag: usize) -> Vec<f64> {
     // Start with original
     let mut out = series.to_vec();
 
-    for _ in 0..d {
-        if out.len() < 2 {
+    for _ in 0..times {
+        if out.len() <= lag {
             // Not enough points to difference further
             return Vec::new();
         }
 
-        // Produce first-difference series
-        let mut diff = Vec::with_capacity(out.len() - 1);
+        // Compute Δx_t = x_t - x_(t-lag)
+        out = (lag..out.len()).map(|i| out[i] - out[i - lag]).collect();
+    }
+
+    out
+}
 
-        // Compute Δx_t = x_t - x_(t-1)
-        for i in 1..out.len() {
-            diff.push(out[i] - out[i - 1]);
+/// Coefficients of `(1 - B)^d (1 - B^m)^D`, lowest power of the backshift operator first.
+fn differencing_polynomial(d: usize, seasonal_d: usize, m: usize) -> Vec<f64> {
+    let multiply = |poly: &[f64], lag: usize| {
+        let mut out = vec![0.0; poly.len() + lag];
+        for (i, c) in poly.iter().enumerate() {
+            out[i] += c;
+            out[i + lag] -= c;
         }
+        out
+    };
 
-        out = diff;
+    let mut poly = vec![1.0];
+    for _ in 0..d {
+        poly = multiply(&poly, 1);
+    }
+    for _ in 0..seasonal_d {
+        poly = multiply(&poly, m);
     }
 
-    out
+    poly
 }
 
 /// Estimate initial AR(1) phi by lag-1 autocorrelation
@@ -580,6 +1187,21 @@ fn compute_residuals_and_fitted(
     ar: &[f64],
     ma: &[f64],
     mu: f64,
+) -> (Vec<f64>, Vec<f64>) {
+    compute_seasonal_residuals_and_fitted(diff, ar, ma, &[], &[], 0, mu)
+}
+
+/// Like [`compute_residuals_and_fitted`], with seasonal AR (`sar`) and MA (`sma`) terms at
+/// lags `m`, `2m`, ... The seasonal terms are added to the regular ones, leaving out the
+/// multiplicative cross terms, which keeps the fit cheap.
+fn compute_seasonal_residuals_and_fitted(
+    diff: &[f64],
+    ar: &[f64],
+    ma: &[f64],
+    sar: &[f64],
+    sma: &[f64],
+    m: usize,
+    mu: f64,
 ) -> (Vec<f64>, Vec<f64>) {
     let n = diff.len();
     let p = ar.len();
@@ -601,6 +1223,18 @@ fn compute_residuals_and_fitted(
                 ma_part += ma[i] * resids[t - 1 - i];
             }
         }
+        for (j, phi) in sar.iter().enumerate() {
+            let lag = m * (j + 1);
+            if t >= lag {
+                ma_part += phi * diff[t - lag];
+            }
+        }
+        for (j, theta) in sma.iter().enumerate() {
+            let lag = m * (j + 1);
+            if t >= lag {
+                ma_part += theta * resids[t - lag];
+            }
+        }
         let pred = mu + ar_part + ma_part;
         fitted[t] = pred;
         resids[t] = diff[t] - pred;
@@ -651,6 +1285,8 @@ mod tests {
             q: 1,
             rolling_window: Some(30),
             min_points: 6,
+            iqr_multiplier: 3.0,
+            seasonal_period: 0,
         };
         let maybe_model = fit_arima_from_chapters(&chapters, spec);
         assert!(maybe_model.is_ok());
@@ -663,6 +1299,38 @@ mod tests {
         assert!(diff_abs < 3 * 86400);
     }
 
+    #[test]
+    fn test_remove_outliers_closes_long_hiatus() {
+        let day = 86400.0;
+        let mut ts = vec![0.0, 400.0 * day];
+        ts.extend((1..=8).map(|i| 400.0 * day + i as f64 * day));
+
+        let cleaned = remove_outliers(&ts, 3.0);
+
+        assert_eq!(cleaned.len(), ts.len() - 1);
+        assert_eq!(cleaned.last(), ts.last());
+        assert!(cleaned.windows(2).all(|w| (w[1] - w[0] - day).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_remove_outliers_keeps_regular_series() {
+        let ts: Vec<f64> = (0..10).map(|i| i as f64 * 86400.0).collect();
+        assert_eq!(remove_outliers(&ts, 3.0), ts);
+        assert_eq!(remove_outliers(&ts[..4], 3.0), &ts[..4]);
+    }
+
+    #[test]
+    fn test_remove_outliers_keeps_slightly_late_chapters_of_a_regular_series() {
+        let day = 86400.0;
+        let mut ts: Vec<f64> = (0..10).map(|i| i as f64 * day).collect();
+        ts.push(9.0 * day + 1.2 * day);
+
+        assert_eq!(remove_outliers(&ts, 3.0), ts);
+
+        let same_time = vec![0.0; 6];
+        assert_eq!(remove_outliers(&same_time, 3.0), same_time);
+    }
+
     #[test]
     fn test_preprocess_sorting() {
         let chapters = vec![
@@ -733,4 +1401,166 @@ mod tests {
         assert!(ts.windows(2).all(|w| w[0] <= w[1]));
         assert!(ts.len() >= 3);
     }
+
+    #[test]
+    fn test_spec_override_can_reset_the_rolling_window() {
+        let keep: ArimaSpecOverride = serde_json::from_str("{}").unwrap();
+        let reset: ArimaSpecOverride =
+            serde_json::from_str(r#"{ "rolling_window": null }"#).unwrap();
+        let set: ArimaSpecOverride = serde_json::from_str(r#"{ "rolling_window": 12 }"#).unwrap();
+
+        assert_eq!(
+            ArimaSpec::from(keep).rolling_window,
+            ArimaSpec::default().rolling_window
+        );
+        assert_eq!(ArimaSpec::from(reset).rolling_window, None);
+        assert_eq!(ArimaSpec::from(set).rolling_window, Some(12));
+
+        for spec in [keep, reset, set] {
+            let stored = serde_json::to_string(&spec).unwrap();
+            assert_eq!(
+                serde_json::from_str::<ArimaSpecOverride>(&stored).unwrap(),
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn test_qnorm() {
+        assert!((qnorm(0.975) - 1.959_964).abs() < 1e-6);
+        assert!((qnorm(0.5)).abs() < 1e-12);
+        assert!((qnorm(0.01) + 2.326_348).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_forecast_interval() {
+        let model = ArimaModel {
+            spec: ArimaSpec {
+                p: 0,
+                d: 1,
+                q: 0,
+                rolling_window: None,
+                min_points: 3,
+                iqr_multiplier: 3.0,
+                seasonal_period: 0,
+            },
+            ar: vec![],
+            ma: vec![],
+            mu: 100.0,
+            sigma2: 25.0,
+            aic: 0.0,
+            bic: 0.0,
+        };
+        let ts = [0.0, 100.0, 200.0];
+
+        let (lower, upper) = model.forecast_interval(&ts, 0.05).unwrap();
+        let forecast = model.forecast_1(&ts).unwrap();
+        assert!((forecast - lower - 5.0 * 1.959_964).abs() < 1e-4);
+        assert!((upper - forecast - 5.0 * 1.959_964).abs() < 1e-4);
+
+        assert!(model.forecast_interval(&ts, 0.0).is_none());
+        assert!(model.forecast_interval(&ts, 1.0).is_none());
+    }
+
+    #[test]
+    fn test_seasonal_difference_and_polynomial() {
+        let ts = [0.0, 1.0, 3.0, 6.0, 10.0, 15.0];
+        assert_eq!(
+            seasonal_difference_series(&ts, 1, 1, 2),
+            vec![2.0, 2.0, 2.0]
+        );
+        assert_eq!(differencing_polynomial(1, 1, 2), vec![1.0, -1.0, -1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_fit_sarima_follows_repeating_gaps() {
+        // two daily releases followed by a five day break, repeated
+        let gaps = [86400i64, 86400, 5 * 86400];
+        let mut timestamps = vec![1_600_000_000i64];
+        for i in 0..29 {
+            timestamps.push(timestamps[i] + gaps[i % 3]);
+        }
+
+        let nums = (1..=30).map(|i| Some(i as f32)).collect();
+        let ts_opts = timestamps.iter().map(|&t| Some(t)).collect();
+        let chapters = build_chapters(nums, ts_opts);
+
+        let spec = SarimaSpec {
+            p: 1,
+            d: 1,
+            q: 1,
+            P: 1,
+            D: 1,
+            Q: 0,
+            m: 3,
+            rolling_window: None,
+            min_points: 6,
+        };
+        let model = fit_sarima_from_chapters(&chapters, spec).unwrap();
+        assert_eq!(model.sar.len(), 1);
+
+        let pred = model.forecast_1_from_chapters(&chapters, None).unwrap();
+        let expected_next = timestamps.last().unwrap() + gaps[29 % 3];
+        assert!((pred - expected_next).abs() < 3600);
+    }
+
+    #[test]
+    fn test_fit_model_is_seasonal_only_with_a_seasonal_period() {
+        let gaps = [86400i64, 86400, 5 * 86400];
+        let mut timestamps = vec![1_600_000_000i64];
+        for i in 0..29 {
+            timestamps.push(timestamps[i] + gaps[i % 3]);
+        }
+        let nums = (1..=30).map(|i| Some(i as f32)).collect();
+        let ts_opts = timestamps.iter().map(|&t| Some(t)).collect();
+        let chapters = build_chapters(nums, ts_opts);
+
+        let spec = ArimaSpec {
+            rolling_window: None,
+            ..Default::default()
+        };
+        assert!(matches!(
+            fit_model_from_chapters(&chapters, spec).unwrap(),
+            UpdateModel::Arima(_)
+        ));
+
+        let spec = ArimaSpec {
+            seasonal_period: 3,
+            ..spec
+        };
+        let model = fit_model_from_chapters(&chapters, spec).unwrap();
+        assert!(matches!(&model, UpdateModel::Sarima(model) if model.spec.m == 3));
+
+        let pred = model.forecast_1_from_chapters(&chapters, None).unwrap();
+        let expected_next = timestamps.last().unwrap() + gaps[29 % 3];
+        assert!((pred - expected_next).abs() < 3600);
+    }
+
+    #[test]
+    fn test_fit_sarima_falls_back_without_enough_seasons() {
+        let timestamps: Vec<i64> = (0..10).map(|i| 1_600_000_000 + i * 86400).collect();
+        let nums = (1..=10).map(|i| Some(i as f32)).collect();
+        let ts_opts = timestamps.iter().map(|&t| Some(t)).collect();
+        let chapters = build_chapters(nums, ts_opts);
+
+        let spec = SarimaSpec {
+            P: 1,
+            D: 1,
+            Q: 1,
+            m: 7,
+            ..ArimaSpec {
+                p: 1,
+                d: 1,
+                q: 1,
+                rolling_window: None,
+                min_points: 6,
+                iqr_multiplier: 3.0,
+                seasonal_period: 0,
+            }
+            .into()
+        };
+        let model = fit_sarima_from_chapters(&chapters, spec).unwrap();
+        assert_eq!(model.spec.m, 0);
+        assert!(model.sar.is_empty() && model.sma.is_empty());
+    }
 }
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use quick_xml::SeError;
 use quick_xml::{de::from_str, se::to_string};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
@@ -9,7 +9,7 @@ use zip::ZipArchive;
 
 use crate::model::{ChapterInformation, MangaInformation};
 use crate::settings::ChapterTitleFormat;
-use crate::source::model::Page;
+use crate::source::model::MangaViewer;
 
 // ComicInfo.xml schema implementation based on ComicRack standard
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -73,10 +73,8 @@ pub struct ComicInfo {
     pub language_iso: String,
     #[serde(default)]
     pub format: String,
-    #[serde(default = "default_unknown")]
-    pub black_and_white: String,
-    #[serde(default = "default_unknown")]
-    pub manga: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manga: Option<MangaYesNo>,
     #[serde(default)]
     pub characters: String,
     #[serde(default)]
@@ -99,12 +97,58 @@ pub struct ComicInfo {
     pub main_character_or_team: String,
     #[serde(default)]
     pub review: String,
-    #[serde(default)]
-    pub gtin: String,
+    #[serde(rename = "GTIN", default, skip_serializing_if = "Option::is_none")]
+    pub gtin: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pages: Option<ComicInfoPages>,
 }
 
+/// Whether a book is a manga, and which way it reads: the `Manga` element of ComicInfo 2.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MangaYesNo {
+    Unknown,
+    No,
+    Yes,
+    YesAndRightToLeft,
+}
+
+impl MangaYesNo {
+    /// `None` when the source didn't say which way the manga reads.
+    pub fn from_viewer(viewer: &MangaViewer) -> Option<Self> {
+        match viewer {
+            MangaViewer::DefaultViewer => None,
+            MangaViewer::Rtl => Some(Self::YesAndRightToLeft),
+            MangaViewer::Ltr | MangaViewer::Vertical | MangaViewer::Scroll => Some(Self::Yes),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Unknown => "Unknown",
+            Self::No => "No",
+            Self::Yes => "Yes",
+            Self::YesAndRightToLeft => "YesAndRightToLeft",
+        }
+    }
+}
+
+impl Serialize for MangaYesNo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for MangaYesNo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match String::deserialize(deserializer)?.as_str() {
+            "No" => Self::No,
+            "Yes" => Self::Yes,
+            "YesAndRightToLeft" => Self::YesAndRightToLeft,
+            _ => Self::Unknown,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct ComicInfoPages {
     #[serde(rename = "Page")]
@@ -155,7 +199,6 @@ impl ComicInfo {
     pub fn from_source_metadata(
         manga_info: MangaInformation,
         chapter_info: ChapterInformation,
-        page_list: &[Page],
         chapter_title_format: ChapterTitleFormat,
     ) -> Self {
         let series_title = manga_info.title.unwrap_or_default();
@@ -221,12 +264,11 @@ impl ComicInfo {
             comic_info.scan_information = format!("Scanlated by {}", scanlator);
         }
 
-        // Set manga flag for Japanese comics
-        comic_info.manga = "YesAndRightToLeft".to_string();
-
-        // Set page count
-        comic_info.page_count = page_list.len() as i32;
+        // Readers like Kavita and Komga pick the reading direction from this
+        comic_info.manga = MangaYesNo::from_viewer(&manga_info.viewer);
 
+        // `page_count` is only known once the pages are downloaded, see
+        // `download_chapter_pages_as_cbz`.
         comic_info
     }
 
@@ -269,3 +311,44 @@ pub fn non_empty_string(s: String) -> Option<String> {
         Some(s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_reading_direction_and_gtin() {
+        let comic_info = ComicInfo {
+            manga: MangaYesNo::from_viewer(&MangaViewer::Rtl),
+            gtin: Some("9781234567897".to_owned()),
+            ..Default::default()
+        };
+        let xml = comic_info.to_xml().unwrap();
+
+        assert!(xml.contains("<Manga>YesAndRightToLeft</Manga>"));
+        assert!(xml.contains("<GTIN>9781234567897</GTIN>"));
+    }
+
+    #[test]
+    fn omits_unknown_fields() {
+        let xml = ComicInfo {
+            manga: MangaYesNo::from_viewer(&MangaViewer::DefaultViewer),
+            ..Default::default()
+        }
+        .to_xml()
+        .unwrap();
+
+        assert!(!xml.contains("<Manga>"));
+        assert!(!xml.contains("<GTIN>"));
+    }
+
+    #[test]
+    fn reads_unknown_yes_no_values() {
+        let comic_info: ComicInfo =
+            from_str("<ComicInfo><Manga>Unknown</Manga></ComicInfo>").unwrap();
+        assert_eq!(comic_info.manga, Some(MangaYesNo::Unknown));
+
+        let comic_info: ComicInfo = from_str("<ComicInfo><Manga>Yes</Manga></ComicInfo>").unwrap();
+        assert_eq!(comic_info.manga, Some(MangaYesNo::Yes));
+    }
+}
@@ -2,30 +2,34 @@ use dom_query::Document;
 use futures::{stream, StreamExt};
 use reqwest::redirect::Policy;
 use std::{
-    io::{Cursor, Seek, Write},
+    collections::{BTreeMap, BTreeSet},
+    io::{BufWriter, Cursor, Seek, Write},
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
 use tempfile::NamedTempFile;
-use tokio_util::sync::CancellationToken;
+use tokio_util::{bytes::Bytes, sync::CancellationToken};
 
 use anyhow::{anyhow, Context};
-use tokio::sync::mpsc;
+use log::warn;
+use tokio::sync::{mpsc, watch};
 use zip::{CompressionMethod, ZipWriter};
 
-use epub_builder::{EpubBuilder, EpubContent, ReferenceType, ZipLibrary};
+use epub_builder::{EpubBuilder, EpubContent, EpubVersion, ReferenceType, ZipLibrary};
 
 use crate::{
     cbz_metadata::ComicInfo,
     chapter_storage::ChapterStorage,
-    model::{ChapterId, ChapterInformation, MangaInformation},
+    database::Database,
+    model::{ChapterId, ChapterInformation, DownloadProgress, DownloadStatus, MangaInformation},
     settings::ChapterTitleFormat,
     source::{model::Page, Source},
-    unscrable_image::{unscrable_image, Block},
+    unscrable_image::{parse_blocks, unscrable_image},
     util::{
-        create_xhtml, download_all_images, generate_error_image, get_image_src, into_html,
-        prepare_cover, request_with_forced_referer_from_request,
+        content_disposition_filename, create_xhtml, download_all_images, generate_error_image,
+        get_base_url, get_image_src, image_mime_for_extension, into_html, prepare_cover,
+        request_with_forced_referer_from_request, Preload,
     },
 };
 
@@ -40,6 +44,7 @@ pub struct DownloadError {
 pub async fn ensure_chapter_is_in_storage(
     token: &CancellationToken,
     chapter_storage: &ChapterStorage,
+    database: &Database,
     source: &Source,
     manga: &MangaInformation,
     chapter: &ChapterInformation,
@@ -61,12 +66,7 @@ pub async fn ensure_chapter_is_in_storage(
 
     // FIXME like downloaderror is a really bad name??
     let pages = source
-        .get_page_list(
-            token.clone(),
-            chapter.id.manga_id().value().clone(),
-            chapter.id.value().clone(),
-            chapter.chapter_number,
-        )
+        .get_page_list(token.clone(), chapter.clone().into())
         .await
         .with_context(|| "Failed to get page list")
         .map_err(Error::DownloadError)?;
@@ -77,7 +77,7 @@ pub async fn ensure_chapter_is_in_storage(
             chapter.id.value()
         )));
     }
-    let is_novel = pages.first().and_then(|p| p.text.as_ref()).is_some();
+    let is_novel = is_novel_page_list(&pages, source.is_next_sdk());
 
     // FIXME this logic should be contained entirely within the storage..? maybe we could return something that's writable
     // and then commit it into the storage (or maybe a implicit commit on drop, but i dont think it works well as there
@@ -85,19 +85,14 @@ pub async fn ensure_chapter_is_in_storage(
     let output_path: PathBuf =
         chapter_storage.get_path_to_store_chapter(&chapter.id, is_novel, use_ram);
 
-    let metadata = ComicInfo::from_source_metadata(
-        manga.clone(),
-        chapter.clone(),
-        &pages,
-        chapter_title_format,
-    );
+    let metadata =
+        ComicInfo::from_source_metadata(manga.clone(), chapter.clone(), chapter_title_format);
 
     // Write chapter pages to a temporary file, so that if things go wrong
     // we do not have a borked .cbz file in the chapter storage.
-    let parent = output_path
-        .parent()
-        .ok_or_else(|| Error::Other(anyhow::anyhow!("Output path has no parent")))?;
-    let temporary_file = NamedTempFile::new_in(parent).map_err(|e| Error::Other(e.into()))?;
+    let temporary_file = chapter_storage
+        .create_temporary_file(&output_path)
+        .map_err(Error::Other)?;
 
     // in mode write to RAM before download to free memory
     if use_ram && current_chapter_id.is_some() {
@@ -105,6 +100,7 @@ pub async fn ensure_chapter_is_in_storage(
             .evict_tmpfs_older_than_current(current_chapter_id.unwrap(), is_novel)
             .await;
     }
+    let mut progress_sender = None;
     let errors = if is_novel {
         // is novel
         let temp_path = temporary_file.path().to_path_buf();
@@ -125,6 +121,17 @@ pub async fn ensure_chapter_is_in_storage(
 
         Vec::<DownloadError>::new()
     } else {
+        // The tracker keeps the `download_progress` row up to date, and deletes it as soon as
+        // `pages_done` is dropped: when this function returns or its future is cancelled.
+        let (pages_done, pages_done_rx) = watch::channel(0);
+        tokio::spawn(track_download_progress(
+            database.clone(),
+            chapter.id.clone(),
+            pages.len(),
+            pages_done_rx,
+        ));
+        progress_sender = Some(pages_done.clone());
+
         download_chapter_pages_as_cbz(
             token,
             &temporary_file,
@@ -134,7 +141,10 @@ pub async fn ensure_chapter_is_in_storage(
             concurrent_requests_pages,
             optimize_image,
             on_progress.clone(),
+            Some(pages_done),
+            parent,
             &chapter.id,
+            chapter.lang.clone(),
         )
         .await
         .map_err(|err| {
@@ -157,6 +167,7 @@ pub async fn ensure_chapter_is_in_storage(
             )
         })
         .map_err(Error::Other)?;
+    drop(progress_sender);
 
     Ok((output_path, errors))
 }
@@ -169,6 +180,42 @@ pub enum Error {
     Other(#[from] anyhow::Error),
 }
 
+async fn track_download_progress(
+    database: Database,
+    chapter_id: ChapterId,
+    pages_total: usize,
+    mut pages_done: watch::Receiver<usize>,
+) {
+    let mut progress = DownloadProgress {
+        pages_total,
+        pages_done: 0,
+        started_at: chrono::Utc::now().timestamp(),
+        status: DownloadStatus::Downloading,
+    };
+
+    loop {
+        if let Err(err) = database
+            .upsert_download_progress(&chapter_id, &progress)
+            .await
+        {
+            warn!("failed to store download progress: {err:?}");
+        }
+
+        if pages_done.changed().await.is_err() {
+            break;
+        }
+
+        progress.pages_done = *pages_done.borrow_and_update();
+        if progress.pages_done >= pages_total {
+            progress.status = DownloadStatus::Persisting;
+        }
+    }
+
+    if let Err(err) = database.delete_download_progress(&chapter_id).await {
+        warn!("failed to delete download progress: {err:?}");
+    }
+}
+
 fn zip_comment(chapter_id: &ChapterId) -> String {
     serde_json::json!({
         "source_id": chapter_id.source_id().value(),
@@ -178,37 +225,53 @@ fn zip_comment(chapter_id: &ChapterId) -> String {
     .to_string()
 }
 
+/// Whether `pages` make up a novel chapter, stored as an EPUB instead of a CBZ. Pages of the
+/// new SDK only have a text when they're novel content, but legacy pages may have one for
+/// other purposes, so theirs must also be `novel` or HTML.
+fn is_novel_page_list(pages: &[Page], next_sdk: bool) -> bool {
+    pages
+        .first()
+        .and_then(|page| page.text.as_deref())
+        .is_some_and(|text| {
+            let text = text.trim_start();
+
+            next_sdk || text == "novel" || text.starts_with('<')
+        })
+}
+
 pub async fn download_chapter_pages_as_cbz<W>(
     cancel_token: &CancellationToken,
     output: W,
-    metadata: ComicInfo,
+    mut metadata: ComicInfo,
     source: &Source,
     pages: Vec<Page>,
     concurrent_requests_pages: usize,
     optimize_image: bool,
     on_progress: Option<Arc<dyn Fn(f32, f32) + Send + Sync>>,
+    pages_done: Option<watch::Sender<usize>>,
+    page_buffer_dir: &Path,
     chapter_id: &ChapterId,
+    lang: Option<String>,
 ) -> anyhow::Result<Vec<DownloadError>, anyhow::Error>
 where
     W: Write + Seek,
 {
-    let total = pages.len() as f32;
+    let page_total = pages.len();
+    let total = page_total as f32;
     let mut processed = 0f32;
+    // Indices of the pages that haven't been downloaded yet.
+    let mut pending_indices: BTreeSet<usize> = pages.iter().map(|page| page.index).collect();
 
     let mut writer = ZipWriter::new(output);
     let file_options: zip::write::FileOptions<'_, ()> =
         zip::write::FileOptions::default().compression_method(CompressionMethod::Stored);
 
-    // Add ComicInfo.xml to the CBZ file
-    writer.start_file("ComicInfo.xml", file_options)?;
-    writer.write_all(metadata.to_xml()?.as_bytes())?;
-
     let client = crate::tls::client_builder()
         .timeout(Duration::from_secs(30))
         .redirect(Policy::none())
         .build()?;
 
-    let (tx, mut rx) = mpsc::channel::<(usize, String, Vec<u8>, Option<DownloadError>)>(
+    let (tx, mut rx) = mpsc::channel::<(usize, String, PageData, Option<DownloadError>)>(
         concurrent_requests_pages * 2,
     );
 
@@ -217,6 +280,8 @@ where
         let client = client.clone();
         let source = source.clone();
         let cancel_token = cancel_token.clone();
+        let page_buffer_dir = page_buffer_dir.to_path_buf();
+        let lang = lang.clone();
 
         async move {
             stream::iter(pages)
@@ -225,6 +290,8 @@ where
                     let client = client.clone();
                     let source = source.clone();
                     let cancel_token = cancel_token.clone();
+                    let page_buffer_dir = page_buffer_dir.clone();
+                    let lang = lang.clone();
 
                     async move {
                         let page_index = page.index;
@@ -241,10 +308,8 @@ where
                             // FIXME we should left pad this number with zeroes up to the maximum
                             // amount of pages needed, but for now we pad 4 digits
                             // stop reading the bible if this ever becomes an issue
-                            let filename = format!("{:0>4}.{}", page.index, extension);
+                            let mut filename = format!("{:0>4}.{}", page.index, extension);
 
-                            // TODO we could stream the data from the client into the file
-                            // would save a bit of memory but i dont think its a big deal
                             let request = source
                                 .get_image_request(image_url, page.ctx.clone())
                                 .await
@@ -254,14 +319,22 @@ where
                                 })?;
                             let req_url = request.url().clone();
                             let req_headers = request.headers().clone();
-                            let response =
-                                request_with_forced_referer_from_request(&client, request, 10)
-                                    .await
-                                    .inspect_err(|err| {
-                                        eprintln!("Request error: {err}");
-                                    })?;
-
-                            let (final_bytes, error_info) = {
+                            let preload = Preload {
+                                source: source.clone(),
+                                token: cancel_token.clone(),
+                            };
+                            let response = request_with_forced_referer_from_request(
+                                &client,
+                                request,
+                                10,
+                                Some(&preload),
+                            )
+                            .await
+                            .inspect_err(|err| {
+                                eprintln!("Request error: {err}");
+                            })?;
+
+                            let (page_data, error_info) = {
                                 if !response.status().is_success() {
                                     let err = DownloadError {
                                         page_index: page.index,
@@ -273,106 +346,162 @@ where
                                     eprintln!("{:?}", err);
 
                                     (
-                                        generate_error_image(
+                                        PageData::Bytes(generate_error_image(
                                             &response.status().as_u16().to_string(),
                                             response
                                                 .status()
                                                 .canonical_reason()
                                                 .unwrap_or("Unknown Error"),
+                                            lang.as_deref(),
                                             500,
                                             667,
-                                        )?,
+                                        )?),
                                         Some(err),
                                     )
                                 } else {
                                     let status = response.status();
                                     let headers = response.headers().clone();
+                                    let req_url_string = req_url.to_string();
+
+                                    // CDNs with opaque URLs may give the real file name in the
+                                    // headers instead. The page index is kept so that pages stay
+                                    // in order.
+                                    if let Some(extension) = headers
+                                        .get(reqwest::header::CONTENT_DISPOSITION)
+                                        .and_then(|value| value.to_str().ok())
+                                        .and_then(content_disposition_filename)
+                                        .and_then(|name| {
+                                            name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase())
+                                        })
+                                        .filter(|ext| image_mime_for_extension(ext).is_some())
+                                    {
+                                        filename = format!("{:0>4}.{}", page.index, extension);
+                                    }
+
+                                    let body = buffer_response_body(response, &page_buffer_dir).await?;
+
+                                    // Only pages that get re-encoded are loaded into memory, the
+                                    // others are copied from the temporary file into the archive.
+                                    if !source.1.features.process_page_image
+                                        && !optimize_image
+                                        && page.base64.is_none()
+                                    {
+                                        (PageData::File(body), None)
+                                    } else {
+                                        let response_bytes = Bytes::from(std::fs::read(body.path())?);
+                                        drop(body);
+
+                                        let response_bytes = if source.1.features.process_page_image {
+                                            let (image_data, output_format) = source
+                                                .process_page_image(
+                                                    cancel_token.clone(),
+                                                    (req_url, req_headers),
+                                                    (status, headers),
+                                                    response_bytes,
+                                                    page.ctx.clone(),
+                                                )
+                                                .await
+                                                .map_err(|err| {
+                                                    eprintln!("Error = {err}");
+                                                    err
+                                                })?;
+
+                                            // The source re-encodes the image, so the extension
+                                            // from the URL no longer applies.
+                                            if let Some(output_format) = output_format {
+                                                filename = format!(
+                                                    "{:0>4}.{}",
+                                                    page.index,
+                                                    output_format.extension()
+                                                );
+                                            }
 
-                                    let response_bytes = response.bytes().await?;
-
-                                    let response_bytes = if source.1.process_page_image {
-                                        source
-                                            .process_page_image(
-                                                cancel_token.clone(),
-                                                (req_url, req_headers),
-                                                (status, headers),
-                                                response_bytes,
-                                                page.ctx.clone(),
-                                            )
-                                            .await
-                                            .map_err(|err| {
-                                                eprintln!("Error = {err}");
-                                                err
-                                            })?
-                                    } else if optimize_image {
-                                        tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<u8>> {
-                                            let data = response_bytes.to_vec();
-                                            if let Some(image) =
-                                                crate::source::decode_image::decode_image_fast(&data)
-                                            {
-                                                if let Ok(image) = image.map_err(|err| {
-                                                    eprintln!("failed to load image with faster {err}")
-                                                }) {
-                                                    match crate::source::decode_image::decode_argb_to_rgb(
-                                                        image.width, image.height, &image.data,
-                                                    ) {
-                                                        Ok(rgb_pixels) => {
-                                                            let mut comp = mozjpeg::Compress::new(
-                                                                mozjpeg::ColorSpace::JCS_RGB,
-                                                            );
-                                                            comp.set_size(
-                                                                image.width as usize,
-                                                                image.height as usize,
-                                                            );
-                                                            comp.set_fastest_defaults();
-
-                                                            let mut comp = comp.start_compress(Vec::new())?;
-                                                            comp.write_scanlines(&rgb_pixels)?;
-
-                                                            Ok(comp.finish()?)
-                                                        }
-                                                        Err(e) => {
-                                                            eprintln!("failed to convert ARGB to RGB: {e}");
-                                                            Ok(data)
+                                            image_data
+                                        } else if optimize_image {
+                                            tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<u8>> {
+                                                let data = response_bytes.to_vec();
+                                                if let Some(image) =
+                                                    crate::source::decode_image::decode_image_fast(&data)
+                                                {
+                                                    if let Ok(image) = image.map_err(|err| {
+                                                        eprintln!("failed to load image with faster {err}")
+                                                    }) {
+                                                        match crate::source::decode_image::decode_argb_to_rgb(
+                                                            image.width, image.height, &image.data,
+                                                        ) {
+                                                            Ok(rgb_pixels) => {
+                                                                let mut comp = mozjpeg::Compress::new(
+                                                                    mozjpeg::ColorSpace::JCS_RGB,
+                                                                );
+                                                                comp.set_size(
+                                                                    image.width as usize,
+                                                                    image.height as usize,
+                                                                );
+                                                                comp.set_fastest_defaults();
+
+                                                                let mut comp = comp.start_compress(Vec::new())?;
+                                                                comp.write_scanlines(&rgb_pixels)?;
+
+                                                                Ok(comp.finish()?)
+                                                            }
+                                                            Err(e) => {
+                                                                eprintln!("failed to convert ARGB to RGB: {e}");
+                                                                Ok(data)
+                                                            }
                                                         }
+                                                    } else {
+                                                        Ok(data)
                                                     }
                                                 } else {
                                                     Ok(data)
                                                 }
-                                            } else {
-                                                Ok(data)
-                                            }
-                                        })
-                                        .await??
-                                    } else {
-                                        response_bytes.to_vec()
-                                    };
-
-                                    let final_image = if let Some(blocks_json) = page.base64.as_ref() {
-                                        let blocks: Vec<Block> = serde_json::from_str(blocks_json)
-                                            .map_err(|e| anyhow!("Invalid blocks JSON: {:?}", e))?;
-
-                                        tokio::task::spawn_blocking(move || {
-                                            match unscrable_image(response_bytes.to_vec(), blocks) {
-                                                Ok(result) => Ok(result),
+                                            })
+                                            .await??
+                                        } else {
+                                            response_bytes.to_vec()
+                                        };
+
+                                        if let Some(blocks_json) = page.base64.as_ref() {
+                                            let blocks_json = blocks_json.clone();
+                                            let unscrambled = tokio::task::spawn_blocking(move || {
+                                                parse_blocks(&blocks_json).and_then(|blocks| {
+                                                    unscrable_image(response_bytes.to_vec(), blocks)
+                                                })
+                                            })
+                                            .await?;
+
+                                            match unscrambled {
+                                                Ok(image) => (PageData::Bytes(image), None),
                                                 Err(e) => {
-                                                    eprintln!("unscrable_image failed: {}", e);
-                                                    anyhow::bail!(e)
+                                                    eprintln!("unscrable_image failed: {e}");
+
+                                                    (
+                                                        PageData::Bytes(generate_error_image(
+                                                            "Error",
+                                                            &e.to_string(),
+                                                            lang.as_deref(),
+                                                            500,
+                                                            667,
+                                                        )?),
+                                                        Some(DownloadError {
+                                                            page_index: page.index,
+                                                            url: req_url_string,
+                                                            reason: e.to_string(),
+                                                            attempts: 1,
+                                                        }),
+                                                    )
                                                 }
                                             }
-                                        })
-                                        .await??
-                                    } else {
-                                        response_bytes.to_vec()
-                                    };
-
-                                    (final_image, None)
+                                        } else {
+                                            (PageData::Bytes(response_bytes.to_vec()), None)
+                                        }
+                                    }
                                 }
                             };
 
                             // Send result
                             let _ = tx
-                                .send((page.index, filename, final_bytes, error_info))
+                                .send((page.index, filename, page_data, error_info))
                                 .await;
 
                             Ok::<_, anyhow::Error>(())
@@ -383,13 +512,19 @@ where
                             Err(e) => {
                                 eprintln!("Error downloading page {}: {e}", page_index);
                                 let filename = format!("{:0>4}.jpg", page_index);
-                                let bytes = generate_error_image("Error", &e.to_string(), 500, 667)
+                                let bytes = generate_error_image(
+                                    "Error",
+                                    &e.to_string(),
+                                    lang.as_deref(),
+                                    500,
+                                    667,
+                                )
                                     .unwrap_or_default();
                                 let _ = tx
                                     .send((
                                         page_index,
                                         filename,
-                                        bytes,
+                                        PageData::Bytes(bytes),
                                         Some(DownloadError {
                                             page_index,
                                             url: page_url
@@ -415,24 +550,99 @@ where
     // Collect errors
     let mut errors = Vec::<DownloadError>::new();
 
+    // Pages arrive in the order they finish downloading, so they're held here until every page
+    // before them has been written, keeping the archive in page order.
+    let mut buffered_pages = BTreeMap::<usize, (String, PageData)>::new();
+    let max_buffered_pages = concurrent_requests_pages * 2;
+
     // Writer task
-    while let Some((_index, filename, data, err)) = rx.recv().await {
+    while let Some((index, filename, data, err)) = rx.recv().await {
         if let Some(e) = err {
             errors.push(e);
         }
 
-        writer.start_file(filename, file_options)?;
+        pending_indices.remove(&index);
+        buffered_pages.insert(index, (filename, data));
+
         processed += 1.0;
         if let Some(ref cb) = on_progress {
             cb(processed, total);
         }
-        writer.write_all(&data)?;
+
+        while let Some(&first_index) = buffered_pages.keys().next() {
+            let is_next_page = pending_indices
+                .first()
+                .is_none_or(|&pending_index| first_index < pending_index);
+            // A single slow page shouldn't keep every page after it around. Writing a page
+            // early only changes its position in the archive: filenames still sort in page
+            // order.
+            if !is_next_page && buffered_pages.len() <= max_buffered_pages {
+                break;
+            }
+
+            if let Some((_, (filename, data))) = buffered_pages.pop_first() {
+                write_page(&mut writer, file_options, filename, data)?;
+            }
+        }
+
+        if let Some(ref pages_done) = pages_done {
+            pages_done.send_replace(processed as usize);
+        }
+    }
+
+    while let Some((_, (filename, data))) = buffered_pages.pop_first() {
+        write_page(&mut writer, file_options, filename, data)?;
     }
 
+    // Add ComicInfo.xml to the CBZ file. It's written last so that pages replaced by an error
+    // image don't count towards `PageCount`.
+    let pages_successfully_downloaded = page_total.saturating_sub(errors.len());
+    metadata.page_count = pages_successfully_downloaded as i32;
+    writer.start_file("ComicInfo.xml", file_options)?;
+    writer.write_all(metadata.to_xml()?.as_bytes())?;
+
     let _ = writer.set_comment(zip_comment(chapter_id));
     Ok(errors)
 }
 
+fn write_page<W: Write + Seek>(
+    writer: &mut ZipWriter<W>,
+    file_options: zip::write::FileOptions<'_, ()>,
+    filename: String,
+    data: PageData,
+) -> anyhow::Result<()> {
+    writer.start_file(filename, file_options)?;
+    match data {
+        PageData::Bytes(data) => writer.write_all(&data)?,
+        PageData::File(file) => {
+            std::io::copy(&mut file.reopen()?, writer)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A downloaded page, on its way to the archive.
+enum PageData {
+    Bytes(Vec<u8>),
+    /// The untouched response body, which is deleted once copied into the archive.
+    File(NamedTempFile),
+}
+
+/// Writes the response body to a temporary file in `dir` chunk by chunk, so that a page never
+/// has to be held in memory as a whole.
+async fn buffer_response_body(
+    mut response: reqwest::Response,
+    dir: &Path,
+) -> anyhow::Result<NamedTempFile> {
+    let mut writer = BufWriter::new(NamedTempFile::new_in(dir)?);
+    while let Some(chunk) = response.chunk().await? {
+        writer.write_all(&chunk)?;
+    }
+
+    writer.into_inner().map_err(|err| err.into_error().into())
+}
+
 pub async fn download_chapter_novel_as_epub<W>(
     _: W,
     token: &CancellationToken,
@@ -509,13 +719,31 @@ where
             .open(&temp_path)?;
 
         let mut epub = EpubBuilder::new(ZipLibrary::new()?)?;
-        if let Some(lang) = lang {
-            epub.set_lang(lang);
+        // EPUB 3 makes the builder emit a `nav.xhtml` navigation document (listing every
+        // page added below, plus the landmarks) next to the legacy `toc.ncx`.
+        epub.epub_version(EpubVersion::V30);
+        if let Some(lang) = &lang {
+            epub.set_lang(lang.as_str());
         }
+        epub.set_toc_name(&book_name);
         epub.set_title(book_name);
 
         let mut index_image = 0;
-        // epub.inline_toc().set_toc_name("Map");
+
+        // Only the first page is marked as the start of the text, so it is the single
+        // "bodymatter" entry of the landmarks instead of every page being one.
+        let mut has_text_landmark = false;
+        let mut page_content = |idx: usize, xhtml: String, title: String| {
+            let content =
+                EpubContent::new(format!("pages/page_{}.xhtml", idx + 1), Cursor::new(xhtml))
+                    .title(title);
+
+            if std::mem::replace(&mut has_text_landmark, true) {
+                content
+            } else {
+                content.reftype(ReferenceType::Text)
+            }
+        };
 
         if let Some(cursor) = cover_img {
             epub.add_cover_image("cover.jpg", Cursor::new(cursor), "image/jpeg")?;
@@ -555,25 +783,20 @@ where
                     }
                 }
 
-                epub.add_content(
-                    EpubContent::new(
-                        format!("pages/page_{}.xhtml", idx + 1),
-                        Cursor::new(create_xhtml(&title, &html)),
-                    )
-                    .title(title)
-                    .reftype(ReferenceType::Text),
-                )?;
+                epub.add_content(page_content(idx, create_xhtml(&title, &html), title))?;
             } else if let Some(text) = &page.text {
                 let document = Document::from(format!(
                     "<html><body>{}</body></html>",
                     into_html(text).to_owned()
                 ));
 
+                let page_base_url = get_base_url(&document, chapter_url.as_ref());
+
                 // Apply results sequentially
                 for img in document.select("img").iter() {
-                    let Some(src) =
-                        get_image_src(chapter_url.as_ref(), |n| img.attr(n).map(|v| v.to_string()))
-                    else {
+                    let Some(src) = get_image_src(page_base_url.as_ref(), |n| {
+                        img.attr(n).map(|v| v.to_string())
+                    }) else {
                         continue;
                     };
                     let Some(image_result) = images.get(&src) else {
@@ -592,7 +815,13 @@ where
                             eprintln!("Failed to download image for EPUB: {:?}", e);
 
                             let image_bytes =
-                                generate_error_image("Image error", &e.to_string(), 500, 667)?;
+                                generate_error_image(
+                                    "Image error",
+                                    &e.to_string(),
+                                    lang.as_deref(),
+                                    500,
+                                    667,
+                                )?;
 
                             let filename = format!("images/img_{}.{}", index_image, "jpeg");
                             index_image += 1;
@@ -606,22 +835,11 @@ where
 
                 let xhtml = create_xhtml(&title, document.select_single("body").html().as_ref());
 
-                epub.add_content(
-                    EpubContent::new(format!("pages/page_{}.xhtml", idx + 1), Cursor::new(xhtml))
-                        .title(title)
-                        .reftype(ReferenceType::Text),
-                )?;
+                epub.add_content(page_content(idx, xhtml, title))?;
             } else {
                 let html =
                     "<p><strong>No content available for this page.</strong></p>".to_string();
-                epub.add_content(
-                    EpubContent::new(
-                        format!("pages/page_{}.xhtml", idx + 1),
-                        Cursor::new(create_xhtml(&title, &html)),
-                    )
-                    .title(title)
-                    .reftype(ReferenceType::Text),
-                )?;
+                epub.add_content(page_content(idx, create_xhtml(&title, &html), title))?;
             }
         }
 
@@ -634,3 +852,51 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(text: Option<&str>) -> Page {
+        Page {
+            source_id: "source".to_owned(),
+            chapter_id: "chapter".to_owned(),
+            index: 0,
+            image_url: url::Url::parse("https://cdn.test/1.jpg").ok(),
+            base64: None,
+            text: text.map(str::to_owned),
+            ctx: None,
+        }
+    }
+
+    #[test]
+    fn legacy_pages_with_unrelated_text_are_not_novels() {
+        for text in ["Page 1 of 20", "", "  1  "] {
+            assert!(
+                !is_novel_page_list(&[page(Some(text))], false),
+                "{text:?} shouldn't be a novel"
+            );
+        }
+    }
+
+    #[test]
+    fn legacy_pages_with_html_or_the_novel_marker_are_novels() {
+        assert!(is_novel_page_list(&[page(Some("novel"))], false));
+        assert!(is_novel_page_list(
+            &[page(Some("  <p>Once upon a time</p>"))],
+            false
+        ));
+        assert!(is_novel_page_list(
+            &[page(Some("<!-- html --><p>Once upon a time</p>"))],
+            false
+        ));
+    }
+
+    #[test]
+    fn next_sdk_pages_with_text_are_novels() {
+        assert!(is_novel_page_list(&[page(Some("# Chapter 1"))], true));
+        assert!(!is_novel_page_list(&[page(None)], true));
+        assert!(!is_novel_page_list(&[page(None)], false));
+        assert!(!is_novel_page_list(&[], true));
+    }
+}
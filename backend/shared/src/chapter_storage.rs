@@ -7,7 +7,7 @@ use std::{fs, future::Future};
 use anyhow::{anyhow, Context, Result};
 use base64::{engine::general_purpose, Engine as _};
 use image::ImageReader;
-use log::debug;
+use log::{debug, info, warn};
 use sha2::{Digest, Sha256};
 use size::Size;
 use tempfile::NamedTempFile;
@@ -21,6 +21,13 @@ use crate::model::{ChapterId, MangaId};
 use crate::source::decode_image::{decode_argb_to_rgb, decode_image_fast};
 
 const CHAPTER_FILE_EXTENSION: [&str; 2] = ["cbz", "epub"];
+/// How many levels below the downloads folder chapter files live:
+/// `{source_id}/{manga_id_prefix}/{chapter_file}`.
+const CHAPTER_DEPTH: usize = 3;
+
+/// Written into the downloads folder once `shard_flat_chapters` went through every chapter,
+/// so flat files that belong to no known chapter don't make it run on every startup.
+const SHARDED_MARKER_FILENAME: &str = ".sharded";
 
 pub struct ChapterStorage {
     /// Always the persistent download path — never changes.
@@ -277,23 +284,116 @@ impl ChapterStorage {
         &self.downloads_folder_path
     }
 
+    /// Every downloaded chapter file (`.cbz` or `.epub`) up to `depth` levels below the
+    /// downloads folder; a `depth` of 1 only looks at the folder itself, and chapters are
+    /// stored [`CHAPTER_DEPTH`] levels below it.
     pub fn collect_all_files(&self, depth: usize) -> std::collections::HashSet<PathBuf> {
         WalkDir::new(&self.downloads_folder_path)
             .max_depth(depth)
             .into_iter()
             .filter_map(Result::ok)
             .filter(|entry| entry.path().is_file())
+            .filter(|entry| Self::is_chapter_file(entry.path()))
+            .map(|entry| entry.path().to_path_buf())
+            .collect()
+    }
+
+    /// Every downloaded chapter file and `.errors` file in the downloads folder, with its
+    /// size in bytes, read in a single traversal of the folder.
+    pub fn collect_all_files_with_sizes(&self) -> std::collections::HashMap<PathBuf, u64> {
+        WalkDir::new(&self.downloads_folder_path)
+            .max_depth(CHAPTER_DEPTH)
+            .into_iter()
+            .filter_map(Result::ok)
             .filter(|entry| {
-                if let Some(ext) = entry.path().extension().and_then(|e| e.to_str()) {
-                    matches!(ext.to_lowercase().as_str(), "cbz" | "epub")
-                } else {
-                    false
-                }
+                Self::is_chapter_file(entry.path())
+                    || entry
+                        .path()
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("errors"))
+            })
+            .filter_map(|entry| {
+                let metadata = fs::metadata(entry.path()).ok()?;
+
+                metadata
+                    .is_file()
+                    .then(|| (entry.path().to_path_buf(), metadata.len()))
             })
-            .map(|entry| entry.path().to_path_buf())
             .collect()
     }
 
+    /// How many bytes the downloaded chapters of `manga_id` take, including their `.errors`
+    /// files. Chapter files are named after a hash, so the IDs of every chapter of the manga
+    /// that may be stored have to be given; only the manga's own shard folder is read, along
+    /// with the unsharded paths of chapters `shard_flat_chapters` hasn't moved yet.
+    pub fn get_chapter_disk_usage(
+        &self,
+        manga_id: &MangaId,
+        chapter_ids: impl IntoIterator<Item = ChapterId>,
+    ) -> u64 {
+        let mut filenames = std::collections::HashSet::new();
+        let mut flat_bytes = 0;
+        for id in chapter_ids {
+            if id.manga_id() != manga_id {
+                continue;
+            }
+
+            for is_novel in [false, true] {
+                let filename = Self::chapter_filename(&id, is_novel);
+                let stem = filename.rsplit_once('.').map_or("", |(stem, _)| stem);
+                filenames.insert(format!(".{stem}.errors"));
+                filenames.insert(filename);
+
+                for flat_path in [
+                    self.path_for_chapter_flat(&id, is_novel),
+                    self.path_for_chapter_legacy(&id, is_novel),
+                ] {
+                    let errors_path = self.errors_source_path(&flat_path).ok();
+                    flat_bytes += [Some(flat_path), errors_path]
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|path| fs::metadata(path).ok())
+                        .filter(|metadata| metadata.is_file())
+                        .map(|metadata| metadata.len())
+                        .sum::<u64>();
+                }
+            }
+        }
+        if filenames.is_empty() {
+            return 0;
+        }
+
+        let manga_id_prefix: String = manga_id.value().chars().take(2).collect();
+        let shard_path = self
+            .downloads_folder_path
+            .join(Self::shard_dirname(manga_id.source_id().value()))
+            .join(Self::shard_dirname(&manga_id_prefix));
+
+        WalkDir::new(shard_path)
+            .max_depth(1)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| filenames.contains(name))
+            })
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len())
+            .sum::<u64>()
+            + flat_bytes
+    }
+
+    fn is_chapter_file(path: &std::path::Path) -> bool {
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            matches!(ext.to_lowercase().as_str(), "cbz" | "epub")
+        } else {
+            false
+        }
+    }
+
     pub async fn delete_filename(&self, filename: String, tmpfs: bool) -> std::io::Result<()> {
         let parent = if tmpfs {
             if self.ram_enabled {
@@ -325,16 +425,40 @@ impl ChapterStorage {
         };
 
         // Perform the deletion
-        tokio::fs::remove_file(file_path).await?;
+        tokio::fs::remove_file(&file_path).await?;
 
         // Update cache only after successful removal
         if let Some(size) = file_size {
             self.cached_storage_size.fetch_sub(size, Ordering::Relaxed);
         }
 
+        // Drop the shard folders the file leaves empty; `remove_dir` refuses non-empty ones.
+        for dir in file_path.ancestors().skip(1) {
+            if !dir.starts_with(&parent) || dir == parent {
+                break;
+            }
+            if tokio::fs::remove_dir(dir).await.is_err() {
+                break;
+            }
+        }
+
         Ok(())
     }
 
+    /// `path` relative to the folder it's stored in, as taken by [`Self::delete_filename`].
+    pub fn relative_filename(&self, path: &std::path::Path, tmpfs: bool) -> Option<String> {
+        let parent = if tmpfs {
+            self.tmpfs_path()
+        } else {
+            self.downloads_folder_path.clone()
+        };
+
+        path.strip_prefix(parent)
+            .ok()?
+            .to_str()
+            .map(|filename| filename.to_owned())
+    }
+
     fn path_for_poster(&self, manga_id: &MangaId) -> PathBuf {
         let mut hasher = Sha256::new();
 
@@ -473,7 +597,17 @@ impl ChapterStorage {
             return None;
         }
 
-        // Backwards compatibility: check the old path format
+        // Backwards compatibility: check the unsharded and the old path formats
+        let flat_path = self.path_for_chapter_flat(id, false);
+        if flat_path.exists() {
+            return Some(flat_path);
+        }
+
+        let flat_path_novel = self.path_for_chapter_flat(id, true);
+        if flat_path_novel.exists() {
+            return Some(flat_path_novel);
+        }
+
         let old_path = self.path_for_chapter_legacy(id, false);
         if old_path.exists() {
             return Some(old_path);
@@ -495,6 +629,8 @@ impl ChapterStorage {
         let candidates = [
             self.path_for_chapter(id, false, false),
             self.path_for_chapter(id, true, false),
+            self.path_for_chapter_flat(id, false),
+            self.path_for_chapter_flat(id, true),
             self.path_for_chapter_legacy(id, false),
             self.path_for_chapter_legacy(id, true),
         ];
@@ -512,6 +648,19 @@ impl ChapterStorage {
         self.path_for_chapter(id, is_novel, use_ram)
     }
 
+    /// Creates the temporary file a chapter is written to before [`Self::persist_chapter`],
+    /// next to `output_path` so persisting it is a rename. The shard folder may not exist yet
+    /// for a new source or manga, so it's created here.
+    pub fn create_temporary_file(&self, output_path: &std::path::Path) -> Result<NamedTempFile> {
+        let parent = output_path
+            .parent()
+            .ok_or_else(|| anyhow!("output path has no parent"))?;
+        fs::create_dir_all(parent)
+            .with_context(|| format!("while creating {}", parent.display()))?;
+
+        Ok(NamedTempFile::new_in(parent)?)
+    }
+
     pub fn errors_source_path(&self, path: &std::path::Path) -> anyhow::Result<std::path::PathBuf> {
         let parent = path
             .parent()
@@ -566,6 +715,9 @@ impl ChapterStorage {
 
         // Persist using the new path format
         let path = self.path_for_chapter(id, is_novel, use_ram);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
         temporary_file.persist(&path)?;
 
         // Update cache with new file size
@@ -687,6 +839,153 @@ impl ChapterStorage {
             })
     }
 
+    /// Whether chapters may still be stored with the older, unsharded path format, i.e.
+    /// `shard_flat_chapters` hasn't gone through them yet.
+    pub fn has_flat_chapters(&self) -> bool {
+        if self
+            .downloads_folder_path
+            .join(SHARDED_MARKER_FILENAME)
+            .exists()
+        {
+            return false;
+        }
+
+        fs::read_dir(&self.downloads_folder_path)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .any(|entry| entry.path().is_file() && Self::is_chapter_file(&entry.path()))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Moves chapters stored with an older, unsharded path format (straight into the
+    /// downloads folder) to their sharded path. Chapter files are named after a hash, so the
+    /// IDs of every chapter that may be stored have to be given. Once every chapter could be
+    /// moved, flat files that are left belong to no known chapter and are ignored from then on.
+    ///
+    /// Returns how many chapters were moved.
+    pub fn shard_flat_chapters(&self, chapter_ids: impl IntoIterator<Item = ChapterId>) -> usize {
+        if !self.has_flat_chapters() {
+            return 0;
+        }
+
+        info!("moving downloaded chapters into per-source folders");
+
+        let mut moved = 0;
+        let mut failed = 0;
+        for id in chapter_ids {
+            for is_novel in [false, true] {
+                let sharded_path = self.path_for_chapter(&id, is_novel, false);
+                let flat_paths = [
+                    self.path_for_chapter_flat(&id, is_novel),
+                    self.path_for_chapter_legacy(&id, is_novel),
+                ];
+                let Some(flat_path) = flat_paths.into_iter().find(|path| path.exists()) else {
+                    continue;
+                };
+                if sharded_path.exists() {
+                    continue;
+                }
+
+                if let Err(e) = Self::move_chapter_file(&flat_path, &sharded_path) {
+                    warn!("couldn't move {}: {e:#}", flat_path.display());
+                    failed += 1;
+                    continue;
+                }
+
+                // The errors file is named after the chapter file, so it moves along with it.
+                if let (Ok(flat_errors), Ok(sharded_errors)) = (
+                    self.errors_source_path(&flat_path),
+                    self.errors_source_path(&sharded_path),
+                ) {
+                    if flat_errors.exists() {
+                        let _ = fs::rename(flat_errors, sharded_errors);
+                    }
+                }
+
+                moved += 1;
+                if moved % 100 == 0 {
+                    info!("moved {moved} downloaded chapters so far");
+                }
+            }
+        }
+
+        info!("moved {moved} downloaded chapters into per-source folders");
+
+        // Chapters that couldn't be moved are tried again on the next startup.
+        if failed == 0 {
+            if let Err(e) = fs::write(
+                self.downloads_folder_path.join(SHARDED_MARKER_FILENAME),
+                b"",
+            ) {
+                warn!("couldn't mark the downloaded chapters as moved: {e}");
+            }
+        }
+
+        moved
+    }
+
+    /// Moves the stored chapters of `old_manga_id` among `chapter_ids`, and its poster, to where
+    /// they belong once the manga is known as `new_manga_id`: the manga ID is part of their
+    /// filenames. Returns how many chapters were moved.
+    pub fn move_manga_chapters(
+        &self,
+        old_manga_id: &MangaId,
+        new_manga_id: &MangaId,
+        chapter_ids: impl IntoIterator<Item = ChapterId>,
+    ) -> usize {
+        let mut moved = 0;
+        for id in chapter_ids {
+            if id.manga_id() != old_manga_id {
+                continue;
+            }
+            let Some(stored_path) = self.get_stored_chapter(&id, false) else {
+                continue;
+            };
+            let is_novel = stored_path.extension().is_some_and(|ext| ext == "epub");
+            let new_id = ChapterId::new(new_manga_id.clone(), id.value().clone());
+            let new_path = self.path_for_chapter(&new_id, is_novel, false);
+            if new_path.exists() {
+                continue;
+            }
+
+            if let Err(e) = Self::move_chapter_file(&stored_path, &new_path) {
+                warn!("couldn't move {}: {e:#}", stored_path.display());
+                continue;
+            }
+
+            if let (Ok(old_errors), Ok(new_errors)) = (
+                self.errors_source_path(&stored_path),
+                self.errors_source_path(&new_path),
+            ) {
+                if old_errors.exists() {
+                    let _ = fs::rename(old_errors, new_errors);
+                }
+            }
+
+            moved += 1;
+        }
+
+        if let Some(old_poster) = self.poster_exists(old_manga_id) {
+            let new_poster = self.path_for_poster(new_manga_id);
+            if !new_poster.exists() {
+                let _ = fs::rename(old_poster, new_poster);
+            }
+        }
+
+        moved
+    }
+
+    fn move_chapter_file(from: &std::path::Path, to: &std::path::Path) -> Result<()> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(from, to)?;
+
+        Ok(())
+    }
+
     // DEPRECATED: This function provides backwards compatibility for the old chapter path format.
     // We should remove it after some versions (enough time for users to have already migrated :eyes:)
     fn path_for_chapter_legacy(&self, chapter_id: &ChapterId, is_novel: bool) -> PathBuf {
@@ -701,6 +1000,42 @@ impl ChapterStorage {
     }
 
     fn path_for_chapter(&self, chapter_id: &ChapterId, is_novel: bool, use_ram: bool) -> PathBuf {
+        let output_filename = Self::chapter_filename(chapter_id, is_novel);
+
+        if use_ram && self.ram_enabled {
+            // tmpfs only ever holds a few chapters, so it doesn't need sharding.
+            return self.tmpfs_path().join(output_filename);
+        }
+
+        // Some filesystems (e.g. FAT32 on Kobo SD cards) struggle with thousands of files in
+        // a single folder, so chapters are spread into `{source_id}/{manga_id_prefix}/`.
+        let manga_id = chapter_id.manga_id().value();
+        let manga_id_prefix: String = manga_id.chars().take(2).collect();
+
+        self.downloads_folder_path
+            .join(Self::shard_dirname(chapter_id.source_id().value()))
+            .join(Self::shard_dirname(&manga_id_prefix))
+            .join(output_filename)
+    }
+
+    fn shard_dirname(name: &str) -> String {
+        let dirname = sanitize_filename::sanitize(name);
+
+        if dirname.is_empty() || dirname.starts_with('.') {
+            format!("_{dirname}")
+        } else {
+            dirname
+        }
+    }
+
+    // DEPRECATED: Chapters used to be stored straight into the downloads folder. They're moved
+    // by `shard_flat_chapters` on startup, this is only needed until then.
+    fn path_for_chapter_flat(&self, chapter_id: &ChapterId, is_novel: bool) -> PathBuf {
+        self.downloads_folder_path
+            .join(Self::chapter_filename(chapter_id, is_novel))
+    }
+
+    fn chapter_filename(chapter_id: &ChapterId, is_novel: bool) -> String {
         let mut hasher = Sha256::new();
         hasher.update(chapter_id.source_id().value().as_bytes());
         hasher.update(chapter_id.manga_id().value().as_bytes());
@@ -710,13 +1045,7 @@ impl ChapterStorage {
         // Use URL-safe base64 encoding without padding for the filename
         let encoded_hash = general_purpose::URL_SAFE_NO_PAD.encode(hash_result);
 
-        let output_filename = format!("{}.{}", encoded_hash, if is_novel { "epub" } else { "cbz" });
-
-        if use_ram && self.ram_enabled {
-            self.tmpfs_path().join(output_filename)
-        } else {
-            self.downloads_folder_path.join(output_filename)
-        }
+        format!("{}.{}", encoded_hash, if is_novel { "epub" } else { "cbz" })
     }
 }
 
@@ -759,4 +1088,118 @@ mod tests {
         let (w, h) = output_dimensions(&output);
         assert_eq!((w, h), (200, 300));
     }
+
+    #[tokio::test]
+    async fn flat_chapters_are_moved_into_shards() {
+        let storage = make_storage();
+        let id = ChapterId::from_strings("source".to_owned(), "manga".to_owned(), "1".to_owned());
+        let flat_path = storage.path_for_chapter_flat(&id, false);
+        fs::write(&flat_path, b"chapter").unwrap();
+
+        assert_eq!(storage.shard_flat_chapters([id.clone()]), 1);
+        assert!(!storage.has_flat_chapters());
+
+        let stored_path = storage.get_stored_chapter(&id, false).unwrap();
+        assert!(!flat_path.exists());
+        assert_eq!(stored_path, storage.path_for_chapter(&id, false, false));
+        assert!(stored_path.starts_with(storage.downloads_path().join("source")));
+
+        let filename = storage.relative_filename(&stored_path, false).unwrap();
+        storage.delete_filename(filename, false).await.unwrap();
+        assert!(!storage.downloads_path().join("source").exists());
+    }
+
+    #[tokio::test]
+    async fn chapters_can_be_downloaded_into_a_fresh_shard() {
+        let storage = make_storage();
+        let id = ChapterId::from_strings("source".to_owned(), "manga".to_owned(), "1".to_owned());
+        let output_path = storage.get_path_to_store_chapter(&id, false, false);
+        assert!(!output_path.parent().unwrap().exists());
+
+        let temporary_file = storage.create_temporary_file(&output_path).unwrap();
+        fs::write(temporary_file.path(), b"chapter").unwrap();
+        let stored_path = storage
+            .persist_chapter(&id, false, temporary_file, &vec![], false)
+            .await
+            .unwrap();
+
+        assert_eq!(stored_path, output_path);
+        assert_eq!(fs::read(&stored_path).unwrap(), b"chapter");
+    }
+
+    #[test]
+    fn unmatched_flat_files_are_only_looked_at_once() {
+        let storage = make_storage();
+        let stray = storage.downloads_path().join("stray.cbz");
+        fs::write(&stray, b"chapter").unwrap();
+        assert!(storage.has_flat_chapters());
+
+        assert_eq!(storage.shard_flat_chapters([]), 0);
+
+        assert!(stray.exists());
+        assert!(!storage.has_flat_chapters());
+    }
+
+    #[test]
+    fn manga_chapters_are_moved_to_the_new_manga_id() {
+        let storage = make_storage();
+        let old = MangaId::from_strings("source".to_owned(), "old".to_owned());
+        let new = MangaId::from_strings("source".to_owned(), "new".to_owned());
+        let old_chapter = ChapterId::new(old.clone(), "1".to_owned());
+        let new_chapter = ChapterId::new(new.clone(), "1".to_owned());
+
+        let old_path = storage.path_for_chapter(&old_chapter, true, false);
+        fs::create_dir_all(old_path.parent().unwrap()).unwrap();
+        fs::write(&old_path, b"chapter").unwrap();
+        fs::write(storage.errors_source_path(&old_path).unwrap(), b"[]").unwrap();
+
+        assert_eq!(
+            storage.move_manga_chapters(&old, &new, [old_chapter.clone()]),
+            1
+        );
+
+        assert!(storage.get_stored_chapter(&old_chapter, false).is_none());
+        let new_path = storage.get_stored_chapter(&new_chapter, false).unwrap();
+        assert_eq!(
+            new_path,
+            storage.path_for_chapter(&new_chapter, true, false)
+        );
+        assert!(storage.errors_source_path(&new_path).unwrap().exists());
+    }
+
+    #[test]
+    fn disk_usage_only_counts_chapters_of_the_manga() {
+        let storage = make_storage();
+        let manga_id = MangaId::from_strings("source".to_owned(), "manga".to_owned());
+        let chapter = ChapterId::new(manga_id.clone(), "1".to_owned());
+        let other_chapter = ChapterId::new(manga_id.clone(), "2".to_owned());
+        // Shares the shard folder, as its ID starts the same way.
+        let other_manga_chapter =
+            ChapterId::from_strings("source".to_owned(), "manhwa".to_owned(), "1".to_owned());
+
+        let chapter_path = storage.path_for_chapter(&chapter, false, false);
+        fs::create_dir_all(chapter_path.parent().unwrap()).unwrap();
+        fs::write(&chapter_path, [0; 10]).unwrap();
+        fs::write(storage.errors_source_path(&chapter_path).unwrap(), [0; 3]).unwrap();
+        fs::write(
+            storage.path_for_chapter(&other_chapter, true, false),
+            [0; 20],
+        )
+        .unwrap();
+        fs::write(
+            storage.path_for_chapter(&other_manga_chapter, false, false),
+            [0; 40],
+        )
+        .unwrap();
+
+        let flat_chapter = ChapterId::new(manga_id.clone(), "3".to_owned());
+        fs::write(storage.path_for_chapter_flat(&flat_chapter, false), [0; 5]).unwrap();
+
+        let usage = storage.get_chapter_disk_usage(
+            &manga_id,
+            [chapter, other_chapter, other_manga_chapter, flat_chapter],
+        );
+
+        assert_eq!(usage, 38);
+    }
 }
@@ -17,6 +17,9 @@ pub struct CookieEntry {
     pub domain: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
+    /// Unix timestamp after which the cookie is no longer sent. Session cookies have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -401,6 +404,7 @@ pub fn apply_synced_cookies(data: &[SyncCookieData]) {
                     value: c.value.clone(),
                     domain: c.domain.clone(),
                     path: None,
+                    expires: None,
                 })
                 .collect();
             cookie_count += cookies.len();
@@ -483,6 +487,7 @@ mod tests {
                 value: "abc".into(),
                 domain: "exact.com".into(),
                 path: None,
+                expires: None,
             }],
         );
         // Host-only cookie matches exact domain
@@ -501,6 +506,7 @@ mod tests {
                 value: "clearance".into(),
                 domain: ".example.com".into(),
                 path: None,
+                expires: None,
             }],
         );
         // Domain cookie matches the parent domain itself
@@ -521,6 +527,7 @@ mod tests {
                 value: "clr".into(),
                 domain: ".example.com".into(),
                 path: None,
+                expires: None,
             }],
         );
         store.set_cookies_for_domain(
@@ -530,6 +537,7 @@ mod tests {
                 value: "tok".into(),
                 domain: "sub.example.com".into(),
                 path: None,
+                expires: None,
             }],
         );
         let cookies = store.get_cookies_for_domain("sub.example.com");
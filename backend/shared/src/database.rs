@@ -1,31 +1,167 @@
 use chrono::TimeZone;
 use futures::TryStreamExt;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex, OnceLock},
 };
 
 use anyhow::Result;
 use sqlx::{
     pool::PoolOptions,
     sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous},
-    Error, Pool, QueryBuilder, Sqlite,
+    Connection, Error, Pool, QueryBuilder, Sqlite, SqliteConnection,
 };
 use tokio::sync::RwLock;
 use url::Url;
 
 use crate::{
+    arima_light::ArimaSpecOverride,
     model::{
-        Chapter, ChapterId, ChapterInformation, ChapterState, Manga, MangaId, MangaInformation,
-        MangaState, NotificationInformation, Playlist, SourceId, SourceInformation,
-        TrackingBinding, TrackingCandidate, TrackingProgressSnapshot, TrackingService,
-        TrackingStatus,
+        Chapter, ChapterId, ChapterInformation, ChapterState, DatabaseStats, DownloadProgress,
+        DownloadStatus, Manga, MangaId, MangaInformation, MangaState, NotificationInformation,
+        Playlist, SchemaVersion, SourceId, SourceInformation, TrackingBinding, TrackingCandidate,
+        TrackingProgressSnapshot, TrackingService, TrackingStatus,
     },
     source::model::{MangaViewer, PublishingStatus},
     source_collection::SourceCollection,
 };
 
+// Emits the query listing the mangas of the library, or of a playlist with `in_playlist(id)`,
+// ordered by `$order`. Every sorting mode shares the same CTEs and columns, sorting by the last
+// update additionally joins the latest chapter update of each manga. The fragments keep their
+// indentation so that the concatenated query strings, and thus their `.sqlx` entries, stay the
+// same.
+macro_rules! library_query {
+    (in_playlist($playlist_id:expr), $($sorting:tt)*) => {
+        library_query!(
+            @sort
+            [
+                "FROM playlist_mangas ml",
+                r#"
+                    WHERE ml.playlist_id = ?1"#,
+                $playlist_id
+            ]
+            $($sorting)*
+        )
+    };
+    (@sort [$($source:tt)*] $order:literal) => {
+        library_query!(@build [$($source)*] "", "", "", $order)
+    };
+    (@sort [$($source:tt)*] with_last_chapter_update, $order:literal) => {
+        library_query!(
+            @build
+            [$($source)*]
+            r#"
+                    ),
+                    last_chapter_update AS (
+                        SELECT
+                            source_id,
+                            manga_id,
+                            MAX(last_updated) AS last_chapter_updated
+                        FROM chapter_informations
+                        GROUP BY source_id, manga_id"#,
+            r#"
+                    LEFT JOIN last_chapter_update lcu
+                        ON lcu.source_id = ml.source_id AND lcu.manga_id = ml.manga_id"#,
+            ", lcu.last_chapter_updated",
+            $order
+        )
+    };
+    (
+        @build
+        [$from:literal, $filter:literal $(, $arg:expr)*]
+        $cte:literal, $join:literal, $group_by:literal, $order:literal
+    ) => {
+        sqlx::query_as!(
+            MangaLibraryRowWithReadCount,
+            r#"
+                    WITH last_read AS (
+                        SELECT
+                            ci.source_id,
+                            ci.manga_id,
+                            MAX(ci.chapter_number) AS last_read_chapter
+                        FROM chapter_informations ci
+                        JOIN chapter_state cs
+                            ON ci.source_id = cs.source_id
+                            AND ci.manga_id = cs.manga_id
+                            AND ci.chapter_id = cs.chapter_id
+                        LEFT JOIN manga_state ms
+                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
+                        WHERE (ms.preferred_scanlators IS NULL
+                        OR ci.scanlator = (SELECT ps.value FROM json_each(ms.preferred_scanlators) ps WHERE EXISTS (SELECT 1 FROM chapter_informations alt WHERE alt.source_id = ci.source_id AND alt.manga_id = ci.manga_id AND alt.chapter_number IS ci.chapter_number AND alt.scanlator = ps.value) ORDER BY ps.key LIMIT 1)
+                        OR ci.scanlator IS NULL)
+                        AND cs.read = 1
+                        GROUP BY ci.source_id, ci.manga_id
+                    ),
+                    last_time_interacted AS (
+                        SELECT
+                            ci.source_id,
+                            ci.manga_id,
+                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
+                        FROM chapter_informations ci
+                        JOIN chapter_state cs
+                            ON ci.source_id = cs.source_id
+                            AND ci.manga_id = cs.manga_id
+                            AND ci.chapter_id = cs.chapter_id
+                        LEFT JOIN manga_state ms
+                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
+                        WHERE (ms.preferred_scanlators IS NULL
+                        OR ci.scanlator = (SELECT ps.value FROM json_each(ms.preferred_scanlators) ps WHERE EXISTS (SELECT 1 FROM chapter_informations alt WHERE alt.source_id = ci.source_id AND alt.manga_id = ci.manga_id AND alt.chapter_number IS ci.chapter_number AND alt.scanlator = ps.value) ORDER BY ps.key LIMIT 1)
+                        OR ci.scanlator IS NULL)
+                        AND cs.last_read IS NOT NULL
+                        GROUP BY ci.source_id, ci.manga_id"#
+                + $cte
+                + r#"
+                    )
+                    SELECT
+                        ml.source_id,
+                        ml.manga_id,
+                        mi.title,
+                        mi.author,
+                        mi.artist,
+                        mi.cover_url,
+                        COUNT(ci.chapter_number) AS unread_chapters_count,
+                        lti.last_read_time AS "last_read?: i64",
+                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
+                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
+                    "#
+                + $from
+                + r#"
+                    JOIN manga_informations mi
+                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
+                    LEFT JOIN manga_state ms
+                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
+                    LEFT JOIN manga_details md
+                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
+                    LEFT JOIN last_read lr
+                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
+                    LEFT JOIN last_time_interacted lti
+                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id"#
+                + $join
+                + r#"
+                    LEFT JOIN chapter_informations ci
+                        ON ci.source_id = ml.source_id
+                        AND ci.manga_id = ml.manga_id
+                        AND (ms.preferred_scanlators IS NULL OR ci.scanlator = (SELECT ps.value FROM json_each(ms.preferred_scanlators) ps WHERE EXISTS (SELECT 1 FROM chapter_informations alt WHERE alt.source_id = ci.source_id AND alt.manga_id = ci.manga_id AND alt.chapter_number IS ci.chapter_number AND alt.scanlator = ps.value) ORDER BY ps.key LIMIT 1) OR ci.scanlator IS NULL)
+                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)"#
+                + $filter
+                + r#"
+                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time"#
+                + $group_by
+                + r#"
+                    ORDER BY "#
+                + $order
+                + r#"
+                    "#
+            $(, $arg)*
+        )
+    };
+    ($($sorting:tt)*) => {
+        library_query!(@sort ["FROM manga_library ml", ""] $($sorting)*)
+    };
+}
+
 pub struct Database {
     pub filename: PathBuf,
     pool: Arc<RwLock<Pool<Sqlite>>>,
@@ -45,9 +181,18 @@ const BIND_LIMIT: usize = 32766;
 // FIXME add proper error handling
 impl Database {
     pub async fn new(filename: &Path) -> Result<Self> {
+        let pool = Self::open_pool(filename, true).await?;
+
+        Ok(Self {
+            pool: Arc::new(RwLock::new(pool)),
+            filename: filename.to_path_buf(),
+        })
+    }
+
+    async fn open_pool(filename: &Path, create_if_missing: bool) -> Result<Pool<Sqlite>> {
         let options = SqliteConnectOptions::new()
             .filename(filename)
-            .create_if_missing(true)
+            .create_if_missing(create_if_missing)
             .journal_mode(SqliteJournalMode::Wal)
             .synchronous(SqliteSynchronous::Normal)
             .pragma("busy_timeout", "5000")
@@ -62,90 +207,154 @@ impl Database {
             .connect_with(options)
             .await?;
 
-        sqlx::migrate!().run(&pool).await?;
+        if let Err(e) = sqlx::migrate!().run(&pool).await {
+            pool.close().await;
+            return Err(e.into());
+        }
 
-        Ok(Self {
-            pool: Arc::new(RwLock::new(pool)),
-            filename: filename.to_path_buf(),
-        })
+        Ok(pool)
     }
 
+    /// Replaces the database with the given SQLite file contents.
+    ///
+    /// The new file is first checked (opened and migrated) next to the current one, and
+    /// the current file is only swapped out once that succeeded. If anything fails after
+    /// the swap, the previous database is restored and re-opened.
     pub async fn hot_replace(&self, buf: &[u8]) -> Result<()> {
-        // Acquire write lock first to prevent any concurrent access
-        let mut pool = self.pool.write().await;
-
-        // Close and drain the current pool before file operations
-        let old_pool = std::mem::replace(&mut *pool, Pool::connect("sqlite::memory:").await?);
-        drop(pool);
-        old_pool.close().await;
+        let tmp_path = self.filename.with_extension("db.tmp");
+        let backup_path = self.filename.with_extension("db.bak");
 
-        // Replace database file via temporary backup swap
-        let backup_path = self.filename.with_extension("db.backup");
-        if self.filename.exists() {
-            tokio::fs::rename(&self.filename, &backup_path).await?;
+        tokio::fs::write(&tmp_path, buf).await?;
+        match Self::open_pool(&tmp_path, false).await {
+            Ok(test_pool) => test_pool.close().await,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&tmp_path).await;
+                return Err(e.context("the new database is not valid"));
+            }
         }
 
-        // Write new database file
-        let write_result = tokio::fs::write(&self.filename, buf).await;
+        // Hold the write lock for the whole swap so nobody uses a closed pool.
+        let mut pool = self.pool.write().await;
+        pool.close().await;
 
-        // If write fails, restore backup
-        if write_result.is_err() {
-            if backup_path.exists() {
-                let _ = tokio::fs::rename(&backup_path, &self.filename).await;
+        if self.filename.exists() {
+            if let Err(e) = tokio::fs::rename(&self.filename, &backup_path).await {
+                let _ = tokio::fs::remove_file(&tmp_path).await;
+                *pool = Self::open_pool(&self.filename, true).await?;
+                return Err(e.into());
             }
-            write_result?;
         }
 
-        // Create new pool with proper configuration
-        let options = SqliteConnectOptions::new()
-            .filename(&self.filename)
-            .create_if_missing(true)
-            .journal_mode(SqliteJournalMode::Wal)
-            .synchronous(SqliteSynchronous::Normal)
-            .pragma("busy_timeout", "5000")
-            .pragma("cache_size", "-2000")
-            .pragma("temp_store", "MEMORY")
-            .foreign_keys(true);
+        let swapped = async {
+            tokio::fs::rename(&tmp_path, &self.filename).await?;
+            Self::open_pool(&self.filename, false).await
+        }
+        .await;
 
-        let new_pool = PoolOptions::new()
-            .max_connections(4)
-            .min_connections(1)
-            .acquire_timeout(std::time::Duration::from_secs(5))
-            .connect_with(options)
-            .await;
+        match swapped {
+            Ok(new_pool) => {
+                *pool = new_pool;
+                let _ = tokio::fs::remove_file(&backup_path).await;
 
-        // If connection fails, restore backup
-        let new_pool = match new_pool {
-            Ok(p) => p,
+                Ok(())
+            }
             Err(e) => {
+                let _ = tokio::fs::remove_file(&tmp_path).await;
                 if backup_path.exists() {
-                    let _ = tokio::fs::rename(&backup_path, &self.filename).await;
+                    tokio::fs::rename(&backup_path, &self.filename).await?;
                 }
-                return Err(e.into());
-            }
-        };
+                *pool = Self::open_pool(&self.filename, true).await?;
 
-        // Run migrations on the new pool
-        let migration_result = sqlx::migrate!().run(&new_pool).await;
-
-        // If migrations fail, restore backup
-        if let Err(e) = migration_result {
-            new_pool.close().await;
-            if backup_path.exists() {
-                let _ = tokio::fs::rename(&backup_path, &self.filename).await;
+                Err(e)
             }
-            return Err(e.into());
         }
+    }
 
-        // Swap new pool into shared reference
-        let mut pool = self.pool.write().await;
-        *pool = new_pool;
-        drop(pool);
+    /// Merges the database at `path` (usually a copy from another device) into this one:
+    /// - each chapter keeps whichever read state was changed last, going by `last_read`, and
+    ///   the local one on ties;
+    /// - the library is the union of both;
+    /// - the other database's manga state (viewer, scanlators, ...) wins.
+    ///
+    /// The file at `path` is migrated to the current schema first, so it's modified.
+    pub async fn merge_from(&self, path: &Path) -> Result<()> {
+        Self::open_pool(path, false).await?.close().await;
+
+        let pool = self.pool.read().await;
+        let mut conn = pool.acquire().await?;
+        sqlx::query("ATTACH DATABASE ? AS remote")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(&mut *conn)
+            .await?;
 
-        // Clean up backup file on success
-        if backup_path.exists() {
-            let _ = tokio::fs::remove_file(&backup_path).await;
-        }
+        let merged = Self::merge_attached(&mut conn).await;
+
+        sqlx::query("DETACH DATABASE remote")
+            .execute(&mut *conn)
+            .await?;
+        merged?;
+
+        // Syncing uploads the database file itself, which has to include the merge.
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn merge_attached(conn: &mut SqliteConnection) -> Result<()> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+                INSERT INTO chapter_state (source_id, manga_id, chapter_id, read, last_read, last_read_page)
+                SELECT source_id, manga_id, chapter_id, read, last_read, last_read_page
+                FROM remote.chapter_state
+                WHERE true
+                ON CONFLICT (source_id, manga_id, chapter_id) DO UPDATE SET
+                    read = excluded.read,
+                    last_read = excluded.last_read,
+                    last_read_page = excluded.last_read_page
+                WHERE COALESCE(excluded.last_read, 0) > COALESCE(chapter_state.last_read, 0)
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+                INSERT OR IGNORE INTO manga_library (source_id, manga_id)
+                SELECT source_id, manga_id FROM remote.manga_library
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+                INSERT INTO manga_state (source_id, manga_id, viewer, arima_spec, preferred_scanlators)
+                SELECT source_id, manga_id, viewer, arima_spec, preferred_scanlators
+                FROM remote.manga_state
+                WHERE true
+                ON CONFLICT (source_id, manga_id) DO UPDATE SET
+                    viewer = excluded.viewer,
+                    arima_spec = excluded.arima_spec,
+                    preferred_scanlators = excluded.preferred_scanlators
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Runs a trivial query, to check that the database can still be reached.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1")
+            .execute(&*self.pool.read().await)
+            .await?;
 
         Ok(())
     }
@@ -174,6 +383,7 @@ impl Database {
             LEFT JOIN manga_details md
                 ON ml.manga_id = md.id
             AND ml.source_id = md.source_id
+            ORDER BY ml.rowid
             "#
         )
         .fetch_all(&*self.pool.read().await)
@@ -199,1103 +409,493 @@ impl Database {
         source_collection: &impl SourceCollection,
         library_sorting_mode: &crate::settings::LibrarySortingMode,
     ) -> Result<Vec<Manga>> {
+        use crate::settings::LibrarySortingMode;
+
+        let pool = self.pool.read().await;
         let rows = match *library_sorting_mode {
-            crate::settings::LibrarySortingMode::Ascending => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY ml.rowid
-                    "#
+            LibrarySortingMode::Ascending => library_query!("ml.rowid").fetch_all(&*pool).await?,
+            LibrarySortingMode::Descending => {
+                library_query!("ml.rowid DESC").fetch_all(&*pool).await?
+            }
+            LibrarySortingMode::TitleAsc => {
+                library_query!("mi.title COLLATE NOCASE ASC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::TitleDesc => {
+                library_query!("mi.title COLLATE NOCASE DESC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::UnreadAsc => {
+                library_query!("unread_chapters_count ASC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::UnreadDesc => {
+                library_query!("unread_chapters_count DESC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::LastReadAsc => {
+                library_query!("lti.last_read_time ASC NULLS LAST")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::LastReadDesc => {
+                library_query!("lti.last_read_time DESC NULLS LAST")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::SourceAsc => {
+                library_query!("ml.source_id COLLATE NOCASE ASC, mi.title COLLATE NOCASE ASC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::SourceDesc => {
+                library_query!("ml.source_id COLLATE NOCASE DESC, mi.title COLLATE NOCASE DESC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::DateAddedAsc => {
+                library_query!("julianday(md.date_added) ASC NULLS LAST")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::DateAddedDesc => {
+                library_query!("julianday(md.date_added) DESC NULLS LAST")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::LastUpdatedAsc => {
+                library_query!(
+                    with_last_chapter_update,
+                    "lcu.last_chapter_updated ASC NULLS LAST"
                 )
-                .fetch_all(&*self.pool.read().await)
+                .fetch_all(&*pool)
                 .await?
             }
-            crate::settings::LibrarySortingMode::Descending => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY ml.rowid DESC
-                    "#
+            LibrarySortingMode::LastUpdatedDesc => {
+                library_query!(
+                    with_last_chapter_update,
+                    "lcu.last_chapter_updated DESC NULLS LAST"
                 )
-                .fetch_all(&*self.pool.read().await)
+                .fetch_all(&*pool)
                 .await?
             }
-            crate::settings::LibrarySortingMode::TitleAsc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY mi.title COLLATE NOCASE ASC
-                    "#
+        };
+
+        let mangas = rows
+            .into_iter()
+            .filter_map(|row| {
+                let source = source_collection.get_by_id(&SourceId::new(row.source_id.clone()))?;
+                let info = MangaInformation {
+                    id: MangaId::from_strings(row.source_id, row.manga_id),
+                    title: row.title,
+                    author: row.author,
+                    artist: row.artist,
+                    cover_url: row.cover_url.and_then(|url| Url::parse(&url).ok()),
+                    viewer: MangaViewer::from(row.viewer.unwrap_or(0) as u8),
+                };
+
+                Some(Manga {
+                    source_information: SourceInformation::from(source.manifest()),
+                    information: info,
+                    state: MangaState::default(),
+                    unread_chapters_count: row.unread_chapters_count.map(|v| v as usize),
+                    last_read: row.last_read,
+                    in_library: true,
+                    state_viewer: row.state_viewer != 0,
+                })
+            })
+            .collect();
+
+        Ok(mangas)
+    }
+
+    pub async fn add_manga_to_library(&self, manga_id: MangaId) -> Result<()> {
+        let source_id = manga_id.source_id().value();
+        let manga_id = manga_id.value();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO manga_library (source_id, manga_id)
+                VALUES (?1, ?2)
+                ON CONFLICT DO NOTHING
+            "#,
+            source_id,
+            manga_id
+        )
+        .execute(&*self.pool.read().await)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_manga_from_library(&self, manga_id: MangaId) -> Result<()> {
+        let source_id = manga_id.source_id().value();
+        let manga_id = manga_id.value();
+
+        sqlx::query!(
+            r#"
+                DELETE FROM manga_library
+                WHERE source_id = ?1 AND manga_id = ?2
+            "#,
+            source_id,
+            manga_id
+        )
+        .execute(&*self.pool.read().await)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The version of source `source_id` whose manga ID migration last ran.
+    pub async fn find_key_migration_version(&self, source_id: &SourceId) -> Result<Option<i64>> {
+        let source_id = source_id.value();
+
+        let row = sqlx::query!(
+            r#"SELECT version FROM source_key_migrations WHERE source_id = ?1"#,
+            source_id
+        )
+        .fetch_optional(&*self.pool.read().await)
+        .await?;
+
+        Ok(row.map(|row| row.version))
+    }
+
+    pub async fn set_key_migration_version(
+        &self,
+        source_id: &SourceId,
+        version: i64,
+    ) -> Result<()> {
+        let source_id = source_id.value();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO source_key_migrations (source_id, version)
+                VALUES (?1, ?2)
+                ON CONFLICT (source_id) DO UPDATE SET version = excluded.version
+            "#,
+            source_id,
+            version
+        )
+        .execute(&*self.pool.read().await)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Moves every row kept about manga `old` over to `new`, for sources that renamed their manga
+    /// IDs: its library entry, playlists, tracking, notifications, cached details and chapters,
+    /// and their read state. Rows `new` already has are kept over the ones of `old`.
+    pub async fn migrate_manga_id(&self, old: &MangaId, new: &MangaId) -> Result<()> {
+        // Every table keyed by manga, along with the columns holding the source and manga IDs.
+        const MANGA_TABLES: [(&str, &str, &str); 13] = [
+            ("manga_library", "source_id", "manga_id"),
+            ("manga_state", "source_id", "manga_id"),
+            ("manga_informations", "source_id", "manga_id"),
+            ("manga_details", "source_id", "id"),
+            ("manga_tags", "source_id", "manga_id"),
+            ("manga_tracking", "source_id", "manga_id"),
+            ("playlist_mangas", "source_id", "manga_id"),
+            ("chapter_informations", "source_id", "manga_id"),
+            ("chapter_state", "source_id", "manga_id"),
+            ("download_progress", "source_id", "manga_id"),
+            ("last_check_update", "source_id", "manga_id"),
+            ("notifications", "source_id", "manga_id"),
+            (
+                "check_update_state",
+                "last_check_batch_source_id",
+                "last_check_batch_cursor",
+            ),
+        ];
+
+        let pool = self.pool.read().await;
+        let mut tx = pool.begin().await?;
+
+        for (table, source_column, column) in MANGA_TABLES {
+            let update = format!(
+                "UPDATE OR IGNORE {table} SET {source_column} = ?3, {column} = ?4 \
+                 WHERE {source_column} = ?1 AND {column} = ?2"
+            );
+            sqlx::query(sqlx::AssertSqlSafe(&*update))
+                .bind(old.source_id().value())
+                .bind(old.value())
+                .bind(new.source_id().value())
+                .bind(new.value())
+                .execute(&mut *tx)
+                .await?;
+
+            // Whatever is left of `old` conflicted with the rows of `new`.
+            let delete =
+                format!("DELETE FROM {table} WHERE {source_column} = ?1 AND {column} = ?2");
+            sqlx::query(sqlx::AssertSqlSafe(&*delete))
+                .bind(old.source_id().value())
+                .bind(old.value())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn count_unread_chapters(&self, manga_id: &MangaId) -> Result<Option<usize>> {
+        // Get preferred scanlators if there are any
+        let preferred_scanlators = self
+            .find_manga_state(manga_id)
+            .await?
+            .map(|state| state.preferred_scanlators)
+            .filter(|scanlators| !scanlators.is_empty())
+            .map(|scanlators| serde_json::to_string(&scanlators))
+            .transpose()?;
+
+        let source_id = manga_id.source_id().value();
+        let manga_id = manga_id.value();
+
+        let row = sqlx::query_as!(
+            UnreadChaptersRow,
+            r#"
+                WITH filtered AS (
+                    SELECT ci.chapter_number, cs.read
+                    FROM chapter_informations ci
+                    LEFT JOIN chapter_state cs
+                        ON ci.source_id = cs.source_id
+                        AND ci.manga_id = cs.manga_id
+                        AND ci.chapter_id = cs.chapter_id
+                    WHERE ci.source_id = ?1
+                    AND ci.manga_id = ?2
+                    AND (?3 IS NULL OR ci.scanlator = (SELECT ps.value FROM json_each(?3) ps WHERE EXISTS (SELECT 1 FROM chapter_informations alt WHERE alt.source_id = ci.source_id AND alt.manga_id = ci.manga_id AND alt.chapter_number IS ci.chapter_number AND alt.scanlator = ps.value) ORDER BY ps.key LIMIT 1) OR ci.scanlator IS NULL)
+                ),
+                max_read AS (
+                    SELECT COALESCE(MAX(chapter_number), -1) AS last_read
+                    FROM filtered
+                    WHERE read = 1
                 )
-                .fetch_all(&*self.pool.read().await)
-                .await?
+                SELECT
+                    (
+                        SELECT COUNT(*)
+                        FROM filtered, max_read
+                        WHERE filtered.chapter_number > max_read.last_read
+                    ) AS count,
+                    EXISTS (SELECT 1 FROM filtered) AS "has_chapters: bool"
+            "#,
+            source_id, manga_id, preferred_scanlators
+        )
+        .fetch_one(&*self.pool.read().await)
+        .await?;
+
+        // Counting happens in a subquery so that a manga whose chapters are all read still
+        // gets a row with a count of 0, which is different from having no chapters at all.
+        if !row.has_chapters.unwrap_or(false) {
+            return Ok(None);
+        }
+
+        Ok(Some(row.count.unwrap_or(0).try_into()?))
+    }
+
+    pub async fn fetch_unread_chapter_counts_minimal(
+        &self,
+        manga_ids: &[MangaId],
+    ) -> Result<HashMap<MangaId, (Option<usize>, Option<i64>, bool)>> {
+        let mut map = HashMap::new();
+
+        if manga_ids.is_empty() {
+            return Ok(map);
+        }
+
+        for batch in manga_ids.chunks(UNREAD_COUNTS_BATCH_SIZE) {
+            let query = unread_chapter_counts_query(batch.len());
+
+            // Bind params
+            let mut query_builder =
+                sqlx::query_as::<_, UnreadChaptersRowFull>(sqlx::AssertSqlSafe(&*query));
+            for id in batch {
+                query_builder = query_builder.bind(id.source_id().value()).bind(id.value());
             }
-            crate::settings::LibrarySortingMode::TitleDesc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
+
+            let rows = query_builder
+                .fetch_all(&*self.pool.read().await)
+                .await
+                .map_err(|e| {
+                    eprintln!("🔥 SQL query failed: {}", e);
+                    e
+                })?;
+
+            for row in rows {
+                let id = MangaId::new(SourceId::new(row.source_id), row.manga_id);
+                map.insert(
+                    id,
+                    (
+                        row.count.map(|v| v as usize),
+                        row.last_time.map(|v| v as i64),
+                        row.in_library,
                     ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY mi.title COLLATE NOCASE DESC
-                    "#
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
+                );
             }
-            crate::settings::LibrarySortingMode::UnreadAsc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY unread_chapters_count ASC
-                    "#
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
+        }
+
+        for id in manga_ids {
+            map.entry(id.clone()).or_insert((None, None, false));
+        }
+
+        Ok(map)
+    }
+
+    pub async fn find_cached_manga_information(
+        &self,
+        manga_id: &MangaId,
+    ) -> Result<Option<MangaInformation>> {
+        let source_id = manga_id.source_id().value();
+        let manga_id = manga_id.value();
+
+        let maybe_row = sqlx::query_as!(
+            MangaInformationsRow,
+            r#"
+                SELECT * FROM manga_informations
+                    WHERE source_id = ?1 AND manga_id = ?2;
+            "#,
+            source_id,
+            manga_id
+        )
+        .fetch_optional(&*self.pool.read().await)
+        .await?;
+
+        Ok(maybe_row.map(|row| row.into()))
+    }
+
+    pub async fn find_cached_chapter_information(
+        &self,
+        chapter_id: &ChapterId,
+    ) -> Result<Option<ChapterInformation>> {
+        let source_id = chapter_id.source_id().value();
+        let manga_id = chapter_id.manga_id().value();
+        let chapter_id = chapter_id.value();
+
+        let maybe_row = sqlx::query_as!(
+            ChapterInformationsRow,
+            r#"
+                SELECT * FROM chapter_informations
+                WHERE source_id = ?1 AND manga_id = ?2 AND chapter_id = ?3;
+            "#,
+            source_id,
+            manga_id,
+            chapter_id
+        )
+        .fetch_optional(&*self.pool.read().await)
+        .await?;
+
+        Ok(maybe_row.map(|row| row.into()))
+    }
+
+    /// Finds downloaded chapter files (and their `.errors` files) that either belong to
+    /// no known chapter (`invalid_mode`) or to a chapter already read, with their sizes.
+    pub async fn find_orphan_or_read_files(
+        &self,
+        chapter_storage: &crate::chapter_storage::ChapterStorage,
+        invalid_mode: bool,
+    ) -> Result<Vec<(PathBuf, u64)>> {
+        let mut files = chapter_storage.collect_all_files_with_sizes();
+
+        if invalid_mode {
+            let pool_lock = self.pool.read().await;
+            let mut stream = sqlx::query_as!(
+                ChapterInformationsRow,
+                r#"SELECT * FROM chapter_informations"#
+            )
+            .fetch(&*pool_lock);
+
+            while let Some(row) = stream.try_next().await? {
+                let id = ChapterId::from_strings(row.source_id, row.manga_id, row.chapter_id);
+
+                for is_novel in [false, true] {
+                    let path = chapter_storage.get_path_to_store_chapter(&id, is_novel, false);
+                    if let Ok(path_file_errors) = chapter_storage.errors_source_path(&path) {
+                        files.remove(&path_file_errors);
+                    }
+                    files.remove(&path);
+                }
             }
-            crate::settings::LibrarySortingMode::UnreadDesc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY unread_chapters_count DESC
-                    "#
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
+
+            Ok(files.into_iter().collect())
+        } else {
+            let mut paths = Vec::new();
+
+            let pool_lock = self.pool.read().await;
+            let mut stream = sqlx::query!(
+                r#"
+                SELECT source_id, manga_id, chapter_id
+                FROM chapter_state
+                WHERE read = 1
+                "#
+            )
+            .fetch(&*pool_lock);
+
+            while let Some(row) = stream.try_next().await? {
+                let id = ChapterId::from_strings(row.source_id, row.manga_id, row.chapter_id);
+
+                for is_novel in [false, true] {
+                    let path = chapter_storage.get_path_to_store_chapter(&id, is_novel, false);
+                    let path_file_errors = chapter_storage.errors_source_path(&path).ok();
+
+                    if let Some(entry) = files.remove_entry(&path) {
+                        paths.push(entry);
+                    }
+                    if let Some(entry) = path_file_errors.and_then(|p| files.remove_entry(&p)) {
+                        paths.push(entry);
+                    }
+                }
             }
-            crate::settings::LibrarySortingMode::LastReadAsc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY lti.last_read_time ASC NULLS LAST
-                    "#
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
-            }
-            crate::settings::LibrarySortingMode::LastReadDesc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY lti.last_read_time DESC NULLS LAST
-                    "#
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
-            }
-            crate::settings::LibrarySortingMode::SourceAsc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY ml.source_id COLLATE NOCASE ASC, mi.title COLLATE NOCASE ASC
-                    "#
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
-            }
-            crate::settings::LibrarySortingMode::SourceDesc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        ml.source_id,
-                        ml.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS unread_chapters_count,
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM manga_library ml
-                    JOIN manga_informations mi
-                        ON mi.source_id = ml.source_id AND mi.manga_id = ml.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = ml.source_id AND ms.manga_id = ml.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = ml.source_id AND md.id = ml.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = ml.source_id AND lr.manga_id = ml.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = ml.source_id AND lti.manga_id = ml.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = ml.source_id
-                        AND ci.manga_id = ml.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    GROUP BY ml.source_id, ml.manga_id, lti.last_read_time
-                    ORDER BY ml.source_id COLLATE NOCASE DESC, mi.title COLLATE NOCASE DESC
-                    "#
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
-            }
-        };
-
-        let mangas = rows
-            .into_iter()
-            .filter_map(|row| {
-                let source = source_collection.get_by_id(&SourceId::new(row.source_id.clone()))?;
-                let info = MangaInformation {
-                    id: MangaId::from_strings(row.source_id, row.manga_id),
-                    title: row.title,
-                    author: row.author,
-                    artist: row.artist,
-                    cover_url: row.cover_url.and_then(|url| Url::parse(&url).ok()),
-                    viewer: MangaViewer::from(row.viewer.unwrap_or(0) as u8),
-                };
-
-                Some(Manga {
-                    source_information: SourceInformation::from(source.manifest()),
-                    information: info,
-                    state: MangaState::default(),
-                    unread_chapters_count: row.unread_chapters_count.map(|v| v as usize),
-                    last_read: row.last_read,
-                    in_library: true,
-                    state_viewer: row.state_viewer != 0,
-                })
-            })
-            .collect();
 
-        Ok(mangas)
+            Ok(paths)
+        }
     }
 
-    pub async fn add_manga_to_library(&self, manga_id: MangaId) -> Result<()> {
+    pub async fn find_cached_chapter_ids(
+        &self,
+        manga_id: &MangaId,
+    ) -> anyhow::Result<HashSet<ChapterId>> {
         let source_id = manga_id.source_id().value();
-        let manga_id = manga_id.value();
-
-        sqlx::query!(
-            r#"
-                INSERT INTO manga_library (source_id, manga_id)
-                VALUES (?1, ?2)
-                ON CONFLICT DO NOTHING
-            "#,
-            source_id,
-            manga_id
-        )
-        .execute(&*self.pool.read().await)
-        .await?;
-
-        Ok(())
-    }
-
-    pub async fn remove_manga_from_library(&self, manga_id: MangaId) -> Result<()> {
-        let source_id = manga_id.source_id().value();
-        let manga_id = manga_id.value();
+        let manga_id_value = manga_id.value();
 
-        sqlx::query!(
+        let rows = sqlx::query!(
             r#"
-                DELETE FROM manga_library
+                SELECT chapter_id
+                FROM chapter_informations
                 WHERE source_id = ?1 AND manga_id = ?2
             "#,
             source_id,
-            manga_id
-        )
-        .execute(&*self.pool.read().await)
-        .await?;
-
-        Ok(())
-    }
-
-    pub async fn count_unread_chapters(&self, manga_id: &MangaId) -> Result<Option<usize>> {
-        // Get preferred scanlator if it exists
-        let preferred_scanlator = self
-            .find_manga_state(manga_id)
-            .await?
-            .and_then(|state| state.preferred_scanlator);
-
-        let source_id = manga_id.source_id().value();
-        let manga_id = manga_id.value();
-
-        let row = sqlx::query_as!(
-            UnreadChaptersRow,
-            r#"
-                WITH filtered AS (
-                    SELECT ci.chapter_number, cs.read
-                    FROM chapter_informations ci
-                    LEFT JOIN chapter_state cs
-                        ON ci.source_id = cs.source_id
-                        AND ci.manga_id = cs.manga_id
-                        AND ci.chapter_id = cs.chapter_id
-                    WHERE ci.source_id = ?1
-                    AND ci.manga_id = ?2
-                    AND (?3 IS NULL OR ci.scanlator = ?3 OR ci.scanlator IS NULL)
-                ),
-                max_read AS (
-                    SELECT COALESCE(MAX(chapter_number), -1) AS last_read
-                    FROM filtered
-                    WHERE read = 1
-                )
-                SELECT
-                    COUNT(*) AS count,
-                    CASE WHEN EXISTS (SELECT 1 FROM filtered) THEN 1 ELSE 0 END AS "has_chapters: bool"
-                FROM filtered, max_read
-                WHERE filtered.chapter_number > max_read.last_read
-            "#,
-            source_id, manga_id, preferred_scanlator
-        )
-        .fetch_one(&*self.pool.read().await)
-        .await?;
-
-        if !row.has_chapters.unwrap_or(false) {
-            return Ok(None);
-        }
-
-        Ok(row.count.and_then(|count| count.try_into().ok()))
-    }
-
-    pub async fn fetch_unread_chapter_counts_minimal(
-        &self,
-        manga_ids: &[MangaId],
-    ) -> Result<HashMap<MangaId, (Option<usize>, Option<i64>, bool)>> {
-        let mut map = HashMap::new();
-
-        if manga_ids.is_empty() {
-            return Ok(map);
-        }
-
-        // Build dynamic SQL placeholders
-        let pairs: Vec<String> = manga_ids.iter().map(|_| "(?, ?)".into()).collect();
-        let in_clause = pairs.join(", ");
-
-        let query = format!(
-            r#"
-            WITH inputs(source_id, manga_id) AS (
-                VALUES
-                {}
-            ),
-            filtered AS (
-                SELECT
-                    i.source_id,
-                    i.manga_id,
-                    ci.chapter_number,
-                    cs.read,
-                    cs.last_read as last_time
-                FROM inputs i
-                LEFT JOIN chapter_informations ci
-                    ON ci.source_id = i.source_id AND ci.manga_id = i.manga_id
-                LEFT JOIN chapter_state cs
-                    ON ci.source_id = cs.source_id
-                    AND ci.manga_id = cs.manga_id
-                    AND ci.chapter_id = cs.chapter_id
-            ),
-            max_read AS (
-                SELECT
-                    source_id,
-                    manga_id,
-                    COALESCE(MAX(CASE WHEN read = 1 THEN chapter_number END), -1) AS last_read,
-                    COALESCE(MAX(last_time), NULL) AS last_read_time
-                FROM filtered
-                GROUP BY source_id, manga_id
-            )
-            SELECT
-                i.source_id,
-                i.manga_id,
-                COALESCE(COUNT(f.chapter_number), -1) AS count,
-                mr.last_read_time AS last_time,
-                CASE WHEN ml.source_id IS NOT NULL THEN TRUE ELSE FALSE END AS in_library
-            FROM inputs i
-            LEFT JOIN filtered f
-                ON i.source_id = f.source_id AND i.manga_id = f.manga_id
-                AND f.chapter_number > COALESCE((SELECT last_read FROM max_read mr2 WHERE mr2.source_id = i.source_id AND mr2.manga_id = i.manga_id), -1)
-            LEFT JOIN max_read mr
-                ON i.source_id = mr.source_id AND i.manga_id = mr.manga_id
-            LEFT JOIN manga_library ml
-                ON i.source_id = ml.source_id AND i.manga_id = ml.manga_id
-            GROUP BY i.source_id, i.manga_id, mr.last_read_time, in_library
-            "#,
-            in_clause
-        );
-
-        // Bind params
-        let mut query_builder =
-            sqlx::query_as::<_, UnreadChaptersRowFull>(sqlx::AssertSqlSafe(&*query));
-        for id in manga_ids {
-            query_builder = query_builder.bind(id.source_id().value()).bind(id.value());
-        }
-
-        let rows = query_builder
-            .fetch_all(&*self.pool.read().await)
-            .await
-            .map_err(|e| {
-                eprintln!("🔥 SQL query failed: {}", e);
-                e
-            })?;
-
-        for row in rows {
-            let id = MangaId::new(SourceId::new(row.source_id), row.manga_id);
-            map.insert(
-                id,
-                (
-                    row.count.map(|v| v as usize),
-                    row.last_time.map(|v| v as i64),
-                    row.in_library,
-                ),
-            );
-        }
-
-        for id in manga_ids {
-            map.entry(id.clone()).or_insert((None, None, false));
-        }
-
-        Ok(map)
-    }
-
-    pub async fn find_cached_manga_information(
-        &self,
-        manga_id: &MangaId,
-    ) -> Result<Option<MangaInformation>> {
-        let source_id = manga_id.source_id().value();
-        let manga_id = manga_id.value();
-
-        let maybe_row = sqlx::query_as!(
-            MangaInformationsRow,
-            r#"
-                SELECT * FROM manga_informations
-                    WHERE source_id = ?1 AND manga_id = ?2;
-            "#,
-            source_id,
-            manga_id
-        )
-        .fetch_optional(&*self.pool.read().await)
-        .await?;
-
-        Ok(maybe_row.map(|row| row.into()))
-    }
-
-    pub async fn find_cached_chapter_information(
-        &self,
-        chapter_id: &ChapterId,
-    ) -> Result<Option<ChapterInformation>> {
-        let source_id = chapter_id.source_id().value();
-        let manga_id = chapter_id.manga_id().value();
-        let chapter_id = chapter_id.value();
-
-        let maybe_row = sqlx::query_as!(
-            ChapterInformationsRow,
-            r#"
-                SELECT * FROM chapter_informations
-                WHERE source_id = ?1 AND manga_id = ?2 AND chapter_id = ?3;
-            "#,
-            source_id,
-            manga_id,
-            chapter_id
+            manga_id_value
         )
-        .fetch_optional(&*self.pool.read().await)
+        .fetch_all(&*self.pool.read().await)
         .await?;
 
-        Ok(maybe_row.map(|row| row.into()))
-    }
-
-    pub async fn find_orphan_or_read_files(
-        &self,
-        chapter_storage: &crate::chapter_storage::ChapterStorage,
-        invalid_mode: bool,
-    ) -> Result<Vec<PathBuf>> {
-        if invalid_mode {
-            let mut remaining = chapter_storage.collect_all_files(1);
-
-            let pool_lock = self.pool.read().await;
-            let mut stream = sqlx::query_as!(
-                ChapterInformationsRow,
-                r#"SELECT * FROM chapter_informations"#
-            )
-            .fetch(&*pool_lock);
-
-            while let Some(row) = stream.try_next().await? {
-                let id = ChapterId::from_strings(row.source_id, row.manga_id, row.chapter_id);
-
-                for is_novel in [false, true] {
-                    let path = chapter_storage.get_path_to_store_chapter(&id, is_novel, false);
-                    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
-                        remaining.remove(&path);
-                    }
-
-                    let Some(path_file_errors) = chapter_storage.errors_source_path(&path).ok()
-                    else {
-                        continue;
-                    };
-                    if tokio::fs::try_exists(&path_file_errors)
-                        .await
-                        .unwrap_or(false)
-                    {
-                        remaining.remove(&path_file_errors);
-                    }
-                }
-            }
-
-            Ok(remaining.into_iter().collect())
-        } else {
-            let mut paths = Vec::new();
-
-            let pool_lock = self.pool.read().await;
-            let mut stream = sqlx::query!(
-                r#"
-                SELECT source_id, manga_id, chapter_id
-                FROM chapter_state
-                WHERE read = 1
-                "#
-            )
-            .fetch(&*pool_lock);
-
-            while let Some(row) = stream.try_next().await? {
-                let id = ChapterId::from_strings(row.source_id, row.manga_id, row.chapter_id);
-
-                for is_novel in [false, true] {
-                    let path = chapter_storage.get_path_to_store_chapter(&id, is_novel, false);
-                    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
-                        paths.push(path.clone());
-                    }
-
-                    let Some(path_file_errors) = chapter_storage.errors_source_path(&path).ok()
-                    else {
-                        continue;
-                    };
-                    if tokio::fs::try_exists(&path_file_errors)
-                        .await
-                        .unwrap_or(false)
-                    {
-                        paths.push(path_file_errors);
-                    }
-                }
-            }
-
-            Ok(paths)
-        }
+        Ok(rows
+            .into_iter()
+            .map(|row| ChapterId::new(manga_id.clone(), row.chapter_id))
+            .collect())
     }
 
-    pub async fn find_cached_chapter_ids(
-        &self,
-        manga_id: &MangaId,
-    ) -> anyhow::Result<HashSet<ChapterId>> {
-        let source_id = manga_id.source_id().value();
-        let manga_id_value = manga_id.value();
-
+    /// IDs of every chapter that is known, either from a chapter list or from having been
+    /// opened.
+    pub async fn find_all_chapter_ids(&self) -> Result<Vec<ChapterId>> {
         let rows = sqlx::query!(
             r#"
-                SELECT chapter_id
+                SELECT source_id AS "source_id!", manga_id AS "manga_id!", chapter_id AS "chapter_id!"
                 FROM chapter_informations
-                WHERE source_id = ?1 AND manga_id = ?2
-            "#,
-            source_id,
-            manga_id_value
+                UNION
+                SELECT source_id, manga_id, chapter_id
+                FROM chapter_state
+            "#
         )
         .fetch_all(&*self.pool.read().await)
         .await?;
 
         Ok(rows
             .into_iter()
-            .map(|row| ChapterId::new(manga_id.clone(), row.chapter_id))
+            .map(|row| ChapterId::from_strings(row.source_id, row.manga_id, row.chapter_id))
             .collect())
     }
 
@@ -1327,6 +927,7 @@ impl Database {
         manga_id: &MangaId,
         chapter_storage: &crate::chapter_storage::ChapterStorage,
         ram_mode_enabled: bool,
+        read: Option<bool>,
     ) -> Result<Vec<Chapter>> {
         let source_id = manga_id.source_id().value();
         let manga_id_val = manga_id.value();
@@ -1344,20 +945,24 @@ impl Database {
                 ci.last_updated,
                 ci.thumbnail,
                 ci.lang,
+                ci.url,
                 ci.locked AS "locked: bool",
                 cs.read AS "read?: bool",
-                cs.last_read AS "last_read?: i64"
+                cs.last_read AS "last_read?: i64",
+                cs.last_read_page AS "last_read_page?: i32"
             FROM chapter_informations ci
             LEFT JOIN chapter_state cs
                 ON ci.source_id = cs.source_id
                 AND ci.manga_id = cs.manga_id
                 AND ci.chapter_id = cs.chapter_id
             WHERE ci.source_id = ?1 AND ci.manga_id = ?2
+                AND (?3 IS NULL OR COALESCE(cs.read, 0) = ?3)
             GROUP BY ci.source_id, ci.manga_id, ci.chapter_id
             ORDER BY ci.manga_order ASC;
             "#,
             source_id,
             manga_id_val,
+            read,
         )
         .fetch_all(&*self.pool.read().await)
         .await?;
@@ -1382,13 +987,14 @@ impl Database {
                     thumbnail: row.thumbnail.and_then(|s| Url::parse(&s).ok()),
                     lang: row.lang,
 
-                    url: None,
+                    url: row.url.and_then(|s| Url::parse(&s).ok()),
                     locked: Some(row.locked),
                 };
 
                 let state = ChapterState {
                     read: row.read.unwrap_or(false),
                     last_read: row.last_read,
+                    last_read_page: row.last_read_page,
                 };
 
                 let mut downloaded = chapter_storage.get_stored_chapter(&id, false).is_some();
@@ -1451,9 +1057,9 @@ impl Database {
             for info in chunk {
                 query = query.bind(info.id.source_id().value());
                 query = query.bind(info.id.value());
-                query = query.bind(&info.title);
-                query = query.bind(&info.author);
-                query = query.bind(&info.artist);
+                query = query.bind(truncate_manga_field(&info.title, "title", &info.id));
+                query = query.bind(truncate_manga_field(&info.author, "author", &info.id));
+                query = query.bind(truncate_manga_field(&info.artist, "artist", &info.id));
                 query = query.bind(info.cover_url.as_ref().map(|url| url.to_string()));
             }
 
@@ -1495,12 +1101,12 @@ impl Database {
             builder.build().execute(&*self.pool.read().await).await?;
         }
 
-        const INSERT_FIELD_COUNT: usize = 8;
+        const INSERT_FIELD_COUNT: usize = 13;
         const CHUNK_SIZE: usize = BIND_LIMIT / INSERT_FIELD_COUNT;
 
         for (offset, chunk) in chapter_informations.chunks(CHUNK_SIZE).enumerate() {
             let mut builder = QueryBuilder::new(
-            "INSERT INTO chapter_informations (source_id, manga_id, chapter_id, manga_order, title, scanlator, chapter_number, volume_number, last_updated, thumbnail, lang, locked)"
+            "INSERT INTO chapter_informations (source_id, manga_id, chapter_id, manga_order, title, scanlator, chapter_number, volume_number, last_updated, thumbnail, lang, url, locked)"
             );
 
             builder.push_values(chunk.iter().enumerate(), |mut b, (i, info)| {
@@ -1519,6 +1125,7 @@ impl Database {
                     .push_bind(last_updated)
                     .push_bind(info.thumbnail.as_ref().map(|s| s.to_string()))
                     .push_bind(info.lang.as_ref().map(|s| s.to_string()))
+                    .push_bind(info.url.as_ref().map(|s| s.to_string()))
                     .push_bind(if info.locked.unwrap_or_default() {
                         1
                     } else {
@@ -1533,7 +1140,8 @@ impl Database {
                 scanlator = excluded.scanlator,
                 chapter_number = excluded.chapter_number,
                 volume_number = excluded.volume_number,
-                last_updated = excluded.last_updated",
+                last_updated = COALESCE(excluded.last_updated, chapter_informations.last_updated),
+                url = excluded.url",
             );
 
             builder.build().execute(&*self.pool.read().await).await?;
@@ -1651,7 +1259,7 @@ impl Database {
                 viewer       = excluded.viewer,
                 last_updated = excluded.last_updated,
                 last_opened  = excluded.last_opened,
-                date_added   = excluded.date_added
+                date_added   = COALESCE(excluded.date_added, manga_details.date_added)
             "#,
             source_id,
             id,
@@ -1675,6 +1283,49 @@ impl Database {
         Ok(())
     }
 
+    /// Every tag of the mangas in the library, deduplicated and sorted.
+    pub async fn get_library_tags(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT md.tags AS "tags!"
+            FROM manga_details md
+            JOIN manga_library ml
+                ON ml.source_id = md.source_id AND ml.manga_id = md.id
+            WHERE md.tags IS NOT NULL
+            "#
+        )
+        .fetch_all(&*self.pool.read().await)
+        .await?;
+
+        let tags: BTreeSet<String> = rows
+            .into_iter()
+            .filter_map(|row| serde_json::from_str::<Vec<String>>(&row.tags).ok())
+            .flatten()
+            .collect();
+
+        Ok(tags.into_iter().collect())
+    }
+
+    /// Every cached manga tagged with `tag`, matched exactly.
+    pub async fn find_mangas_by_tag(&self, tag: &str) -> Result<Vec<MangaId>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT source_id, manga_id
+            FROM manga_tags
+            WHERE tag = ?1
+            ORDER BY source_id, manga_id
+            "#,
+            tag
+        )
+        .fetch_all(&*self.pool.read().await)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| MangaId::new(SourceId::new(row.source_id), row.manga_id))
+            .collect())
+    }
+
     pub async fn find_manga_state(&self, manga_id: &MangaId) -> Result<Option<MangaState>> {
         let source_id = manga_id.source_id().value();
         let manga_id = manga_id.value();
@@ -1682,7 +1333,7 @@ impl Database {
         let maybe_row = sqlx::query_as!(
             MangaStateRow,
             r#"
-                SELECT source_id, manga_id, preferred_scanlator
+                SELECT source_id, manga_id, preferred_scanlators
                 FROM manga_state
                 WHERE source_id = ?1 AND manga_id = ?2;
             "#,
@@ -1699,16 +1350,23 @@ impl Database {
         let source_id = manga_id.source_id().value();
         let manga_id = manga_id.value();
 
-        sqlx::query!(
-            r#"
-                INSERT INTO manga_state (source_id, manga_id, preferred_scanlator)
-                VALUES (?1, ?2, ?3)
+        // An empty list is stored as NULL, so that queries can tell "no preference" apart cheaply.
+        let preferred_scanlators = if state.preferred_scanlators.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&state.preferred_scanlators)?)
+        };
+
+        sqlx::query!(
+            r#"
+                INSERT INTO manga_state (source_id, manga_id, preferred_scanlators)
+                VALUES (?1, ?2, ?3)
                 ON CONFLICT DO UPDATE SET
-                    preferred_scanlator = excluded.preferred_scanlator
+                    preferred_scanlators = excluded.preferred_scanlators
             "#,
             source_id,
             manga_id,
-            state.preferred_scanlator
+            preferred_scanlators
         )
         .execute(&*self.pool.read().await)
         .await?;
@@ -1737,6 +1395,138 @@ impl Database {
         Ok(())
     }
 
+    pub async fn find_manga_arima_spec(
+        &self,
+        manga_id: &MangaId,
+    ) -> Result<Option<ArimaSpecOverride>> {
+        let source_id = manga_id.source_id().value();
+        let manga_id = manga_id.value();
+
+        let maybe_row = sqlx::query!(
+            r#"
+                SELECT arima_spec
+                FROM manga_state
+                WHERE source_id = ?1 AND manga_id = ?2;
+            "#,
+            source_id,
+            manga_id,
+        )
+        .fetch_optional(&*self.pool.read().await)
+        .await?;
+
+        let Some(json) = maybe_row.and_then(|row| row.arima_spec) else {
+            return Ok(None);
+        };
+
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    pub async fn set_manga_arima_spec(
+        &self,
+        manga_id: &MangaId,
+        spec: Option<&ArimaSpecOverride>,
+    ) -> Result<()> {
+        let source_id = manga_id.source_id().value();
+        let manga_id = manga_id.value();
+        let arima_spec = spec.map(serde_json::to_string).transpose()?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO manga_state (source_id, manga_id, arima_spec)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT DO UPDATE SET
+                    arima_spec = excluded.arima_spec
+            "#,
+            source_id,
+            manga_id,
+            arima_spec,
+        )
+        .execute(&*self.pool.read().await)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_download_progress(
+        &self,
+        chapter_id: &ChapterId,
+    ) -> Result<Option<DownloadProgress>> {
+        let source_id = chapter_id.source_id().value();
+        let manga_id = chapter_id.manga_id().value();
+        let chapter_id = chapter_id.value();
+
+        let maybe_row = sqlx::query_as!(
+            DownloadProgressRow,
+            r#"
+                SELECT pages_total, pages_done, started_at, status FROM download_progress
+                WHERE source_id = ?1 AND manga_id = ?2 AND chapter_id = ?3;
+            "#,
+            source_id,
+            manga_id,
+            chapter_id,
+        )
+        .fetch_optional(&*self.pool.read().await)
+        .await?;
+
+        maybe_row.map(DownloadProgress::try_from).transpose()
+    }
+
+    pub async fn upsert_download_progress(
+        &self,
+        chapter_id: &ChapterId,
+        progress: &DownloadProgress,
+    ) -> Result<()> {
+        let source_id = chapter_id.source_id().value();
+        let manga_id = chapter_id.manga_id().value();
+        let chapter_id = chapter_id.value();
+        let pages_total = progress.pages_total as i64;
+        let pages_done = progress.pages_done as i64;
+        let status = progress.status.as_str();
+
+        sqlx::query!(
+            r#"
+                INSERT INTO download_progress (source_id, manga_id, chapter_id, pages_total, pages_done, started_at, status)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT DO UPDATE SET
+                    pages_total = excluded.pages_total,
+                    pages_done = excluded.pages_done,
+                    started_at = excluded.started_at,
+                    status = excluded.status
+            "#,
+            source_id,
+            manga_id,
+            chapter_id,
+            pages_total,
+            pages_done,
+            progress.started_at,
+            status,
+        )
+        .execute(&*self.pool.read().await)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_download_progress(&self, chapter_id: &ChapterId) -> Result<()> {
+        let source_id = chapter_id.source_id().value();
+        let manga_id = chapter_id.manga_id().value();
+        let chapter_id = chapter_id.value();
+
+        sqlx::query!(
+            r#"
+                DELETE FROM download_progress
+                WHERE source_id = ?1 AND manga_id = ?2 AND chapter_id = ?3;
+            "#,
+            source_id,
+            manga_id,
+            chapter_id,
+        )
+        .execute(&*self.pool.read().await)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn find_chapter_state(&self, chapter_id: &ChapterId) -> Result<Option<ChapterState>> {
         let source_id = chapter_id.source_id().value();
         let manga_id = chapter_id.manga_id().value();
@@ -1747,7 +1537,7 @@ impl Database {
         let maybe_row = sqlx::query_as!(
             ChapterStateRow,
             r#"
-                SELECT source_id, manga_id, chapter_id, read AS "read: bool", last_read AS "last_read?: i64" FROM chapter_state
+                SELECT source_id, manga_id, chapter_id, read AS "read: bool", last_read AS "last_read?: i64", last_read_page AS "last_read_page?: i32" FROM chapter_state
                 WHERE source_id = ?1 AND manga_id = ?2 AND chapter_id = ?3;
             "#,
             source_id,
@@ -1770,7 +1560,7 @@ impl Database {
         let rows = sqlx::query_as!(
             ChapterStateRow,
             r#"
-                SELECT source_id, manga_id, chapter_id, read AS "read: bool", last_read AS "last_read?: i64" FROM chapter_state
+                SELECT source_id, manga_id, chapter_id, read AS "read: bool", last_read AS "last_read?: i64", last_read_page AS "last_read_page?: i32" FROM chapter_state
                 WHERE source_id = ?1 AND manga_id = ?2;
             "#,
             source_id,
@@ -1796,17 +1586,19 @@ impl Database {
 
         sqlx::query!(
             r#"
-                INSERT INTO chapter_state (source_id, manga_id, chapter_id, read, last_read)
-                VALUES (?1, ?2, ?3, ?4, ?5)
+                INSERT INTO chapter_state (source_id, manga_id, chapter_id, read, last_read, last_read_page)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
                 ON CONFLICT DO UPDATE SET
                     read = excluded.read,
-                    last_read = excluded.last_read
+                    last_read = excluded.last_read,
+                    last_read_page = excluded.last_read_page
             "#,
             source_id,
             manga_id,
             chapter_id,
             state.read,
             state.last_read,
+            state.last_read_page,
         )
         .execute(&*self.pool.read().await)
         .await?;
@@ -1849,7 +1641,13 @@ impl Database {
         Ok(())
     }
 
-    pub async fn update_last_read_chapter(&self, id: &ChapterId) -> Result<()> {
+    /// Records that the chapter was just read, at `last_read_page` if given. Without a page
+    /// the previously stored one is kept.
+    pub async fn update_last_read_chapter(
+        &self,
+        id: &ChapterId,
+        last_read_page: Option<i32>,
+    ) -> Result<()> {
         let now = chrono::Utc::now().timestamp();
 
         let source_id = id.source_id().value();
@@ -1858,15 +1656,17 @@ impl Database {
 
         sqlx::query!(
             r#"
-            INSERT INTO chapter_state (source_id, manga_id, chapter_id, read, last_read)
-            VALUES (?1, ?2, ?3, FALSE, ?4)
+            INSERT INTO chapter_state (source_id, manga_id, chapter_id, read, last_read, last_read_page)
+            VALUES (?1, ?2, ?3, FALSE, ?4, ?5)
             ON CONFLICT DO UPDATE SET
-                last_read = excluded.last_read
+                last_read = excluded.last_read,
+                last_read_page = COALESCE(excluded.last_read_page, chapter_state.last_read_page)
         "#,
             source_id,
             manga_id,
             chapter_id,
             now,
+            last_read_page,
         )
         .execute(&*self.pool.read().await)
         .await?;
@@ -1889,14 +1689,52 @@ impl Database {
         Ok(maybe_row.map(|row| (row.last_check, row.next_ts_arima)))
     }
 
+    /// Returns every tracked manga with its last and next update check, soonest check first.
+    pub async fn get_update_schedule(&self) -> Result<Vec<(MangaId, Option<String>, i64, i64)>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                lcu.source_id,
+                lcu.manga_id,
+                COALESCE(md.title, mi.title) AS "manga_title?: String",
+                lcu.last_check,
+                COALESCE(lcu.next_ts_arima_lower, lcu.next_ts_arima) AS "next_check!: i64"
+            FROM
+                last_check_update lcu
+            LEFT JOIN manga_informations mi
+                ON mi.manga_id = lcu.manga_id AND mi.source_id = lcu.source_id
+            LEFT JOIN manga_details md
+                ON md.id = lcu.manga_id AND md.source_id = lcu.source_id
+            ORDER BY
+                COALESCE(lcu.next_ts_arima_lower, lcu.next_ts_arima) ASC
+            "#,
+        )
+        .fetch_all(&*self.pool.read().await)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    MangaId::from_strings(row.source_id, row.manga_id),
+                    row.manga_title,
+                    row.last_check,
+                    row.next_check,
+                )
+            })
+            .collect())
+    }
+
     pub async fn set_last_check_update_manga(
         &self,
         id: &MangaId,
         value: i64,
         next_ts_arima: i64,
+        next_ts_arima_interval: Option<(i64, i64)>,
     ) -> Result<()> {
         let source_id = id.source_id().value();
         let manga_id = id.value();
+        let (next_ts_arima_lower, next_ts_arima_upper) = next_ts_arima_interval.unzip();
 
         sqlx::query!(
             r#"
@@ -1904,16 +1742,22 @@ impl Database {
                 source_id,
                 manga_id,
                 last_check,
-                next_ts_arima
-            ) VALUES (?1, ?2, ?3, ?4)
+                next_ts_arima,
+                next_ts_arima_lower,
+                next_ts_arima_upper
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
             ON CONFLICT(source_id, manga_id) DO UPDATE SET
                 last_check = excluded.last_check,
-                next_ts_arima = excluded.next_ts_arima
+                next_ts_arima = excluded.next_ts_arima,
+                next_ts_arima_lower = excluded.next_ts_arima_lower,
+                next_ts_arima_upper = excluded.next_ts_arima_upper
             "#,
             source_id,
             manga_id,
             value,
-            next_ts_arima
+            next_ts_arima,
+            next_ts_arima_lower,
+            next_ts_arima_upper
         )
         .execute(&*self.pool.read().await)
         .await?;
@@ -1938,6 +1782,52 @@ impl Database {
         Ok(())
     }
 
+    /// The last manga processed by an unfinished run of `check_mangas_update`.
+    pub async fn get_check_update_cursor(&self) -> Result<Option<MangaId>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT last_check_batch_source_id, last_check_batch_cursor
+            FROM check_update_state
+            WHERE id = 0
+            "#
+        )
+        .fetch_optional(&*self.pool.read().await)
+        .await?;
+
+        Ok(row.map(|row| {
+            MangaId::from_strings(row.last_check_batch_source_id, row.last_check_batch_cursor)
+        }))
+    }
+
+    pub async fn set_check_update_cursor(&self, id: &MangaId) -> Result<()> {
+        let source_id = id.source_id().value();
+        let manga_id = id.value();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO check_update_state (id, last_check_batch_source_id, last_check_batch_cursor)
+            VALUES (0, ?1, ?2)
+            ON CONFLICT (id) DO UPDATE SET
+                last_check_batch_source_id = excluded.last_check_batch_source_id,
+                last_check_batch_cursor = excluded.last_check_batch_cursor
+            "#,
+            source_id,
+            manga_id
+        )
+        .execute(&*self.pool.read().await)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear_check_update_cursor(&self) -> Result<()> {
+        sqlx::query!("DELETE FROM check_update_state")
+            .execute(&*self.pool.read().await)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn insert_notification(
         &self,
         manga_id: &MangaId,
@@ -1994,13 +1884,15 @@ impl Database {
             )
         };
 
+        // The lower bound of the prediction interval, when known, so that the check happens
+        // early rather than late.
         let sql = format!(
             r#"
-            SELECT manga_id, source_id, next_ts_arima
+            SELECT manga_id, source_id, COALESCE(next_ts_arima_lower, next_ts_arima) AS next_ts_arima
             FROM last_check_update
             WHERE next_ts_arima IS NOT NULL
             {condition}
-            ORDER BY next_ts_arima ASC
+            ORDER BY COALESCE(next_ts_arima_lower, next_ts_arima) ASC
             LIMIT 1
             "#
         );
@@ -2036,7 +1928,7 @@ impl Database {
             LEFT JOIN manga_details md
             ON ml.manga_id = md.id AND
                 ml.source_id = md.source_id
-            WHERE ml.next_ts_arima <= ?1
+            WHERE COALESCE(ml.next_ts_arima_lower, ml.next_ts_arima) <= ?1
             "#,
             now
         )
@@ -2074,7 +1966,16 @@ impl Database {
         Ok(value.count)
     }
 
-    pub async fn get_notifications(&self) -> Result<Vec<NotificationInformation>> {
+    /// Returns up to `limit` unread notifications, newest first. `before_id` is the
+    /// `next_cursor` of the previous page, if any. Notifications are ordered by ID rather than
+    /// by `created_at`, since that's what the cursor compares.
+    pub async fn get_notifications(
+        &self,
+        before_id: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<Vec<NotificationInformation>> {
+        // A negative LIMIT means no limit to SQLite
+        let limit = limit.unwrap_or(-1);
         let rows = sqlx::query_as!(
             NotificationInformationRow,
             r#"
@@ -2099,9 +2000,13 @@ impl Database {
                 ON ci.manga_id = n.manga_id AND ci.source_id = n.source_id AND ci.chapter_id = n.chapter_id
             WHERE
                 n.is_read = 0
+                AND (?1 IS NULL OR n.id < ?1)
             ORDER BY
-                n.created_at DESC
+                n.id DESC
+            LIMIT ?2
             "#,
+            before_id,
+            limit,
         )
         .fetch_all(&*self.pool.read().await)
         .await?;
@@ -2358,8 +2263,8 @@ impl Database {
             WHERE ci.source_id = ?1
                 AND ci.manga_id = ?2
                 AND (
-                    ms.preferred_scanlator IS NULL
-                    OR ci.scanlator = ms.preferred_scanlator
+                    ms.preferred_scanlators IS NULL
+                    OR ci.scanlator = (SELECT ps.value FROM json_each(ms.preferred_scanlators) ps WHERE EXISTS (SELECT 1 FROM chapter_informations alt WHERE alt.source_id = ci.source_id AND alt.manga_id = ci.manga_id AND alt.chapter_number IS ci.chapter_number AND alt.scanlator = ps.value) ORDER BY ps.key LIMIT 1)
                     OR ci.scanlator IS NULL
                 )
             "#,
@@ -2442,778 +2347,117 @@ impl Database {
         let source_id = manga_id.source_id().value();
         let manga_id = manga_id.value();
 
-        sqlx::query!("DELETE FROM playlist_mangas WHERE playlist_id = ?1 AND source_id = ?2 AND manga_id = ?3", playlist_id, source_id, manga_id)
-            .execute(&*self.pool.read().await)
-            .await?;
-
-        Ok(())
-    }
-
-    pub async fn get_manga_library_in_playlist_with_read_count(
-        &self,
-        playlist_id: i64,
-        source_collection: &impl SourceCollection,
-        library_sorting_mode: &crate::settings::LibrarySortingMode,
-    ) -> Result<Vec<Manga>> {
-        let rows = match *library_sorting_mode {
-            crate::settings::LibrarySortingMode::Ascending => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY pm.rowid
-                    "#,
-                    playlist_id
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
-            }
-             crate::settings::LibrarySortingMode::Descending => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY pm.rowid DESC
-                    "#,
-                    playlist_id
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
-            }
-            crate::settings::LibrarySortingMode::TitleAsc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY mi.title COLLATE NOCASE ASC
-                    "#,
-                    playlist_id
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
-            }
-            crate::settings::LibrarySortingMode::TitleDesc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY mi.title COLLATE NOCASE DESC
-                    "#,
-                    playlist_id
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
-            }
-            crate::settings::LibrarySortingMode::UnreadAsc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY 7 ASC
-                    "#,
-                    playlist_id
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
+        sqlx::query!("DELETE FROM playlist_mangas WHERE playlist_id = ?1 AND source_id = ?2 AND manga_id = ?3", playlist_id, source_id, manga_id)
+            .execute(&*self.pool.read().await)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_manga_library_in_playlist_with_read_count(
+        &self,
+        playlist_id: i64,
+        source_collection: &impl SourceCollection,
+        library_sorting_mode: &crate::settings::LibrarySortingMode,
+    ) -> Result<Vec<Manga>> {
+        use crate::settings::LibrarySortingMode;
+
+        let pool = self.pool.read().await;
+        let rows = match *library_sorting_mode {
+            LibrarySortingMode::Ascending => {
+                library_query!(in_playlist(playlist_id), "ml.rowid")
+                    .fetch_all(&*pool)
+                    .await?
             }
-            crate::settings::LibrarySortingMode::UnreadDesc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY 7 DESC
-                    "#,
-                    playlist_id
-                )
-                .fetch_all(&*self.pool.read().await)
-                .await?
+            LibrarySortingMode::Descending => {
+                library_query!(in_playlist(playlist_id), "ml.rowid DESC")
+                    .fetch_all(&*pool)
+                    .await?
             }
-            crate::settings::LibrarySortingMode::LastReadAsc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY lti.last_read_time ASC NULLS LAST
-                    "#,
-                    playlist_id
+            LibrarySortingMode::TitleAsc => {
+                library_query!(in_playlist(playlist_id), "mi.title COLLATE NOCASE ASC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::TitleDesc => {
+                library_query!(in_playlist(playlist_id), "mi.title COLLATE NOCASE DESC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::UnreadAsc => {
+                library_query!(in_playlist(playlist_id), "unread_chapters_count ASC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::UnreadDesc => {
+                library_query!(in_playlist(playlist_id), "unread_chapters_count DESC")
+                    .fetch_all(&*pool)
+                    .await?
+            }
+            LibrarySortingMode::LastReadAsc => {
+                library_query!(
+                    in_playlist(playlist_id),
+                    "lti.last_read_time ASC NULLS LAST"
                 )
-                .fetch_all(&*self.pool.read().await)
+                .fetch_all(&*pool)
                 .await?
             }
-            crate::settings::LibrarySortingMode::LastReadDesc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY lti.last_read_time DESC NULLS LAST
-                    "#,
-                    playlist_id
+            LibrarySortingMode::LastReadDesc => {
+                library_query!(
+                    in_playlist(playlist_id),
+                    "lti.last_read_time DESC NULLS LAST"
                 )
-                .fetch_all(&*self.pool.read().await)
+                .fetch_all(&*pool)
                 .await?
             }
-            crate::settings::LibrarySortingMode::SourceAsc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY pm.source_id COLLATE NOCASE ASC, mi.title COLLATE NOCASE ASC
-                    "#,
-                    playlist_id
+            LibrarySortingMode::SourceAsc => {
+                library_query!(
+                    in_playlist(playlist_id),
+                    "ml.source_id COLLATE NOCASE ASC, mi.title COLLATE NOCASE ASC"
                 )
-                .fetch_all(&*self.pool.read().await)
+                .fetch_all(&*pool)
                 .await?
-            }
-            crate::settings::LibrarySortingMode::SourceDesc => {
-                sqlx::query_as!(
-                    MangaLibraryRowWithReadCount,
-                    r#"
-                    WITH last_read AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            MAX(ci.chapter_number) AS last_read_chapter
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.read = 1
-                        GROUP BY ci.source_id, ci.manga_id
-                    ),
-                    last_time_interacted AS (
-                        SELECT
-                            ci.source_id,
-                            ci.manga_id,
-                            COALESCE(MAX(cs.last_read), 0) AS last_read_time
-                        FROM chapter_informations ci
-                        JOIN chapter_state cs
-                            ON ci.source_id = cs.source_id
-                            AND ci.manga_id = cs.manga_id
-                            AND ci.chapter_id = cs.chapter_id
-                        LEFT JOIN manga_state ms
-                            ON ms.source_id = ci.source_id AND ms.manga_id = ci.manga_id
-                        WHERE (ms.preferred_scanlator IS NULL
-                        OR ci.scanlator = ms.preferred_scanlator
-                        OR ci.scanlator IS NULL)
-                        AND cs.last_read IS NOT NULL
-                        GROUP BY ci.source_id, ci.manga_id
-                    )
-                    SELECT
-                        pm.source_id,
-                        pm.manga_id,
-                        mi.title,
-                        mi.author,
-                        mi.artist,
-                        mi.cover_url,
-                        COUNT(ci.chapter_number) AS "unread_chapters_count: i64",
-                        lti.last_read_time AS "last_read?: i64",
-                        COALESCE(ms.viewer, md.viewer, 0) AS viewer,
-                        CASE WHEN ms.viewer IS NOT NULL THEN 1 ELSE 0 END AS "state_viewer!"
-                    FROM playlist_mangas pm
-                    JOIN manga_informations mi
-                        ON mi.source_id = pm.source_id AND mi.manga_id = pm.manga_id
-                    LEFT JOIN manga_state ms
-                        ON ms.source_id = pm.source_id AND ms.manga_id = pm.manga_id
-                    LEFT JOIN manga_details md
-                        ON md.source_id = pm.source_id AND md.id = pm.manga_id
-                    LEFT JOIN last_read lr
-                        ON lr.source_id = pm.source_id AND lr.manga_id = pm.manga_id
-                    LEFT JOIN last_time_interacted lti
-                        ON lti.source_id = pm.source_id AND lti.manga_id = pm.manga_id
-                    LEFT JOIN chapter_informations ci
-                        ON ci.source_id = pm.source_id
-                        AND ci.manga_id = pm.manga_id
-                        AND (ms.preferred_scanlator IS NULL OR ci.scanlator = ms.preferred_scanlator OR ci.scanlator IS NULL)
-                        AND ci.chapter_number > COALESCE(lr.last_read_chapter, -1)
-                    WHERE pm.playlist_id = ?1
-                    GROUP BY pm.source_id, pm.manga_id, lti.last_read_time
-                    ORDER BY pm.source_id COLLATE NOCASE DESC, mi.title COLLATE NOCASE DESC
-                    "#,
-                    playlist_id
+            }
+            LibrarySortingMode::SourceDesc => {
+                library_query!(
+                    in_playlist(playlist_id),
+                    "ml.source_id COLLATE NOCASE DESC, mi.title COLLATE NOCASE DESC"
                 )
-                .fetch_all(&*self.pool.read().await)
+                .fetch_all(&*pool)
+                .await?
+            }
+            LibrarySortingMode::DateAddedAsc => {
+                library_query!(
+                    in_playlist(playlist_id),
+                    "julianday(md.date_added) ASC NULLS LAST"
+                )
+                .fetch_all(&*pool)
+                .await?
+            }
+            LibrarySortingMode::DateAddedDesc => {
+                library_query!(
+                    in_playlist(playlist_id),
+                    "julianday(md.date_added) DESC NULLS LAST"
+                )
+                .fetch_all(&*pool)
+                .await?
+            }
+            LibrarySortingMode::LastUpdatedAsc => {
+                library_query!(
+                    in_playlist(playlist_id),
+                    with_last_chapter_update,
+                    "lcu.last_chapter_updated ASC NULLS LAST"
+                )
+                .fetch_all(&*pool)
+                .await?
+            }
+            LibrarySortingMode::LastUpdatedDesc => {
+                library_query!(
+                    in_playlist(playlist_id),
+                    with_last_chapter_update,
+                    "lcu.last_chapter_updated DESC NULLS LAST"
+                )
+                .fetch_all(&*pool)
                 .await?
             }
         };
@@ -3245,6 +2489,150 @@ impl Database {
 
         Ok(mangas)
     }
+
+    pub async fn get_schema_version(&self) -> Result<SchemaVersion> {
+        let migrations: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT version, description FROM _sqlx_migrations WHERE success = 1 ORDER BY version",
+        )
+        .fetch_all(&*self.pool.read().await)
+        .await?;
+
+        Ok(SchemaVersion {
+            version: migrations.last().map(|(version, _)| *version).unwrap_or(0),
+            applied_migrations: migrations
+                .into_iter()
+                .map(|(version, description)| format!("{version} {description}"))
+                .collect(),
+        })
+    }
+
+    pub async fn get_stats(&self) -> Result<DatabaseStats> {
+        const TABLES: [&str; 5] = [
+            "manga_library",
+            "chapter_informations",
+            "chapter_state",
+            "notifications",
+            "last_check_update",
+        ];
+
+        let pool = self.pool.read().await;
+
+        let mut row_counts = BTreeMap::new();
+        for table in TABLES {
+            // Table names cannot be bound, but they all come from the list above.
+            let sql = format!("SELECT COUNT(*) FROM {table}");
+            let (count,): (i64,) = sqlx::query_as(sqlx::AssertSqlSafe(&*sql))
+                .fetch_one(&*pool)
+                .await?;
+
+            row_counts.insert(table.to_owned(), count);
+        }
+
+        let (size_bytes,): (i64,) = sqlx::query_as(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        )
+        .fetch_one(&*pool)
+        .await?;
+
+        Ok(DatabaseStats {
+            row_counts,
+            size_bytes,
+        })
+    }
+}
+
+/// Longest title, author or artist kept in `manga_informations`. A source occasionally gives a
+/// whole description as the title, which makes the row too big for older SQLite versions.
+const MAX_MANGA_FIELD_CHARS: usize = 512;
+
+fn truncate_manga_field(value: &Option<String>, field: &str, id: &MangaId) -> Option<String> {
+    let value = value.as_ref()?;
+    let Some((end, _)) = value.char_indices().nth(MAX_MANGA_FIELD_CHARS) else {
+        return Some(value.clone());
+    };
+
+    log::warn!(
+        "truncating the {field} of manga {} from source {} to {MAX_MANGA_FIELD_CHARS} characters",
+        id.value(),
+        id.source_id().value()
+    );
+
+    Some(value[..end].to_owned())
+}
+
+/// `fetch_unread_chapter_counts_minimal` queries in batches of at most this many mangas, so only
+/// a bounded number of distinct statements is ever built.
+const UNREAD_COUNTS_BATCH_SIZE: usize = 64;
+
+/// Returns the unread chapter counts query for `batch_size` mangas. The SQL is built once per
+/// batch size, and as the same string is reused, SQLx's statement cache also gets to skip
+/// re-preparing it.
+fn unread_chapter_counts_query(batch_size: usize) -> Arc<str> {
+    static QUERIES: OnceLock<Mutex<HashMap<usize, Arc<str>>>> = OnceLock::new();
+
+    let mut queries = QUERIES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    queries
+        .entry(batch_size)
+        .or_insert_with(|| {
+            // Build dynamic SQL placeholders
+            let pairs: Vec<String> = (0..batch_size).map(|_| "(?, ?)".into()).collect();
+            let in_clause = pairs.join(", ");
+
+            format!(
+                r#"
+                WITH inputs(source_id, manga_id) AS (
+                    VALUES
+                    {}
+                ),
+                filtered AS (
+                    SELECT
+                        i.source_id,
+                        i.manga_id,
+                        ci.chapter_number,
+                        cs.read,
+                        cs.last_read as last_time
+                    FROM inputs i
+                    LEFT JOIN chapter_informations ci
+                        ON ci.source_id = i.source_id AND ci.manga_id = i.manga_id
+                    LEFT JOIN chapter_state cs
+                        ON ci.source_id = cs.source_id
+                        AND ci.manga_id = cs.manga_id
+                        AND ci.chapter_id = cs.chapter_id
+                ),
+                max_read AS (
+                    SELECT
+                        source_id,
+                        manga_id,
+                        COALESCE(MAX(CASE WHEN read = 1 THEN chapter_number END), -1) AS last_read,
+                        COALESCE(MAX(last_time), NULL) AS last_read_time
+                    FROM filtered
+                    GROUP BY source_id, manga_id
+                )
+                SELECT
+                    i.source_id,
+                    i.manga_id,
+                    COALESCE(COUNT(f.chapter_number), -1) AS count,
+                    mr.last_read_time AS last_time,
+                    CASE WHEN ml.source_id IS NOT NULL THEN TRUE ELSE FALSE END AS in_library
+                FROM inputs i
+                LEFT JOIN filtered f
+                    ON i.source_id = f.source_id AND i.manga_id = f.manga_id
+                    AND f.chapter_number > COALESCE((SELECT last_read FROM max_read mr2 WHERE mr2.source_id = i.source_id AND mr2.manga_id = i.manga_id), -1)
+                LEFT JOIN max_read mr
+                    ON i.source_id = mr.source_id AND i.manga_id = mr.manga_id
+                LEFT JOIN manga_library ml
+                    ON i.source_id = ml.source_id AND i.manga_id = ml.manga_id
+                GROUP BY i.source_id, i.manga_id, mr.last_read_time, in_library
+                "#,
+                in_clause
+            )
+            .into()
+        })
+        .clone()
 }
 
 /// Represents a manga entry in the user's library, joined with its information
@@ -3405,6 +2793,7 @@ struct ChapterInformationsRow {
     thumbnail: Option<String>,
     lang: Option<String>,
     locked: i64,
+    url: Option<String>,
 }
 
 impl From<ChapterInformationsRow> for ChapterInformation {
@@ -3419,7 +2808,7 @@ impl From<ChapterInformationsRow> for ChapterInformation {
             thumbnail: value.thumbnail.and_then(|s| Url::parse(&s).ok()),
             lang: value.lang,
 
-            url: None,
+            url: value.url.and_then(|s| Url::parse(&s).ok()),
             locked: Some(value.locked != 0),
         }
     }
@@ -3445,6 +2834,7 @@ struct ChapterStateRow {
     chapter_id: String,
     read: bool,
     last_read: Option<i64>,
+    last_read_page: Option<i32>,
 }
 
 impl From<ChapterStateRow> for ChapterState {
@@ -3452,6 +2842,7 @@ impl From<ChapterStateRow> for ChapterState {
         Self {
             read: value.read,
             last_read: value.last_read,
+            last_read_page: value.last_read_page,
         }
     }
 }
@@ -3476,13 +2867,16 @@ struct UnreadChaptersRowFull {
 struct MangaStateRow {
     source_id: String,
     manga_id: String,
-    preferred_scanlator: Option<String>,
+    preferred_scanlators: Option<String>,
 }
 
 impl From<MangaStateRow> for MangaState {
     fn from(value: MangaStateRow) -> Self {
         Self {
-            preferred_scanlator: value.preferred_scanlator,
+            preferred_scanlators: value
+                .preferred_scanlators
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
         }
     }
 }
@@ -3501,6 +2895,27 @@ struct NotificationInformationRow {
     created_at: i64,
 }
 
+#[derive(sqlx::FromRow)]
+struct DownloadProgressRow {
+    pages_total: i64,
+    pages_done: i64,
+    started_at: i64,
+    status: String,
+}
+
+impl TryFrom<DownloadProgressRow> for DownloadProgress {
+    type Error = anyhow::Error;
+
+    fn try_from(value: DownloadProgressRow) -> std::result::Result<Self, Self::Error> {
+        Ok(Self {
+            pages_total: value.pages_total.try_into()?,
+            pages_done: value.pages_done.try_into()?,
+            started_at: value.started_at,
+            status: DownloadStatus::try_from(value.status.as_str())?,
+        })
+    }
+}
+
 #[derive(sqlx::FromRow)]
 struct TrackingBindingRow {
     service: String,
@@ -3555,6 +2970,717 @@ impl From<NotificationInformationRow> for NotificationInformation {
             chapter_title: value.chapter_title.unwrap_or("Unknown".to_owned()),
             chapter_number: value.chapter_number.unwrap_or(-1.0),
             created_at: value.created_at,
+            downloaded: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hot_replace_with_corrupted_file_keeps_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("database.db");
+        let database = Database::new(&filename).await.unwrap();
+
+        let manga_id = MangaId::from_strings("source".to_owned(), "manga".to_owned());
+        database
+            .add_manga_to_library(manga_id.clone())
+            .await
+            .unwrap();
+
+        let mut corrupted = b"SQLite format 3\0".to_vec();
+        corrupted.extend(std::iter::repeat_n(0xAB, 4096));
+
+        assert!(database.hot_replace(&corrupted).await.is_err());
+
+        assert_eq!(database.get_manga_library().await.unwrap(), vec![manga_id]);
+        assert!(!filename.with_extension("db.tmp").exists());
+        assert!(!filename.with_extension("db.bak").exists());
+    }
+
+    #[tokio::test]
+    async fn test_upsert_chapter_informations_keeps_last_updated_when_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+
+        let manga_id = MangaId::from_strings("source".to_owned(), "manga".to_owned());
+        let chapter = |last_updated| ChapterInformation {
+            id: ChapterId::new(manga_id.clone(), "chapter".to_owned()),
+            title: None,
+            scanlator: None,
+            chapter_number: Some(1.0),
+            volume_number: None,
+            last_updated,
+            thumbnail: None,
+            lang: None,
+            url: None,
+            locked: None,
+        };
+
+        database
+            .upsert_cached_chapter_informations(&manga_id, &[chapter(Some(1_700_000_000))])
+            .await
+            .unwrap();
+        database
+            .upsert_cached_chapter_informations(&manga_id, &[chapter(None)])
+            .await
+            .unwrap();
+
+        let informations = database
+            .find_cached_chapter_informations(&manga_id)
+            .await
+            .unwrap();
+        assert_eq!(informations[0].last_updated, Some(1_700_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_count_unread_chapters_takes_each_chapter_from_the_best_ranked_scanlator() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+
+        let manga_id = MangaId::from_strings("source".to_owned(), "manga".to_owned());
+        let chapter = |id: &str, scanlator: &str, chapter_number| ChapterInformation {
+            id: ChapterId::new(manga_id.clone(), id.to_owned()),
+            title: None,
+            scanlator: Some(scanlator.to_owned()),
+            chapter_number: Some(chapter_number),
+            volume_number: None,
+            last_updated: None,
+            thumbnail: None,
+            lang: None,
+            url: None,
+            locked: None,
+        };
+
+        // B released both chapters, but A, preferred over it, only caught up with the first.
+        database
+            .upsert_cached_chapter_informations(
+                &manga_id,
+                &[
+                    chapter("1-a", "A", 1.0),
+                    chapter("1-b", "B", 1.0),
+                    chapter("2-b", "B", 2.0),
+                    chapter("2-c", "C", 2.0),
+                ],
+            )
+            .await
+            .unwrap();
+        database
+            .upsert_manga_state(
+                &manga_id,
+                MangaState {
+                    preferred_scanlators: vec!["A".to_owned(), "B".to_owned()],
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            database.count_unread_chapters(&manga_id).await.unwrap(),
+            Some(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_count_unread_chapters_is_zero_when_all_chapters_are_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+
+        let manga_id = MangaId::from_strings("source".to_owned(), "manga".to_owned());
+        assert_eq!(
+            database.count_unread_chapters(&manga_id).await.unwrap(),
+            None
+        );
+
+        let chapters: Vec<_> = (1..=3)
+            .map(|number| ChapterInformation {
+                id: ChapterId::new(manga_id.clone(), format!("chapter-{number}")),
+                title: None,
+                scanlator: None,
+                chapter_number: Some(number as f32),
+                volume_number: None,
+                last_updated: None,
+                thumbnail: None,
+                lang: None,
+                url: None,
+                locked: None,
+            })
+            .collect();
+        database
+            .upsert_cached_chapter_informations(&manga_id, &chapters)
+            .await
+            .unwrap();
+        assert_eq!(
+            database.count_unread_chapters(&manga_id).await.unwrap(),
+            Some(3)
+        );
+
+        for chapter in &chapters {
+            database
+                .mark_chapter_as_read(&chapter.id, Some(true))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            database.count_unread_chapters(&manga_id).await.unwrap(),
+            Some(0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_cached_chapters_filters_by_read_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+        let chapter_storage = crate::chapter_storage::ChapterStorage::new(
+            dir.path().join("downloads"),
+            size::Size::from_mebibytes(100.0),
+            false,
+        )
+        .unwrap();
+
+        let manga_id = MangaId::from_strings("source".to_owned(), "manga".to_owned());
+        let chapters: Vec<_> = (1..=3)
+            .map(|number| ChapterInformation {
+                id: ChapterId::new(manga_id.clone(), format!("chapter-{number}")),
+                title: None,
+                scanlator: None,
+                chapter_number: Some(number as f32),
+                volume_number: None,
+                last_updated: None,
+                thumbnail: None,
+                lang: None,
+                url: None,
+                locked: None,
+            })
+            .collect();
+        database
+            .upsert_cached_chapter_informations(&manga_id, &chapters)
+            .await
+            .unwrap();
+        database
+            .mark_chapter_as_read(&chapters[0].id, Some(true))
+            .await
+            .unwrap();
+
+        let mut counts = Vec::new();
+        for read in [Some(true), Some(false), None] {
+            let found = database
+                .find_cached_chapters(&manga_id, &chapter_storage, false, read)
+                .await
+                .unwrap();
+            counts.push(found.len());
+
+            if read == Some(true) {
+                assert_eq!(found[0].information.id, chapters[0].id);
+            }
+        }
+
+        assert_eq!(counts, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_unread_chapter_counts_minimal_spans_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+
+        let manga_ids: Vec<_> = (0..UNREAD_COUNTS_BATCH_SIZE * 2 + 1)
+            .map(|n| MangaId::from_strings("source".to_owned(), format!("manga-{n}")))
+            .collect();
+        let last_manga_id = manga_ids.last().unwrap();
+        let chapters: Vec<_> = (1..=2)
+            .map(|number| ChapterInformation {
+                id: ChapterId::new(last_manga_id.clone(), format!("chapter-{number}")),
+                title: None,
+                scanlator: None,
+                chapter_number: Some(number as f32),
+                volume_number: None,
+                last_updated: None,
+                thumbnail: None,
+                lang: None,
+                url: None,
+                locked: None,
+            })
+            .collect();
+        database
+            .upsert_cached_chapter_informations(last_manga_id, &chapters)
+            .await
+            .unwrap();
+
+        let counts = database
+            .fetch_unread_chapter_counts_minimal(&manga_ids)
+            .await
+            .unwrap();
+
+        assert_eq!(counts.len(), manga_ids.len());
+        assert_eq!(counts[last_manga_id].0, Some(2));
+        assert!(Arc::ptr_eq(
+            &unread_chapter_counts_query(UNREAD_COUNTS_BATCH_SIZE),
+            &unread_chapter_counts_query(UNREAD_COUNTS_BATCH_SIZE)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_upsert_cached_manga_information_truncates_long_titles() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+
+        let manga_id = MangaId::from_strings("source".to_owned(), "manga".to_owned());
+        database
+            .upsert_cached_manga_information(&[MangaInformation {
+                id: manga_id.clone(),
+                title: Some("é".repeat(10_000)),
+                author: Some("author".to_owned()),
+                artist: None,
+                cover_url: None,
+                viewer: MangaViewer::default(),
+            }])
+            .await
+            .unwrap();
+
+        let information = database
+            .find_cached_manga_information(&manga_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(information.title, Some("é".repeat(MAX_MANGA_FIELD_CHARS)));
+        assert_eq!(information.author.as_deref(), Some("author"));
+    }
+
+    #[tokio::test]
+    async fn test_find_mangas_by_tag_follows_manga_details_tags() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+
+        let manga_id = MangaId::from_strings("source".to_owned(), "manga".to_owned());
+        let details = |tags: &[&str]| crate::source::model::Manga {
+            source_id: "source".to_owned(),
+            id: "manga".to_owned(),
+            tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            ..Default::default()
+        };
+
+        database
+            .upsert_cached_manga_details(&manga_id, &details(&["Action", "Comedy"]))
+            .await
+            .unwrap();
+        assert_eq!(
+            database.find_mangas_by_tag("Action").await.unwrap(),
+            vec![manga_id.clone()]
+        );
+
+        database
+            .upsert_cached_manga_details(&manga_id, &details(&["Comedy"]))
+            .await
+            .unwrap();
+        assert!(database
+            .find_mangas_by_tag("Action")
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            database.find_mangas_by_tag("Comedy").await.unwrap(),
+            vec![manga_id]
+        );
+    }
+
+    async fn count_manga_rows(database: &Database, table: &str, column: &str, id: &str) -> i64 {
+        let sql = format!("SELECT COUNT(*) FROM {table} WHERE {column} = ?1");
+        let (count,): (i64,) = sqlx::query_as(sqlx::AssertSqlSafe(&*sql))
+            .bind(id)
+            .fetch_one(&*database.pool.read().await)
+            .await
+            .unwrap();
+
+        count
+    }
+
+    #[tokio::test]
+    async fn test_migrate_manga_id_moves_every_manga_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+
+        let old = MangaId::from_strings("source".to_owned(), "old".to_owned());
+        let new = MangaId::from_strings("source".to_owned(), "new".to_owned());
+        let chapter = |manga_id: &MangaId| ChapterInformation {
+            id: ChapterId::new(manga_id.clone(), "chapter".to_owned()),
+            title: None,
+            scanlator: None,
+            chapter_number: Some(1.0),
+            volume_number: None,
+            last_updated: None,
+            thumbnail: None,
+            lang: None,
+            url: None,
+            locked: None,
+        };
+
+        database.add_manga_to_library(old.clone()).await.unwrap();
+        database
+            .upsert_manga_viewer(&old, Some(MangaViewer::Rtl as i64))
+            .await
+            .unwrap();
+        database
+            .upsert_cached_manga_information(&[MangaInformation {
+                id: old.clone(),
+                title: Some("Title".to_owned()),
+                author: None,
+                artist: None,
+                cover_url: None,
+                viewer: MangaViewer::default(),
+            }])
+            .await
+            .unwrap();
+        database
+            .upsert_cached_manga_details(
+                &old,
+                &crate::source::model::Manga {
+                    source_id: "source".to_owned(),
+                    id: "old".to_owned(),
+                    tags: Some(vec!["Action".to_owned()]),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        database
+            .upsert_tracking_binding(
+                &old,
+                &TrackingCandidate {
+                    service: TrackingService::Anilist,
+                    remote_media_id: 1,
+                    title: "Title".to_owned(),
+                    url: None,
+                    total_chapters: None,
+                    total_volumes: None,
+                },
+            )
+            .await
+            .unwrap();
+        let playlist = database
+            .create_playlist("Reading".to_owned())
+            .await
+            .unwrap();
+        database
+            .add_manga_to_playlist(playlist.id, old.clone())
+            .await
+            .unwrap();
+        database
+            .upsert_cached_chapter_informations(&old, &[chapter(&old)])
+            .await
+            .unwrap();
+        database
+            .mark_chapter_as_read(&chapter(&old).id, Some(true))
+            .await
+            .unwrap();
+        database
+            .insert_notification(&old, &[chapter(&old)])
+            .await
+            .unwrap();
+        database
+            .set_last_check_update_manga(&old, 1, 2, None)
+            .await
+            .unwrap();
+        database.set_check_update_cursor(&old).await.unwrap();
+
+        database.migrate_manga_id(&old, &new).await.unwrap();
+
+        for (table, column) in [
+            ("manga_library", "manga_id"),
+            ("manga_state", "manga_id"),
+            ("manga_informations", "manga_id"),
+            ("manga_details", "id"),
+            ("manga_tags", "manga_id"),
+            ("manga_tracking", "manga_id"),
+            ("playlist_mangas", "manga_id"),
+            ("chapter_informations", "manga_id"),
+            ("chapter_state", "manga_id"),
+            ("last_check_update", "manga_id"),
+            ("notifications", "manga_id"),
+            ("check_update_state", "last_check_batch_cursor"),
+        ] {
+            assert_eq!(
+                count_manga_rows(&database, table, column, "old").await,
+                0,
+                "{table} still has rows of the old ID"
+            );
+            assert_eq!(
+                count_manga_rows(&database, table, column, "new").await,
+                1,
+                "{table} has no row of the new ID"
+            );
+        }
+
+        let state = database
+            .find_chapter_state(&chapter(&new).id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(state.read);
+        assert_eq!(
+            database.find_mangas_by_tag("Action").await.unwrap(),
+            vec![new.clone()]
+        );
+
+        // Migrating onto a manga that is already in the library keeps a single entry.
+        database.add_manga_to_library(old.clone()).await.unwrap();
+        database.migrate_manga_id(&old, &new).await.unwrap();
+        assert_eq!(database.get_manga_library().await.unwrap(), vec![new]);
+    }
+
+    #[tokio::test]
+    async fn test_key_migration_version_is_recorded_per_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+        let source_id = SourceId::new("source".to_owned());
+
+        assert_eq!(
+            database
+                .find_key_migration_version(&source_id)
+                .await
+                .unwrap(),
+            None
+        );
+
+        database
+            .set_key_migration_version(&source_id, 3)
+            .await
+            .unwrap();
+        database
+            .set_key_migration_version(&source_id, 4)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            database
+                .find_key_migration_version(&source_id)
+                .await
+                .unwrap(),
+            Some(4)
+        );
+        assert_eq!(
+            database
+                .find_key_migration_version(&SourceId::new("other".to_owned()))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_notifications_pages_with_before_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database::new(&dir.path().join("database.db"))
+            .await
+            .unwrap();
+
+        let manga_id = MangaId::from_strings("source".to_owned(), "manga".to_owned());
+        let chapters: Vec<_> = (1..=3)
+            .map(|number| ChapterInformation {
+                id: ChapterId::new(manga_id.clone(), format!("chapter-{number}")),
+                title: None,
+                scanlator: None,
+                chapter_number: Some(number as f32),
+                volume_number: None,
+                last_updated: None,
+                thumbnail: None,
+                lang: None,
+                url: None,
+                locked: None,
+            })
+            .collect();
+        database
+            .insert_notification(&manga_id, &chapters)
+            .await
+            .unwrap();
+
+        let ids = |notifications: Vec<NotificationInformation>| {
+            notifications
+                .into_iter()
+                .map(|notification| notification.id)
+                .collect::<Vec<_>>()
+        };
+
+        let first_page = ids(database.get_notifications(None, Some(2)).await.unwrap());
+        assert_eq!(first_page, vec![3, 2]);
+
+        let second_page = database
+            .get_notifications(Some(first_page[1]), Some(2))
+            .await
+            .unwrap();
+        assert_eq!(ids(second_page), vec![1]);
+
+        assert_eq!(
+            database.get_notifications(None, None).await.unwrap().len(),
+            3
+        );
+    }
+
+    async fn merge_test_databases() -> (tempfile::TempDir, Database, Database) {
+        let dir = tempfile::tempdir().unwrap();
+        let local = Database::new(&dir.path().join("local.db")).await.unwrap();
+        let remote = Database::new(&dir.path().join("remote.db")).await.unwrap();
+
+        (dir, local, remote)
+    }
+
+    fn chapter_state(read: bool, last_read: i64, last_read_page: i32) -> ChapterState {
+        ChapterState {
+            read,
+            last_read: Some(last_read),
+            last_read_page: Some(last_read_page),
         }
     }
+
+    #[tokio::test]
+    async fn test_merge_keeps_local_chapter_state_when_local_is_ahead() {
+        let (dir, local, remote) = merge_test_databases().await;
+        let id = ChapterId::from_strings("source".to_owned(), "manga".to_owned(), "1".to_owned());
+
+        local
+            .upsert_chapter_state(&id, chapter_state(true, 200, 20))
+            .await
+            .unwrap();
+        remote
+            .upsert_chapter_state(&id, chapter_state(false, 100, 3))
+            .await
+            .unwrap();
+
+        local
+            .merge_from(&dir.path().join("remote.db"))
+            .await
+            .unwrap();
+
+        let state = local.find_chapter_state(&id).await.unwrap().unwrap();
+        assert!(state.read);
+        assert_eq!(state.last_read, Some(200));
+        assert_eq!(state.last_read_page, Some(20));
+    }
+
+    #[tokio::test]
+    async fn test_merge_takes_remote_chapter_state_when_remote_is_ahead() {
+        let (dir, local, remote) = merge_test_databases().await;
+        let id = ChapterId::from_strings("source".to_owned(), "manga".to_owned(), "1".to_owned());
+        let remote_only_id =
+            ChapterId::from_strings("source".to_owned(), "manga".to_owned(), "2".to_owned());
+
+        local
+            .upsert_chapter_state(&id, chapter_state(false, 100, 3))
+            .await
+            .unwrap();
+        remote
+            .upsert_chapter_state(&id, chapter_state(true, 200, 20))
+            .await
+            .unwrap();
+        remote
+            .upsert_chapter_state(&remote_only_id, chapter_state(true, 50, 1))
+            .await
+            .unwrap();
+
+        local
+            .merge_from(&dir.path().join("remote.db"))
+            .await
+            .unwrap();
+
+        let state = local.find_chapter_state(&id).await.unwrap().unwrap();
+        assert!(state.read);
+        assert_eq!(state.last_read, Some(200));
+        assert_eq!(state.last_read_page, Some(20));
+        assert!(local
+            .find_chapter_state(&remote_only_id)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_merge_keeps_local_chapter_state_on_equal_timestamps() {
+        let (dir, local, remote) = merge_test_databases().await;
+        let id = ChapterId::from_strings("source".to_owned(), "manga".to_owned(), "1".to_owned());
+
+        local
+            .upsert_chapter_state(&id, chapter_state(true, 100, 20))
+            .await
+            .unwrap();
+        remote
+            .upsert_chapter_state(&id, chapter_state(false, 100, 3))
+            .await
+            .unwrap();
+
+        local
+            .merge_from(&dir.path().join("remote.db"))
+            .await
+            .unwrap();
+
+        let state = local.find_chapter_state(&id).await.unwrap().unwrap();
+        assert!(state.read);
+        assert_eq!(state.last_read_page, Some(20));
+    }
+
+    #[tokio::test]
+    async fn test_merge_unions_library_and_prefers_remote_manga_state() {
+        let (dir, local, remote) = merge_test_databases().await;
+        let local_manga = MangaId::from_strings("source".to_owned(), "local".to_owned());
+        let remote_manga = MangaId::from_strings("source".to_owned(), "remote".to_owned());
+
+        local
+            .add_manga_to_library(local_manga.clone())
+            .await
+            .unwrap();
+        remote
+            .add_manga_to_library(remote_manga.clone())
+            .await
+            .unwrap();
+        local
+            .upsert_manga_state(
+                &local_manga,
+                MangaState {
+                    preferred_scanlators: vec!["local".to_owned()],
+                },
+            )
+            .await
+            .unwrap();
+        remote
+            .upsert_manga_state(
+                &local_manga,
+                MangaState {
+                    preferred_scanlators: vec!["remote".to_owned()],
+                },
+            )
+            .await
+            .unwrap();
+
+        local
+            .merge_from(&dir.path().join("remote.db"))
+            .await
+            .unwrap();
+
+        let mut library = local.get_manga_library().await.unwrap();
+        library.sort_by(|a, b| a.value().cmp(b.value()));
+        assert_eq!(library, vec![local_manga.clone(), remote_manga]);
+
+        let state = local.find_manga_state(&local_manga).await.unwrap().unwrap();
+        assert_eq!(state.preferred_scanlators, vec!["remote".to_owned()]);
+    }
 }
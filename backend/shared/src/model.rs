@@ -1,4 +1,7 @@
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use chrono::TimeZone;
+use serde::{de, Deserialize, Deserializer, Serialize};
 use url::Url;
 
 use crate::source::{
@@ -12,12 +15,16 @@ pub struct SourceId {
     source_id: String,
 }
 
+/// Serialized as `{ source_id, manga_id }`. Deserializing also accepts the same fields as
+/// path parameters.
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize)]
 pub struct MangaId {
     source_id: SourceId,
     manga_id: String,
 }
 
+/// Serialized as `{ manga_id: { source_id, manga_id }, chapter_id }`. Deserializing also
+/// accepts a flat `{ source_id, manga_id, chapter_id }`, as path parameters are.
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize)]
 pub struct ChapterId {
     manga_id: MangaId,
@@ -90,6 +97,67 @@ impl ChapterId {
     }
 }
 
+#[derive(Deserialize)]
+struct MangaIdFields {
+    source_id: String,
+    manga_id: String,
+}
+
+impl<'de> Deserialize<'de> for MangaId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let MangaIdFields {
+            source_id,
+            manga_id,
+        } = MangaIdFields::deserialize(deserializer)?;
+
+        Ok(Self::from_strings(source_id, manga_id))
+    }
+}
+
+/// The `manga_id` of a [`ChapterId`] is either a nested [`MangaId`] or, in the flat form, just
+/// its value next to `source_id`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChapterMangaIdField {
+    Value(String),
+    Nested(MangaId),
+}
+
+#[derive(Deserialize)]
+struct ChapterIdFields {
+    source_id: Option<String>,
+    manga_id: ChapterMangaIdField,
+    chapter_id: String,
+}
+
+impl<'de> Deserialize<'de> for ChapterId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ChapterIdFields {
+            source_id,
+            manga_id,
+            chapter_id,
+        } = ChapterIdFields::deserialize(deserializer)?;
+
+        let manga_id = match (source_id, manga_id) {
+            (_, ChapterMangaIdField::Nested(manga_id)) => manga_id,
+            (Some(source_id), ChapterMangaIdField::Value(manga_id)) => {
+                MangaId::from_strings(source_id, manga_id)
+            }
+            (None, ChapterMangaIdField::Value(_)) => {
+                return Err(de::Error::missing_field("source_id"))
+            }
+        };
+
+        Ok(Self::new(manga_id, chapter_id))
+    }
+}
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct SourceInformation {
     pub id: SourceId,
@@ -127,7 +195,8 @@ pub struct ChapterInformation {
 
 #[derive(Default, Clone, Debug)]
 pub struct MangaState {
-    pub preferred_scanlator: Option<String>,
+    /// Scanlators whose chapters are shown, in order of preference. Empty means all of them.
+    pub preferred_scanlators: Vec<String>,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Hash, Serialize)]
@@ -256,6 +325,8 @@ pub struct TrackingSyncResult {
 pub struct ChapterState {
     pub read: bool,
     pub last_read: Option<i64>,
+    /// The page the chapter was left at, counted from 1.
+    pub last_read_page: Option<i32>,
 }
 
 pub struct Chapter {
@@ -299,6 +370,61 @@ impl From<SourceManga> for MangaInformation {
     }
 }
 
+impl From<MangaInformation> for SourceManga {
+    fn from(value: MangaInformation) -> Self {
+        let MangaId {
+            source_id: SourceId { source_id },
+            manga_id: id,
+        } = value.id;
+
+        Self {
+            source_id,
+            id,
+            title: value.title,
+            author: value.author,
+            artist: value.artist,
+            cover_url: value.cover_url,
+            viewer: value.viewer,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<ChapterInformation> for SourceChapter {
+    fn from(value: ChapterInformation) -> Self {
+        let ChapterId {
+            manga_id:
+                MangaId {
+                    source_id: SourceId { source_id },
+                    manga_id,
+                },
+            chapter_id: id,
+        } = value.id;
+
+        Self {
+            source_id,
+            id,
+            manga_id,
+            title: value.title,
+            scanlator: value.scanlator,
+            url: value.url,
+            lang: value.lang,
+            chapter_num: value.chapter_number,
+            volume_num: value.volume_number,
+            date_uploaded: value.last_updated.and_then(|v| {
+                chrono::Utc
+                    .timestamp_opt(v, 0)
+                    .single()
+                    .map(|d| d.with_timezone(&chrono_tz::UTC))
+            }),
+            // The order is not stored, only the chapter list call returns it.
+            source_order: usize::MAX,
+            thumbnail: value.thumbnail,
+            locked: value.locked,
+        }
+    }
+}
+
 impl From<SourceChapter> for ChapterInformation {
     fn from(value: SourceChapter) -> Self {
         Self {
@@ -327,7 +453,71 @@ pub struct NotificationInformation {
     pub chapter_title: String,
     pub chapter_number: f64,
     pub created_at: i64,
+    pub downloaded: bool,
 }
+
+/// A page of notifications. `next_cursor` is the `before_id` of the next page, if there is one.
+#[derive(Serialize)]
+pub struct NotificationPage {
+    pub items: Vec<NotificationInformation>,
+    pub next_cursor: Option<i64>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadStatus {
+    Downloading,
+    /// Every page was written, the chapter file is being moved into the storage.
+    Persisting,
+}
+
+impl DownloadStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Downloading => "downloading",
+            Self::Persisting => "persisting",
+        }
+    }
+}
+
+impl TryFrom<&str> for DownloadStatus {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "downloading" => Ok(Self::Downloading),
+            "persisting" => Ok(Self::Persisting),
+            other => Err(anyhow::anyhow!("unknown download status: {other}")),
+        }
+    }
+}
+
+/// Progress of a chapter download that is still running.
+#[derive(Serialize, Debug, Clone)]
+pub struct DownloadProgress {
+    pub pages_total: usize,
+    pub pages_done: usize,
+    pub started_at: i64,
+    pub status: DownloadStatus,
+}
+
+/// The migrations applied to the database, for debugging a schema that got out of sync.
+#[derive(Serialize, Debug, Clone)]
+pub struct SchemaVersion {
+    /// Version of the latest applied migration, `0` if none was.
+    pub version: i64,
+    /// `"<version> <description>"` of every applied migration, oldest first.
+    pub applied_migrations: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DatabaseStats {
+    /// Number of rows of each user-facing table, by table name.
+    pub row_counts: BTreeMap<String, i64>,
+    /// Size of the database file, from SQLite's `page_count * page_size`.
+    pub size_bytes: i64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct Playlist {
@@ -347,6 +537,22 @@ pub struct PlaylistManga {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_chapter_id_deserializes_nested_and_flat_forms() {
+        let id = ChapterId::from_strings("source".into(), "manga".into(), "chapter".into());
+
+        let nested: ChapterId = serde_json::from_value(serde_json::to_value(&id).unwrap()).unwrap();
+        let flat: ChapterId = serde_json::from_value(serde_json::json!({
+            "source_id": "source",
+            "manga_id": "manga",
+            "chapter_id": "chapter",
+        }))
+        .unwrap();
+
+        assert_eq!(nested, id);
+        assert_eq!(flat, id);
+    }
+
     #[test]
     fn test_source_id_new_and_value() {
         let id = SourceId::new("test_source".to_string());
@@ -449,7 +655,7 @@ mod tests {
     #[test]
     fn test_manga_state_default() {
         let state = MangaState::default();
-        assert!(state.preferred_scanlator.is_none());
+        assert!(state.preferred_scanlators.is_empty());
     }
 
     #[test]
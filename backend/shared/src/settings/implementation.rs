@@ -2,7 +2,18 @@ use std::{fs::File, path::Path};
 
 use anyhow::{Context, Result};
 
-use super::schema::Settings;
+use super::schema::{Settings, CONCURRENT_REQUESTS_PAGES_RANGE};
+
+const DEFAULT_CONCURRENT_SOURCE_OPERATIONS: usize = 4;
+
+fn default_concurrent_requests_pages() -> usize {
+    // Kindles and other ARM e-readers choke on too many images being decoded at once.
+    if cfg!(target_arch = "arm") && cfg!(target_os = "linux") {
+        4
+    } else {
+        5
+    }
+}
 
 impl Settings {
     pub fn from_file(path: &Path) -> Result<Self> {
@@ -11,17 +22,30 @@ impl Settings {
             .with_context(|| "Couldn't parse file contents")?;
 
         if settings.concurrent_requests_pages.is_none() {
-            settings.concurrent_requests_pages =
-                Some(if cfg!(target_arch = "arm") && cfg!(target_os = "linux") {
-                    4
-                } else {
-                    5
-                });
+            settings.concurrent_requests_pages = Some(default_concurrent_requests_pages());
         }
 
         Ok(settings)
     }
 
+    /// How many pages of a chapter to download at the same time, always within
+    /// [`CONCURRENT_REQUESTS_PAGES_RANGE`].
+    pub fn concurrent_requests_pages_or_default(&self) -> usize {
+        self.concurrent_requests_pages
+            .unwrap_or_else(default_concurrent_requests_pages)
+            .clamp(
+                *CONCURRENT_REQUESTS_PAGES_RANGE.start(),
+                *CONCURRENT_REQUESTS_PAGES_RANGE.end(),
+            )
+    }
+
+    /// How many sources to query at the same time when checking the library for updates.
+    pub fn concurrent_source_operations_or_default(&self) -> usize {
+        self.concurrent_source_operations
+            .unwrap_or(DEFAULT_CONCURRENT_SOURCE_OPERATIONS)
+            .max(1)
+    }
+
     pub fn save_to_file(&self, path: &Path) -> Result<()> {
         let file = File::create(path)?;
 
@@ -1,7 +1,11 @@
 mod implementation;
 mod schema;
+pub mod secure;
 
+pub(crate) use schema::deserialize_concurrent_requests_pages;
 pub use schema::{
     ChapterSortingMode, ChapterTitleFormat, LibrarySortingMode, LibraryViewMode, SearchViewMode,
-    Settings, SourceSettingValue, StorageSizeLimit, TrackingServiceSettings,
+    SettingOutOfRangeError, Settings, SourceSettingValue, StorageSizeLimit,
+    TrackingServiceSettings, CONCURRENT_REQUESTS_PAGES_RANGE,
 };
+pub use secure::SecureString;
@@ -1,14 +1,16 @@
-use std::{borrow::Cow, collections::HashMap, path::PathBuf};
+use std::{borrow::Cow, collections::HashMap, ops::RangeInclusive, path::PathBuf};
 
 use regex::Regex;
 use schemars::JsonSchema;
 use serde::{
     de::{Unexpected, Visitor},
-    Deserialize, Serialize,
+    Deserialize, Deserializer, Serialize,
 };
 use size::{Base, Size};
 use url::Url;
 
+use super::secure::SecureString;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct StorageSizeLimit(pub Size);
 
@@ -20,6 +22,8 @@ pub enum SourceSettingValue {
     Int(i64),
     Float(f64),
     String(String),
+    /// A value entered in a `secure-input` setting, encrypted when written to disk.
+    SecureString(#[schemars(with = "String")] SecureString),
     Vec(Vec<String>),
     Null,
 }
@@ -46,6 +50,10 @@ pub enum LibrarySortingMode {
     LastReadDesc,
     SourceAsc,
     SourceDesc,
+    DateAddedAsc,
+    DateAddedDesc,
+    LastUpdatedAsc,
+    LastUpdatedDesc,
 }
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
@@ -136,9 +144,16 @@ pub struct Settings {
     #[serde(default)]
     pub library_sorting_mode: LibrarySortingMode,
 
-    #[serde(default)]
+    /// How many pages of a chapter are downloaded at the same time, between 1 and 32.
+    /// Use [`Settings::concurrent_requests_pages_or_default`] to read it.
+    #[serde(default, deserialize_with = "deserialize_concurrent_requests_pages")]
     pub concurrent_requests_pages: Option<usize>,
 
+    /// How many sources are queried at the same time when checking the library for
+    /// updates. Defaults to 4.
+    #[serde(default)]
+    pub concurrent_source_operations: Option<usize>,
+
     #[serde(default)]
     pub api_sync: Option<String>,
 
@@ -181,6 +196,17 @@ pub struct Settings {
     #[serde(default)]
     pub optimize_image: bool,
 
+    /// How many images processed by a source's `process_page_image` are kept in memory
+    /// per source, so downloading the same page again skips the processing. `0` disables
+    /// the cache.
+    #[serde(default = "default_image_cache_max_entries")]
+    pub image_cache_max_entries: usize,
+
+    /// How long to wait for each source when searching, in seconds. Sources that take
+    /// longer are reported as timed out. Defaults to 15 seconds.
+    #[serde(default)]
+    pub source_search_timeout_secs: Option<u64>,
+
     #[serde(default)]
     pub library_view_mode: LibraryViewMode,
 
@@ -236,6 +262,41 @@ pub struct Settings {
     pub delete_downloaded_after_read: bool,
 }
 
+/// The values accepted for `concurrent_requests_pages`.
+pub const CONCURRENT_REQUESTS_PAGES_RANGE: RangeInclusive<usize> = 1..=32;
+
+#[derive(Debug, thiserror::Error)]
+#[error("`{name}` must be between {} and {}, got {value}", .range.start(), .range.end())]
+pub struct SettingOutOfRangeError {
+    pub name: &'static str,
+    pub value: usize,
+    pub range: RangeInclusive<usize>,
+}
+
+pub(crate) fn deserialize_concurrent_requests_pages<'de, D>(
+    deserializer: D,
+) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<usize>::deserialize(deserializer)?;
+
+    match value {
+        Some(value) if !CONCURRENT_REQUESTS_PAGES_RANGE.contains(&value) => {
+            Err(serde::de::Error::custom(SettingOutOfRangeError {
+                name: "concurrent_requests_pages",
+                value,
+                range: CONCURRENT_REQUESTS_PAGES_RANGE,
+            }))
+        }
+        _ => Ok(value),
+    }
+}
+
+fn default_image_cache_max_entries() -> usize {
+    32
+}
+
 fn default_ram_storage_size_mb() -> usize {
     32
 }
@@ -502,4 +563,32 @@ mod tests {
             settings.chapter_title_format
         );
     }
+
+    #[test]
+    fn test_concurrent_requests_pages_range() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"concurrent_requests_pages": 8}"#).unwrap();
+        assert_eq!(settings.concurrent_requests_pages_or_default(), 8);
+
+        for value in [0, 33] {
+            let json = format!(r#"{{"concurrent_requests_pages": {value}}}"#);
+            let err = serde_json::from_str::<Settings>(&json).unwrap_err();
+            assert!(err.to_string().contains("between 1 and 32"));
+        }
+    }
+
+    #[test]
+    fn test_concurrent_or_default_values() {
+        let settings = Settings {
+            concurrent_requests_pages: Some(100),
+            concurrent_source_operations: Some(0),
+            ..Settings::default()
+        };
+        assert_eq!(settings.concurrent_requests_pages_or_default(), 32);
+        assert_eq!(settings.concurrent_source_operations_or_default(), 1);
+
+        let settings = Settings::default();
+        assert!((1..=32).contains(&settings.concurrent_requests_pages_or_default()));
+        assert_eq!(settings.concurrent_source_operations_or_default(), 4);
+    }
 }
@@ -0,0 +1,187 @@
+use std::{fs, path::Path, sync::OnceLock};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use log::warn;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const KEY_LEN: usize = 32;
+
+static DEVICE_KEY: OnceLock<Option<LessSafeKey>> = OnceLock::new();
+
+/// Loads the device key used to encrypt [`SecureString`]s from `key_path`, generating a new
+/// one if the file doesn't exist yet. Must be called before the settings are read.
+///
+/// If the key can't be loaded, secure values are stored as plain text instead.
+pub fn init(key_path: &Path) {
+    let key = match load_or_create_key(key_path) {
+        Ok(key) => Some(key),
+        Err(err) => {
+            warn!(
+                "couldn't load the settings key at {}, secure settings will be stored as plain text: {err:?}",
+                key_path.display()
+            );
+            None
+        }
+    };
+
+    let _ = DEVICE_KEY.set(key);
+}
+
+fn load_or_create_key(key_path: &Path) -> Result<LessSafeKey> {
+    let bytes = if key_path.exists() {
+        fs::read(key_path).with_context(|| format!("while reading {}", key_path.display()))?
+    } else {
+        let mut bytes = vec![0u8; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| anyhow!("couldn't generate a random key"))?;
+        write_key_file(key_path, &bytes)?;
+        bytes
+    };
+
+    let key = UnboundKey::new(&CHACHA20_POLY1305, &bytes)
+        .map_err(|_| anyhow!("invalid key length {}", bytes.len()))?;
+
+    Ok(LessSafeKey::new(key))
+}
+
+fn write_key_file(key_path: &Path, bytes: &[u8]) -> Result<()> {
+    fs::write(key_path, bytes).with_context(|| format!("while writing {}", key_path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(key_path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
+
+fn device_key() -> Option<&'static LessSafeKey> {
+    DEVICE_KEY.get().and_then(Option::as_ref)
+}
+
+fn encrypt(key: &LessSafeKey, plaintext: &str) -> Result<String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("couldn't generate a nonce"))?;
+
+    let mut in_out = plaintext.as_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut in_out,
+    )
+    .map_err(|_| anyhow!("couldn't encrypt value"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(in_out);
+
+    Ok(BASE64.encode(sealed))
+}
+
+fn decrypt(key: &LessSafeKey, sealed: &str) -> Result<String> {
+    let sealed = BASE64.decode(sealed)?;
+    if sealed.len() < NONCE_LEN {
+        bail!("encrypted value is too short");
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("invalid nonce"))?;
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::empty(), &mut in_out)
+        .map_err(|_| anyhow!("couldn't decrypt value, was the settings key replaced?"))?;
+
+    Ok(String::from_utf8(plaintext.to_vec())?)
+}
+
+/// A setting value, such as an API key, that is encrypted with the device key whenever it is
+/// written to disk. It is stored as `{"secure": "<base64 nonce + ciphertext>"}`.
+#[derive(Clone, PartialEq, Default)]
+pub struct SecureString(pub String);
+
+impl std::fmt::Debug for SecureString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecureString(<redacted>)")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Sealed {
+    secure: String,
+}
+
+impl Serialize for SecureString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let sealed = device_key()
+            .ok_or_else(|| anyhow!("no settings key was loaded"))
+            .and_then(|key| encrypt(key, &self.0));
+
+        match sealed {
+            Ok(secure) => Sealed { secure }.serialize(serializer),
+            Err(err) => {
+                warn!("storing secure setting as plain text: {err:?}");
+                serializer.serialize_str(&self.0)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SecureString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Sealed { secure } = Sealed::deserialize(deserializer)?;
+        let value = device_key()
+            .ok_or_else(|| anyhow!("no settings key was loaded"))
+            .and_then(|key| decrypt(key, &secure));
+
+        // Failing here would make the whole settings file unreadable, so drop the value and let
+        // the user enter it again instead.
+        Ok(Self(value.unwrap_or_else(|err| {
+            warn!("ignoring secure setting: {err:?}");
+            String::new()
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips_through_the_device_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".settings.key");
+
+        let key = load_or_create_key(&path).unwrap();
+        let sealed = encrypt(&key, "api-key").unwrap();
+        assert!(!sealed.contains("api-key"));
+
+        let reloaded = load_or_create_key(&path).unwrap();
+        assert_eq!(decrypt(&reloaded, &sealed).unwrap(), "api-key");
+    }
+
+    #[test]
+    fn rejects_values_sealed_with_another_key() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let key = load_or_create_key(&dir.path().join("a.key")).unwrap();
+        let other = load_or_create_key(&dir.path().join("b.key")).unwrap();
+
+        let sealed = encrypt(&key, "api-key").unwrap();
+        assert!(decrypt(&other, &sealed).is_err());
+    }
+}
@@ -0,0 +1,318 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use url::Url;
+
+use crate::cookie_store::{CookieEntry, CookieStoreData};
+
+/// Cookies belonging to a single source, set either through `net.set_cookie` or by
+/// `Set-Cookie` response headers. Unlike the global cookie store, which holds the cookies
+/// synced from the bot, this jar is persisted next to the source's `.aix` file, so login
+/// sessions survive daemon restarts.
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    path: Option<PathBuf>,
+    data: CookieStoreData,
+}
+
+impl CookieJar {
+    /// Loads the jar from `path`, starting empty if the file is missing or unreadable.
+    /// Every change is written back to the same file.
+    pub fn load(path: PathBuf) -> Self {
+        let data = if path.exists() {
+            CookieStoreData::load_from_file(&path).unwrap_or_else(|err| {
+                warn!("ignoring cookie jar {}: {err:?}", path.display());
+                CookieStoreData::default()
+            })
+        } else {
+            CookieStoreData::default()
+        };
+
+        Self {
+            path: Some(path),
+            data,
+        }
+    }
+
+    pub fn set_cookie(&mut self, name: String, value: String, domain: String) {
+        self.insert(CookieEntry {
+            name,
+            value,
+            domain,
+            path: None,
+            expires: None,
+        });
+        self.save();
+    }
+
+    /// Stores every cookie from the `Set-Cookie` headers of a response to `url`. Cookies
+    /// without a `Domain` attribute are host-only, and those without a `Path` one only apply
+    /// to the folder of `url`.
+    pub fn store_response_cookies(&mut self, url: &Url, headers: &HeaderMap) {
+        let now = Utc::now().timestamp();
+
+        let mut changed = false;
+        for header in headers.get_all(SET_COOKIE) {
+            let Some(cookie) = header
+                .to_str()
+                .ok()
+                .and_then(|header| parse_set_cookie(header, url, now))
+            else {
+                continue;
+            };
+
+            self.insert(cookie);
+            changed = true;
+        }
+
+        if changed {
+            self.save();
+        }
+    }
+
+    /// The `Cookie` header value for a request to `url`, if any unexpired cookie matches its
+    /// host and path.
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let now = Utc::now().timestamp();
+        let cookies: Vec<_> = self
+            .data
+            .get_cookies_for_domain(url.host_str()?)
+            .into_iter()
+            .filter(|c| c.expires.is_none_or(|expires| expires > now))
+            .filter(|c| path_matches(c.path.as_deref().unwrap_or("/"), url.path()))
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+
+        Some(
+            cookies
+                .iter()
+                .map(|c| format!("{}={}", c.name, c.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+
+    fn insert(&mut self, cookie: CookieEntry) {
+        let cookies = self.data.domains.entry(cookie.domain.clone()).or_default();
+
+        let now = Utc::now().timestamp();
+        cookies.retain(|c| {
+            (c.name != cookie.name || c.path != cookie.path)
+                && c.expires.is_none_or(|expires| expires > now)
+        });
+        // An empty value or a past expiry is how servers (and sources) clear a cookie.
+        if !cookie.value.is_empty() && cookie.expires.is_none_or(|expires| expires > now) {
+            cookies.push(cookie);
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        if let Err(err) = self.data.save_to_file(path) {
+            warn!("failed to save cookie jar: {err:?}");
+        }
+    }
+}
+
+fn parse_set_cookie(header: &str, url: &Url, now: i64) -> Option<CookieEntry> {
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut domain = url.host_str()?.to_owned();
+    let mut path = None;
+    let mut max_age = None;
+    let mut expires = None;
+    for attribute in parts {
+        let (key, attribute_value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let attribute_value = attribute_value.trim();
+
+        match key.trim().to_ascii_lowercase().as_str() {
+            "domain" if !attribute_value.is_empty() => {
+                domain = format!(".{}", attribute_value.trim_start_matches('.'));
+            }
+            "path" if attribute_value.starts_with('/') => path = Some(attribute_value.to_owned()),
+            "max-age" => max_age = attribute_value.parse::<i64>().ok(),
+            "expires" => expires = parse_cookie_date(attribute_value),
+            _ => {}
+        }
+    }
+
+    Some(CookieEntry {
+        name: name.to_owned(),
+        value: value.trim().trim_matches('"').to_owned(),
+        domain,
+        path: Some(path.unwrap_or_else(|| default_path(url))),
+        // Max-Age wins over Expires when both are set.
+        expires: max_age
+            .map(|max_age| now.saturating_add(max_age))
+            .or(expires),
+    })
+}
+
+/// Parses the `Expires` attribute, e.g. `Wed, 21 Oct 2015 07:28:00 GMT`. Some servers separate
+/// the date with dashes (`21-Oct-2015`) instead.
+fn parse_cookie_date(value: &str) -> Option<i64> {
+    let value = value.replace('-', " ");
+
+    DateTime::parse_from_rfc2822(&value)
+        .ok()
+        .map(|date| date.timestamp())
+}
+
+/// The folder of `url`, which cookies without a `Path` attribute are limited to (RFC 6265
+/// §5.1.4).
+fn default_path(url: &Url) -> String {
+    match url.path().rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(end) => url.path()[..end].to_owned(),
+    }
+}
+
+/// Whether a cookie with path `cookie_path` is sent to `request_path` (RFC 6265 §5.1.4).
+fn path_matches(cookie_path: &str, request_path: &str) -> bool {
+    let Some(rest) = request_path.strip_prefix(cookie_path) else {
+        return false;
+    };
+
+    rest.is_empty() || cookie_path.ends_with('/') || rest.starts_with('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn set_cookie_replaces_cookie_with_same_name() {
+        let mut jar = CookieJar::default();
+        jar.set_cookie("session".into(), "a".into(), "example.com".into());
+        jar.set_cookie("session".into(), "b".into(), "example.com".into());
+
+        let url = Url::parse("https://example.com/path").unwrap();
+        assert_eq!(jar.cookie_header(&url).as_deref(), Some("session=b"));
+    }
+
+    #[test]
+    fn stores_set_cookie_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            SET_COOKIE,
+            HeaderValue::from_static("token=abc; Path=/; HttpOnly"),
+        );
+        headers.append(
+            SET_COOKIE,
+            HeaderValue::from_static("cf=xyz; Domain=example.com; Secure"),
+        );
+
+        let mut jar = CookieJar::default();
+        jar.store_response_cookies(&Url::parse("https://www.example.com/").unwrap(), &headers);
+
+        let sub = Url::parse("https://www.example.com/").unwrap();
+        let other = Url::parse("https://cdn.example.com/").unwrap();
+        let mut cookies: Vec<_> = jar
+            .cookie_header(&sub)
+            .unwrap()
+            .split("; ")
+            .map(str::to_owned)
+            .collect();
+        cookies.sort();
+
+        assert_eq!(cookies, vec!["cf=xyz", "token=abc"]);
+        assert_eq!(jar.cookie_header(&other).as_deref(), Some("cf=xyz"));
+    }
+
+    fn jar_with(url: &str, set_cookies: &[&'static str]) -> CookieJar {
+        let mut headers = HeaderMap::new();
+        for set_cookie in set_cookies {
+            headers.append(SET_COOKIE, HeaderValue::from_static(set_cookie));
+        }
+
+        let mut jar = CookieJar::default();
+        jar.store_response_cookies(&Url::parse(url).unwrap(), &headers);
+        jar
+    }
+
+    #[test]
+    fn expired_cookies_are_not_sent() {
+        let url = Url::parse("https://example.com/").unwrap();
+
+        let jar = jar_with(
+            "https://example.com/",
+            &[
+                "old=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+                "dashed=1; Expires=Wed, 21-Oct-2015 07:28:00 GMT",
+                "gone=1; Max-Age=0",
+                "kept=1; Max-Age=3600; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+                "future=1; Expires=Fri, 01 Jan 2100 00:00:00 GMT",
+            ],
+        );
+        let mut cookies: Vec<_> = jar
+            .cookie_header(&url)
+            .unwrap()
+            .split("; ")
+            .map(str::to_owned)
+            .collect();
+        cookies.sort();
+        assert_eq!(cookies, vec!["future=1", "kept=1"]);
+
+        let mut jar = jar_with("https://example.com/", &["session=1"]);
+        let mut headers = HeaderMap::new();
+        headers.append(SET_COOKIE, HeaderValue::from_static("session=1; Max-Age=0"));
+        jar.store_response_cookies(&url, &headers);
+        assert_eq!(jar.cookie_header(&url), None);
+    }
+
+    #[test]
+    fn cookies_are_only_sent_under_their_path() {
+        let jar = jar_with("https://example.com/login", &["api=1; Path=/api", "root=1"]);
+        let header = |path: &str| {
+            jar.cookie_header(&Url::parse(&format!("https://example.com{path}")).unwrap())
+        };
+
+        assert_eq!(header("/").as_deref(), Some("root=1"));
+        assert_eq!(header("/apix").as_deref(), Some("root=1"));
+        let mut cookies: Vec<_> = header("/api/chapters")
+            .unwrap()
+            .split("; ")
+            .map(str::to_owned)
+            .collect();
+        cookies.sort();
+        assert_eq!(cookies, vec!["api=1", "root=1"]);
+
+        // Without a `Path`, cookies are limited to the folder of the URL that set them.
+        let jar = jar_with("https://example.com/user/login", &["session=1"]);
+        let url = |path: &str| Url::parse(&format!("https://example.com{path}")).unwrap();
+        assert_eq!(
+            jar.cookie_header(&url("/user/profile")).as_deref(),
+            Some("session=1")
+        );
+        assert_eq!(jar.cookie_header(&url("/")), None);
+    }
+
+    #[test]
+    fn persists_cookies_across_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".source.cookies");
+
+        CookieJar::load(path.clone()).set_cookie(
+            "session".into(),
+            "abc".into(),
+            "example.com".into(),
+        );
+
+        let jar = CookieJar::load(path);
+        let url = Url::parse("https://example.com/").unwrap();
+        assert_eq!(jar.cookie_header(&url).as_deref(), Some("session=abc"));
+    }
+}
@@ -3,9 +3,64 @@ use zune_jpeg::JpegDecoder;
 use zune_png::PngDecoder;
 
 use anyhow::{Context, Result};
+use image::{ImageError, ImageFormat};
+use thiserror::Error;
 
 use crate::source::wasm_store::ImageData;
 
+#[derive(Error, Debug)]
+pub enum DecodeImageError {
+    #[error("could not recognize the image format")]
+    UnknownFormat,
+    #[error("{0:?} images are not supported by this build")]
+    UnsupportedFormat(ImageFormat),
+    #[error("corrupted {format:?} image data: {source}")]
+    Corrupted {
+        format: ImageFormat,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+/// Decodes an image into ARGB pixels. PNG and JPEG go through the faster zune decoders
+/// first; everything else (and anything they fail on) goes through the `image` crate.
+pub fn decode_image(data: &[u8]) -> Result<ImageData, DecodeImageError> {
+    if let Some(result) = decode_image_fast(data) {
+        match result {
+            Ok(image) => return Ok(image),
+            Err(err) => eprintln!("failed to load image with faster {err}, retrying with image"),
+        }
+    }
+
+    let format = image::guess_format(data).map_err(|_| DecodeImageError::UnknownFormat)?;
+    let rgba_img = image::load_from_memory_with_format(data, format)
+        .map_err(|err| match err {
+            ImageError::Unsupported(_) => DecodeImageError::UnsupportedFormat(format),
+            err => DecodeImageError::Corrupted {
+                format,
+                source: err.into(),
+            },
+        })?
+        .to_rgba8();
+
+    let width = rgba_img.width() as i32;
+    let height = rgba_img.height() as i32;
+    let data = rgba_img
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+
+            ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32)
+        })
+        .collect();
+
+    Ok(ImageData {
+        data,
+        width,
+        height,
+    })
+}
+
 pub fn decode_image_fast(data: &[u8]) -> Option<Result<ImageData>> {
     // Detect format
     let is_png = data.starts_with(b"\x89PNG\r\n\x1a\n");
@@ -100,3 +155,44 @@ pub fn decode_argb_to_rgb(width: i32, height: i32, data: &[u32]) -> Result<Vec<u
 
     Ok(rgb_pixels)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{codecs::webp::WebPEncoder, ExtendedColorType};
+
+    #[test]
+    fn decodes_webp_through_image() {
+        let mut webp = Vec::new();
+        WebPEncoder::new_lossless(&mut webp)
+            .encode(
+                &[255, 0, 0, 255, 0, 255, 0, 255],
+                2,
+                1,
+                ExtendedColorType::Rgba8,
+            )
+            .unwrap();
+
+        let image = decode_image(&webp).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.data, vec![0xFFFF0000, 0xFF00FF00]);
+    }
+
+    #[test]
+    fn distinguishes_unknown_format_from_corrupted_data() {
+        assert!(matches!(
+            decode_image(b"definitely not an image"),
+            Err(DecodeImageError::UnknownFormat)
+        ));
+
+        let truncated_png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        assert!(matches!(
+            decode_image(truncated_png),
+            Err(DecodeImageError::Corrupted {
+                format: ImageFormat::Png,
+                ..
+            })
+        ));
+    }
+}
@@ -1,5 +1,10 @@
+use std::sync::Arc;
+
 use anyhow::{anyhow, Result};
-use dom_query::{Matcher, NodeId, NodeRef, Selection};
+use dom_query::{Document, Matcher, NodeId, NodeRef, Selection};
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
 
 use crate::source::wasm_store::WasmStore;
 
@@ -23,12 +28,11 @@ impl HTMLElement {
         }
     }
     pub fn select_soup(&self, store: &mut WasmStore, selector: &str) -> Result<Option<Vec<Self>>> {
+        let matcher = matcher(store, selector)?;
         let Some(node) = self.node_ref(store) else {
             return Ok(None);
         };
 
-        let matcher = Matcher::new(&normalize_contains(selector))
-            .map_err(|err| anyhow!("[{selector}]{:?}", err))?;
         let mut elements = if matcher.match_element(&node) {
             vec![self.clone()]
         } else {
@@ -47,12 +51,11 @@ impl HTMLElement {
         Ok(Some(elements))
     }
     pub fn select_soup_first(&self, store: &mut WasmStore, selector: &str) -> Result<Option<Self>> {
+        let matcher = matcher(store, selector)?;
         let Some(node) = self.node_ref(store) else {
             return Ok(None);
         };
 
-        let matcher = Matcher::new(&normalize_contains(selector))
-            .map_err(|err| anyhow!("[{selector}]{:?}", err))?;
         if matcher.match_element(&node) {
             return Ok(Some(self.clone()));
         }
@@ -117,6 +120,28 @@ impl HTMLElement {
             .collect::<Vec<_>>()
             .into()
     }
+    /// Like `child_nodes`, but skipping text, comment and other non-element nodes.
+    pub fn children_elements(&self, store: &mut WasmStore) -> Option<Vec<Self>> {
+        let node = self.node_ref(store)?;
+
+        node.children()
+            .into_iter()
+            .filter(|node| node.is_element())
+            .map(|node| self.to_element(node.id))
+            .collect::<Vec<_>>()
+            .into()
+    }
+    /// The number of children `children_elements` would return, without collecting them.
+    pub fn children_count(&self, store: &mut WasmStore) -> Option<usize> {
+        let node = self.node_ref(store)?;
+
+        Some(
+            node.children()
+                .into_iter()
+                .filter(|node| node.is_element())
+                .count(),
+        )
+    }
     // pub fn next_siblings(&self, store: &mut WasmStore) -> Option<Vec<Self>> {
     //     let mut node = self.node_ref(store)?;
 
@@ -316,6 +341,154 @@ impl HTMLElement {
 
         ().into()
     }
+    /// The element and its descendants named `local_name` in the `namespace_uri` namespace, with
+    /// prefixes resolved through the `xmlns` attributes of each element and its ancestors.
+    pub fn select_ns(
+        &self,
+        store: &mut WasmStore,
+        namespace_uri: &str,
+        local_name: &str,
+    ) -> Option<Vec<Self>> {
+        let node = self.node_ref(store)?;
+
+        ns_elements(node, namespace_uri, local_name)
+            .into_iter()
+            .map(|node| self.to_element(node.id))
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+/// Builds a document out of XML, like an RSS feed, which the HTML parser would mangle: it knows
+/// `<link>` as an empty element and ignores self-closing tags. Element names are lowercased, as
+/// the HTML parser does, so that the same selectors work on both.
+pub fn parse_xml(xml: &str) -> Result<Document> {
+    let document = Document::default();
+    let mut parents = vec![document.root().id];
+    let mut text = String::new();
+    let mut reader = Reader::from_str(xml);
+
+    loop {
+        let event = reader.read_event()?;
+        if !matches!(
+            event,
+            Event::Text(_) | Event::CData(_) | Event::GeneralRef(_)
+        ) {
+            flush_xml_text(&document, *parents.last().unwrap(), &mut text);
+        }
+
+        match event {
+            Event::Start(tag) => {
+                let element = xml_element(&document, &tag)?;
+                NodeRef::new(*parents.last().unwrap(), &document.tree).append_child(&element);
+                parents.push(element);
+            }
+            Event::Empty(tag) => {
+                let element = xml_element(&document, &tag)?;
+                NodeRef::new(*parents.last().unwrap(), &document.tree).append_child(&element);
+            }
+            Event::End(_) => {
+                if parents.len() > 1 {
+                    parents.pop();
+                }
+            }
+            Event::Text(content) => text.push_str(&unescape_or_raw(std::str::from_utf8(&content)?)),
+            Event::CData(content) => text.push_str(std::str::from_utf8(&content)?),
+            Event::GeneralRef(reference) => text.push_str(&unescape_or_raw(&format!(
+                "&{};",
+                std::str::from_utf8(&reference)?
+            ))),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(document)
+}
+
+fn xml_element(document: &Document, tag: &BytesStart) -> Result<NodeId> {
+    let name = std::str::from_utf8(tag.name().as_ref())?.to_lowercase();
+    let element = document.tree.new_element(&name);
+    for attribute in tag.attributes() {
+        let attribute = attribute?;
+        element.set_attr(
+            std::str::from_utf8(attribute.key.as_ref())?,
+            &unescape_or_raw(std::str::from_utf8(&attribute.value)?),
+        );
+    }
+
+    Ok(element.id)
+}
+
+fn flush_xml_text(document: &Document, parent: NodeId, text: &mut String) {
+    if text.is_empty() {
+        return;
+    }
+
+    let node = document.tree.new_text(std::mem::take(text));
+    NodeRef::new(parent, &document.tree).append_child(&node.id);
+}
+
+/// Entities XML doesn't define, like the `&nbsp;` feeds copy from their HTML, are kept as-is.
+fn unescape_or_raw(value: &str) -> String {
+    unescape(value)
+        .map(|value| value.into_owned())
+        .unwrap_or_else(|_| value.to_owned())
+}
+
+fn ns_elements<'a>(node: NodeRef<'a>, namespace_uri: &str, local_name: &str) -> Vec<NodeRef<'a>> {
+    let mut elements = vec![];
+    if node.is_element() {
+        let name = node.node_name().unwrap_or_default().to_string();
+        let (prefix, name) = match name.split_once(':') {
+            Some((prefix, name)) => (Some(prefix), name),
+            None => (None, name.as_str()),
+        };
+
+        if name.eq_ignore_ascii_case(local_name)
+            && namespace_of(&node, prefix).unwrap_or_default() == namespace_uri
+        {
+            elements.push(node.clone());
+        }
+    }
+
+    elements.extend(
+        node.children()
+            .into_iter()
+            .flat_map(|node| ns_elements(node, namespace_uri, local_name)),
+    );
+
+    elements
+}
+
+/// The URI `prefix` (or the default namespace, without one) is bound to at `node`.
+fn namespace_of(node: &NodeRef, prefix: Option<&str>) -> Option<String> {
+    let attr = prefix.map_or_else(|| "xmlns".to_owned(), |prefix| format!("xmlns:{prefix}"));
+
+    let mut current = Some(node.clone());
+    while let Some(node) = current {
+        if let Some(uri) = node.attr(&attr) {
+            return Some(uri.to_string());
+        }
+        current = node.parent();
+    }
+
+    None
+}
+
+/// Parses `selector`, reusing the matcher from an earlier call with the same selector.
+fn matcher(store: &mut WasmStore, selector: &str) -> Result<Arc<Matcher>> {
+    if let Some(matcher) = store.get_cached_selector(selector) {
+        return Ok(matcher);
+    }
+
+    let matcher = Arc::new(
+        Matcher::new(&normalize_contains(selector))
+            .map_err(|err| anyhow!("[{selector}]{:?}", err))?,
+    );
+    store.cache_selector(selector.to_owned(), matcher.clone());
+
+    Ok(matcher)
 }
 
 fn normalize_contains(selector: &str) -> String {
@@ -391,10 +564,15 @@ mod tests {
     use super::normalize_contains;
 
     fn setup_html_store(html: &str) -> (crate::source::wasm_store::WasmStore, super::HTMLElement) {
+        setup_store(dom_query::Document::from(html))
+    }
+
+    fn setup_store(
+        document: dom_query::Document,
+    ) -> (crate::source::wasm_store::WasmStore, super::HTMLElement) {
         use crate::settings::Settings;
         use crate::source::source_settings::SourceSettings;
         use crate::source_manager::SourceManager;
-        use dom_query::Document;
         use std::collections::HashMap;
         use std::path::PathBuf;
         use std::sync::Arc;
@@ -412,7 +590,6 @@ mod tests {
         .unwrap();
 
         let mut store = crate::source::wasm_store::WasmStore::default(source_settings);
-        let document = Document::from(html);
         let root_id = document.root().id;
         let html_idx = store.set_html(document);
         let element = super::HTMLElement {
@@ -423,6 +600,35 @@ mod tests {
         (store, element)
     }
 
+    #[test]
+    fn test_children_count_skips_non_element_nodes() {
+        let (mut store, element) =
+            setup_html_store("<div><p>a</p>text<!-- comment --><span>b</span></div>");
+        let div = element
+            .select_soup(&mut store, "div")
+            .unwrap()
+            .unwrap()
+            .remove(0);
+
+        assert_eq!(div.children_count(&mut store), Some(2));
+    }
+
+    #[test]
+    fn repeated_selectors_are_parsed_once() {
+        let (mut store, element) = setup_html_store("<div><p>a</p><p>b</p></div>");
+
+        let first = element.select_soup(&mut store, "p").unwrap().unwrap();
+        let cached = store.get_cached_selector("p").unwrap();
+        let second = element.select_soup(&mut store, "p").unwrap().unwrap();
+
+        assert_eq!(first.len(), 2);
+        assert_eq!(second.len(), 2);
+        assert!(std::sync::Arc::ptr_eq(
+            &cached,
+            &store.get_cached_selector("p").unwrap()
+        ));
+    }
+
     #[test]
     fn test_kind_element() {
         let (mut store, _) = setup_html_store("<div><p>hello</p><span>world</span></div>");
@@ -579,4 +785,46 @@ mod tests {
             "section[data-id=\"123\"] .card:contains(\"New (2025)\") .title:contains(\"Đặc biệt\")";
         assert_eq!(normalize_contains(sel), expected);
     }
+
+    #[test]
+    fn xml_keeps_elements_the_html_parser_treats_as_empty() {
+        let (mut store, element) = setup_store(
+            super::parse_xml(
+                r#"<rss><channel><item><title>A &amp; B</title><link>https://a.test/1</link><guid/></item></channel></rss>"#,
+            )
+            .unwrap(),
+        );
+
+        let link = element
+            .select_soup(&mut store, "item > link")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            link[0].text(&mut store).as_deref(),
+            Some("https://a.test/1")
+        );
+
+        let title = element.select_soup(&mut store, "title").unwrap().unwrap();
+        assert_eq!(title[0].own_text(&mut store).as_deref(), Some("A & B"));
+    }
+
+    #[test]
+    fn selects_xml_elements_by_namespace() {
+        let (mut store, element) = setup_store(
+            super::parse_xml(
+                r#"<rss xmlns:media="http://search.yahoo.com/mrss/"><item><media:content url="a.jpg"/><content url="b.jpg"/></item></rss>"#,
+            )
+            .unwrap(),
+        );
+
+        let media = element
+            .select_ns(&mut store, "http://search.yahoo.com/mrss/", "content")
+            .unwrap();
+        assert_eq!(media.len(), 1);
+        assert_eq!(media[0].attr(&mut store, "url").as_deref(), Some("a.jpg"));
+
+        let plain = element.select_ns(&mut store, "", "content").unwrap();
+        assert_eq!(plain.len(), 1);
+        assert_eq!(plain[0].attr(&mut store, "url").as_deref(), Some("b.jpg"));
+    }
 }
@@ -0,0 +1,207 @@
+use std::collections::{HashMap, VecDeque};
+
+use reqwest::Request;
+use tokio::sync::watch;
+use url::Url;
+
+use super::source_settings::SourceSettingChanged;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageRequestKey {
+    url: Url,
+    /// The page context entries, sorted so that equal contexts give equal keys.
+    context: Option<Vec<(String, String)>>,
+}
+
+impl ImageRequestKey {
+    pub fn new(url: &Url, context: Option<&aidoku::PageContext>) -> Self {
+        let context = context.map(|context| {
+            let mut entries: Vec<_> = context
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            entries.sort();
+            entries
+        });
+
+        Self {
+            url: url.clone(),
+            context,
+        }
+    }
+}
+
+/// A bounded, least-recently-used cache of the requests built by a source's
+/// `modify_image_request`/`get_image_request`. Sources that only add static headers build
+/// the same request for every page, so this saves a WASM call per page. Everything is dropped
+/// once the source changes one of its settings, as logging in usually stores the token the
+/// requests are built with.
+pub struct ImageRequestCache {
+    max_entries: usize,
+    entries: HashMap<ImageRequestKey, Request>,
+    /// Keys ordered from least to most recently used.
+    order: VecDeque<ImageRequestKey>,
+    settings_changes: watch::Receiver<SourceSettingChanged>,
+}
+
+impl ImageRequestCache {
+    pub fn new(
+        max_entries: usize,
+        settings_changes: watch::Receiver<SourceSettingChanged>,
+    ) -> Self {
+        Self {
+            max_entries,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            settings_changes,
+        }
+    }
+
+    /// Returns a copy of the cached request, since a [`Request`] can only be sent once.
+    pub fn get(&mut self, key: &ImageRequestKey) -> Option<Request> {
+        self.clear_if_settings_changed();
+
+        let request = self.entries.get(key)?.try_clone()?;
+        self.touch(key);
+
+        Some(request)
+    }
+
+    /// Caches a copy of `request`. Requests that can't be copied, like those with a streamed
+    /// body, are not cached.
+    pub fn insert(&mut self, key: ImageRequestKey, request: &Request) {
+        self.clear_if_settings_changed();
+        if self.max_entries == 0 {
+            return;
+        }
+
+        let Some(request) = request.try_clone() else {
+            return;
+        };
+
+        if self.entries.insert(key.clone(), request).is_some() {
+            self.touch(&key);
+            return;
+        }
+
+        self.order.push_back(key);
+        while self.order.len() > self.max_entries {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn clear_if_settings_changed(&mut self) {
+        // An error means the settings were dropped, which only happens along with the source.
+        if self.settings_changes.has_changed().unwrap_or(false) {
+            self.settings_changes.mark_unchanged();
+            self.entries.clear();
+            self.order.clear();
+        }
+    }
+
+    fn touch(&mut self, key: &ImageRequestKey) {
+        if let Some(position) = self.order.iter().position(|k| k == key) {
+            if let Some(key) = self.order.remove(position) {
+                self.order.push_back(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::SourceSettingValue;
+    use reqwest::Method;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn request(url_str: &str) -> Request {
+        Request::new(Method::GET, url(url_str))
+    }
+
+    fn changes() -> watch::Sender<SourceSettingChanged> {
+        watch::channel(SourceSettingChanged {
+            key: String::new(),
+            value: SourceSettingValue::Null,
+        })
+        .0
+    }
+
+    #[test]
+    fn returns_copies_of_cached_requests() {
+        let changes = changes();
+        let mut cache = ImageRequestCache::new(2, changes.subscribe());
+        let key = ImageRequestKey::new(&url("https://a.test/1"), None);
+        cache.insert(key.clone(), &request("https://a.test/1"));
+
+        assert_eq!(cache.get(&key).unwrap().url().as_str(), "https://a.test/1");
+        assert!(cache.get(&key).is_some());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entry() {
+        let changes = changes();
+        let mut cache = ImageRequestCache::new(2, changes.subscribe());
+        let key = |n: usize| ImageRequestKey::new(&url(&format!("https://a.test/{n}")), None);
+        cache.insert(key(1), &request("https://a.test/1"));
+        cache.insert(key(2), &request("https://a.test/2"));
+
+        assert!(cache.get(&key(1)).is_some());
+
+        cache.insert(key(3), &request("https://a.test/3"));
+
+        assert!(cache.get(&key(2)).is_none());
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(3)).is_some());
+    }
+
+    #[test]
+    fn page_context_is_part_of_the_key() {
+        let changes = changes();
+        let mut cache = ImageRequestCache::new(4, changes.subscribe());
+        let first: aidoku::PageContext = [
+            ("a".to_owned(), "1".to_owned()),
+            ("b".to_owned(), "2".to_owned()),
+        ]
+        .into_iter()
+        .collect();
+        let other: aidoku::PageContext = [("a".to_owned(), "2".to_owned())].into_iter().collect();
+
+        let page_url = url("https://a.test/1");
+        cache.insert(
+            ImageRequestKey::new(&page_url, Some(&first)),
+            &request("https://a.test/1"),
+        );
+
+        assert!(cache
+            .get(&ImageRequestKey::new(&page_url, Some(&first.clone())))
+            .is_some());
+        assert!(cache
+            .get(&ImageRequestKey::new(&page_url, Some(&other)))
+            .is_none());
+        assert!(cache.get(&ImageRequestKey::new(&page_url, None)).is_none());
+    }
+
+    #[test]
+    fn setting_changes_clear_the_cache() {
+        let changes = changes();
+        let mut cache = ImageRequestCache::new(4, changes.subscribe());
+        let key = ImageRequestKey::new(&url("https://a.test/1"), None);
+        cache.insert(key.clone(), &request("https://a.test/1"));
+
+        changes.send_replace(SourceSettingChanged {
+            key: "token".to_owned(),
+            value: SourceSettingValue::String("abc".to_owned()),
+        });
+
+        assert!(cache.get(&key).is_none());
+
+        cache.insert(key.clone(), &request("https://a.test/1"));
+        assert!(cache.get(&key).is_some());
+    }
+}
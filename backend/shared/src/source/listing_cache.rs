@@ -0,0 +1,113 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::watch;
+
+use super::{source_settings::SourceSettingChanged, NextMangaPageResult};
+
+/// Pages of listings browsed recently, so that scrolling back and forth through a listing
+/// doesn't call the source again for pages it just returned. Everything is dropped once the
+/// source changes one of its settings, as the listing may depend on them.
+pub struct ListingCache {
+    ttl: Duration,
+    /// Keyed by listing ID and page.
+    entries: HashMap<(String, i32), (NextMangaPageResult, Instant)>,
+    settings_changes: watch::Receiver<SourceSettingChanged>,
+}
+
+impl ListingCache {
+    pub fn new(ttl: Duration, settings_changes: watch::Receiver<SourceSettingChanged>) -> Self {
+        Self {
+            ttl,
+            entries: HashMap::new(),
+            settings_changes,
+        }
+    }
+
+    pub fn get(&mut self, listing_id: &str, page: i32) -> Option<NextMangaPageResult> {
+        self.clear_if_settings_changed();
+
+        let key = (listing_id.to_owned(), page);
+        let (result, cached_at) = self.entries.get(&key)?;
+        if cached_at.elapsed() >= self.ttl {
+            self.entries.remove(&key);
+            return None;
+        }
+
+        Some(result.clone())
+    }
+
+    pub fn insert(&mut self, listing_id: String, page: i32, result: NextMangaPageResult) {
+        self.clear_if_settings_changed();
+
+        let ttl = self.ttl;
+        self.entries
+            .retain(|_, (_, cached_at)| cached_at.elapsed() < ttl);
+        self.entries
+            .insert((listing_id, page), (result, Instant::now()));
+    }
+
+    fn clear_if_settings_changed(&mut self) {
+        // An error means the settings were dropped, which only happens along with the source.
+        if self.settings_changes.has_changed().unwrap_or(false) {
+            self.settings_changes.mark_unchanged();
+            self.entries.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::SourceSettingValue;
+
+    fn page(has_next_page: bool) -> NextMangaPageResult {
+        NextMangaPageResult {
+            entries: Vec::new(),
+            has_next_page,
+        }
+    }
+
+    fn changes() -> watch::Sender<SourceSettingChanged> {
+        watch::channel(SourceSettingChanged {
+            key: String::new(),
+            value: SourceSettingValue::Null,
+        })
+        .0
+    }
+
+    #[test]
+    fn returns_pages_until_they_expire() {
+        let changes = changes();
+        let mut cache = ListingCache::new(Duration::from_secs(60), changes.subscribe());
+        cache.insert("popular".to_owned(), 1, page(true));
+
+        assert_eq!(cache.get("popular", 1), Some(page(true)));
+        assert_eq!(cache.get("popular", 2), None);
+        assert_eq!(cache.get("latest", 1), None);
+
+        let mut cache = ListingCache::new(Duration::ZERO, changes.subscribe());
+        cache.insert("popular".to_owned(), 1, page(true));
+
+        assert_eq!(cache.get("popular", 1), None);
+    }
+
+    #[test]
+    fn setting_changes_clear_the_cache() {
+        let changes = changes();
+        let mut cache = ListingCache::new(Duration::from_secs(60), changes.subscribe());
+        cache.insert("popular".to_owned(), 1, page(false));
+
+        changes.send_replace(SourceSettingChanged {
+            key: "url".to_owned(),
+            value: SourceSettingValue::String("https://example.com".to_owned()),
+        });
+
+        assert_eq!(cache.get("popular", 1), None);
+
+        cache.insert("popular".to_owned(), 1, page(false));
+        assert_eq!(cache.get("popular", 1), Some(page(false)));
+    }
+}
@@ -3,11 +3,14 @@ use anyhow::{anyhow, bail, Context, Result};
 use reqwest::{header::HeaderMap, Method, Request, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{HashMap, HashSet},
     fs,
     io::Read,
     path::Path,
     sync::{Arc, Mutex},
+    time::Duration,
 };
+use tokio::sync::watch;
 use tokio_util::bytes::Bytes;
 use tokio_util::sync::CancellationToken;
 use url::Url;
@@ -25,8 +28,15 @@ use crate::{
 };
 
 use self::{
-    model::{Chapter, Filter, Manga, MangaPageResult, Page, SettingDefinition},
-    source_settings::SourceSettings,
+    image_request_cache::{ImageRequestCache, ImageRequestKey},
+    listing_cache::ListingCache,
+    model::{
+        Chapter, DeepLink, Filter, Manga, MangaPageResult, Page, PublishingStatus,
+        SettingDefinition,
+    },
+    preload_cache::PreloadCache,
+    processed_image_cache::{CacheKey, ProcessedImageCache},
+    source_settings::{SourceSettingChanged, SourceSettings},
     wasm_imports::{
         aidoku::register_aidoku_imports,
         defaults::register_defaults_imports,
@@ -37,12 +47,17 @@ use self::{
         std::register_std_imports,
     },
     wasm_store::{
-        ObjectValue, OperationContext, OperationContextObject, RequestBuildingState, RequestState,
-        Value, ValueMap, WasmStore,
+        BandwidthCounters, BandwidthStats, ObjectValue, OperationContext, OperationContextObject,
+        RequestBuildingState, RequestState, Value, ValueMap, WasmStore,
     },
 };
 
+pub(crate) mod cookie_jar;
 pub(crate) mod decode_image;
+mod image_request_cache;
+mod listing_cache;
+pub mod preload_cache;
+pub mod processed_image_cache;
 
 #[cfg(not(feature = "all"))]
 pub mod html_element;
@@ -66,31 +81,35 @@ pub mod wasm_store;
 #[cfg(not(any(feature = "ffi", feature = "all")))]
 mod wasm_store;
 
+/// How many distinct image requests each source keeps, see [`ImageRequestCache`].
+const IMAGE_REQUEST_CACHE_MAX_ENTRIES: usize = 64;
+/// How long a page of a listing is reused before the source is asked for it again.
+const LISTING_CACHE_TTL: Duration = Duration::from_secs(60);
+
 /**
  * params need mark encode
  * handle_notification
- * handle_deep_link
- * handle_basic_login
  * handle_web_login
- * handle_key_migration
  *
  */
 
 #[derive(Clone)]
 pub struct Source(
     /// In order to avoid issues when calling functions that block inside the `Source` from an
-    /// async context, we wrap all data and functions that need to block inside `BlockingSource`
-    /// and call them using `spawn_blocking` from within the facades exposed by `Source`.
-    /// Particularly, all calls to `reqwest::blocking` methods from an async context causes the
-    /// program to panic (see https://github.com/seanmonstar/reqwest/issues/1017), and we do call
-    /// them inside the `net` module.
+    /// async context, we wrap all data and functions that need to block inside
+    /// `BlockingSourceMutable` and call them using `spawn_blocking` from within the facades
+    /// exposed by `Source`. Particularly, all calls to `reqwest::blocking` methods from an
+    /// async context causes the program to panic
+    /// (see https://github.com/seanmonstar/reqwest/issues/1017), and we do call them inside the
+    /// `net` module.
     ///
-    /// This also provides interior mutability, but we probably could also do it inside the
-    /// `BlockingSource` itself, by placing things inside a mutex. It might be a cleaner design.
+    /// This also provides interior mutability for the WASM store and instance.
     #[cfg(feature = "all")]
-    Arc<Mutex<BlockingSource>>,
-    #[cfg(not(feature = "all"))] pub Arc<Mutex<BlockingSource>>,
-    pub SourceFeatures,
+    Arc<Mutex<BlockingSourceMutable>>,
+    #[cfg(not(feature = "all"))] pub Arc<Mutex<BlockingSourceMutable>>,
+    /// Everything that never changes after the source is loaded, readable without waiting on
+    /// the lock above while a WASM call is running.
+    pub Arc<BlockingSourceShared>,
 );
 
 #[macro_export]
@@ -139,33 +158,99 @@ impl Source {
         arc_manager: &Arc<tokio::sync::Mutex<SourceManager>>,
     ) -> Result<Self> {
         #[cfg(feature = "all")]
-        let mut blocking_source = BlockingSource::from_aix_file(path, manager, arc_manager, None)?;
+        let mut blocking_source =
+            BlockingSourceMutable::from_aix_file(path, manager, arc_manager, None)?;
 
         #[cfg(feature = "all")]
-        if blocking_source.next_sdk {
+        if blocking_source.shared.next_sdk {
             blocking_source.start()?;
+        } else if blocking_source.exports("start") {
+            // Some legacy sources built from newer templates export it, but crash when it's
+            // called.
+            log::warn!(
+                "source {} uses the legacy SDK but exports `start`, not calling it",
+                blocking_source.id
+            );
         }
 
         #[cfg(not(feature = "all"))]
-        let blocking_source = BlockingSource::from_aix_file(path, manager, arc_manager, None)?;
+        let blocking_source =
+            BlockingSourceMutable::from_aix_file(path, manager, arc_manager, None)?;
 
-        let features = { blocking_source.features.clone() };
+        let shared = blocking_source.shared.clone();
 
-        Ok(Self(Arc::new(Mutex::new(blocking_source)), features))
+        Ok(Self(Arc::new(Mutex::new(blocking_source)), shared))
     }
 
     pub fn manifest(&self) -> SourceManifest {
         // FIXME we dont actually need to clone here but yeah it's easier
-        self.0.lock().unwrap().manifest.clone()
+        self.1.manifest.clone()
+    }
+
+    pub fn is_next_sdk(&self) -> bool {
+        self.1.next_sdk
+    }
+
+    /// How much the source sent and received since it was (re)loaded.
+    pub fn bandwidth_stats(&self) -> BandwidthStats {
+        self.1.bandwidth.stats()
+    }
+
+    /// Counts traffic done on the source's behalf outside of its own requests, like the
+    /// prefetches of preload hints.
+    pub fn record_bandwidth(&self, sent: usize, received: usize) {
+        self.1.bandwidth.record(sent, received);
+    }
+
+    /// Responses prefetched from the preload hints of image responses.
+    pub fn preload_cache(&self) -> Arc<PreloadCache> {
+        self.1.preload_cache.clone()
+    }
+
+    /// Notifies of every setting the source persists through `defaults.set`.
+    pub fn subscribe_setting_changes(&self) -> watch::Receiver<SourceSettingChanged> {
+        self.0
+            .lock()
+            .unwrap()
+            .store
+            .data()
+            .source_settings
+            .subscribe()
     }
 
     pub fn setting_definitions(&self) -> Vec<SettingDefinition> {
-        self.0.lock().unwrap().setting_definitions.clone()
+        self.1.setting_definitions.clone()
+    }
+
+    /// Encrypts the plain strings the frontend sent for this source's `secure-input` settings.
+    pub fn seal_secure_settings(
+        &self,
+        stored_settings: HashMap<String, SourceSettingValue>,
+    ) -> HashMap<String, SourceSettingValue> {
+        let secure_keys: HashSet<_> = self
+            .1
+            .setting_definitions
+            .iter()
+            .flat_map(source_settings::secure_keys_for_definition)
+            .collect();
+
+        stored_settings
+            .into_iter()
+            .map(|(key, value)| {
+                let value = if secure_keys.contains(&key) {
+                    source_settings::seal_secure_value(value)
+                } else {
+                    value
+                };
+
+                (key, value)
+            })
+            .collect()
     }
 
     pub fn write_meta_file(path: &Path, source_of_source: String) -> anyhow::Result<()> {
         fs::write(
-            BlockingSource::meta_source_path(path)?,
+            BlockingSourceMutable::meta_source_path(path)?,
             serde_json::to_string(&SourceMeta {
                 source_of_source: Some(source_of_source),
                 is_next_sdk: None,
@@ -193,7 +278,7 @@ impl Source {
         get_manga_details,
         Result<Manga>,
         cancellation_token: CancellationToken,
-        manga_id: String
+        manga: Manga
     );
 
     wrap_blocking_source_fn!(
@@ -207,9 +292,7 @@ impl Source {
         get_page_list,
         Result<Vec<Page>>,
         cancellation_token: CancellationToken,
-        manga_id: String,
-        chapter_id: String,
-        chapter_num: Option<f32>
+        chapter: Chapter
     );
 
     wrap_blocking_source_fn!(
@@ -221,7 +304,7 @@ impl Source {
 
     wrap_blocking_source_fn!(
         process_page_image,
-        Result<Vec<u8>>,
+        Result<(Vec<u8>, Option<ProcessedImageFormat>)>,
         cancellation_token: CancellationToken,
         request: (Url, HeaderMap),
         response: (StatusCode, HeaderMap),
@@ -270,12 +353,50 @@ impl Source {
         page: i32
     );
 
+    wrap_blocking_source_fn!(
+        get_manga_by_tag,
+        Result<Vec<Manga>>,
+        cancellation_token: CancellationToken,
+        tag: String,
+        page: u32
+    );
+
+    wrap_blocking_source_fn!(
+        get_tag_list,
+        Result<Vec<String>>,
+        cancellation_token: CancellationToken
+    );
+
+    wrap_blocking_source_fn!(
+        get_listings_next,
+        Result<Vec<String>>,
+        cancellation_token: CancellationToken
+    );
+
     wrap_blocking_source_fn!(
         handle_notification_next,
         Result<()>,
         cancellation_token: CancellationToken,
         key: String
     );
+
+    wrap_blocking_source_fn!(handle_deep_link, Result<DeepLink>, url: Url);
+
+    wrap_blocking_source_fn!(
+        handle_key_migration,
+        Result<Option<String>>,
+        cancellation_token: CancellationToken,
+        old_id: String
+    );
+
+    wrap_blocking_source_fn!(
+        handle_basic_login,
+        Result<bool>,
+        cancellation_token: CancellationToken,
+        key: String,
+        username: String,
+        password: String
+    );
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -300,6 +421,79 @@ pub struct SourceInfo {
 pub struct SourceConfig {
     #[serde(rename = "allowsBaseUrlSelect")]
     pub allows_base_url_select: Option<bool>,
+    /// The format images returned by `process_page_image` should be encoded in.
+    #[serde(rename = "outputFormat")]
+    pub output_format: Option<ProcessedImageFormat>,
+}
+
+/// The encoding used for images produced by a source's `process_page_image`.
+///
+/// In `source.json` this is written as `"png"`, `"lossless"` or
+/// `{ "jpeg": { "quality": 90 } }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessedImageFormat {
+    Jpeg {
+        quality: u8,
+    },
+    Png,
+    /// PNG with the best (and slowest) compression.
+    Lossless,
+}
+
+impl Default for ProcessedImageFormat {
+    fn default() -> Self {
+        // libjpeg's default quality, which is what we used before sources could choose.
+        Self::Jpeg { quality: 75 }
+    }
+}
+
+/// Hash of an image's size and pixels, to tell whether `process_page_image` changed it.
+fn pixels_hash(image: &wasm_store::ImageData) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (image.width, image.height, &image.data).hash(&mut hasher);
+
+    hasher.finish()
+}
+
+impl ProcessedImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jpeg { .. } => "jpg",
+            Self::Png | Self::Lossless => "png",
+        }
+    }
+
+    fn encode(&self, width: u32, height: u32, rgb_pixels: &[u8]) -> Result<Vec<u8>> {
+        use image::{
+            codecs::png::{CompressionType, FilterType, PngEncoder},
+            ExtendedColorType, ImageEncoder,
+        };
+
+        let compression = match *self {
+            Self::Jpeg { quality } => {
+                let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+                comp.set_size(width as usize, height as usize);
+                comp.set_fastest_defaults();
+                comp.set_quality(quality.clamp(1, 100) as f32);
+
+                let mut comp = comp.start_compress(Vec::new())?;
+                comp.write_scanlines(rgb_pixels)?;
+
+                return Ok(comp.finish()?);
+            }
+            Self::Png => CompressionType::Default,
+            Self::Lossless => CompressionType::Best,
+        };
+
+        let mut png_data = Vec::new();
+        PngEncoder::new_with_quality(&mut png_data, compression, FilterType::Adaptive)
+            .write_image(rgb_pixels, width, height, ExtendedColorType::Rgb8)?;
+
+        Ok(png_data)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -310,9 +504,12 @@ pub struct SourceManifest {
     pub source_of_source: Option<String>,
 }
 
+/// Optional exports of the source's WASM module. They're detected on every load, as the
+/// `.aix` file may have been replaced since it was last loaded.
 #[derive(Debug, Clone)]
 pub struct SourceFeatures {
     pub process_page_image: bool,
+    pub has_handle_notification: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -329,6 +526,30 @@ fn get_memory(instance: Instance, store: &mut Store<WasmStore>) -> Result<Memory
     }
 }
 
+/// Drops every manga whose ID was already seen, keeping the first occurrence. Some sources list
+/// the same manga more than once, e.g. once per matching chapter.
+fn dedup_mangas_by_id(mut mangas: Vec<Manga>) -> Vec<Manga> {
+    let mut seen_ids = HashSet::new();
+    mangas.retain(|manga| seen_ids.insert(manga.id.clone()));
+
+    mangas
+}
+
+/// Gives a title to chapters that come without one, from their number (or their position in
+/// the list, `index`, when they don't have one either).
+fn with_fallback_title(mut chapter: Chapter, index: usize) -> Chapter {
+    if chapter.title.as_ref().map(|s| s.is_empty()).unwrap_or(true) {
+        chapter.title = Some(format!(
+            "Ch.{}",
+            chapter
+                .chapter_num
+                .unwrap_or(chapter.volume_num.unwrap_or(index as f32))
+        ));
+    }
+
+    chapter
+}
+
 /// from aidoku sdk
 /// A page of manga entries.
 #[derive(Default, Clone, Debug, PartialEq, Deserialize)]
@@ -339,28 +560,41 @@ pub struct NextMangaPageResult {
     pub has_next_page: bool,
 }
 
+/// The parts of a source that are fixed once it is loaded.
+#[derive(Debug)]
+pub struct BlockingSourceShared {
+    pub manifest: SourceManifest,
+    pub setting_definitions: Vec<SettingDefinition>,
+    pub features: SourceFeatures,
+    pub next_sdk: bool,
+    /// The same counters as the WASM store's; only the counts inside change.
+    pub bandwidth: Arc<BandwidthCounters>,
+    /// The same cache as the WASM store's.
+    pub preload_cache: Arc<PreloadCache>,
+}
+
 #[cfg(not(feature = "all"))]
-pub struct BlockingSource {
+pub struct BlockingSourceMutable {
     pub id: String,
     pub store: Store<WasmStore>,
     pub instance: Instance,
-    pub manifest: SourceManifest,
-    pub setting_definitions: Vec<SettingDefinition>,
-    pub next_sdk: bool,
-    pub features: SourceFeatures,
+    pub shared: Arc<BlockingSourceShared>,
+    pub processed_image_cache: ProcessedImageCache,
+    image_request_cache: ImageRequestCache,
+    listing_cache: ListingCache,
 }
 #[cfg(feature = "all")]
-struct BlockingSource {
+struct BlockingSourceMutable {
     id: String,
     store: Store<WasmStore>,
     instance: Instance,
-    manifest: SourceManifest,
-    setting_definitions: Vec<SettingDefinition>,
-    pub next_sdk: bool,
-    pub features: SourceFeatures,
+    pub shared: Arc<BlockingSourceShared>,
+    processed_image_cache: ProcessedImageCache,
+    image_request_cache: ImageRequestCache,
+    listing_cache: ListingCache,
 }
 
-impl BlockingSource {
+impl BlockingSourceMutable {
     pub fn from_aix_file(
         path: &Path,
         manager: &SourceManager,
@@ -400,13 +634,28 @@ impl BlockingSource {
 
         let url_settings = {
             let manifest = manifest.clone();
-            manifest.info.urls.map(|urls| SettingDefinition::Select {
-                title: "URL".to_owned(),
-                key: "url".to_owned(),
-                default: Some(urls.first().unwrap_or(&"".to_owned()).to_string()),
-                values: urls,
-                titles: None,
-            })
+            let allows_base_url_select = manifest
+                .config
+                .as_ref()
+                .and_then(|config| config.allows_base_url_select)
+                .unwrap_or(false);
+
+            match manifest.info.urls {
+                Some(urls) => Some(SettingDefinition::Select {
+                    title: "URL".to_owned(),
+                    key: "url".to_owned(),
+                    default: Some(urls.first().unwrap_or(&"".to_owned()).to_string()),
+                    values: urls,
+                    titles: None,
+                }),
+                // Without a list of mirrors to pick from, the user gets to type any URL.
+                None if allows_base_url_select => Some(SettingDefinition::Text {
+                    placeholder: manifest.info.url.clone(),
+                    key: "url".to_owned(),
+                    default: manifest.info.url,
+                }),
+                None => None,
+            }
         };
         let url_settings_support = url_settings.is_some();
 
@@ -443,7 +692,8 @@ impl BlockingSource {
             &setting_definitions,
             &stored_source_settings,
             arc_manager,
-        )?;
+        )?
+        .with_persisted_file(Self::meta_source_path(path)?.with_extension("settings.json"));
         if !url_settings_support && source_settings.get(&"url".to_string()).is_none() {
             if let Some(url) = manifest.info.url.clone() {
                 source_settings.set("url", SourceSettingValue::String(url));
@@ -458,11 +708,13 @@ impl BlockingSource {
             .with_context(|| format!("failed reading wasm from zip entry {}", path.display()))?;
 
         let engine = Engine::default();
-        let wasm_store = WasmStore::new(
+        let mut wasm_store = WasmStore::new(
             manifest.info.id.clone(),
             source_settings,
             manager.settings.clone(),
         );
+        wasm_store.cookie_jar =
+            cookie_jar::CookieJar::load(Self::meta_source_path(path)?.with_extension("cookies"));
         let mut store = Store::new(&engine, wasm_store);
 
         let module = Module::new(&engine, &wasm_bytes)
@@ -497,8 +749,8 @@ impl BlockingSource {
             Ok(instance) => instance,
             Err(error) => {
                 if force_mode.is_none() {
-                    println!(
-                        "Info: failed instantiating {id} retry mode {}",
+                    log::info!(
+                        "failed instantiating {id}, retrying in {} mode",
                         if aidoku_sdk_next { "legacy" } else { "next" }
                     );
 
@@ -510,12 +762,39 @@ impl BlockingSource {
             }
         };
 
+        // A wrongly typed export would only fail once it's called, so catch it here instead.
+        if let Err(error) = Self::check_export_signatures(&instance, &mut store, aidoku_sdk_next)
+            .with_context(|| format!("invalid exports in {}", path.display()))
+        {
+            if force_mode.is_none() {
+                log::info!(
+                    "exports of {id} don't match, retrying in {} mode",
+                    if aidoku_sdk_next { "legacy" } else { "next" }
+                );
+
+                return Self::from_aix_file(path, manager, arc_manager, Some(!aidoku_sdk_next));
+            }
+
+            return Err(error);
+        }
+
+        // Only sources using the new SDK get these called, and an optional export with the
+        // wrong signature just leaves its feature off.
         let features = SourceFeatures {
-            process_page_image: instance
-                .get_typed_func::<(i32, i32), i32>(&mut store, "process_page_image")
-                .map(|_| true)
-                .ok()
-                .unwrap_or(false),
+            process_page_image: aidoku_sdk_next
+                && Self::has_optional_export::<(i32, i32), i32>(
+                    &instance,
+                    &store,
+                    &id,
+                    "process_page_image",
+                ),
+            has_handle_notification: aidoku_sdk_next
+                && Self::has_optional_export::<i32, i32>(
+                    &instance,
+                    &store,
+                    &id,
+                    "handle_notification",
+                ),
         };
 
         if aidoku_sdk_next_from_meta.is_none()
@@ -532,17 +811,114 @@ impl BlockingSource {
             );
         }
 
+        let bandwidth = store.data().bandwidth.clone();
+        let preload_cache = store.data().preload_cache.clone();
+        let listing_cache =
+            ListingCache::new(LISTING_CACHE_TTL, store.data().source_settings.subscribe());
+        let image_request_cache = ImageRequestCache::new(
+            IMAGE_REQUEST_CACHE_MAX_ENTRIES,
+            store.data().source_settings.subscribe(),
+        );
+
         Ok(Self {
             id,
             store,
             instance,
-            manifest,
-            next_sdk: aidoku_sdk_next,
-            setting_definitions,
-            features,
+            shared: Arc::new(BlockingSourceShared {
+                manifest,
+                setting_definitions,
+                features,
+                next_sdk: aidoku_sdk_next,
+                bandwidth,
+                preload_cache,
+            }),
+            processed_image_cache: ProcessedImageCache::new(
+                manager.settings.image_cache_max_entries,
+            ),
+            image_request_cache,
+            listing_cache,
         })
     }
 
+    /// Checks that every export the source defines, out of those called by this host, has
+    /// the signature the host calls it with. Exports that don't exist are fine, as most of
+    /// them are optional.
+    fn check_export_signatures(
+        instance: &Instance,
+        store: &mut Store<WasmStore>,
+        next_sdk: bool,
+    ) -> Result<()> {
+        let mut mismatches = Vec::new();
+
+        macro_rules! expect_export {
+            ($name:literal: $params:ty => $results:ty) => {
+                if let Some(func) = instance.get_func(&*store, $name) {
+                    if instance
+                        .get_typed_func::<$params, $results>(&*store, $name)
+                        .is_err()
+                    {
+                        mismatches.push(format!(
+                            "`{}` should be {} -> {}, found {:?}",
+                            $name,
+                            stringify!($params),
+                            stringify!($results),
+                            func.ty(&*store)
+                        ));
+                    }
+                }
+            };
+        }
+
+        if next_sdk {
+            expect_export!("start": () => ());
+            expect_export!("free_memory": i32 => ());
+            expect_export!("get_search_manga_list": (i32, i32, i32) => i32);
+            expect_export!("get_manga_update": (i32, i32, i32) => i32);
+            expect_export!("get_page_list": (i32, i32) => i32);
+            expect_export!("get_manga_list": (i32, i32) => i32);
+            expect_export!("get_image_request": (i32, i32) => i32);
+            expect_export!("handle_basic_login": (i32, i32, i32) => i32);
+        } else {
+            expect_export!("get_manga_list": (i32, i32) => i32);
+            expect_export!("get_manga_details": i32 => i32);
+            expect_export!("get_chapter_list": i32 => i32);
+            expect_export!("get_page_list": i32 => i32);
+            expect_export!("modify_image_request": i32 => ());
+            expect_export!("handle_basic_login": (i32, i32) => i32);
+        }
+
+        if !mismatches.is_empty() {
+            bail!("{}", mismatches.join("; "));
+        }
+
+        Ok(())
+    }
+
+    fn has_optional_export<Params: WasmParams, Results: WasmResults>(
+        instance: &Instance,
+        store: &Store<WasmStore>,
+        id: &str,
+        name: &str,
+    ) -> bool {
+        let Some(func) = instance.get_func(store, name) else {
+            return false;
+        };
+
+        if instance
+            .get_typed_func::<Params, Results>(store, name)
+            .is_err()
+        {
+            log::warn!(
+                "source {id} exports `{name}` as {:?}, which isn't supported, disabling it",
+                func.ty(store)
+            );
+
+            return false;
+        }
+
+        true
+    }
+
     pub fn meta_source_path(path: &Path) -> anyhow::Result<std::path::PathBuf> {
         let parent = path
             .parent()
@@ -582,19 +958,22 @@ impl BlockingSource {
         cancellation_token: CancellationToken,
         listing: aidoku::Listing,
     ) -> Result<Vec<Manga>> {
-        if self.next_sdk {
+        if self.shared.next_sdk {
             return self
                 .get_manga_list_next(cancellation_token, listing, 1)
                 .map(|list| {
-                    list.entries
-                        .into_iter()
-                        .map(|v| Manga::from(v, self.id.clone()))
-                        .collect::<Vec<_>>()
+                    dedup_mangas_by_id(
+                        list.entries
+                            .into_iter()
+                            .map(|v| Manga::from(v, self.id.clone()))
+                            .collect::<Vec<_>>(),
+                    )
                 });
         }
         self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
-            this.search_mangas_by_filters_inner(vec![])
+            this.search_mangas_by_filters_inner(vec![], 1)
         })
+        .map(dedup_mangas_by_id)
     }
 
     pub fn search_mangas(
@@ -603,7 +982,7 @@ impl BlockingSource {
         query: String,
         page: i32,
     ) -> Result<(Vec<Manga>, bool)> {
-        if self.next_sdk {
+        if self.shared.next_sdk {
             return self
                 .get_search_manga_list_next(cancellation_token, query, page, [].to_vec())
                 .map(|list| {
@@ -612,19 +991,93 @@ impl BlockingSource {
                         .into_iter()
                         .map(|v| Manga::from(v, self.id.clone()))
                         .collect::<Vec<_>>();
-                    (mangas, list.has_next_page)
+                    (dedup_mangas_by_id(mangas), list.has_next_page)
                 });
         }
         if page > 1 {
             return Ok((Vec::new(), false));
         }
         self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
-            this.search_mangas_by_filters_inner(vec![Filter::Title(query)])
+            this.search_mangas_by_filters_inner(vec![Filter::Title(query)], 1)
+        })
+        .map(|mangas| (dedup_mangas_by_id(mangas), false))
+    }
+
+    /// Lists the mangas tagged with `tag`, as returned by [`Self::get_tag_list`].
+    pub fn get_manga_by_tag(
+        &mut self,
+        cancellation_token: CancellationToken,
+        tag: String,
+        page: u32,
+    ) -> Result<Vec<Manga>> {
+        if self.shared.next_sdk {
+            bail!("browsing by tag is not supported by sources using the new SDK yet");
+        }
+
+        let page = i32::try_from(page).context("page is too large")?;
+
+        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
+            this.search_mangas_by_filters_inner(vec![Filter::Genre(tag)], page)
         })
-        .map(|mangas| (mangas, false))
+        .map(dedup_mangas_by_id)
+    }
+
+    /// Returns the tags (genres) the source can list mangas by. Sources without a
+    /// `get_tag_list` export have none.
+    pub fn get_tag_list(&mut self, cancellation_token: CancellationToken) -> Result<Vec<String>> {
+        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
+            this.get_tag_list_inner()
+        })
+    }
+
+    fn get_tag_list_inner(&mut self) -> Result<Vec<String>> {
+        let Ok(wasm_function) = self
+            .instance
+            .get_typed_func::<(), i32>(&mut self.store, "get_tag_list")
+        else {
+            return Ok(vec![]);
+        };
+        let next_sdk = self.shared.next_sdk;
+
+        call_cleanup!(
+            blocking = self,
+            func = wasm_function,
+            args = (),
+            free = [],
+            as Vec<String>,
+            parse = |descriptor, store: &mut Store<WasmStore>, instance| {
+                if next_sdk {
+                    let memory = get_memory(instance, store)?;
+
+                    return read_next::<Vec<String>>(&memory, &store, descriptor);
+                }
+
+                match store.data_mut()
+                    .get_std_value(descriptor as usize)
+                    .ok_or(anyhow!("could not read data from tag list descriptor"))?
+                    .as_ref()
+                {
+                    Value::Array(tags) => tags
+                        .iter()
+                        .map(|tag| match tag {
+                            Value::String(tag) => Ok(tag.clone()),
+                            other => bail!("expected tag to be a string, found {:?} instead", other),
+                        })
+                        .collect(),
+                    other => bail!(
+                        "expected tag list descriptor to be an array, found {:?} instead",
+                        other
+                    ),
+                }
+            }
+        )
     }
 
-    fn search_mangas_by_filters_inner(&mut self, filters: Vec<Filter>) -> Result<Vec<Manga>> {
+    fn search_mangas_by_filters_inner(
+        &mut self,
+        filters: Vec<Filter>,
+        page: i32,
+    ) -> Result<Vec<Manga>> {
         let wasm_function = self
             .instance
             .get_typed_func::<(i32, i32), i32>(&mut self.store, "get_manga_list")?;
@@ -642,7 +1095,7 @@ impl BlockingSource {
         let mangas = call_cleanup!(
             blocking = self,
             func = wasm_function,
-            args = (filters_descriptor as i32, 1),
+            args = (filters_descriptor as i32, page),
             free = [filters_descriptor],
             as Vec<Manga>,
             parse = |descriptor, store: &mut Store<WasmStore>, _| {
@@ -665,16 +1118,18 @@ impl BlockingSource {
         Ok(mangas)
     }
 
+    /// Fetches the details of `manga`, which holds whatever is already known about it (at
+    /// least its `id`). Fields the source leaves empty keep their known value.
     pub fn get_manga_details(
         &mut self,
         cancellation_token: CancellationToken,
-        manga_id: String,
+        manga: Manga,
     ) -> Result<Manga> {
-        if self.next_sdk {
+        if self.shared.next_sdk {
             return self
                 .get_manga_update_next(
                     cancellation_token,
-                    BlockingSource::create_aidoku_manga(manga_id),
+                    BlockingSourceMutable::create_aidoku_manga(manga.id),
                     true,
                     false,
                 )
@@ -683,21 +1138,17 @@ impl BlockingSource {
         self.run_under_context(
             cancellation_token,
             OperationContextObject::Manga {
-                id: manga_id.clone(),
+                id: manga.id.clone(),
             },
-            |this| this.get_manga_details_inner(manga_id),
+            |this| this.get_manga_details_inner(manga),
         )
     }
 
-    fn get_manga_details_inner(&mut self, manga_id: String) -> Result<Manga> {
-        // HACK aidoku actually places the entire `Manga` object into the store, but it seems only
-        // the `id` field is needed, so we just store a `HashMap` with the `id` set.
-        // surely this wont break in the future!
-        let mut manga_hashmap = ValueMap::new();
-        manga_hashmap.insert("id".to_string(), manga_id.into());
-
+    fn get_manga_details_inner(&mut self, known: Manga) -> Result<Manga> {
+        // Like aidoku, place the entire `Manga` object into the store, so sources can reuse what
+        // we have from the cache instead of fetching it again.
         let manga_descriptor = self.store.data_mut().store_std_value(
-            Value::Object(ObjectValue::ValueMap(manga_hashmap)).into(),
+            Value::Object(ObjectValue::Manga(known.clone())).into(),
             None,
         );
 
@@ -726,7 +1177,21 @@ impl BlockingSource {
             }
         )?;
 
-        Ok(manga)
+        Ok(Manga {
+            title: manga.title.or(known.title),
+            author: manga.author.or(known.author),
+            artist: manga.artist.or(known.artist),
+            description: manga.description.or(known.description),
+            tags: manga.tags.or(known.tags),
+            cover_url: manga.cover_url.or(known.cover_url),
+            url: manga.url.or(known.url),
+            status: if manga.status == PublishingStatus::Unknown {
+                known.status
+            } else {
+                manga.status
+            },
+            ..manga
+        })
     }
 
     fn create_aidoku_manga(manga_id: String) -> aidoku::Manga {
@@ -767,11 +1232,11 @@ impl BlockingSource {
         cancellation_token: CancellationToken,
         manga_id: String,
     ) -> Result<Vec<Chapter>> {
-        if self.next_sdk {
+        if self.shared.next_sdk {
             return self
                 .get_manga_update_next(
                     cancellation_token,
-                    BlockingSource::create_aidoku_manga(manga_id.clone()),
+                    BlockingSourceMutable::create_aidoku_manga(manga_id.clone()),
                     false,
                     true,
                 )
@@ -780,7 +1245,13 @@ impl BlockingSource {
                         .chapters
                         .unwrap_or_default()
                         .into_iter()
-                        .map(|v| Chapter::from(v, self.id.clone(), manga_id.clone()))
+                        .enumerate()
+                        .map(|(index, v)| {
+                            with_fallback_title(
+                                Chapter::from(v, self.id.clone(), manga_id.clone()),
+                                index,
+                            )
+                        })
                         .collect::<Vec<_>>()
                 });
         }
@@ -826,18 +1297,7 @@ impl BlockingSource {
                         .enumerate()
                         .map(|(index, v)| match v {
                             Value::Object(ObjectValue::Chapter(chapter)) => {
-                                let mut chapter = chapter.clone();
-
-                                if chapter.title.as_ref().map(|s| s.is_empty()).unwrap_or(true) {
-                                    chapter.title = Some(format!(
-                                        "Ch.{}",
-                                        chapter
-                                            .chapter_num
-                                            .unwrap_or(chapter.volume_num.unwrap_or(index as f32))
-                                    ));
-                                }
-
-                                Some(chapter)
+                                Some(with_fallback_title(chapter.clone(), index))
                             }
                             _ => None,
                         })
@@ -856,16 +1316,16 @@ impl BlockingSource {
     pub fn get_page_list(
         &mut self,
         cancellation_token: CancellationToken,
-        manga_id: String,
-        chapter_id: String,
-        chapter_num: Option<f32>,
+        chapter: Chapter,
     ) -> Result<Vec<Page>> {
-        if self.next_sdk {
+        let manga_id = chapter.manga_id.clone();
+
+        if self.shared.next_sdk {
             return self
                 .get_page_list_next(
                     cancellation_token,
-                    BlockingSource::create_aidoku_manga(manga_id.clone()),
-                    BlockingSource::create_aidoku_chapter(chapter_id),
+                    BlockingSourceMutable::create_aidoku_manga(manga_id.clone()),
+                    BlockingSourceMutable::create_aidoku_chapter(chapter.id),
                 )
                 .map(|pages| {
                     pages
@@ -880,29 +1340,40 @@ impl BlockingSource {
         self.run_under_context(
             cancellation_token,
             OperationContextObject::Chapter {
-                id: chapter_id.clone(),
+                id: chapter.id.clone(),
             },
-            |this| this.get_page_list_inner(manga_id, chapter_id, chapter_num),
+            |this| this.get_page_list_inner(chapter),
         )
     }
 
-    fn get_page_list_inner(
-        &mut self,
-        manga_id: String,
-        chapter_id: String,
-        chapter_num: Option<f32>,
-    ) -> Result<Vec<Page>> {
-        // HACK the same thing with the `Manga` said above, we also usually only need the `id`
-        // from the `Chapter` object and the `mangaId`.
-        let mut chapter_hashmap = ValueMap::new();
-        chapter_hashmap.insert("id".to_string(), Value::String(chapter_id));
-        chapter_hashmap.insert("mangaId".to_string(), Value::String(manga_id));
-
-        // HACK guya sources actually use the `chapterNum` field for some fucking reason????
-        // like it's a huge fucking hack it's not even by accident XD
+    fn get_page_list_inner(&mut self, chapter: Chapter) -> Result<Vec<Page>> {
+        // Unlike the `Manga` said above, sources do read more than the `id` and `mangaId` of
+        // the `Chapter` object: guya sources use the `chapterNum` field for example, and others
+        // the title, volume, language or scanlator.
         // ref: https://github.com/Skittyblock/aidoku-community-sources/blob/bd79840e182ff7c90c8444ed160e2e8d50b6a219/src/rust/guya/sources/dankefurslesen/src/lib.rs#L54
-        if let Some(chapter_num) = chapter_num {
-            chapter_hashmap.insert("chapterNum".to_string(), Value::Float(chapter_num as f64));
+        let mut chapter_hashmap = ValueMap::new();
+        chapter_hashmap.insert("id".to_string(), Value::String(chapter.id));
+        chapter_hashmap.insert("mangaId".to_string(), Value::String(chapter.manga_id));
+
+        let optional_fields = [
+            ("title", chapter.title.map(Value::String)),
+            ("scanlator", chapter.scanlator.map(Value::String)),
+            ("url", chapter.url.map(|url| Value::String(url.to_string()))),
+            ("lang", chapter.lang.map(Value::String)),
+            (
+                "chapterNum",
+                chapter.chapter_num.map(|num| Value::Float(num as f64)),
+            ),
+            (
+                "volumeNum",
+                chapter.volume_num.map(|num| Value::Float(num as f64)),
+            ),
+            ("dateUpdated", chapter.date_uploaded.map(Value::Date)),
+        ];
+        for (key, value) in optional_fields {
+            if let Some(value) = value {
+                chapter_hashmap.insert(key.to_string(), value);
+            }
         }
 
         let chapter_descriptor = self.store.data_mut().store_std_value(
@@ -949,11 +1420,19 @@ impl BlockingSource {
         url: Url,
         ctx: Option<aidoku::PageContext>,
     ) -> Result<Request> {
-        if self.next_sdk {
+        let cache_key = ImageRequestKey::new(&url, ctx.as_ref());
+        if let Some(request) = self.image_request_cache.get(&cache_key) {
+            return Ok(request);
+        }
+
+        let request = if self.shared.next_sdk {
             self.get_image_request_next(url, ctx)
         } else {
             self.get_image_request_inner(url)
-        }
+        }?;
+        self.image_request_cache.insert(cache_key, &request);
+
+        Ok(request)
     }
     pub fn get_image_request_inner(&mut self, url: Url) -> Result<Request> {
         let request_descriptor = self.store.data_mut().create_request();
@@ -1009,6 +1488,11 @@ impl BlockingSource {
 
     // next sdk
 
+    #[cfg(feature = "all")]
+    fn exports(&self, name: &str) -> bool {
+        self.instance.get_func(&self.store, name).is_some()
+    }
+
     pub fn start(&mut self) -> Result<()> {
         let wasm_function = self
             .instance
@@ -1260,24 +1744,39 @@ impl BlockingSource {
         response: (StatusCode, HeaderMap),
         bytes: Bytes,
         ctx: Option<aidoku::PageContext>,
-    ) -> Result<Vec<u8>> {
+    ) -> Result<(Vec<u8>, Option<ProcessedImageFormat>)> {
         self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
             this.process_page_image_inner(request, response, bytes, ctx)
         })
     }
 
+    /// Returns the `bytes` the source gave back along with their format, or `bytes` as is
+    /// with no format when the source left the image untouched.
     pub fn process_page_image_inner(
         &mut self,
         request: (Url, HeaderMap),
         response: (StatusCode, HeaderMap),
         bytes: Bytes,
         context: Option<aidoku::PageContext>,
-    ) -> Result<Vec<u8>> {
-        let (image_id, image_ref, context_id) = {
+    ) -> Result<(Vec<u8>, Option<ProcessedImageFormat>)> {
+        let output_format = self
+            .shared
+            .manifest
+            .config
+            .as_ref()
+            .and_then(|config| config.output_format)
+            .unwrap_or_default();
+
+        let cache_key = CacheKey::new(&request.0, context.as_ref(), &response.1);
+        if let Some(image_data) = self.processed_image_cache.get(&cache_key) {
+            return Ok((image_data, Some(output_format)));
+        }
+
+        let (image_id, image_ref, context_id, original_hash) = {
             let store = self.store.data_mut();
 
-            let image_ref = store.create_image(&bytes).unwrap_or_else(|| {
-                eprintln!("failed create image for process_page_image use mode image raw");
+            let image_ref = store.create_image(&bytes).unwrap_or_else(|err| {
+                eprintln!("failed create image for process_page_image ({err}) use mode image raw");
 
                 store.set_image_data(wasm_store::ImageData {
                     data: bytes
@@ -1294,6 +1793,12 @@ impl BlockingSource {
                     height: 0,
                 })
             });
+            // Images kept raw can't be told apart from a re-encoded one, so they're always
+            // treated as changed.
+            let original_hash = store
+                .get_image(image_ref)
+                .filter(|image| image.width != 0 || image.height != 0)
+                .map(pixels_hash);
 
             let image_response = ImageResponse {
                 code: response.0.into(),
@@ -1333,7 +1838,7 @@ impl BlockingSource {
                 -1
             };
 
-            (image_id, image_ref, context_id)
+            (image_id, image_ref, context_id, original_hash)
         };
 
         let wasm_function = self
@@ -1345,7 +1850,7 @@ impl BlockingSource {
         func = wasm_function,
         args = (image_id, context_id),
         free = [image_id, context_id, image_ref],
-        as  Vec<u8>,
+        as  Option<Vec<u8>>,
         parse = |pointer, store: &mut Store<WasmStore>, instance| {
             let memory = get_memory(instance, store)?;
 
@@ -1353,6 +1858,13 @@ impl BlockingSource {
                 return Err(anyhow::anyhow!("pointer image error {pointer}"));
             };
 
+            // Re-encoding an image the source didn't touch would only lose quality.
+            let output_hash = store.data_mut().get_image(image_pointer as usize).map(pixels_hash);
+            if original_hash.is_some() && output_hash == original_hash {
+                store.data_mut().take_std_value(image_pointer as usize);
+                return Ok(None);
+            }
+
             let image_data = {
                 let store =store.data_mut();
                 let (width, height, pixels) = {
@@ -1373,21 +1885,21 @@ impl BlockingSource {
                 let rgb_pixels = crate::source::decode_image::decode_argb_to_rgb(
                     width as i32, height as i32, &pixels,
                 )?;
-                let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
-                comp.set_size(width as usize, height as usize);
-                comp.set_fastest_defaults();
-
-                let mut comp =  comp.start_compress(Vec::new())?;
-                comp.write_scanlines(&rgb_pixels)?;
 
-
-                comp.finish()?
+                output_format.encode(width, height, &rgb_pixels)?
             };
 
-            Ok(image_data)
+            Ok(Some(image_data))
         })?;
 
-        Ok(image_data)
+        let Some(image_data) = image_data else {
+            return Ok((bytes.to_vec(), None));
+        };
+
+        self.processed_image_cache
+            .insert(cache_key, image_data.clone());
+
+        Ok((image_data, Some(output_format)))
     }
 
     pub fn get_manga_list_next(
@@ -1396,9 +1908,18 @@ impl BlockingSource {
         listing: aidoku::Listing,
         page: i32,
     ) -> Result<NextMangaPageResult> {
-        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
-            this.get_manga_list_next_inner(listing, page)
-        })
+        if let Some(result) = self.listing_cache.get(&listing.id, page) {
+            return Ok(result);
+        }
+
+        let listing_id = listing.id.clone();
+        let result =
+            self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
+                this.get_manga_list_next_inner(listing, page)
+            })?;
+        self.listing_cache.insert(listing_id, page, result.clone());
+
+        Ok(result)
     }
 
     fn get_manga_list_next_inner(
@@ -1429,11 +1950,51 @@ impl BlockingSource {
         Ok(result)
     }
 
+    /// Returns the names of the listings (e.g. "Popular" or "Latest") the source offers. Sources
+    /// without a `get_listing_names` export only have their default listing.
+    pub fn get_listings_next(
+        &mut self,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<String>> {
+        if !self.shared.next_sdk {
+            return Ok(vec![]);
+        }
+
+        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
+            this.get_listings_next_inner()
+        })
+    }
+
+    fn get_listings_next_inner(&mut self) -> Result<Vec<String>> {
+        let Ok(wasm_function) = self
+            .instance
+            .get_typed_func::<(), i32>(&mut self.store, "get_listing_names")
+        else {
+            return Ok(vec![]);
+        };
+
+        call_cleanup!(
+        blocking = self,
+        func = wasm_function,
+        args = (),
+        free = [],
+        as Vec<String>,
+        parse = |pointer, store: &mut Store<WasmStore>, instance| {
+            let memory = get_memory(instance, store)?;
+
+            read_next::<Vec<String>>(&memory, &store, pointer)
+        })
+    }
+
     pub fn handle_notification_next(
         &mut self,
         cancellation_token: CancellationToken,
         key: String,
     ) -> Result<()> {
+        if !self.shared.features.has_handle_notification {
+            return Ok(());
+        }
+
         self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
             this.handle_notification_next_inner(key)
         })
@@ -1454,6 +2015,204 @@ impl BlockingSource {
         Ok(())
     }
 
+    /// Asks the source which manga and chapter `url` (e.g. a link shared from its website)
+    /// points to.
+    pub fn handle_deep_link(&mut self, url: Url) -> Result<DeepLink> {
+        if self.shared.next_sdk {
+            bail!("deep links are not supported by sources using the new SDK yet");
+        }
+
+        self.run_under_context(
+            CancellationToken::new(),
+            OperationContextObject::None,
+            |this| this.handle_deep_link_inner(url),
+        )
+    }
+
+    fn handle_deep_link_inner(&mut self, url: Url) -> Result<DeepLink> {
+        // The export is named after aidoku's `handleUrl`, which is what calls it.
+        let wasm_function = self
+            .instance
+            .get_typed_func::<i32, i32>(&mut self.store, "handle_url")
+            .context("source does not support deep links")?;
+        let url_descriptor = self
+            .store
+            .data_mut()
+            .store_std_value(Value::from(url.to_string()).into(), None);
+
+        call_cleanup!(
+            blocking = self,
+            func = wasm_function,
+            args = (url_descriptor as i32),
+            free = [url_descriptor],
+            as DeepLink,
+            parse = |descriptor, store: &mut Store<WasmStore>, _| {
+                match store.data_mut()
+                    .get_std_value(descriptor as usize)
+                    .ok_or(anyhow!("could not read data from deep link descriptor"))?
+                    .as_ref()
+                {
+                    Value::Object(ObjectValue::DeepLink(deep_link)) => Ok(deep_link.clone()),
+                    other => bail!(
+                        "expected deep link descriptor to be a deep link object, found {:?} instead",
+                        other
+                    ),
+                }
+            }
+        )
+    }
+
+    /// Asks the source for the current ID of the manga it used to call `old_id`, for sources
+    /// that renamed their manga IDs in an update. Returns `None` if the source doesn't migrate
+    /// IDs, or the ID didn't change.
+    pub fn handle_key_migration(
+        &mut self,
+        cancellation_token: CancellationToken,
+        old_id: String,
+    ) -> Result<Option<String>> {
+        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
+            this.handle_key_migration_inner(old_id)
+        })
+    }
+
+    fn handle_key_migration_inner(&mut self, old_id: String) -> Result<Option<String>> {
+        let new_id = if self.shared.next_sdk {
+            if !self.exports("handle_key_migration") {
+                return Ok(None);
+            }
+
+            let wasm_function = self
+                .instance
+                .get_typed_func::<(i32, i32), i32>(&mut self.store, "handle_key_migration")?;
+
+            let key = {
+                let store = self.store.data_mut();
+                let descriptor = store.store_std_value(Value::from(old_id.clone()).into(), None);
+                store.mark_str_encode(descriptor);
+                descriptor
+            };
+
+            // A negative chapter key asks for the manga's key instead of one of its chapters'.
+            call_cleanup!(
+                blocking = self,
+                func = wasm_function,
+                args = (key as i32, -1),
+                free = [key],
+                as String,
+                parse = |pointer, store: &mut Store<WasmStore>, instance| {
+                    let memory = get_memory(instance, store)?;
+
+                    read_next::<String>(&memory, &store, pointer)
+                }
+            )?
+        } else {
+            if !self.exports("handle_id_migration") {
+                return Ok(None);
+            }
+
+            // The legacy SDK passes the kind of ID being migrated, 0 being a manga.
+            let wasm_function = self
+                .instance
+                .get_typed_func::<(i32, i32), i32>(&mut self.store, "handle_id_migration")?;
+            let id_descriptor = self
+                .store
+                .data_mut()
+                .store_std_value(Value::from(old_id.clone()).into(), None);
+
+            call_cleanup!(
+                blocking = self,
+                func = wasm_function,
+                args = (id_descriptor as i32, 0),
+                free = [id_descriptor],
+                as String,
+                parse = |descriptor, store: &mut Store<WasmStore>, _| {
+                    match store.data_mut()
+                        .get_std_value(descriptor as usize)
+                        .ok_or(anyhow!("could not read data from migrated ID descriptor"))?
+                        .as_ref()
+                    {
+                        Value::String(id) => Ok(id.clone()),
+                        other => bail!(
+                            "expected migrated ID descriptor to be a string, found {:?} instead",
+                            other
+                        ),
+                    }
+                }
+            )?
+        };
+
+        Ok((!new_id.is_empty() && new_id != old_id).then_some(new_id))
+    }
+
+    /// Asks the source to log in with a username and password. Any cookie set by the
+    /// requests the source makes while doing so ends up in its cookie jar.
+    pub fn handle_basic_login(
+        &mut self,
+        cancellation_token: CancellationToken,
+        key: String,
+        username: String,
+        password: String,
+    ) -> Result<bool> {
+        self.run_under_context(cancellation_token, OperationContextObject::None, |this| {
+            this.handle_basic_login_inner(key, username, password)
+        })
+    }
+
+    fn handle_basic_login_inner(
+        &mut self,
+        key: String,
+        username: String,
+        password: String,
+    ) -> Result<bool> {
+        if !self.shared.next_sdk {
+            let wasm_function = self
+                .instance
+                .get_typed_func::<(i32, i32), i32>(&mut self.store, "handle_basic_login")
+                .context("source does not support basic login")?;
+
+            let store = self.store.data_mut();
+            let username = store.store_std_value(Value::from(username).into(), None);
+            let password = store.store_std_value(Value::from(password).into(), None);
+
+            let result = wasm_function.call(&mut self.store, (username as i32, password as i32));
+
+            let store = self.store.data_mut();
+            store.take_std_value(username);
+            store.take_std_value(password);
+
+            return Ok(result? != 0);
+        }
+
+        let wasm_function = self
+            .instance
+            .get_typed_func::<(i32, i32, i32), i32>(&mut self.store, "handle_basic_login")
+            .context("source does not support basic login")?;
+
+        let (key, username, password) = {
+            let store = self.store.data_mut();
+
+            let mut store_str = |value: String| {
+                let descriptor = store.store_std_value(Value::from(value).into(), None);
+                store.mark_str_encode(descriptor);
+                descriptor
+            };
+
+            (store_str(key), store_str(username), store_str(password))
+        };
+
+        call_cleanup!(
+        blocking = self,
+        func = wasm_function,
+        args = (key as i32, username as i32, password as i32),
+        free = [key, username, password],
+        as bool,
+        parse = |pointer, store: &mut Store<WasmStore>, instance| {
+            let memory = get_memory(instance, store)?;
+
+            read_next::<bool>(&memory, &store, pointer)
+        })
+    }
+
     pub fn run_under_context<T, F>(
         &mut self,
         cancellation_token: CancellationToken,
@@ -1468,10 +2227,13 @@ impl BlockingSource {
             current_object,
         };
 
-        let result = f(self);
+        // `call_cleanup!` panics when a WASM call traps. Unless panics abort (as in release
+        // builds), the source outlives that, so the context must not keep the failed
+        // operation's token.
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(self)));
 
         self.store.data_mut().context = OperationContext::default();
 
-        result
+        result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
     }
 }
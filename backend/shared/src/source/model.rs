@@ -74,6 +74,15 @@ pub enum SettingDefinition {
         // FIXME is text the only setting type that's allowed to not have a default?
         default: Option<String>,
     },
+    /// A text field whose value is encrypted on disk, meant for API keys and passwords.
+    #[serde(rename = "secure-input", rename_all = "camelCase")]
+    SecureInput {
+        placeholder: Option<String>,
+        key: String,
+        default: Option<String>,
+        #[serde(default = "return_true")]
+        is_secure: bool,
+    },
     #[serde(rename = "link")]
     Link { title: String, url: String },
 }
@@ -84,6 +93,9 @@ fn empty_vector() -> Vec<String> {
 fn return_false() -> bool {
     false
 }
+fn return_true() -> bool {
+    true
+}
 
 #[derive(Serialize, Debug, Clone, Default, PartialEq, FromPrimitive)]
 #[repr(u8)]
@@ -304,12 +316,15 @@ pub enum FilterType {
 #[derive(Debug, Clone)]
 pub enum Filter {
     Title(String),
+    /// Only mangas tagged with this genre.
+    Genre(String),
 }
 
 impl From<&Filter> for FilterType {
     fn from(value: &Filter) -> Self {
         match &value {
             Filter::Title(_) => FilterType::Title,
+            Filter::Genre(_) => FilterType::Genre,
         }
     }
 }
@@ -318,6 +333,7 @@ impl Filter {
     pub fn name(&self) -> String {
         match &self {
             Filter::Title(_) => "Title".into(),
+            Filter::Genre(genre) => genre.clone(),
         }
     }
 }
@@ -0,0 +1,165 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use reqwest::{header::HeaderMap, StatusCode};
+use tokio_util::bytes::Bytes;
+use url::Url;
+
+/// How many prefetched responses are kept before the oldest are dropped.
+const MAX_ENTRIES: usize = 32;
+
+/// A response fetched ahead of time, because a previous response hinted at it.
+pub struct PreloadedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl PreloadedResponse {
+    pub fn into_response(self) -> reqwest::Response {
+        let mut response = http::Response::new(self.body);
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers;
+
+        response.into()
+    }
+}
+
+/// Responses prefetched from the `Link: <url>; rel=preload` hints some image CDNs send,
+/// keyed by URL. Shared by everything downloading through the same source, so it can be
+/// filled without holding the source's lock. Each response is handed out only once.
+#[derive(Default)]
+pub struct PreloadCache {
+    entries: Mutex<VecDeque<(Url, PreloadedResponse)>>,
+    /// URLs being prefetched right now, so that hints repeated by several responses are
+    /// only fetched once.
+    pending: Mutex<HashSet<Url>>,
+}
+
+impl PreloadCache {
+    /// Marks `url` as being prefetched, unless it already is or was.
+    pub fn claim(&self, url: &Url) -> bool {
+        if self.entries.lock().unwrap().iter().any(|(u, _)| u == url) {
+            return false;
+        }
+
+        self.pending.lock().unwrap().insert(url.clone())
+    }
+
+    /// Stores the response of a URL claimed with [`Self::claim`], or just releases the claim
+    /// when the prefetch failed.
+    pub fn complete(&self, url: Url, response: Option<PreloadedResponse>) {
+        self.pending.lock().unwrap().remove(&url);
+
+        let Some(response) = response else {
+            return;
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.push_back((url, response));
+        while entries.len() > MAX_ENTRIES {
+            entries.pop_front();
+        }
+    }
+
+    pub fn take(&self, url: &Url) -> Option<PreloadedResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        let position = entries.iter().position(|(u, _)| u == url)?;
+
+        entries.remove(position).map(|(_, response)| response)
+    }
+}
+
+/// A URL claimed with [`PreloadCache::claim`] until its prefetch completes. Dropping it
+/// without completing it, e.g. when the prefetch is cancelled, releases the claim.
+pub struct PreloadClaim {
+    cache: Arc<PreloadCache>,
+    url: Option<Url>,
+}
+
+impl PreloadClaim {
+    /// Claims `url`, unless it's already being prefetched or was.
+    pub fn new(cache: &Arc<PreloadCache>, url: Url) -> Option<Self> {
+        cache.claim(&url).then(|| Self {
+            cache: cache.clone(),
+            url: Some(url),
+        })
+    }
+
+    pub fn url(&self) -> &Url {
+        self.url.as_ref().expect("the claim was already completed")
+    }
+
+    pub fn complete(mut self, response: Option<PreloadedResponse>) {
+        if let Some(url) = self.url.take() {
+            self.cache.complete(url, response);
+        }
+    }
+}
+
+impl Drop for PreloadClaim {
+    fn drop(&mut self) {
+        if let Some(url) = self.url.take() {
+            self.cache.complete(url, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(n: usize) -> Url {
+        Url::parse(&format!("https://cdn.test/{n}.jpg")).unwrap()
+    }
+
+    fn response(body: &'static str) -> PreloadedResponse {
+        PreloadedResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(body.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn hands_out_prefetched_responses_once() {
+        let cache = PreloadCache::default();
+
+        assert!(cache.claim(&url(1)));
+        assert!(!cache.claim(&url(1)));
+        cache.complete(url(1), Some(response("page")));
+        assert!(!cache.claim(&url(1)));
+
+        let preloaded = cache.take(&url(1)).unwrap();
+        assert_eq!(preloaded.body, "page");
+        assert!(cache.take(&url(1)).is_none());
+        assert!(cache.claim(&url(1)));
+    }
+
+    #[test]
+    fn drops_the_oldest_responses() {
+        let cache = PreloadCache::default();
+        for n in 0..=MAX_ENTRIES {
+            cache.complete(url(n), Some(response("page")));
+        }
+
+        assert!(cache.take(&url(0)).is_none());
+        assert!(cache.take(&url(MAX_ENTRIES)).is_some());
+    }
+
+    #[test]
+    fn dropped_claims_are_released() {
+        let cache = Arc::new(PreloadCache::default());
+
+        let claim = PreloadClaim::new(&cache, url(1)).unwrap();
+        assert!(PreloadClaim::new(&cache, url(1)).is_none());
+        drop(claim);
+
+        let claim = PreloadClaim::new(&cache, url(1)).unwrap();
+        claim.complete(Some(response("page")));
+        assert!(PreloadClaim::new(&cache, url(1)).is_none());
+        assert!(cache.take(&url(1)).is_some());
+    }
+}
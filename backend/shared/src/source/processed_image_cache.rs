@@ -0,0 +1,189 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use reqwest::header::{HeaderMap, ETAG, LAST_MODIFIED};
+use serde::Serialize;
+use url::Url;
+
+use super::image_request_cache::ImageRequestKey;
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Process-wide counters for the processed image caches of every source.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ProcessedImageCacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+pub fn metrics() -> ProcessedImageCacheMetrics {
+    ProcessedImageCacheMetrics {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// The page's URL and context; sources may process the same image differently depending
+    /// on the context, e.g. to descramble it with a per-page key.
+    request: ImageRequestKey,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl CacheKey {
+    /// Builds a key from the requested URL, the page context and the validators sent back by
+    /// the server, so that a changed image on the same URL is processed again.
+    pub fn new(
+        url: &Url,
+        context: Option<&aidoku::PageContext>,
+        response_headers: &HeaderMap,
+    ) -> Self {
+        let header = |name| {
+            response_headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+
+        Self {
+            request: ImageRequestKey::new(url, context),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+}
+
+/// A bounded, least-recently-used cache of images already run through a source's
+/// `process_page_image`. It lives inside the source itself, so reloading a source
+/// also drops its cache.
+pub struct ProcessedImageCache {
+    max_entries: usize,
+    entries: HashMap<CacheKey, Vec<u8>>,
+    /// Keys ordered from least to most recently used.
+    order: VecDeque<CacheKey>,
+}
+
+impl ProcessedImageCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn get(&mut self, key: &CacheKey) -> Option<Vec<u8>> {
+        if self.max_entries == 0 {
+            return None;
+        }
+
+        let Some(data) = self.entries.get(key).cloned() else {
+            MISSES.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+
+        self.touch(key);
+        HITS.fetch_add(1, Ordering::Relaxed);
+
+        Some(data)
+    }
+
+    pub fn insert(&mut self, key: CacheKey, data: Vec<u8>) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        if self.entries.insert(key.clone(), data).is_some() {
+            self.touch(&key);
+            return;
+        }
+
+        self.order.push_back(key);
+        while self.order.len() > self.max_entries {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(position) = self.order.iter().position(|k| k == key) {
+            if let Some(key) = self.order.remove(position) {
+                self.order.push_back(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn key(url: &str, etag: Option<&str>) -> CacheKey {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = etag {
+            headers.insert(ETAG, HeaderValue::from_str(etag).unwrap());
+        }
+
+        CacheKey::new(&Url::parse(url).unwrap(), None, &headers)
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entry() {
+        let mut cache = ProcessedImageCache::new(2);
+        cache.insert(key("https://a.test/1", None), vec![1]);
+        cache.insert(key("https://a.test/2", None), vec![2]);
+
+        assert_eq!(cache.get(&key("https://a.test/1", None)), Some(vec![1]));
+
+        cache.insert(key("https://a.test/3", None), vec![3]);
+
+        assert_eq!(cache.get(&key("https://a.test/2", None)), None);
+        assert_eq!(cache.get(&key("https://a.test/1", None)), Some(vec![1]));
+        assert_eq!(cache.get(&key("https://a.test/3", None)), Some(vec![3]));
+    }
+
+    #[test]
+    fn changed_etag_is_a_different_entry() {
+        let mut cache = ProcessedImageCache::new(4);
+        cache.insert(key("https://a.test/1", Some("\"v1\"")), vec![1]);
+
+        assert_eq!(cache.get(&key("https://a.test/1", Some("\"v2\""))), None);
+    }
+
+    #[test]
+    fn different_page_context_is_a_different_entry() {
+        let url = Url::parse("https://a.test/1").unwrap();
+        let context = |value: &str| -> aidoku::PageContext {
+            [("key".to_owned(), value.to_owned())].into_iter().collect()
+        };
+        let mut cache = ProcessedImageCache::new(4);
+        cache.insert(
+            CacheKey::new(&url, Some(&context("a")), &HeaderMap::new()),
+            vec![1],
+        );
+
+        assert_eq!(
+            cache.get(&CacheKey::new(&url, Some(&context("b")), &HeaderMap::new())),
+            None
+        );
+        assert_eq!(
+            cache.get(&CacheKey::new(&url, Some(&context("a")), &HeaderMap::new())),
+            Some(vec![1])
+        );
+    }
+
+    #[test]
+    fn zero_entries_disables_the_cache() {
+        let mut cache = ProcessedImageCache::new(0);
+        cache.insert(key("https://a.test/1", None), vec![1]);
+
+        assert_eq!(cache.get(&key("https://a.test/1", None)), None);
+    }
+}
@@ -1,21 +1,42 @@
-use std::{cell::RefCell, collections::HashMap, sync::Arc};
-use tokio::sync::Mutex;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::sync::{watch, Mutex};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use log::warn;
 
-use crate::{settings::SourceSettingValue, source_manager::SourceManager};
+use crate::{
+    settings::{SecureString, SourceSettingValue},
+    source_manager::SourceManager,
+};
 
 use super::model::SettingDefinition;
 
 pub struct SourceSettings {
     source_id: String,
     defaults: HashMap<String, SourceSettingValue>,
+    secure_keys: HashSet<String>,
     #[cfg(feature = "all")]
     stored: RefCell<HashMap<String, SourceSettingValue>>,
     #[cfg(not(feature = "all"))]
     pub stored: RefCell<HashMap<String, SourceSettingValue>>,
     arc_manager: Arc<Mutex<SourceManager>>,
+    persisted_path: Option<PathBuf>,
+    changes: watch::Sender<SourceSettingChanged>,
 }
+
+/// The last setting persisted through [`SourceSettings::persist_change`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceSettingChanged {
+    pub key: String,
+    pub value: SourceSettingValue,
+}
+
 impl std::fmt::Debug for SourceSettings {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SourceSettings")
@@ -24,6 +45,7 @@ impl std::fmt::Debug for SourceSettings {
             .field("stored", &self.stored)
             // SourceManager はデバッグ対象外（内部状態が重すぎる）
             .field("arc_manager", &"<SourceManager>")
+            .field("persisted_path", &self.persisted_path)
             .finish()
     }
 }
@@ -39,15 +61,45 @@ impl SourceSettings {
             .iter()
             .flat_map(default_values_for_definition)
             .collect();
+        let secure_keys = setting_definitions
+            .iter()
+            .flat_map(secure_keys_for_definition)
+            .collect();
+
+        let (changes, _) = watch::channel(SourceSettingChanged {
+            key: String::new(),
+            value: SourceSettingValue::Null,
+        });
 
         Ok(Self {
             source_id,
             defaults,
+            secure_keys,
             stored: RefCell::new(stored_settings.clone()),
             arc_manager: arc_manager.clone(),
+            persisted_path: None,
+            changes,
         })
     }
 
+    /// Loads the settings the source persisted itself into the JSON file at `path`, under the
+    /// stored ones: a key the user has set since keeps their value. Later calls to
+    /// [`Self::persist_change`] write to the same file.
+    pub fn with_persisted_file(mut self, path: PathBuf) -> Self {
+        match read_persisted(&path) {
+            Ok(persisted) => {
+                let stored = self.stored.get_mut();
+                for (key, value) in persisted {
+                    stored.entry(key).or_insert(value);
+                }
+            }
+            Err(err) => warn!("ignoring source settings {}: {err:?}", path.display()),
+        }
+        self.persisted_path = Some(path);
+
+        self
+    }
+
     pub fn get(&self, key: &String) -> Option<SourceSettingValue> {
         self.stored
             .borrow()
@@ -57,21 +109,63 @@ impl SourceSettings {
     }
 
     pub fn set(&self, key: &str, value: SourceSettingValue) {
-        self.stored.borrow_mut().insert(key.to_owned(), value);
+        self.stored
+            .borrow_mut()
+            .insert(key.to_owned(), self.seal(key, value));
     }
 
-    pub fn save(&self, key: &str, value: SourceSettingValue) -> Result<()> {
-        let snapshot = {
-            let mut store = self.stored.borrow_mut();
-            store.insert(key.to_owned(), value);
-            store.clone()
-        };
+    /// Wraps plain strings set on a `secure-input` key, so that they are encrypted on disk.
+    fn seal(&self, key: &str, value: SourceSettingValue) -> SourceSettingValue {
+        if self.secure_keys.contains(key) {
+            seal_secure_value(value)
+        } else {
+            value
+        }
+    }
+
+    /// Sets `key` and immediately writes it to the source's settings file. Listeners from
+    /// [`Self::subscribe`] are notified once the change is on disk.
+    pub fn persist_change(&self, key: &str, value: SourceSettingValue) -> Result<()> {
+        let value = self.seal(key, value);
+        self.set(key, value.clone());
+
+        if let Some(path) = &self.persisted_path {
+            let mut persisted = read_persisted(path).unwrap_or_default();
+            persisted.insert(key.to_owned(), value.clone());
+
+            fs::write(path, serde_json::to_string(&persisted)?)
+                .with_context(|| format!("while writing {}", path.display()))?;
+        }
+
+        // Keep the manager's copy in sync too, so that the value is not lost if it saves the
+        // settings before the next reload.
+        self.arc_manager
+            .blocking_lock()
+            .settings
+            .source_settings
+            .entry(self.source_id.clone())
+            .or_default()
+            .insert(key.to_owned(), value.clone());
 
-        let mut manager = self.arc_manager.blocking_lock();
-        manager.update_source_setting(self.source_id.clone(), snapshot, &self.arc_manager)?;
+        self.changes.send_replace(SourceSettingChanged {
+            key: key.to_owned(),
+            value,
+        });
 
         Ok(())
     }
+
+    pub fn subscribe(&self) -> watch::Receiver<SourceSettingChanged> {
+        self.changes.subscribe()
+    }
+}
+
+fn read_persisted(path: &Path) -> Result<HashMap<String, SourceSettingValue>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
 }
 // only pub use in aidoku android
 pub fn default_values_for_definition(
@@ -109,10 +203,36 @@ pub fn default_values_for_definition(
             key.clone(),
             SourceSettingValue::String(default.clone().unwrap()),
         )]),
+        SettingDefinition::SecureInput { key, default, .. } if default.is_some() => {
+            HashMap::from([(
+                key.clone(),
+                SourceSettingValue::SecureString(SecureString(default.clone().unwrap())),
+            )])
+        }
         _ => HashMap::new(),
     }
 }
 
+/// The keys of every `secure-input` setting in `setting_definition`.
+pub fn secure_keys_for_definition(setting_definition: &SettingDefinition) -> HashSet<String> {
+    match setting_definition {
+        SettingDefinition::Group { items, .. } => {
+            items.iter().flat_map(secure_keys_for_definition).collect()
+        }
+        SettingDefinition::SecureInput { key, is_secure, .. } if *is_secure => {
+            HashSet::from([key.clone()])
+        }
+        _ => HashSet::new(),
+    }
+}
+
+pub fn seal_secure_value(value: SourceSettingValue) -> SourceSettingValue {
+    match value {
+        SourceSettingValue::String(value) => SourceSettingValue::SecureString(SecureString(value)),
+        value => value,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -122,7 +242,15 @@ mod tests {
     };
     use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
-    use super::SourceSettings;
+    use super::{SourceSettingChanged, SourceSettings};
+
+    fn source_manager() -> Arc<tokio::sync::Mutex<SourceManager>> {
+        Arc::new(tokio::sync::Mutex::new(SourceManager::new(
+            PathBuf::new(),
+            HashMap::new(),
+            Settings::default(),
+        )))
+    }
 
     #[test]
     fn it_defaults_to_definition_value_if_no_stored_setting_is_present() {
@@ -179,4 +307,83 @@ mod tests {
             source_settings.get(&"ok".into())
         );
     }
+
+    #[test]
+    fn it_persists_changes_and_notifies_listeners() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".source.settings.json");
+
+        let source_settings =
+            SourceSettings::new("".to_owned(), &[], &HashMap::new(), &source_manager())
+                .unwrap()
+                .with_persisted_file(path.clone());
+        let mut changes = source_settings.subscribe();
+
+        source_settings
+            .persist_change("token", SourceSettingValue::String("abc".into()))
+            .unwrap();
+
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(
+            SourceSettingChanged {
+                key: "token".into(),
+                value: SourceSettingValue::String("abc".into()),
+            },
+            *changes.borrow_and_update()
+        );
+
+        let reloaded = SourceSettings::new("".to_owned(), &[], &HashMap::new(), &source_manager())
+            .unwrap()
+            .with_persisted_file(path);
+        assert_eq!(
+            Some(SourceSettingValue::String("abc".into())),
+            reloaded.get(&"token".into())
+        );
+    }
+
+    #[test]
+    fn stored_settings_win_over_persisted_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".source.settings.json");
+
+        SourceSettings::new("".to_owned(), &[], &HashMap::new(), &source_manager())
+            .unwrap()
+            .with_persisted_file(path.clone())
+            .persist_change("url", SourceSettingValue::String("source".into()))
+            .unwrap();
+
+        let stored = HashMap::from([("url".to_owned(), SourceSettingValue::String("user".into()))]);
+        let reloaded = SourceSettings::new("".to_owned(), &[], &stored, &source_manager())
+            .unwrap()
+            .with_persisted_file(path);
+        assert_eq!(
+            Some(SourceSettingValue::String("user".into())),
+            reloaded.get(&"url".into())
+        );
+    }
+
+    #[test]
+    fn it_defaults_multi_select_settings_to_a_list() {
+        let definition: SettingDefinition = serde_json::from_value(serde_json::json!({
+            "type": "multi-select",
+            "title": "Languages",
+            "key": "languages",
+            "values": ["en", "pt-br", "ja"],
+            "default": ["en", "ja"],
+        }))
+        .unwrap();
+
+        let source_settings = SourceSettings::new(
+            "".to_owned(),
+            &[definition],
+            &HashMap::new(),
+            &source_manager(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(SourceSettingValue::Vec(vec!["en".into(), "ja".into()])),
+            source_settings.get(&"languages".into())
+        );
+    }
 }
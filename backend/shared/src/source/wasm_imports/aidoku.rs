@@ -227,7 +227,8 @@ pub fn create_page(
 #[aidoku_wasm_function]
 pub fn create_deeplink(mut caller: Caller<'_, WasmStore>, manga: i32, chapter: i32) -> Result<i32> {
     let manga: usize = manga.try_into().context("invalid manga descriptor")?;
-    let chapter: usize = chapter.try_into().context("invalid chapter descriptor")?;
+    // Links to a manga (rather than to one of its chapters) come without a chapter.
+    let chapter = descriptor_from_i32(chapter);
 
     let wasm_store = caller.data_mut();
     let manga = match wasm_store
@@ -240,19 +241,24 @@ pub fn create_deeplink(mut caller: Caller<'_, WasmStore>, manga: i32, chapter: i
     }
     .context("expected a Manga object")?;
 
-    let chapter = match wasm_store
-        .get_std_value(chapter)
-        .context("couldn't read chapter from store")?
-        .as_ref()
-    {
-        Value::Object(ObjectValue::Chapter(chapter)) => Some(chapter.clone()),
-        _ => None,
-    }
-    .context("expected a Chapter object")?;
+    let chapter = match chapter {
+        Some(chapter) => Some(
+            match wasm_store
+                .get_std_value(chapter)
+                .context("couldn't read chapter from store")?
+                .as_ref()
+            {
+                Value::Object(ObjectValue::Chapter(chapter)) => Some(chapter.clone()),
+                _ => None,
+            }
+            .context("expected a Chapter object")?,
+        ),
+        None => None,
+    };
 
     let deeplink = DeepLink {
         manga: Some(manga),
-        chapter: Some(chapter),
+        chapter,
     };
 
     Ok(
@@ -4,7 +4,7 @@ use dom_query::Document;
 use wasm_macros::{aidoku_wasm_function, register_wasm_function};
 use wasmi::{Caller, Linker};
 
-use crate::source::html_element::HTMLElement;
+use crate::source::html_element::{parse_xml, HTMLElement};
 use crate::source::wasm_store::{Value, WasmStore};
 
 pub fn register_html_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
@@ -18,7 +18,11 @@ pub fn register_html_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
         parse_fragment_with_uri
     )?;
 
+    register_wasm_function!(linker, "html", "xml_parse", xml_parse)?;
+    register_wasm_function!(linker, "html", "xml_select_ns", xml_select_ns)?;
+
     register_wasm_function!(linker, "html", "select", select)?;
+    register_wasm_function!(linker, "html", "select_nth", select_nth)?;
     register_wasm_function!(linker, "html", "attr", attr)?;
 
     register_wasm_function!(linker, "html", "set_text", set_text)?;
@@ -33,11 +37,14 @@ pub fn register_html_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "html", "base_uri", base_uri)?;
     register_wasm_function!(linker, "html", "body", body)?;
     register_wasm_function!(linker, "html", "text", text)?;
+    register_wasm_function!(linker, "html", "text_concat", text_concat)?;
     register_wasm_function!(linker, "html", "untrimmed_text", untrimmed_text)?;
     register_wasm_function!(linker, "html", "own_text", own_text)?;
 
     register_wasm_function!(linker, "html", "data", data)?;
     register_wasm_function!(linker, "html", "array", array)?;
+    register_wasm_function!(linker, "html", "children_elements", children_elements)?;
+    register_wasm_function!(linker, "html", "children_count", children_count)?;
     register_wasm_function!(linker, "html", "html", html)?;
     register_wasm_function!(linker, "html", "outer_html", outer_html)?;
 
@@ -102,6 +109,53 @@ fn parse_fragment_with_uri(
     Ok(store.store_std_value(Value::from(vec![html_element]).into(), None) as i32)
 }
 
+#[aidoku_wasm_function]
+fn xml_parse(mut caller: Caller<'_, WasmStore>, data: Option<String>) -> Result<i32> {
+    let store = caller.data_mut();
+    let document = parse_xml(&data.context("data is required for xml_parse")?)?;
+
+    let node_id = document.root().id;
+    let html_element = HTMLElement {
+        document: store.set_html(document),
+        node_id,
+        base_uri: None,
+    };
+
+    Ok(store.store_std_value(Value::from(vec![html_element]).into(), None) as i32)
+}
+
+/// Selects elements by namespace, as CSS selectors can't tell `media:content` from `content`
+/// in another namespace.
+#[aidoku_wasm_function]
+fn xml_select_ns(
+    mut caller: Caller<'_, WasmStore>,
+    descriptor_i32: i32,
+    namespace_uri: Option<String>,
+    local_name: Option<String>,
+) -> Result<i32> {
+    let descriptor: usize = descriptor_i32.try_into().context("invalid descriptor")?;
+    let namespace_uri = namespace_uri.context("namespace_uri is required for xml_select_ns")?;
+    let local_name = local_name.context("local_name is required for xml_select_ns")?;
+
+    let wasm_store = caller.data_mut();
+    let std_value = wasm_store
+        .get_std_value(descriptor)
+        .ok_or_else(|| anyhow!("failed to get value from store"))?;
+    let html_elements = match std_value.as_ref() {
+        Value::HTMLElements(elements) => Some(elements),
+        _ => None,
+    }
+    .context("expected HTMLElements value")?;
+
+    let selected_elements: Vec<_> = html_elements
+        .iter()
+        .filter_map(|element| element.select_ns(wasm_store, &namespace_uri, &local_name))
+        .flatten()
+        .collect();
+
+    Ok(wasm_store.store_std_value(Value::from(selected_elements).into(), Some(descriptor)) as i32)
+}
+
 #[aidoku_wasm_function]
 pub fn select(
     mut caller: Caller<'_, WasmStore>,
@@ -135,6 +189,50 @@ pub fn select(
     Ok(wasm_store.store_std_value(Value::from(selected_elements).into(), Some(descriptor)) as i32)
 }
 
+/// Emulates `selector:nth-child(n)`, which the selector engine does not support, by
+/// evaluating `selector` and picking the `n`-th match (1-based, like the CSS pseudo-class).
+#[aidoku_wasm_function]
+pub fn select_nth(
+    mut caller: Caller<'_, WasmStore>,
+    descriptor_i32: i32,
+    selector: Option<String>,
+    n: i32,
+) -> Result<i32> {
+    let descriptor: usize = descriptor_i32
+        .try_into()
+        .context("couldn't convert descriptor to i32")?;
+    let index = usize::try_from(n)
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .with_context(|| format!("n must be at least 1, got {n}"))?;
+
+    let wasm_store = caller.data_mut();
+    let std_value = wasm_store
+        .get_std_value(descriptor)
+        .ok_or_else(|| anyhow!("failed to get value from store"))?;
+    let html_elements = match std_value.as_ref() {
+        Value::HTMLElements(elements) => Some(elements),
+        _ => None,
+    }
+    .context("expected HTMLElements value")?;
+
+    let selector = selector.context("selector is required for select_nth function")?;
+    let selected_element = html_elements
+        .iter()
+        .map(|element| element.select_soup(wasm_store, &selector))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .flatten()
+        .nth(index)
+        .with_context(|| format!("no element #{n} matching {selector}"))?;
+
+    Ok(
+        wasm_store.store_std_value(Value::from(vec![selected_element]).into(), Some(descriptor))
+            as i32,
+    )
+}
+
 #[derive(Debug)]
 pub struct AttributeNotFound;
 
@@ -406,8 +504,25 @@ fn body(caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i32> {
     select(caller, descriptor_i32, Some("body".into()))
 }
 
+/// The text of every matched element, separated by a space, as unrelated elements (like the
+/// items of a list) would otherwise run into each other.
 #[aidoku_wasm_function]
-pub fn text(mut caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i32> {
+pub fn text(caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i32> {
+    joined_text(caller, descriptor_i32, " ")
+}
+
+/// Like [`text`], but with nothing between the elements, for when they are pieces of the same
+/// text, like a chapter number split across `<span>`s.
+#[aidoku_wasm_function]
+pub fn text_concat(caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i32> {
+    joined_text(caller, descriptor_i32, "")
+}
+
+fn joined_text(
+    mut caller: Caller<'_, WasmStore>,
+    descriptor_i32: i32,
+    separator: &str,
+) -> Result<i32> {
     let descriptor: usize = descriptor_i32.try_into().context("invalid descriptor")?;
 
     let wasm_store = caller.data_mut();
@@ -424,7 +539,7 @@ pub fn text(mut caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i3
         .iter()
         .filter_map(|element| element.text(wasm_store))
         .collect::<Vec<_>>()
-        .join(" ")
+        .join(separator)
         .trim()
         .to_owned();
 
@@ -511,6 +626,52 @@ fn array(mut caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i32>
     Ok(wasm_store.store_std_value(Value::from(array_value).into(), Some(descriptor)) as i32)
 }
 
+#[aidoku_wasm_function]
+fn children_elements(mut caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i32> {
+    let descriptor: usize = descriptor_i32.try_into().context("invalid descriptor")?;
+
+    let wasm_store = caller.data_mut();
+    let std_value = wasm_store
+        .get_std_value(descriptor)
+        .context("failed to get value from store")?;
+    let elements = match std_value.as_ref() {
+        Value::HTMLElements(elements) => Some(elements),
+        _ => None,
+    }
+    .context("expected HTMLElements value")?;
+
+    let array_value: Vec<Value> = elements
+        .iter()
+        .filter_map(|element| element.children_elements(wasm_store))
+        .flatten()
+        .map(|element| vec![element].into())
+        .collect();
+
+    Ok(wasm_store.store_std_value(Value::from(array_value).into(), Some(descriptor)) as i32)
+}
+
+#[aidoku_wasm_function]
+fn children_count(mut caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i32> {
+    let descriptor: usize = descriptor_i32.try_into().context("invalid descriptor")?;
+
+    let wasm_store = caller.data_mut();
+    let std_value = wasm_store
+        .get_std_value(descriptor)
+        .context("failed to get value from store")?;
+    let elements = match std_value.as_ref() {
+        Value::HTMLElements(elements) => Some(elements),
+        _ => None,
+    }
+    .context("expected HTMLElements value")?;
+
+    let count: usize = elements
+        .iter()
+        .filter_map(|element| element.children_count(wasm_store))
+        .sum();
+
+    Ok(count.try_into()?)
+}
+
 #[aidoku_wasm_function]
 pub fn html(mut caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i32> {
     let descriptor: usize = descriptor_i32.try_into().context("invalid descriptor")?;
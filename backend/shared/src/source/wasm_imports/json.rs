@@ -7,6 +7,7 @@ use crate::source::wasm_store::{ObjectValue, Value, ValueMap, WasmStore};
 
 pub fn register_json_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "json", "parse", parse)?;
+    register_wasm_function!(linker, "json", "encode", json_encode)?;
 
     Ok(())
 }
@@ -20,6 +21,48 @@ fn parse(mut caller: Caller<'_, WasmStore>, json: Option<String>) -> Result<i32>
     Ok(wasm_store.store_std_value(value.into(), None) as i32)
 }
 
+/// Serializes the value at `descriptor_i32` into a JSON string value, returning its
+/// descriptor, or -1 if the value contains something with no JSON equivalent (e.g. HTML
+/// elements).
+#[aidoku_wasm_function]
+fn json_encode(mut caller: Caller<'_, WasmStore>, descriptor_i32: i32) -> Result<i32> {
+    let descriptor: usize = descriptor_i32
+        .try_into()
+        .context("failed to convert descriptor_i32 in json_encode")?;
+    let wasm_store = caller.data_mut();
+    let value = wasm_store
+        .get_std_value(descriptor)
+        .context("failed to get value in json_encode")?;
+
+    let Some(json) = to_json(&value) else {
+        return Ok(-1);
+    };
+
+    Ok(wasm_store.store_std_value(Value::String(json.to_string()).into(), None) as i32)
+}
+
+fn to_json(value: &Value) -> Option<serde_json::Value> {
+    Some(match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Int(v) => (*v).into(),
+        // NaN and infinities have no JSON representation.
+        Value::Float(v) => serde_json::Number::from_f64(*v)?.into(),
+        Value::String(v) => v.clone().into(),
+        Value::Bool(v) => (*v).into(),
+        Value::Array(array) => array
+            .iter()
+            .map(to_json)
+            .collect::<Option<Vec<_>>>()?
+            .into(),
+        Value::Object(ObjectValue::ValueMap(map)) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, value)| Some((key.clone(), to_json(value)?)))
+                .collect::<Option<_>>()?,
+        ),
+        _ => return None,
+    })
+}
+
 impl<'de> Deserialize<'de> for Value {
     #[inline]
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
@@ -110,3 +153,27 @@ impl<'de> Deserialize<'de> for Value {
         deserializer.deserialize_any(ValueVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_nested_values() {
+        let value: Value =
+            serde_json::from_str(r#"{"a": [1, 2.5, "x", null], "b": {"c": true}}"#).unwrap();
+
+        assert_eq!(
+            to_json(&value),
+            Some(serde_json::json!({"a": [1, 2.5, "x", null], "b": {"c": true}}))
+        );
+    }
+
+    #[test]
+    fn rejects_values_without_json_equivalent() {
+        let value = Value::Array(vec![Value::Int(1), Value::HTMLElements(vec![])]);
+
+        assert_eq!(to_json(&value), None);
+        assert_eq!(to_json(&Value::Float(f64::NAN)), None);
+    }
+}
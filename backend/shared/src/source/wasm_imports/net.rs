@@ -29,6 +29,8 @@ pub fn register_net_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "net", "set_url", set_url)?;
     register_wasm_function!(linker, "net", "set_header", set_header)?;
     register_wasm_function!(linker, "net", "set_body", set_body)?;
+    register_wasm_function!(linker, "net", "set_cookie", set_cookie)?;
+    register_wasm_function!(linker, "net", "cookies_for_url", cookies_for_url)?;
     register_wasm_function!(linker, "net", "set_rate_limit", set_rate_limit)?;
     register_wasm_function!(
         linker,
@@ -158,10 +160,54 @@ pub fn set_body(
 ) -> Result<()> {
     let request_descriptor: usize = request_descriptor_i32.try_into()?;
     let builder = get_building_request(caller.data_mut(), request_descriptor)?;
-    builder.body = Some(bytes.context("body required")?);
+    builder.set_body(bytes.context("body required")?);
     Ok(())
 }
 
+/// Stores a cookie in the source's cookie jar. Without a `domain`, the cookie is host-only
+/// for the host of the request's URL.
+#[aidoku_wasm_function]
+pub fn set_cookie(
+    mut caller: Caller<'_, WasmStore>,
+    request_descriptor_i32: i32,
+    name: Option<String>,
+    value: Option<String>,
+    domain: Option<String>,
+) -> Result<()> {
+    let request_descriptor: usize = request_descriptor_i32.try_into()?;
+    let wasm_store = caller.data_mut();
+    let domain = match domain {
+        Some(domain) => domain,
+        None => get_building_request(wasm_store, request_descriptor)?
+            .url
+            .as_ref()
+            .and_then(|url| url.host_str())
+            .context("cookie domain required when the request has no URL")?
+            .to_owned(),
+    };
+
+    wasm_store.cookie_jar.set_cookie(
+        name.context("cookie name required")?,
+        value.context("cookie value required")?,
+        domain,
+    );
+    Ok(())
+}
+
+/// Returns the `Cookie` header value the source's cookie jar would send to `url`.
+#[aidoku_wasm_function]
+pub fn cookies_for_url(mut caller: Caller<'_, WasmStore>, url: Option<String>) -> Result<i32> {
+    let url = Url::parse(&url.context("url required")?).context("invalid url")?;
+
+    let wasm_store = caller.data_mut();
+    let cookies = wasm_store
+        .cookie_jar
+        .cookie_header(&url)
+        .unwrap_or_default();
+
+    Ok(wasm_store.store_std_value(Value::String(cookies).into(), None) as i32)
+}
+
 #[aidoku_wasm_function]
 fn set_rate_limit(mut caller: Caller<'_, WasmStore>, rate_limit: i32) -> Result<()> {
     let store = caller.data_mut();
@@ -198,8 +244,11 @@ pub fn send(mut caller: Caller<'_, WasmStore>, request_descriptor_i32: i32) -> R
     let cookie_sync_chat_id = wasm_store.settings.cookie_sync_chat_id;
     #[cfg(not(any(feature = "ffi", not(feature = "all"))))]
     let cookie_sync_api_token = wasm_store.settings.cookie_sync_api_token.clone();
+    add_jar_cookies(wasm_store, request_descriptor_i32)?;
     let request_builder = get_building_request(wasm_store, request_descriptor_i32)?;
 
+    let bytes_sent = request_builder.encoded_body().map_or(0, |body| body.len());
+
     // HACK Before everything, we want to fail fast if no internet connection is available.
     // In theory, it would be easier to just let things fail naturally and move on
     // with our lives; but DNS resolution takes forever (~5s or so) when we have no connection
@@ -238,16 +287,12 @@ pub fn send(mut caller: Caller<'_, WasmStore>, request_descriptor_i32: i32) -> R
     }
     #[cfg(not(any(feature = "ffi", not(feature = "all"))))]
     if let Some(ref cval) = cookie_value {
-        if let Ok(header_val) = reqwest::header::HeaderValue::from_str(cval) {
-            // println!(
-            //     "[cookie] setting Cookie header for {}: {} cookies",
-            //     request.url().host_str().unwrap_or("?"),
-            //     cval.matches(';').count() + 1
-            // );
-            request
-                .headers_mut()
-                .insert(reqwest::header::COOKIE, header_val);
-        }
+        // println!(
+        //     "[cookie] setting Cookie header for {}: {} cookies",
+        //     request.url().host_str().unwrap_or("?"),
+        //     cval.matches(';').count() + 1
+        // );
+        append_cookie_header(&mut request, cval);
     }
 
     #[cfg(not(any(feature = "ffi", not(feature = "all"))))]
@@ -322,16 +367,12 @@ pub fn send(mut caller: Caller<'_, WasmStore>, request_descriptor_i32: i32) -> R
                     }
                 }
                 if let Some(ref cval) = cookie_value {
-                    if let Ok(header_val) = reqwest::header::HeaderValue::from_str(cval) {
-                        // println!(
-                        //     "[cookie] setting Cookie header for {} (retry): {} cookies",
-                        //     host.as_deref().unwrap_or("?"),
-                        //     cval.matches(';').count() + 1
-                        // );
-                        retry_request
-                            .headers_mut()
-                            .insert(reqwest::header::COOKIE, header_val);
-                    }
+                    // println!(
+                    //     "[cookie] setting Cookie header for {} (retry): {} cookies",
+                    //     host.as_deref().unwrap_or("?"),
+                    //     cval.matches(';').count() + 1
+                    // );
+                    append_cookie_header(&mut retry_request, cval);
                 }
                 // println!("[cookie] retry request headers for {}:", retry_url);
                 // for (name, value) in retry_request.headers() {
@@ -387,12 +428,73 @@ pub fn send(mut caller: Caller<'_, WasmStore>, request_descriptor_i32: i32) -> R
             })
             .context("failed to execute request")?;
 
+    wasm_store
+        .bandwidth
+        .record(bytes_sent, response_data.body.as_ref().map_or(0, Vec::len));
+    wasm_store
+        .cookie_jar
+        .store_response_cookies(&response_data.url, &response_data.headers);
+
     *wasm_store
         .get_mut_request(request_descriptor_i32)
         .context("failed to get request state")? = RequestState::Sent(response_data);
     Ok(())
 }
 
+/// Adds the cookies of the source's cookie jar matching its URL to the request being built,
+/// after the ones the source set itself.
+pub(crate) fn add_jar_cookies(wasm_store: &mut WasmStore, request_descriptor: usize) -> Result<()> {
+    let jar_cookies = get_building_request(wasm_store, request_descriptor)?
+        .url
+        .clone()
+        .and_then(|url| wasm_store.cookie_jar.cookie_header(&url));
+    let Some(jar_cookies) = jar_cookies else {
+        return Ok(());
+    };
+
+    let request_builder = get_building_request(wasm_store, request_descriptor)?;
+    let existing = request_builder
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("cookie"))
+        .map(|(name, value)| (name.clone(), value.clone()));
+
+    match existing {
+        Some((name, value)) => {
+            request_builder
+                .headers
+                .insert(name, format!("{value}; {jar_cookies}"));
+        }
+        None => {
+            request_builder
+                .headers
+                .insert("Cookie".to_owned(), jar_cookies);
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds `cookies` to the `Cookie` header of `request`, keeping the cookies the source (or
+/// its cookie jar) already set.
+#[cfg(not(any(feature = "ffi", not(feature = "all"))))]
+fn append_cookie_header(request: &mut reqwest::Request, cookies: &str) {
+    let value = match request
+        .headers()
+        .get(reqwest::header::COOKIE)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(existing) => format!("{existing}; {cookies}"),
+        None => cookies.to_owned(),
+    };
+
+    if let Ok(header_val) = reqwest::header::HeaderValue::from_str(&value) {
+        request
+            .headers_mut()
+            .insert(reqwest::header::COOKIE, header_val);
+    }
+}
+
 #[aidoku_wasm_function]
 pub fn get_url(mut caller: Caller<'_, WasmStore>, request_descriptor_i32: i32) -> Result<i32> {
     let descriptor: usize = request_descriptor_i32.try_into()?;
@@ -31,10 +31,17 @@ pub fn register_canvas_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "canvas", "new_font", new_font)?; // check
     register_wasm_function!(linker, "canvas", "system_font", system_font)?; // check
     register_wasm_function!(linker, "canvas", "load_font", load_font)?; // check
+    register_wasm_function!(
+        linker,
+        "canvas",
+        "set_font_from_wasm_memory",
+        set_font_from_wasm_memory
+    )?;
 
     // Image
     register_wasm_function!(linker, "canvas", "new_image", new_image)?; // check
     register_wasm_function!(linker, "canvas", "get_image", get_image)?; // check
+    register_wasm_function!(linker, "canvas", "to_image", get_image)?; // alias used by some sources
     register_wasm_function!(linker, "canvas", "get_image_data", get_image_data)?; // check
     register_wasm_function!(linker, "canvas", "get_image_width", get_image_width)?; // check
     register_wasm_function!(linker, "canvas", "get_image_height", get_image_height)?; // check
@@ -436,6 +443,24 @@ fn load_font(mut caller: Caller<'_, WasmStore>, url: Option<String>) -> Result<i
     let store = caller.data_mut();
     Ok(store.set_font_online(&bytes) as i32)
 }
+/// Loads a font the source carries itself (e.g. bundled in its `.aix`), given as bytes in its
+/// own memory.
+#[aidoku_wasm_function]
+fn set_font_from_wasm_memory(
+    mut caller: Caller<'_, WasmStore>,
+    bytes: Option<Vec<u8>>,
+) -> Result<i32> {
+    let Some(bytes) = bytes else {
+        return Ok(ResultContext::FontLoadFailed.into());
+    };
+    // Fonts are only parsed when drawing, so make sure this one can be.
+    if font_kit::font::Font::from_bytes(bytes.clone().into(), 0).is_err() {
+        return Ok(ResultContext::FontLoadFailed.into());
+    }
+
+    let store = caller.data_mut();
+    Ok(store.set_font_online(&bytes) as i32)
+}
 
 // ----------------- Image -----------------
 #[aidoku_wasm_function]
@@ -446,10 +471,13 @@ fn new_image(mut caller: Caller<'_, WasmStore>, bytes: Option<Vec<u8>>) -> Resul
         return Ok(ResultContext::InvalidData.into());
     };
 
-    Ok(store
-        .create_image(&bytes)
-        .map(|v| v as i32)
-        .unwrap_or(ResultContext::InvalidData.into()))
+    Ok(match store.create_image(&bytes) {
+        Ok(v) => v as i32,
+        Err(err) => {
+            eprintln!("new_image: {err}");
+            ResultContext::InvalidData.into()
+        }
+    })
 }
 #[aidoku_wasm_function]
 fn get_image(mut caller: Caller<'_, WasmStore>, ctx_id: i32) -> Result<i32> {
@@ -128,9 +128,7 @@ fn set(
     {
         let wasm_store = caller.data_mut();
 
-        wasm_store
-            .source_settings
-            .save(&key.clone(), value.clone())?;
+        wasm_store.source_settings.persist_change(&key, value)?;
     }
     #[cfg(not(feature = "all"))]
     (anyhow::Context::context(DEFAULTS_SET.get(), "Please set DEFAULTS_SET")?)(
@@ -139,6 +137,5 @@ fn set(
         &value,
     )?;
 
-    println!("defaults.set: {:?} -> {:?}", key, value);
     Ok(0)
 }
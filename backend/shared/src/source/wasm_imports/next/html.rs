@@ -16,8 +16,10 @@ pub fn register_html_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "html", "unescape", unescape)?;
     register_wasm_function!(linker, "html", "select", select)?; // OK
     register_wasm_function!(linker, "html", "select_first", select_first)?; // OK
+    register_wasm_function!(linker, "html", "select_nth", select_nth)?;
     register_wasm_function!(linker, "html", "attr", attr)?; // OK
     register_wasm_function!(linker, "html", "text", text)?; // OK
+    register_wasm_function!(linker, "html", "text_concat", text_concat)?;
     register_wasm_function!(linker, "html", "untrimmed_text", untrimmed_text)?;
     register_wasm_function!(linker, "html", "html", html)?; // OK
     register_wasm_function!(linker, "html", "outer_html", outer_html)?;
@@ -204,6 +206,52 @@ fn select_first(
     ) as i32)
 }
 #[aidoku_wasm_function]
+fn select_nth(
+    mut caller: Caller<'_, WasmStore>,
+    descriptor: i32,
+    selector: Option<String>,
+    n: i32,
+) -> Result<i32> {
+    let wasm_store = caller.data_mut();
+    let Some(std_value) = wasm_store.get_std_value(descriptor as usize) else {
+        return ResultContext::InvalidDescriptor.into();
+    };
+    let Some(html_elements) = (match std_value.as_ref() {
+        Value::HTMLElements(elements) => Some(elements),
+        _ => None,
+    }) else {
+        return ResultContext::NoResult.into();
+    };
+
+    let Some(selector) = selector else {
+        return ResultContext::InvalidQuery.into();
+    };
+    // `n` is 1-based, like `:nth-child(n)`
+    let Some(index) = usize::try_from(n).ok().and_then(|n| n.checked_sub(1)) else {
+        return ResultContext::NoResult.into();
+    };
+
+    let mut remaining = index;
+    let mut selected_element = None;
+    for el in html_elements.iter() {
+        let found = el.select_soup(wasm_store, &selector)?.unwrap_or_default();
+        if remaining < found.len() {
+            selected_element = found.into_iter().nth(remaining);
+            break;
+        }
+        remaining -= found.len();
+    }
+
+    let Some(selected_element) = selected_element else {
+        return ResultContext::NoResult.into();
+    };
+
+    Ok(wasm_store.store_std_value(
+        Value::from(vec![selected_element]).into(),
+        Some(descriptor as usize),
+    ) as i32)
+}
+#[aidoku_wasm_function]
 fn attr(caller: Caller<'_, WasmStore>, ptr: i32, selector: Option<String>) -> Result<i32> {
     match crate::source::wasm_imports::html::attr(caller, ptr, selector) {
         Ok(v) => Ok(v),
@@ -224,6 +272,10 @@ fn text(caller: Caller<'_, WasmStore>, ptr: i32) -> Result<i32> {
     crate::source::wasm_imports::html::text(caller, ptr)
 }
 #[aidoku_wasm_function]
+fn text_concat(caller: Caller<'_, WasmStore>, ptr: i32) -> Result<i32> {
+    crate::source::wasm_imports::html::text_concat(caller, ptr)
+}
+#[aidoku_wasm_function]
 fn untrimmed_text(caller: Caller<'_, WasmStore>, ptr: i32) -> Result<i32> {
     crate::source::wasm_imports::html::untrimmed_text(caller, ptr)
 }
@@ -14,6 +14,7 @@ pub fn register_js_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "js", "context_eval", context_eval)?;
     register_wasm_function!(linker, "js", "context_eval_async", context_eval_async)?;
     register_wasm_function!(linker, "js", "context_get", context_get)?;
+    register_wasm_function!(linker, "js", "context_reset", context_reset)?;
 
     register_wasm_function!(linker, "js", "webview_create", webview_create)?;
     register_wasm_function!(linker, "js", "webview_set_rule_list", webview_set_rule_list)?;
@@ -124,6 +125,18 @@ fn context_get(mut caller: Caller<'_, WasmStore>, ctx_id: i32, name: Option<Stri
     Ok(store.store_std_value(Value::String(result_string).into(), None) as i32)
 }
 
+/// Gives the source a clean global scope, e.g. between chapters, since its context is
+/// shared across calls.
+#[aidoku_wasm_function]
+fn context_reset(mut caller: Caller<'_, WasmStore>, ctx_id: i32) -> FFIResult {
+    let store = caller.data_mut();
+    if !store.reset_js_context(ctx_id as usize) {
+        return Ok(ResultContext::InvalidContext.into());
+    }
+
+    Ok(0)
+}
+
 #[aidoku_wasm_function]
 fn webview_create(mut caller: Caller<'_, WasmStore>) -> FFIResult {
     #[cfg(not(feature = "all"))]
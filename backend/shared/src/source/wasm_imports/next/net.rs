@@ -14,7 +14,7 @@ use wasm_macros::{aidoku_wasm_function, register_wasm_function};
 use wasm_shared::{get_memory, memory_reader::read_values};
 use wasmi::{Caller, Linker};
 
-use crate::source::wasm_store::{RequestState, WasmStore};
+use crate::source::wasm_store::{MultipartPart, RequestState, WasmStore};
 
 #[repr(C)]
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
@@ -37,6 +37,9 @@ pub fn register_net_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "net", "set_url", set_url)?; // OK
     register_wasm_function!(linker, "net", "set_header", set_header)?; // OK
     register_wasm_function!(linker, "net", "set_body", set_body)?; // OK
+    register_wasm_function!(linker, "net", "set_multipart_field", set_multipart_field)?;
+    register_wasm_function!(linker, "net", "set_multipart_file", set_multipart_file)?;
+    register_wasm_function!(linker, "net", "set_form_field", set_form_field)?;
     register_wasm_function!(linker, "net", "set_timeout", set_timeout)?; // OK
     register_wasm_function!(linker, "net", "data_len", data_len)?; // OK
     register_wasm_function!(linker, "net", "read_data", read_data)?; // OK
@@ -186,7 +189,9 @@ fn send_all(mut caller: Caller<'_, WasmStore>, rd: i32, len: i32) -> FFIResult {
         };
         store.rate_limit_acquire();
 
+        crate::source::wasm_imports::net::add_jar_cookies(store, request_descriptor_i32)?;
         let request_builder = get_building_request(store, request_descriptor_i32)?;
+        let bytes_sent = request_builder.encoded_body().map_or(0, |body| body.len());
         #[cfg(all(not(feature = "ffi"), feature = "all"))]
         let client = crate::tls::client_builder()
             .timeout(std::time::Duration::from_secs(60))
@@ -250,6 +255,12 @@ fn send_all(mut caller: Caller<'_, WasmStore>, rd: i32, len: i32) -> FFIResult {
             })
             .context("failed to execute request")?;
 
+        store
+            .bandwidth
+            .record(bytes_sent, response_data.body.as_ref().map_or(0, Vec::len));
+        store
+            .cookie_jar
+            .store_response_cookies(&response_data.url, &response_data.headers);
         *store
             .get_mut_request(request_descriptor_i32)
             .context("failed to get request state")? = RequestState::Sent(response_data);
@@ -285,6 +296,65 @@ fn set_body(caller: Caller<'_, WasmStore>, request_ptr: i32, bytes: Option<Vec<u
     ResultContext::Success.into()
 }
 
+#[aidoku_wasm_function]
+fn set_multipart_field(
+    mut caller: Caller<'_, WasmStore>,
+    request_ptr: i32,
+    name: Option<String>,
+    value: Option<String>,
+) -> FFIResult {
+    let (Some(name), Some(value)) = (name, value) else {
+        return ResultContext::InvalidString.into();
+    };
+    let builder = get_building_request(caller.data_mut(), request_ptr.try_into()?)?;
+    builder.push_multipart_part(MultipartPart::Field { name, value });
+
+    ResultContext::Success.into()
+}
+
+/// Adds a file part; without a `content_type`, it's sent as `application/octet-stream`.
+#[aidoku_wasm_function]
+fn set_multipart_file(
+    mut caller: Caller<'_, WasmStore>,
+    request_ptr: i32,
+    name: Option<String>,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    data: Option<Vec<u8>>,
+) -> FFIResult {
+    let (Some(name), Some(file_name)) = (name, file_name) else {
+        return ResultContext::InvalidString.into();
+    };
+    let Some(data) = data else {
+        return ResultContext::MissingData.into();
+    };
+    let builder = get_building_request(caller.data_mut(), request_ptr.try_into()?)?;
+    builder.push_multipart_part(MultipartPart::File {
+        name,
+        file_name,
+        content_type,
+        data,
+    });
+
+    ResultContext::Success.into()
+}
+
+#[aidoku_wasm_function]
+fn set_form_field(
+    mut caller: Caller<'_, WasmStore>,
+    request_ptr: i32,
+    key: Option<String>,
+    value: Option<String>,
+) -> FFIResult {
+    let (Some(key), Some(value)) = (key, value) else {
+        return ResultContext::InvalidString.into();
+    };
+    let builder = get_building_request(caller.data_mut(), request_ptr.try_into()?)?;
+    builder.push_form_field(key, value);
+
+    ResultContext::Success.into()
+}
+
 #[aidoku_wasm_function]
 fn set_timeout(mut caller: Caller<'_, WasmStore>, request_ptr: i32, value: f64) -> FFIResult {
     let builder = get_building_request(caller.data_mut(), request_ptr.try_into()?)?;
@@ -331,10 +401,13 @@ fn get_image(mut caller: Caller<'_, WasmStore>, request_ptr: i32) -> FFIResult {
             .clone()
     };
 
-    Ok(wasm_store
-        .create_image(&bytes_to_create_image)
-        .map(|v| v as i32)
-        .unwrap_or(ResultContext::NotAnImage.into()))
+    Ok(match wasm_store.create_image(&bytes_to_create_image) {
+        Ok(v) => v as i32,
+        Err(err) => {
+            eprintln!("get_image: {err}");
+            ResultContext::NotAnImage.into()
+        }
+    })
 }
 #[aidoku_wasm_function]
 fn get_status_code(caller: Caller<'_, WasmStore>, request_ptr: i32) -> Result<i32> {
@@ -1,6 +1,7 @@
 #![allow(clippy::too_many_arguments)]
 
 use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use wasm_macros::{aidoku_wasm_function, register_wasm_function};
 use wasm_shared::{get_memory, memory_reader::write_bytes};
 use wasmi::{Caller, Linker, F64};
@@ -15,6 +16,8 @@ pub fn register_std_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "std", "utc_offset", utc_offset)?; // OK
     register_wasm_function!(linker, "std", "parse_date", parse_date)?; // OK
     register_wasm_function!(linker, "std", "print", print)?; // OK
+    register_wasm_function!(linker, "std", "base64_encode", base64_encode)?;
+    register_wasm_function!(linker, "std", "base64_decode", base64_decode)?;
     linker.func_wrap(
         "std",
         "abort",
@@ -261,3 +264,46 @@ fn parse_date(
 fn print(caller: Caller<'_, WasmStore>, string: Option<String>) -> Result<()> {
     crate::source::wasm_imports::next::env::print(caller, string)
 }
+
+/// Encodes the bytes of `ptr` (a byte vector, or a string's UTF-8 bytes) into a base64
+/// string.
+#[aidoku_wasm_function]
+fn base64_encode(mut caller: Caller<'_, WasmStore>, ptr: i32) -> Result<i32> {
+    let Some(ptr) = usize::try_from(ptr).ok() else {
+        return Ok(ResultContext::InvalidDescriptor.into());
+    };
+    let wasm_store = caller.data_mut();
+    let Some(value) = wasm_store.get_std_value(ptr) else {
+        return Ok(ResultContext::InvalidDescriptor.into());
+    };
+
+    let encoded = match &*value {
+        Value::Vec(bytes) => BASE64.encode(bytes),
+        Value::String(string) => BASE64.encode(string),
+        _ => return Ok(ResultContext::InvalidDescriptor.into()),
+    };
+
+    Ok(wasm_store.store_std_value(Value::String(encoded).into(), None) as i32)
+}
+
+/// Decodes the base64 string of `ptr` into a byte vector, or returns -1 if it isn't valid
+/// base64.
+#[aidoku_wasm_function]
+fn base64_decode(mut caller: Caller<'_, WasmStore>, ptr: i32) -> Result<i32> {
+    let Some(ptr) = usize::try_from(ptr).ok() else {
+        return Ok(ResultContext::InvalidDescriptor.into());
+    };
+    let wasm_store = caller.data_mut();
+    let Some(value) = wasm_store.get_std_value(ptr) else {
+        return Ok(ResultContext::InvalidDescriptor.into());
+    };
+    let Value::String(string) = &*value else {
+        return Ok(ResultContext::InvalidDescriptor.into());
+    };
+
+    let Ok(bytes) = BASE64.decode(string.trim()) else {
+        return Ok(ResultContext::InvalidDescriptor.into());
+    };
+
+    Ok(wasm_store.store_std_value(Value::Vec(bytes).into(), None) as i32)
+}
@@ -64,6 +64,7 @@ pub fn register_std_imports(linker: &mut Linker<WasmStore>) -> Result<()> {
     register_wasm_function!(linker, "std", "array_set", array_set)?;
     register_wasm_function!(linker, "std", "array_append", array_append)?;
     register_wasm_function!(linker, "std", "array_remove", array_remove)?;
+    register_wasm_function!(linker, "std", "format", format)?;
     Ok(())
 }
 
@@ -657,10 +658,9 @@ fn object_set(
         .clone();
     let mut hm_value = Parc::unwrap_or_clone(
         wasm_store
-            .get_std_value(descriptor)
+            .detach_std_value(descriptor)
             .context("failed to take value in object_set")?,
     );
-    wasm_store.take_std_value(descriptor);
     let object_value = hm_value
         .try_unwrap_object_mut()
         .map_err(|_| anyhow::anyhow!("expected object in object_set"))?;
@@ -696,10 +696,9 @@ fn object_remove(
     let wasm_store = caller.data_mut();
     let mut value = Parc::unwrap_or_clone(
         wasm_store
-            .get_std_value(descriptor)
+            .detach_std_value(descriptor)
             .context("failed to take value in object_remove")?,
     );
-    wasm_store.take_std_value(descriptor);
     let object_value = value
         .try_unwrap_object_mut()
         .map_err(|_| anyhow::anyhow!("expected object in object_remove"))?;
@@ -810,10 +809,9 @@ fn array_set(
         .context("failed to get value in array_set")?;
     let mut array_value = Parc::unwrap_or_clone(
         wasm_store
-            .get_std_value(descriptor)
+            .detach_std_value(descriptor)
             .context("failed to take value in array_set")?,
     );
-    wasm_store.take_std_value(descriptor);
     let array = array_value
         .try_unwrap_array_mut()
         .map_err(|_| anyhow::anyhow!("expected array in array_set"))?;
@@ -839,14 +837,20 @@ fn array_append(
     let value_descriptor: usize = value_i32
         .try_into()
         .context("failed to convert value_i32 in array_append")?;
-    let wasm_store = caller.data_mut();
+    append_to_array(caller.data_mut(), descriptor, value_descriptor)
+}
+
+fn append_to_array(
+    wasm_store: &mut WasmStore,
+    descriptor: usize,
+    value_descriptor: usize,
+) -> Result<()> {
     let value_ref = wasm_store
         .get_std_value(value_descriptor)
         .context("failed to get value in array_append")?;
     let array_value_ref = wasm_store
-        .get_std_value(descriptor)
+        .detach_std_value(descriptor)
         .context("failed to take value in array_append")?;
-    wasm_store.take_std_value(descriptor);
     if Parc::strong_count(&array_value_ref) > 1 {
         debug!(
             "attempting to add to array with more than 1 reference (got {}), slow!",
@@ -960,17 +964,169 @@ impl FieldAsValue for Filter {
             // FIXME i dont think this should be here but
             "value" => match &self {
                 Filter::Title(title) => Some(Value::String(title.clone())),
+                // Genre filters are named after their genre, their value tells whether it's
+                // included (1) or excluded (2)
+                Filter::Genre(_) => Some(Value::Int(1)),
             },
             _ => None,
         }
     }
 }
 
+/// Fills the `%d`, `%s` and `%f` specifiers of a template string with the values of an
+/// array, like `std.format("Chapter %d", [12])`.
+#[aidoku_wasm_function]
+fn format(mut caller: Caller<'_, WasmStore>, template_i32: i32, args_i32: i32) -> Result<i32> {
+    let template_descriptor: usize = template_i32
+        .try_into()
+        .context("failed to convert template_i32 in format")?;
+    let args_descriptor: usize = args_i32
+        .try_into()
+        .context("failed to convert args_i32 in format")?;
+    let wasm_store = caller.data_mut();
+    let template = wasm_store
+        .get_std_value(template_descriptor)
+        .context("failed to get template in format")?;
+    let template = template
+        .try_unwrap_string_ref()
+        .ok()
+        .context("expected string template in format")?;
+    let args = wasm_store
+        .get_std_value(args_descriptor)
+        .context("failed to get args in format")?;
+    let args = args
+        .try_unwrap_array_ref()
+        .ok()
+        .context("expected array of args in format")?;
+
+    let formatted = format_template(template, args)?;
+
+    Ok(wasm_store.store_std_value(Value::from(formatted).into(), None) as i32)
+}
+
+fn format_template(template: &str, args: &[Value]) -> Result<String> {
+    let mut formatted = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+
+        // An optional precision, as in `%.2f`
+        let precision = if chars.next_if_eq(&'.').is_some() {
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                digits.push(digit);
+            }
+            Some(
+                digits
+                    .parse::<usize>()
+                    .context("missing precision in format")?,
+            )
+        } else {
+            None
+        };
+
+        let specifier = chars.next().context("unterminated specifier in format")?;
+        if specifier == '%' && precision.is_none() {
+            formatted.push('%');
+            continue;
+        }
+
+        let arg = args
+            .next()
+            .with_context(|| format!("missing argument for %{specifier} in format"))?;
+        match (specifier, arg) {
+            ('d', Value::Int(i)) => formatted.push_str(&i.to_string()),
+            ('d', Value::Float(f)) => formatted.push_str(&(f.trunc() as i64).to_string()),
+            ('d', Value::Bool(b)) => formatted.push_str(if *b { "1" } else { "0" }),
+            ('f', Value::Int(i)) => {
+                formatted.push_str(&format!("{:.*}", precision.unwrap_or(6), *i as f64))
+            }
+            ('f', Value::Float(f)) => {
+                formatted.push_str(&format!("{:.*}", precision.unwrap_or(6), f))
+            }
+            ('s', Value::String(s)) => formatted.push_str(s),
+            ('s', Value::Int(i)) => formatted.push_str(&i.to_string()),
+            ('s', Value::Float(f)) => formatted.push_str(&f.to_string()),
+            ('s', Value::Bool(b)) => formatted.push_str(&b.to_string()),
+            _ => bail!("can't format {arg:?} with %{specifier}"),
+        }
+    }
+
+    Ok(formatted)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
     use chrono::{Datelike, Timelike};
 
     use super::*;
+    use crate::{
+        settings::Settings, source::source_settings::SourceSettings, source_manager::SourceManager,
+    };
+
+    fn store() -> WasmStore {
+        let source_settings = SourceSettings::new(
+            "test".to_owned(),
+            &[],
+            &HashMap::new(),
+            &Arc::new(tokio::sync::Mutex::new(SourceManager::new(
+                PathBuf::new(),
+                HashMap::new(),
+                Settings::default(),
+            ))),
+        )
+        .unwrap();
+
+        WasmStore::default(source_settings)
+    }
+
+    #[test]
+    fn appending_to_an_array_keeps_its_descriptor_allocated() {
+        let mut store = store();
+        let array = store.store_std_value(Value::Array(vec![]).into(), None);
+        let item = store.store_std_value(Value::Int(1).into(), None);
+
+        append_to_array(&mut store, array, item).unwrap();
+        append_to_array(&mut store, array, item).unwrap();
+        let other = store.store_std_value(Value::String("other".to_owned()).into(), None);
+
+        assert_ne!(other, array);
+        assert_ne!(other, item);
+        assert!(matches!(
+            store.get_std_value(array).as_deref(),
+            Some(Value::Array(items)) if matches!(items.as_slice(), [Value::Int(1), Value::Int(1)])
+        ));
+    }
+
+    #[test]
+    fn formats_specifiers_in_order() {
+        let args = [
+            Value::Int(12),
+            Value::String("Extra".to_owned()),
+            Value::Float(1.5),
+        ];
+
+        assert_eq!(
+            format_template("Chapter %d: %s (%.1f%%)", &args).unwrap(),
+            "Chapter 12: Extra (1.5%)"
+        );
+        assert_eq!(format_template("%f", &[Value::Int(2)]).unwrap(), "2.000000");
+    }
+
+    #[test]
+    fn format_rejects_malformed_templates() {
+        assert!(format_template("Chapter %d", &[]).is_err());
+        assert!(format_template("Chapter %d", &[Value::Null]).is_err());
+        assert!(format_template("Chapter %", &[Value::Int(1)]).is_err());
+        assert!(format_template("Chapter %x", &[Value::Int(1)]).is_err());
+    }
 
     #[test]
     fn test_basic_conversions() {
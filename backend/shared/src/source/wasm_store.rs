@@ -1,11 +1,13 @@
-use dom_query::Document;
+use dom_query::{Document, Matcher};
 use font_kit::{family_name::FamilyName, font::Font, properties::Properties, source::SystemSource};
-use image::ImageReader;
 use pared::sync::Parc;
 use raqote::DrawTarget;
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
-    io::Cursor,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     thread::sleep,
     time::{Duration, Instant},
 };
@@ -26,7 +28,10 @@ use crate::{
 };
 
 use super::{
+    cookie_jar::CookieJar,
+    decode_image::DecodeImageError,
     model::{Chapter, DeepLink, Filter, Manga, MangaPageResult, Page},
+    preload_cache::PreloadCache,
     source_settings::SourceSettings,
 };
 
@@ -34,6 +39,15 @@ use super::{
 // https://ntietz.com/blog/rust-hashmap-overhead/
 pub type ValueMap = BTreeMap<String, Value>;
 
+/// Sources usually use a handful of selectors over and over, but one that builds them from
+/// page data could grow the selector cache forever, so past this many the oldest is dropped.
+const SELECTOR_CACHE_MAX_ENTRIES: usize = 256;
+
+/// How many bytes of pixels freed canvases may keep around for reuse. Sources that stitch or
+/// descramble pages create a canvas per page, usually of the same size, so a few pages' worth is
+/// enough; a long webtoon strip shouldn't be able to pin dozens of megabytes on its own.
+const CANVAS_POOL_MAX_BYTES: usize = 64 * 1024 * 1024;
+
 #[derive(Debug, Clone, From, TryUnwrap)]
 #[try_unwrap(ref, ref_mut)]
 // FIXME Apply the suggestion from the following `clippy` lint
@@ -97,11 +111,185 @@ pub type ValueRef = Parc<Value>;
 pub struct RequestBuildingState {
     pub url: Option<Url>,
     pub method: Option<Method>,
-    pub body: Option<Vec<u8>>,
+    pub body: RequestBody,
     pub headers: HashMap<String, String>,
     pub timeout: Option<f64>,
 }
 
+/// The body of a request being built. Sources build it in one of three ways, and starting a
+/// different kind of body replaces the one built so far.
+#[derive(Debug, Default)]
+pub enum RequestBody {
+    #[default]
+    Empty,
+    Raw(Vec<u8>),
+    Multipart(Vec<MultipartPart>),
+    /// Fields of an `application/x-www-form-urlencoded` body.
+    Form(Vec<(String, String)>),
+}
+
+#[derive(Debug, Clone)]
+pub enum MultipartPart {
+    Field {
+        name: String,
+        value: String,
+    },
+    File {
+        name: String,
+        file_name: String,
+        content_type: Option<String>,
+        data: Vec<u8>,
+    },
+}
+
+impl RequestBuildingState {
+    pub fn set_body(&mut self, bytes: Vec<u8>) {
+        self.body = RequestBody::Raw(bytes);
+    }
+
+    /// Adds a part to the request's `multipart/form-data` body. The parts are only encoded
+    /// when the request is sent.
+    pub fn push_multipart_part(&mut self, part: MultipartPart) {
+        match &mut self.body {
+            RequestBody::Multipart(parts) => parts.push(part),
+            body => *body = RequestBody::Multipart(vec![part]),
+        }
+    }
+
+    pub fn push_form_field(&mut self, key: String, value: String) {
+        match &mut self.body {
+            RequestBody::Form(fields) => fields.push((key, value)),
+            body => *body = RequestBody::Form(vec![(key, value)]),
+        }
+    }
+
+    /// The body to send, with the form fields or multipart parts encoded.
+    ///
+    /// Every way of sending the request (blocking, async or through the Android bridge) goes
+    /// through here, so that they send the same bytes without knowing about either.
+    pub fn encoded_body(&self) -> Option<Vec<u8>> {
+        match &self.body {
+            RequestBody::Empty => None,
+            RequestBody::Raw(bytes) => Some(bytes.clone()),
+            RequestBody::Multipart(parts) => {
+                Some(encode_multipart(parts, &multipart_boundary(parts)))
+            }
+            RequestBody::Form(fields) => Some(
+                url::form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(fields)
+                    .finish()
+                    .into_bytes(),
+            ),
+        }
+    }
+
+    /// The headers to send. Form bodies get their `Content-Type` unless the source set one,
+    /// while multipart ones always do, as it holds the boundary.
+    pub fn encoded_headers(&self) -> HashMap<String, String> {
+        let mut headers = self.headers.clone();
+        let has_content_type = headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case("content-type"));
+
+        match &self.body {
+            RequestBody::Multipart(parts) => {
+                headers.retain(|name, _| !name.eq_ignore_ascii_case("content-type"));
+                headers.insert(
+                    "Content-Type".to_owned(),
+                    format!(
+                        "multipart/form-data; boundary={}",
+                        multipart_boundary(parts)
+                    ),
+                );
+            }
+            RequestBody::Form(_) if !has_content_type => {
+                headers.insert(
+                    "Content-Type".to_owned(),
+                    "application/x-www-form-urlencoded".to_owned(),
+                );
+            }
+            _ => {}
+        }
+
+        headers
+    }
+}
+
+/// Picks a boundary that doesn't show up in any of `parts`.
+fn multipart_boundary(parts: &[MultipartPart]) -> String {
+    let contains = |haystack: &[u8], needle: &[u8]| {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    };
+
+    let mut boundary = "----RakuyomiFormBoundary".to_owned();
+    while parts.iter().any(|part| match part {
+        MultipartPart::Field { value, .. } => contains(value.as_bytes(), boundary.as_bytes()),
+        MultipartPart::File { data, .. } => contains(data, boundary.as_bytes()),
+    }) {
+        boundary.push('x');
+    }
+
+    boundary
+}
+
+fn encode_multipart(parts: &[MultipartPart], boundary: &str) -> Vec<u8> {
+    // Like browsers do, percent-encode the characters that would break the quoted strings.
+    let quote = |value: &str| {
+        value
+            .replace('"', "%22")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    };
+
+    let mut body = Vec::new();
+    for part in parts {
+        body.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+        match part {
+            MultipartPart::Field { name, value } => {
+                body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                        quote(name)
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(value.as_bytes());
+            }
+            MultipartPart::File {
+                name,
+                file_name,
+                content_type,
+                data,
+            } => {
+                body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n",
+                        quote(name),
+                        quote(file_name)
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(
+                    format!(
+                        "Content-Type: {}\r\n\r\n",
+                        content_type
+                            .as_deref()
+                            .unwrap_or("application/octet-stream")
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(data);
+            }
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+    body
+}
+
 #[derive(Debug)]
 pub struct ResponseData {
     pub url: Url,
@@ -119,6 +307,49 @@ pub enum RequestState {
     Closed,
 }
 
+/// Bytes of request and response bodies a source moved since it was loaded. They're atomics
+/// behind an `Arc` so that they can be read while the source is busy with a WASM call.
+#[derive(Debug)]
+pub struct BandwidthCounters {
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    reset_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct BandwidthStats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// When counting started, as a unix timestamp.
+    pub reset_at: i64,
+}
+
+impl Default for BandwidthCounters {
+    fn default() -> Self {
+        Self {
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            reset_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+impl BandwidthCounters {
+    pub fn record(&self, sent: usize, received: usize) {
+        self.bytes_sent.fetch_add(sent as u64, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(received as u64, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> BandwidthStats {
+        BandwidthStats {
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            reset_at: self.reset_at,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RateLimit {
     pub permits: usize,
@@ -195,6 +426,19 @@ pub struct Canvas(pub(crate) DrawTarget);
 unsafe impl Send for Canvas {}
 unsafe impl Sync for Canvas {}
 
+/// The pixels of a freed canvas, waiting in `WasmStore::canvas_pool` to back a new one.
+struct PooledCanvas {
+    width: i32,
+    height: i32,
+    pixels: Vec<u32>,
+}
+
+impl PooledCanvas {
+    fn bytes(&self) -> usize {
+        self.pixels.len() * std::mem::size_of::<u32>()
+    }
+}
+
 #[cfg(not(feature = "all"))]
 pub struct WebView {
     pub id: usize,
@@ -275,16 +519,29 @@ pub struct WasmStore {
     // FIXME this probably should be source-specific, and not a copy of all settigns
     // we do rely on the `languages` global setting right now, so maybe this is really needed? idk
     pub settings: Settings,
-    std_descriptor_pointer: usize,
-    std_descriptors: HashMap<usize, ValueRef>,
+    // Every kind of descriptor (std values, requests, canvases...) shares this index space.
+    // A slot of a descriptor that is not a std value stays `None`.
+    std_descriptors: Vec<Option<ValueRef>>,
+    // Freed descriptors, reused oldest first so that a stale descriptor kept by a source is
+    // not immediately handed out again.
+    free_descriptors: VecDeque<usize>,
     std_references: HashMap<usize, Vec<usize>>,
     std_strs_encode: HashSet<usize>,
 
     requests: HashMap<usize, RequestState>,
+    // net cookies
+    pub cookie_jar: CookieJar,
     // net rate limit
     rate_limit: Option<RateLimit>,
+    // net body bytes, shared with the `Source` so they're readable while it's busy
+    pub bandwidth: Arc<BandwidthCounters>,
+    // responses prefetched from preload hints, shared with the `Source` like `bandwidth`
+    pub preload_cache: Arc<PreloadCache>,
     // canvas
     canvass: HashMap<usize, Canvas>,
+    // Pixel buffers of canvases freed by the source, oldest first, reused by `create_canvas`
+    // when the size matches.
+    canvas_pool: Vec<PooledCanvas>,
     // image
     images: HashMap<usize, ImageData>,
     // font
@@ -292,9 +549,16 @@ pub struct WasmStore {
     fonts_online: HashMap<usize, Vec<u8>>,
     // js context
     jscontexts: HashMap<usize, JsContext>,
+    /// Descriptor of the context every `context_create` of this source shares, created on
+    /// first use and kept alive until the store is dropped.
+    shared_js_context: Option<usize>,
     // html and gc html
     htmls: HashMap<usize, Html>,
     html_references: HashMap<usize, usize>,
+    // parsed html selectors, keyed by the selector string the source gave
+    selector_cache: HashMap<String, Arc<Matcher>>,
+    // cached selectors from oldest to newest
+    selector_order: VecDeque<String>,
     // webview
     #[cfg(not(feature = "all"))]
     webviews: HashMap<usize, WebView>,
@@ -306,8 +570,8 @@ impl std::fmt::Debug for WasmStore {
             .field("context", &self.context)
             .field("source_settings", &self.source_settings)
             .field("settings", &self.settings)
-            .field("std_descriptor_pointer", &self.std_descriptor_pointer)
             .field("std_descriptors", &self.std_descriptors)
+            .field("free_descriptors", &self.free_descriptors)
             .field("std_references", &self.std_references)
             .field("requests", &self.requests)
             // ignore non-debug fields
@@ -327,14 +591,18 @@ impl WasmStore {
 
             settings: Settings::default(),
 
-            std_descriptor_pointer: 0,
-            std_descriptors: HashMap::new(),
+            std_descriptors: Vec::new(),
+            free_descriptors: VecDeque::new(),
             std_references: HashMap::new(),
             std_strs_encode: HashSet::new(),
             requests: HashMap::new(),
+            cookie_jar: CookieJar::default(),
             rate_limit: None,
+            bandwidth: Arc::default(),
+            preload_cache: Arc::default(),
 
             canvass: HashMap::new(),
+            canvas_pool: Vec::new(),
 
             images: HashMap::new(),
 
@@ -342,9 +610,12 @@ impl WasmStore {
             fonts_online: HashMap::new(),
 
             jscontexts: HashMap::new(),
+            shared_js_context: None,
 
             htmls: HashMap::new(),
             html_references: HashMap::new(),
+            selector_cache: HashMap::new(),
+            selector_order: VecDeque::new(),
 
             #[cfg(not(feature = "all"))]
             webviews: HashMap::new(),
@@ -362,7 +633,7 @@ impl WasmStore {
     }
 
     pub fn get_std_value(&self, descriptor: usize) -> Option<ValueRef> {
-        self.std_descriptors.get(&descriptor).cloned()
+        self.std_descriptors.get(descriptor).cloned().flatten()
     }
 
     pub fn take_std_value(&mut self, descriptor: usize) {
@@ -371,12 +642,27 @@ impl WasmStore {
 
         self.free_std_reference(descriptor);
 
-        if let Some(value) = self.std_descriptors.remove(&descriptor) {
+        if let Some(value) = self
+            .std_descriptors
+            .get_mut(descriptor)
+            .and_then(Option::take)
+        {
             if let Ok(elements) = value.try_unwrap_html_elements_ref() {
                 for element in elements {
                     self.free_reference_html(element);
                 }
             }
+            // The image a response points to belongs to it unless the source manages it.
+            if let Value::NextImageResponse(response) = &*value {
+                if !response.image.externally_managed {
+                    if let Ok(rid) = usize::try_from(response.image.rid) {
+                        if self.images.remove(&rid).is_some() {
+                            self.release_descriptor(rid);
+                        }
+                    }
+                }
+            }
+            self.release_descriptor(descriptor);
 
             return;
         }
@@ -384,23 +670,38 @@ impl WasmStore {
         macro_rules! try_remove {
             ($map:expr) => {
                 if $map.remove(&descriptor).is_some() {
+                    self.release_descriptor(descriptor);
                     return; // stop searching
                 }
             };
         }
 
         try_remove!(self.requests);
-        try_remove!(self.canvass);
+        if self.free_canvas(descriptor) {
+            return;
+        }
         try_remove!(self.images);
         try_remove!(self.fonts);
         try_remove!(self.fonts_online);
-        try_remove!(self.jscontexts);
+        // The shared context outlives the value handed to the source.
+        if self.shared_js_context != Some(descriptor) {
+            try_remove!(self.jscontexts);
+        }
         #[cfg(not(feature = "all"))]
         if let Some(webview) = self.webviews.remove(&descriptor) {
             webview.destroy();
+            self.release_descriptor(descriptor);
         }
     }
 
+    /// Takes the value out of `descriptor` so the caller can modify it and put it back with
+    /// [`Self::set_std_value`]. Unlike [`Self::take_std_value`], the descriptor stays
+    /// allocated, so nothing else is handed it in the meantime. Only meant for objects and
+    /// arrays, which hold no references of their own.
+    pub fn detach_std_value(&mut self, descriptor: usize) -> Option<ValueRef> {
+        self.std_descriptors.get_mut(descriptor)?.take()
+    }
+
     pub fn mark_str_encode(&mut self, pointer: usize) {
         self.std_strs_encode.insert(pointer);
     }
@@ -416,12 +717,15 @@ impl WasmStore {
             }
         }
 
-        self.std_descriptors.insert(descriptor, data);
+        if descriptor >= self.std_descriptors.len() {
+            self.std_descriptors.resize(descriptor + 1, None);
+        }
+        self.std_descriptors[descriptor] = Some(data);
     }
 
     pub fn store_std_value(&mut self, data: ValueRef, _from: Option<usize>) -> usize {
-        let pointer = self.increase_and_get_std_desciptor_pointer();
-        self.std_descriptors.insert(pointer, data);
+        let pointer = self.allocate_descriptor();
+        self.std_descriptors[pointer] = Some(data);
 
         // if let Some(from) = from {
         //     self.add_std_reference(from, pointer);
@@ -450,7 +754,7 @@ impl WasmStore {
     // TODO change this into a request descriptor
     pub fn create_request(&mut self) -> usize {
         let new_request_state = RequestState::Building(RequestBuildingState::default());
-        let idx = self.increase_and_get_std_desciptor_pointer();
+        let idx = self.allocate_descriptor();
 
         self.requests.insert(idx, new_request_state);
 
@@ -462,7 +766,10 @@ impl WasmStore {
     }
 
     pub fn remove_request(&mut self, descriptor: usize) -> Option<RequestState> {
-        self.requests.remove(&descriptor)
+        let request = self.requests.remove(&descriptor)?;
+        self.release_descriptor(descriptor);
+
+        Some(request)
     }
 
     pub fn set_rate_limit(&mut self, permits: Option<usize>, period_secs: Option<usize>) {
@@ -507,17 +814,39 @@ impl WasmStore {
         }
     }
 
-    fn increase_and_get_std_desciptor_pointer(&mut self) -> usize {
-        let idx = self.std_descriptor_pointer;
-        self.std_descriptor_pointer += 1;
+    fn allocate_descriptor(&mut self) -> usize {
+        if let Some(idx) = self.free_descriptors.pop_front() {
+            return idx;
+        }
 
-        idx
+        self.std_descriptors.push(None);
+
+        self.std_descriptors.len() - 1
+    }
+
+    /// Must only be called once the descriptor was removed from the map holding it.
+    fn release_descriptor(&mut self, descriptor: usize) {
+        self.free_descriptors.push_back(descriptor);
     }
 
     // canvas.rs
     pub fn create_canvas(&mut self, width: f32, height: f32) -> usize {
-        let new_canvas_state = DrawTarget::new(width as i32, height as i32);
-        let idx = self.increase_and_get_std_desciptor_pointer();
+        let (width, height) = (width as i32, height as i32);
+        let new_canvas_state = match self
+            .canvas_pool
+            .iter()
+            .position(|canvas| canvas.width == width && canvas.height == height)
+        {
+            Some(position) => {
+                let mut pixels = self.canvas_pool.remove(position).pixels;
+                pixels.fill(0);
+                // A fresh target over the old pixels, so no transform, clip or layer of the
+                // previous canvas leaks into the new one.
+                DrawTarget::from_backing(width, height, pixels)
+            }
+            None => DrawTarget::new(width, height),
+        };
+        let idx = self.allocate_descriptor();
 
         self.canvass.insert(idx, Canvas(new_canvas_state));
 
@@ -526,47 +855,41 @@ impl WasmStore {
     pub fn get_mut_canvas(&mut self, descriptor: usize) -> Option<&mut Canvas> {
         self.canvass.get_mut(&descriptor)
     }
-    pub fn create_image(&mut self, data: &[u8]) -> Option<usize> {
-        if let Some(data) = super::decode_image::decode_image_fast(data) {
-            let data = data
-                .map_err(|err| eprintln!("failed to load image with faster {err}"))
-                .ok()?;
+    /// Releases the canvas behind `descriptor`, keeping its pixels around for a later
+    /// `create_canvas` of the same size. The oldest pooled pixels are dropped once the pool
+    /// holds more than `CANVAS_POOL_MAX_BYTES`. Returns whether `descriptor` was a canvas.
+    pub fn free_canvas(&mut self, descriptor: usize) -> bool {
+        let Some(Canvas(canvas)) = self.canvass.remove(&descriptor) else {
+            return false;
+        };
+        self.release_descriptor(descriptor);
 
-            return Some(self.set_image_data(data));
+        let canvas = PooledCanvas {
+            width: canvas.width(),
+            height: canvas.height(),
+            pixels: canvas.into_inner(),
+        };
+        if canvas.bytes() > CANVAS_POOL_MAX_BYTES {
+            return true;
         }
-        // fallback with image
-
-        let cursor = Cursor::new(data);
-        let rgba_img = ImageReader::new(cursor)
-            .with_guessed_format()
-            .ok()
-            .and_then(|r| r.decode().ok())
-            .map(|img| img.to_rgba8())?;
-
-        let width = rgba_img.width() as i32;
-        let height = rgba_img.height() as i32;
-        let mut pixels: Vec<u32> = Vec::with_capacity((width * height) as usize);
-
-        for pixel in rgba_img.pixels() {
-            let [r, g, b, a] = pixel.0;
-
-            let val = ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
-
-            pixels.push(val);
+        let mut pooled_bytes: usize = self.canvas_pool.iter().map(PooledCanvas::bytes).sum();
+        while pooled_bytes + canvas.bytes() > CANVAS_POOL_MAX_BYTES {
+            pooled_bytes -= self.canvas_pool.remove(0).bytes();
         }
-        let image = ImageData {
-            data: pixels,
-            width,
-            height,
-        };
+        self.canvas_pool.push(canvas);
+
+        true
+    }
+    pub fn create_image(&mut self, data: &[u8]) -> Result<usize, DecodeImageError> {
+        let image = super::decode_image::decode_image(data)?;
 
-        Some(self.set_image_data(image))
+        Ok(self.set_image_data(image))
     }
     pub fn get_image(&mut self, descriptor: usize) -> Option<&ImageData> {
         self.images.get(&descriptor)
     }
     pub fn set_image_data(&mut self, image: ImageData) -> usize {
-        let idx = self.increase_and_get_std_desciptor_pointer();
+        let idx = self.allocate_descriptor();
 
         self.images.insert(idx, image);
 
@@ -581,7 +904,7 @@ impl WasmStore {
             .ok()
             .and_then(|h| h.load().ok())?;
 
-        let idx = self.increase_and_get_std_desciptor_pointer();
+        let idx = self.allocate_descriptor();
 
         self.fonts
             .insert(idx, (font.family_name(), font.properties()));
@@ -601,25 +924,43 @@ impl WasmStore {
             .and_then(|h| h.load().ok())
     }
     pub fn set_font_online(&mut self, buffer: &[u8]) -> usize {
-        let idx = self.increase_and_get_std_desciptor_pointer();
+        let idx = self.allocate_descriptor();
 
         self.fonts_online.insert(idx, buffer.to_vec());
 
         idx
     }
+    /// Returns the JS context shared by every call of this source, creating it the first
+    /// time, as setting up a `boa_engine::Context` is slow.
     pub fn create_js_context(&mut self) -> usize {
-        let idx = self.increase_and_get_std_desciptor_pointer();
+        if let Some(idx) = self.shared_js_context {
+            return idx;
+        }
+
+        let idx = self.allocate_descriptor();
 
         self.jscontexts
             .insert(idx, JsContext(boa_engine::Context::default()));
+        self.shared_js_context = Some(idx);
 
         idx
     }
+    /// Replaces the context at `pointer` with a fresh one, dropping everything its scripts
+    /// defined. Returns `false` if there is no such context.
+    pub fn reset_js_context(&mut self, pointer: usize) -> bool {
+        let Some(context) = self.jscontexts.get_mut(&pointer) else {
+            return false;
+        };
+
+        *context = JsContext(boa_engine::Context::default());
+
+        true
+    }
     pub fn get_js_context(&mut self, pointer: usize) -> Option<&mut JsContext> {
         self.jscontexts.get_mut(&pointer)
     }
     pub fn set_html(&mut self, html: Document) -> usize {
-        let idx = self.increase_and_get_std_desciptor_pointer();
+        let idx = self.allocate_descriptor();
 
         self.htmls.insert(idx, Html(html));
 
@@ -628,6 +969,24 @@ impl WasmStore {
     pub fn get_html(&mut self, idx: usize) -> Option<&Html> {
         self.htmls.get(&idx)
     }
+    pub fn get_cached_selector(&self, selector: &str) -> Option<Arc<Matcher>> {
+        self.selector_cache.get(selector).cloned()
+    }
+    pub fn cache_selector(&mut self, selector: String, matcher: Arc<Matcher>) {
+        if self.selector_cache.contains_key(&selector) {
+            self.selector_cache.insert(selector, matcher);
+            return;
+        }
+
+        if self.selector_cache.len() >= SELECTOR_CACHE_MAX_ENTRIES {
+            if let Some(oldest) = self.selector_order.pop_front() {
+                self.selector_cache.remove(&oldest);
+            }
+        }
+
+        self.selector_order.push_back(selector.clone());
+        self.selector_cache.insert(selector, matcher);
+    }
     // pub fn get_mut_html(&mut self, idx: usize) -> Option<&mut Html> {
     //     self.htmls.get_mut(&idx)
     // }
@@ -650,8 +1009,10 @@ impl WasmStore {
             }
 
             if *count == 0 {
-                self.htmls.remove(&key);
                 self.html_references.remove(&key);
+                if self.htmls.remove(&key).is_some() {
+                    self.release_descriptor(key);
+                }
             }
         }
     }
@@ -659,7 +1020,7 @@ impl WasmStore {
     // webview
     #[cfg(not(feature = "all"))]
     pub fn create_webview(&mut self) -> usize {
-        let idx = self.increase_and_get_std_desciptor_pointer();
+        let idx = self.allocate_descriptor();
 
         self.webviews.insert(idx, WebView { id: idx });
 
@@ -714,15 +1075,15 @@ impl TryFrom<&RequestBuildingState> for BlockingRequest {
                 .ok_or(anyhow!("expected to have an URL"))?,
         );
 
-        for (k, v) in value.headers.iter() {
+        for (k, v) in value.encoded_headers().iter() {
             request.headers_mut().append(
                 HeaderName::from_bytes(k.clone().as_bytes())?,
                 HeaderValue::from_str(v.clone().as_str())?,
             );
         }
 
-        if let Some(body) = &value.body {
-            *request.body_mut() = Some(body.clone().into());
+        if let Some(body) = value.encoded_body() {
+            *request.body_mut() = Some(body.into());
         }
 
         if let Some(timeout) = &value.timeout {
@@ -762,15 +1123,15 @@ impl TryFrom<&RequestBuildingState> for Request {
                     .ok_or(anyhow!("expected to have an URL"))?,
             );
 
-            for (k, v) in value.headers.iter() {
+            for (k, v) in value.encoded_headers().iter() {
                 request.headers_mut().append(
                     HeaderName::from_bytes(k.clone().as_bytes())?,
                     HeaderValue::from_str(v.as_str())?,
                 );
             }
 
-            if let Some(body) = &value.body {
-                *request.body_mut() = Some(body.clone().into());
+            if let Some(body) = value.encoded_body() {
+                *request.body_mut() = Some(body.into());
             }
 
             if let Some(timeout) = &value.timeout {
@@ -810,8 +1171,332 @@ impl From<SourceSettingValue> for Value {
             SourceSettingValue::Int(v) => Value::Int(v),
             SourceSettingValue::Float(v) => Value::Float(v),
             SourceSettingValue::String(v) => Value::String(v),
+            SourceSettingValue::SecureString(v) => Value::String(v.0),
             SourceSettingValue::Vec(v) => Value::Array(v.into_iter().map(Value::String).collect()),
             SourceSettingValue::Null => Value::Null,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+    use crate::{
+        settings::Settings, source::source_settings::SourceSettings, source_manager::SourceManager,
+    };
+
+    use dom_query::Matcher;
+
+    use super::{
+        ImageData, ImageRef, ImageRequest, ImageResponse, MultipartPart, RequestBuildingState,
+        Value, WasmStore, SELECTOR_CACHE_MAX_ENTRIES,
+    };
+
+    fn store() -> WasmStore {
+        let source_settings = SourceSettings::new(
+            "test".to_owned(),
+            &[],
+            &HashMap::new(),
+            &Arc::new(tokio::sync::Mutex::new(SourceManager::new(
+                PathBuf::new(),
+                HashMap::new(),
+                Settings::default(),
+            ))),
+        )
+        .unwrap();
+
+        WasmStore::default(source_settings)
+    }
+
+    #[test]
+    fn a_full_selector_cache_drops_only_its_oldest_selector() {
+        let mut store = store();
+        let matcher = Arc::new(Matcher::new("p").unwrap());
+
+        for i in 0..=SELECTOR_CACHE_MAX_ENTRIES {
+            store.cache_selector(format!("p.c{i}"), matcher.clone());
+        }
+
+        assert!(store.get_cached_selector("p.c0").is_none());
+        assert!(store.get_cached_selector("p.c1").is_some());
+        assert!(store
+            .get_cached_selector(&format!("p.c{SELECTOR_CACHE_MAX_ENTRIES}"))
+            .is_some());
+        assert_eq!(store.selector_cache.len(), SELECTOR_CACHE_MAX_ENTRIES);
+        assert_eq!(store.selector_order.len(), SELECTOR_CACHE_MAX_ENTRIES);
+    }
+
+    #[test]
+    fn adds_up_bandwidth_of_every_request() {
+        let store = store();
+        store.bandwidth.record(10, 100);
+        store.bandwidth.record(0, 50);
+
+        let stats = store.bandwidth.stats();
+        assert_eq!((stats.bytes_sent, stats.bytes_received), (10, 150));
+    }
+
+    #[test]
+    fn reuses_freed_canvases_of_the_same_size() {
+        let mut store = store();
+        let first = store.create_canvas(4.0, 4.0);
+        store.get_mut_canvas(first).unwrap().0.get_data_mut()[0] = 0xffffffff;
+        store.remove_std_value(first);
+
+        assert!(store.get_mut_canvas(first).is_none());
+        assert_eq!(store.canvas_pool.len(), 1);
+
+        let other_size = store.create_canvas(2.0, 2.0);
+        assert_eq!(store.canvas_pool.len(), 1);
+
+        let reused = store.create_canvas(4.0, 4.0);
+        assert!(store.canvas_pool.is_empty());
+        assert!(store
+            .get_mut_canvas(reused)
+            .unwrap()
+            .0
+            .get_data()
+            .iter()
+            .all(|&pixel| pixel == 0));
+
+        store.remove_std_value(other_size);
+        store.remove_std_value(reused);
+        assert_eq!(store.canvas_pool.len(), 2);
+    }
+
+    #[test]
+    fn caps_the_canvas_pool_by_bytes() {
+        let mut store = store();
+        // 1024x1024 canvases take 4 MiB each.
+        let per_canvas = 1024 * 1024 * 4;
+        let canvases: Vec<_> = (0..super::CANVAS_POOL_MAX_BYTES / per_canvas + 4)
+            .map(|_| store.create_canvas(1024.0, 1024.0))
+            .collect();
+        for canvas in canvases {
+            assert!(store.free_canvas(canvas));
+        }
+
+        assert_eq!(
+            store.canvas_pool.len(),
+            super::CANVAS_POOL_MAX_BYTES / per_canvas
+        );
+
+        // A canvas bigger than the whole pool is never kept.
+        let huge = store.create_canvas(4096.0, 4097.0);
+        assert!(store.free_canvas(huge));
+        assert!(store
+            .canvas_pool
+            .iter()
+            .all(|canvas| canvas.width == 1024 && canvas.height == 1024));
+    }
+
+    #[test]
+    fn reused_canvases_start_without_transform_or_clip() {
+        let mut store = store();
+        let first = store.create_canvas(4.0, 4.0);
+        {
+            let canvas = &mut store.get_mut_canvas(first).unwrap().0;
+            canvas.set_transform(&raqote::Transform::translation(2.0, 2.0));
+            canvas.push_clip_rect(raqote::IntRect::new(
+                raqote::IntPoint::new(0, 0),
+                raqote::IntPoint::new(1, 1),
+            ));
+            canvas.push_layer(0.5);
+        }
+        store.free_canvas(first);
+
+        let reused = store.create_canvas(4.0, 4.0);
+        let canvas = &mut store.get_mut_canvas(reused).unwrap().0;
+        assert_eq!(*canvas.get_transform(), raqote::Transform::identity());
+        canvas.fill_rect(
+            0.0,
+            0.0,
+            4.0,
+            4.0,
+            &raqote::Source::Solid(raqote::SolidSource::from_unpremultiplied_argb(
+                0xff, 0xff, 0xff, 0xff,
+            )),
+            &raqote::DrawOptions::new(),
+        );
+        assert!(canvas.get_data().iter().all(|&pixel| pixel == 0xffffffff));
+    }
+
+    #[test]
+    fn encodes_multipart_parts_into_the_body() {
+        let mut request = RequestBuildingState::default();
+        request
+            .headers
+            .insert("content-type".to_owned(), "text/plain".to_owned());
+
+        request.push_multipart_part(MultipartPart::Field {
+            name: "q".to_owned(),
+            value: "cover".to_owned(),
+        });
+        request.push_multipart_part(MultipartPart::File {
+            name: "image".to_owned(),
+            file_name: "a\"b.png".to_owned(),
+            content_type: Some("image/png".to_owned()),
+            data: vec![1, 2, 3],
+        });
+
+        let boundary = "----RakuyomiFormBoundary";
+        let mut expected = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"q\"\r\n\r\ncover\r\n\
+             --{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"a%22b.png\"\r\n\
+             Content-Type: image/png\r\n\r\n"
+        )
+        .into_bytes();
+        expected.extend_from_slice(&[1, 2, 3]);
+        expected.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        assert_eq!(request.encoded_body(), Some(expected));
+        let headers = request.encoded_headers();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(
+            headers.get("Content-Type").map(String::as_str),
+            Some("multipart/form-data; boundary=----RakuyomiFormBoundary")
+        );
+    }
+
+    #[test]
+    fn multipart_boundary_avoids_part_contents() {
+        let mut request = RequestBuildingState::default();
+        request.push_multipart_part(MultipartPart::Field {
+            name: "q".to_owned(),
+            value: "----RakuyomiFormBoundary".to_owned(),
+        });
+
+        assert_eq!(
+            request
+                .encoded_headers()
+                .get("Content-Type")
+                .map(String::as_str),
+            Some("multipart/form-data; boundary=----RakuyomiFormBoundaryx")
+        );
+    }
+
+    #[test]
+    fn encodes_form_fields_into_the_body() {
+        let mut request = RequestBuildingState::default();
+        request.set_body(b"ignored".to_vec());
+        request.push_form_field("q".to_owned(), "one piece".to_owned());
+        request.push_form_field("page".to_owned(), "&2".to_owned());
+
+        assert_eq!(
+            request.encoded_body(),
+            Some(b"q=one+piece&page=%262".to_vec())
+        );
+        assert_eq!(
+            request
+                .encoded_headers()
+                .get("Content-Type")
+                .map(String::as_str),
+            Some("application/x-www-form-urlencoded")
+        );
+
+        request.headers.insert(
+            "content-type".to_owned(),
+            "application/x-www-form-urlencoded; charset=UTF-8".to_owned(),
+        );
+
+        let headers = request.encoded_headers();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(
+            headers.get("content-type").map(String::as_str),
+            Some("application/x-www-form-urlencoded; charset=UTF-8")
+        );
+    }
+
+    #[test]
+    fn starting_another_kind_of_body_replaces_the_previous_one() {
+        let mut request = RequestBuildingState::default();
+        request.push_multipart_part(MultipartPart::Field {
+            name: "q".to_owned(),
+            value: "cover".to_owned(),
+        });
+        request.push_form_field("q".to_owned(), "cover".to_owned());
+
+        assert_eq!(request.encoded_body(), Some(b"q=cover".to_vec()));
+        assert_eq!(
+            request
+                .encoded_headers()
+                .get("Content-Type")
+                .map(String::as_str),
+            Some("application/x-www-form-urlencoded")
+        );
+
+        request.set_body(b"raw".to_vec());
+        assert_eq!(request.encoded_body(), Some(b"raw".to_vec()));
+        assert!(request.encoded_headers().is_empty());
+    }
+
+    #[test]
+    fn reuses_freed_descriptors() {
+        let mut store = store();
+        let first = store.store_std_value(Value::from("a".to_owned()).into(), None);
+        let second = store.store_std_value(Value::from("b".to_owned()).into(), None);
+
+        store.take_std_value(first);
+        assert!(store.get_std_value(first).is_none());
+
+        let request = store.create_request();
+        assert_eq!(request, first);
+        assert!(store.get_std_value(second).is_some());
+
+        // Taking a descriptor twice must not hand it out twice.
+        store.take_std_value(first);
+        store.take_std_value(first);
+        let third = store.store_std_value(Value::from("c".to_owned()).into(), None);
+        let fourth = store.store_std_value(Value::from("d".to_owned()).into(), None);
+        assert_ne!(third, fourth);
+    }
+
+    #[test]
+    fn frees_the_image_of_a_taken_image_response() {
+        let mut store = store();
+        let image = |store: &mut WasmStore| {
+            store.set_image_data(ImageData {
+                data: vec![0],
+                width: 1,
+                height: 1,
+            })
+        };
+        let response = |rid: usize, externally_managed: bool| {
+            Value::NextImageResponse(ImageResponse {
+                code: 200,
+                headers: HashMap::new(),
+                request: ImageRequest {
+                    url: None,
+                    headers: HashMap::new(),
+                },
+                image: ImageRef {
+                    rid: rid as i32,
+                    externally_managed,
+                },
+            })
+        };
+
+        let owned = image(&mut store);
+        let descriptor = store.store_std_value(response(owned, false).into(), None);
+        store.take_std_value(descriptor);
+        assert!(store.get_image(owned).is_none());
+
+        let managed = image(&mut store);
+        let descriptor = store.store_std_value(response(managed, true).into(), None);
+        store.take_std_value(descriptor);
+        assert!(store.get_image(managed).is_some());
+    }
+
+    #[test]
+    fn shares_one_js_context() {
+        let mut store = store();
+        let context = store.create_js_context();
+        store.take_std_value(context);
+
+        assert_eq!(store.create_js_context(), context);
+        assert!(store.get_js_context(context).is_some());
+        assert!(store.reset_js_context(context));
+        assert!(!store.reset_js_context(context + 1));
+    }
+}
@@ -1,7 +1,14 @@
-use std::{collections::HashMap, fs, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 use tokio::sync::Mutex;
 
 use anyhow::{Context, Result};
+use log::info;
 
 use crate::{
     model::SourceId,
@@ -10,6 +17,24 @@ use crate::{
     source_collection::SourceCollection,
 };
 
+/// What a source file looked like when it was loaded, to tell whether it changed since.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+
+        Some(Self {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct SourceManager {
     sources_folder: PathBuf,
@@ -17,6 +42,8 @@ pub struct SourceManager {
     pub settings: Settings,
     #[cfg(not(feature = "all"))]
     pub file_sources: HashMap<String, String>,
+    /// The source each file was last loaded as.
+    loaded_files: HashMap<PathBuf, (SourceId, FileStamp)>,
 }
 
 impl SourceManager {
@@ -31,6 +58,7 @@ impl SourceManager {
             settings,
             #[cfg(not(feature = "all"))]
             file_sources: HashMap::new(),
+            loaded_files: HashMap::new(),
         }
     }
 
@@ -43,6 +71,7 @@ impl SourceManager {
             settings,
             #[cfg(not(feature = "all"))]
             file_sources: HashMap::new(),
+            loaded_files: HashMap::new(),
         })
     }
 
@@ -60,6 +89,7 @@ impl SourceManager {
 
         let source = Source::from_aix_file(&target_path, self, arc_manager)?;
         self.sources_by_id.insert(id.clone(), source);
+        self.mark_loaded(&target_path, id);
         #[cfg(not(feature = "all"))]
         self.file_sources.insert(
             id.value().to_owned(),
@@ -74,12 +104,69 @@ impl SourceManager {
         fs::remove_file(&source_path)?;
 
         self.sources_by_id.remove(&id.clone());
+        self.loaded_files.remove(&source_path);
         #[cfg(not(feature = "all"))]
         self.file_sources.remove(id.value());
 
         Ok(())
     }
 
+    /// Loads (or reloads) a single source from `path`, replacing only the entry with the
+    /// same ID and leaving every other loaded source untouched.
+    pub fn reload_source(
+        &mut self,
+        path: &Path,
+        arc_manager: &Arc<Mutex<SourceManager>>,
+    ) -> Result<SourceId> {
+        let source = Source::from_aix_file(path, self, arc_manager)?;
+        let id = SourceId::new(source.manifest().info.id.clone());
+
+        info!(
+            "Reloaded source {} from {} using the {} SDK",
+            id.value(),
+            path.display(),
+            if source.is_next_sdk() {
+                "next"
+            } else {
+                "legacy"
+            }
+        );
+
+        #[cfg(not(feature = "all"))]
+        self.file_sources
+            .insert(id.value().to_owned(), path.to_string_lossy().to_string());
+        self.sources_by_id.insert(id.clone(), source);
+        self.mark_loaded(path, &id);
+
+        Ok(id)
+    }
+
+    /// Whether the source file at `path` is loaded and hasn't changed since, in which case
+    /// reloading it would change nothing.
+    pub fn is_up_to_date(&self, path: &Path) -> bool {
+        self.loaded_files.get(path).is_some_and(|(id, stamp)| {
+            self.sources_by_id.contains_key(id) && FileStamp::of(path).as_ref() == Some(stamp)
+        })
+    }
+
+    fn mark_loaded(&mut self, path: &Path, id: &SourceId) {
+        match FileStamp::of(path) {
+            Some(stamp) => self
+                .loaded_files
+                .insert(path.to_owned(), (id.clone(), stamp)),
+            None => self.loaded_files.remove(path),
+        };
+    }
+
+    /// Removes a loaded source without deleting its file. Returns `false` if no source
+    /// with this ID was loaded.
+    pub fn unload_source(&mut self, id: &SourceId) -> bool {
+        #[cfg(not(feature = "all"))]
+        self.file_sources.remove(id.value());
+
+        self.sources_by_id.remove(id).is_some()
+    }
+
     pub fn update_settings(
         &mut self,
         settings: Settings,
@@ -119,6 +206,7 @@ impl SourceManager {
 
         #[cfg(not(feature = "all"))]
         self.file_sources.clear();
+        self.loaded_files.clear();
 
         let mut sources_by_id = HashMap::new();
         for entry in files.flatten() {
@@ -137,7 +225,9 @@ impl SourceManager {
                 path.as_path().to_string_lossy().to_string(),
             );
 
-            sources_by_id.insert(SourceId::new(source.manifest().info.id.clone()), source);
+            let id = SourceId::new(source.manifest().info.id.clone());
+            self.mark_loaded(&path, &id);
+            sources_by_id.insert(id, source);
         }
 
         Ok(sources_by_id)
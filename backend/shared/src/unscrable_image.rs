@@ -1,9 +1,46 @@
 use image::{DynamicImage, GenericImage, GenericImageView, ImageFormat};
 use serde::Deserialize;
 use std::io::Cursor;
+use thiserror::Error;
 
-fn load_image(image: Vec<u8>) -> Result<DynamicImage, String> {
-    image::load_from_memory(image.as_slice()).map_err(|e| format!("Failed to load image: {e}"))
+#[derive(Error, Debug)]
+pub enum UnscrambleError {
+    #[error("invalid blocks JSON: {0}")]
+    InvalidBlocks(#[from] serde_json::Error),
+    #[error("failed to load image: {0}")]
+    LoadImage(#[source] image::ImageError),
+    #[error("block {index} has a negative source offset ({sx}, {sy})")]
+    NegativeOffset { index: usize, sx: i32, sy: i32 },
+    #[error(
+        "source rectangle of block {index} ({x}, {y}, {width}x{height}) does not fit in the {image_width}x{image_height} image"
+    )]
+    SourceOutOfBounds {
+        index: usize,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        image_width: u32,
+        image_height: u32,
+    },
+    #[error(
+        "destination rectangle of block {index} ({x}, {y}, {width}x{height}) does not fit in the {image_width}x{image_height} image"
+    )]
+    DestinationOutOfBounds {
+        index: usize,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        image_width: u32,
+        image_height: u32,
+    },
+    #[error("failed to encode unscrambled image: {0}")]
+    Encode(#[source] image::ImageError),
+}
+
+fn load_image(image: Vec<u8>) -> Result<DynamicImage, UnscrambleError> {
+    image::load_from_memory(image.as_slice()).map_err(UnscrambleError::LoadImage)
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -25,58 +62,189 @@ pub struct Block {
 fn default_minus_one() -> i32 {
     -1
 }
-pub fn unscrable_image(image_data: Vec<u8>, blocks: Vec<Block>) -> Result<Vec<u8>, String> {
+
+/// A block with every default filled in, ready to be copied.
+struct Rect {
+    sx: u32,
+    sy: u32,
+    dx: u32,
+    dy: u32,
+    width: u32,
+    height: u32,
+}
+
+pub fn parse_blocks(blocks_json: &str) -> Result<Vec<Block>, UnscrambleError> {
+    Ok(serde_json::from_str(blocks_json)?)
+}
+
+/// Resolves the defaults of every block and checks that both its source and destination
+/// rectangles fit inside the image, before any pixel is copied.
+fn resolve_blocks(
+    blocks: Vec<Block>,
+    img_width: u32,
+    img_height: u32,
+) -> Result<Vec<Rect>, UnscrambleError> {
+    let fits = |x: u32, y: u32, width: u32, height: u32| {
+        x.checked_add(width).is_some_and(|end| end <= img_width)
+            && y.checked_add(height).is_some_and(|end| end <= img_height)
+    };
+
+    let mut current_x = 0u32;
+    let mut current_y = 0u32;
+
+    blocks
+        .into_iter()
+        .enumerate()
+        .map(|(index, block)| {
+            let width = if block.width == 0 {
+                img_width
+            } else {
+                block.width
+            };
+            let height = if block.height == 0 {
+                img_height
+            } else {
+                block.height
+            };
+
+            let sx = match block.sx {
+                -1 => Some(current_x),
+                sx => u32::try_from(sx).ok(),
+            };
+            let sy = match block.sy {
+                -1 => Some(current_y),
+                sy => u32::try_from(sy).ok(),
+            };
+            let (Some(sx), Some(sy)) = (sx, sy) else {
+                return Err(UnscrambleError::NegativeOffset {
+                    index,
+                    sx: block.sx,
+                    sy: block.sy,
+                });
+            };
+
+            if !fits(sx, sy, width, height) {
+                return Err(UnscrambleError::SourceOutOfBounds {
+                    index,
+                    x: sx,
+                    y: sy,
+                    width,
+                    height,
+                    image_width: img_width,
+                    image_height: img_height,
+                });
+            }
+            if !fits(block.dx, block.dy, width, height) {
+                return Err(UnscrambleError::DestinationOutOfBounds {
+                    index,
+                    x: block.dx,
+                    y: block.dy,
+                    width,
+                    height,
+                    image_width: img_width,
+                    image_height: img_height,
+                });
+            }
+
+            current_x = current_x.saturating_add(width);
+            current_y = current_y.saturating_add(height);
+
+            Ok(Rect {
+                sx,
+                sy,
+                dx: block.dx,
+                dy: block.dy,
+                width,
+                height,
+            })
+        })
+        .collect()
+}
+
+pub fn unscrable_image(
+    image_data: Vec<u8>,
+    blocks: Vec<Block>,
+) -> Result<Vec<u8>, UnscrambleError> {
     let img = load_image(image_data)?;
     let (img_width, img_height) = img.dimensions();
+    let rects = resolve_blocks(blocks, img_width, img_height)?;
+
     let mut dst = DynamicImage::new_rgba8(img_width, img_height);
+    for rect in rects {
+        let cropped = img.crop_imm(rect.sx, rect.sy, rect.width, rect.height);
+        // Cannot fail, the destination rectangle was checked in `resolve_blocks`.
+        let _ = dst.copy_from(&cropped, rect.dx, rect.dy);
+    }
 
-    let mut current_x = 0i32;
-    let mut current_y = 0i32;
+    let mut out = Vec::new();
+    dst.write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+        .map_err(UnscrambleError::Encode)?;
 
-    for mut block in blocks {
-        if block.width == 0 {
-            block.width = img_width;
-        }
-        if block.height == 0 {
-            block.height = img_height;
-        }
+    Ok(out)
+}
 
-        if block.sx == -1 {
-            block.sx = current_x;
-        }
-        if block.sy == -1 {
-            block.sy = current_y;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let sx = block.sx as u32;
-        let sy = block.sy as u32;
-        let dx = block.dx;
-        let dy = block.dy;
-        let width = block.width;
-        let height = block.height;
-
-        if sx.checked_add(width).map_or(true, |end| end > img_width)
-            || sy.checked_add(height).map_or(true, |end| end > img_height)
-        {
-            return Err("Source block out of bounds".to_string());
-        }
-        if dx.checked_add(width).map_or(true, |end| end > img_width)
-            || dy.checked_add(height).map_or(true, |end| end > img_height)
-        {
-            return Err("Destination block out of bounds".to_string());
+    fn block(sx: i32, sy: i32, dx: u32, dy: u32, width: u32, height: u32) -> Block {
+        Block {
+            sx,
+            sy,
+            dx,
+            dy,
+            width,
+            height,
         }
+    }
 
-        let cropped = img.crop_imm(sx, sy, width, height);
-        dst.copy_from(&cropped, dx, dy)
-            .map_err(|e| format!("copy_from error: {:?}", e))?;
+    #[test]
+    fn rejects_malformed_json() {
+        assert!(matches!(
+            parse_blocks("[{\"sx\": \"a\"}]"),
+            Err(UnscrambleError::InvalidBlocks(_))
+        ));
+    }
 
-        current_x += width as i32;
-        current_y += height as i32;
+    #[test]
+    fn rejects_source_out_of_bounds() {
+        let result = resolve_blocks(vec![block(5, 0, 0, 0, 10, 10)], 10, 10);
+
+        assert!(matches!(
+            result,
+            Err(UnscrambleError::SourceOutOfBounds { index: 0, .. })
+        ));
     }
 
-    let mut out = Vec::new();
-    dst.write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
-        .map_err(|e| e.to_string())?;
+    #[test]
+    fn rejects_destination_out_of_bounds() {
+        let result = resolve_blocks(
+            vec![block(0, 0, 0, 0, 5, 5), block(0, 0, 8, 8, 5, 5)],
+            10,
+            10,
+        );
 
-    Ok(out)
+        assert!(matches!(
+            result,
+            Err(UnscrambleError::DestinationOutOfBounds { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_negative_offsets() {
+        let result = resolve_blocks(vec![block(-3, 0, 0, 0, 5, 5)], 10, 10);
+
+        assert!(matches!(
+            result,
+            Err(UnscrambleError::NegativeOffset { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn fills_in_defaults() {
+        let rects = resolve_blocks(vec![block(-1, -1, 0, 0, 0, 0)], 10, 10).unwrap();
+
+        assert_eq!(rects.len(), 1);
+        assert_eq!((rects[0].width, rects[0].height), (10, 10));
+    }
 }
@@ -0,0 +1,176 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Error, Result};
+
+/// A selection of chapters by chapter number, written as one of:
+///
+/// - `{n}`: the chapter `n`,
+/// - `{start}-{end}`: every chapter from `start` to `end`, both included,
+/// - `{start}-`: every chapter from `start` onwards,
+/// - `-{end}`: every chapter up to `end`,
+/// - `all`: every chapter.
+///
+/// Chapter numbers may have decimals (e.g. `10.5`), and spaces around them are ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChapterRange {
+    All,
+    Single(f32),
+    Between(f32, f32),
+    From(f32),
+    UpTo(f32),
+}
+
+impl ChapterRange {
+    pub fn contains(&self, number: f32) -> bool {
+        match *self {
+            ChapterRange::All => true,
+            ChapterRange::Single(n) => number == n,
+            ChapterRange::Between(start, end) => (start..=end).contains(&number),
+            ChapterRange::From(start) => number >= start,
+            ChapterRange::UpTo(end) => number <= end,
+        }
+    }
+}
+
+impl FromStr for ChapterRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(ChapterRange::All);
+        }
+
+        let Some((start, end)) = s.split_once('-') else {
+            return Ok(ChapterRange::Single(parse_number(s)?));
+        };
+
+        let range = match (start.trim(), end.trim()) {
+            ("", "") => bail!("Invalid range {s}: it needs a start or an end"),
+            (start, "") => ChapterRange::From(parse_number(start)?),
+            ("", end) => ChapterRange::UpTo(parse_number(end)?),
+            (start, end) => {
+                let (start, end) = (parse_number(start)?, parse_number(end)?);
+                if start > end {
+                    bail!("Invalid range {s}: start > stop");
+                }
+
+                ChapterRange::Between(start, end)
+            }
+        };
+
+        Ok(range)
+    }
+}
+
+fn parse_number(s: &str) -> Result<f32> {
+    let number: f32 = s
+        .parse()
+        .with_context(|| format!("Invalid chapter number {s}"))?;
+    if !number.is_finite() {
+        bail!("Invalid chapter number {s}");
+    }
+
+    Ok(number)
+}
+
+/// Parses a comma-separated list of [`ChapterRange`]s. An empty list selects every chapter.
+pub fn parse_chapter_ranges(text: &str) -> Result<Vec<ChapterRange>> {
+    let ranges = text
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(ChapterRange::from_str)
+        .collect::<Result<Vec<_>>>()?;
+
+    if ranges.is_empty() {
+        return Ok(vec![ChapterRange::All]);
+    }
+
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> ChapterRange {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_single_chapters() {
+        assert_eq!(parse("5"), ChapterRange::Single(5.0));
+        assert_eq!(parse(" 10.5 "), ChapterRange::Single(10.5));
+    }
+
+    #[test]
+    fn parses_closed_ranges() {
+        assert_eq!(parse("1-10.5"), ChapterRange::Between(1.0, 10.5));
+        assert_eq!(parse("20 - 100"), ChapterRange::Between(20.0, 100.0));
+        assert_eq!(parse("3-3"), ChapterRange::Between(3.0, 3.0));
+    }
+
+    #[test]
+    fn parses_open_ended_ranges() {
+        assert_eq!(parse("5-"), ChapterRange::From(5.0));
+        assert_eq!(parse("-5"), ChapterRange::UpTo(5.0));
+        assert_eq!(parse(" - 2.5"), ChapterRange::UpTo(2.5));
+    }
+
+    #[test]
+    fn parses_all() {
+        assert_eq!(parse("all"), ChapterRange::All);
+        assert_eq!(parse(" ALL "), ChapterRange::All);
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        for text in [
+            "", "-", "abc", "1-abc", "abc-1", "10-5", "1-2-3", "inf", "NaN-",
+        ] {
+            assert!(
+                text.parse::<ChapterRange>().is_err(),
+                "{text:?} should fail"
+            );
+        }
+    }
+
+    #[test]
+    fn ranges_contain_their_chapters() {
+        assert!(ChapterRange::All.contains(0.0));
+
+        assert!(ChapterRange::Single(10.5).contains(10.5));
+        assert!(!ChapterRange::Single(10.5).contains(10.0));
+
+        let between = ChapterRange::Between(1.0, 10.5);
+        assert!(between.contains(1.0) && between.contains(10.5));
+        assert!(!between.contains(0.5) && !between.contains(11.0));
+
+        assert!(ChapterRange::From(5.0).contains(5.0));
+        assert!(ChapterRange::From(5.0).contains(500.0));
+        assert!(!ChapterRange::From(5.0).contains(4.5));
+
+        assert!(ChapterRange::UpTo(5.0).contains(5.0));
+        assert!(ChapterRange::UpTo(5.0).contains(0.0));
+        assert!(!ChapterRange::UpTo(5.0).contains(5.5));
+    }
+
+    #[test]
+    fn parses_comma_separated_lists() {
+        assert_eq!(
+            parse_chapter_ranges("1, 3-5, 10-, -0.5").unwrap(),
+            vec![
+                ChapterRange::Single(1.0),
+                ChapterRange::Between(3.0, 5.0),
+                ChapterRange::From(10.0),
+                ChapterRange::UpTo(0.5),
+            ]
+        );
+        assert_eq!(
+            parse_chapter_ranges(" , ").unwrap(),
+            vec![ChapterRange::All]
+        );
+        assert!(parse_chapter_ranges("1, 5-3").is_err());
+    }
+}
@@ -1,16 +1,21 @@
 use std::sync::atomic::Ordering;
-use std::{collections::HashSet, sync::atomic::AtomicBool};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::AtomicBool,
+};
 
 use anyhow::{bail, Result};
+use futures::{stream, StreamExt};
 use once_cell::sync::Lazy;
+use tokio::sync::Mutex;
 use tokio_util::sync::CancellationToken;
 
 use crate::settings::Settings;
 use crate::{
-    arima_light::{fit_arima_from_chapters, ArimaSpec},
+    arima_light::{fit_model_from_chapters, ArimaSpec},
     chapter_storage::ChapterStorage,
     database::Database,
-    model::MangaId,
+    model::{MangaId, SourceId},
     source::model::PublishingStatus,
     source_collection::SourceCollection,
     source_manager::SourceManager,
@@ -18,12 +23,18 @@ use crate::{
 };
 
 const MIN_UPDATE_INTERVAL: i64 = 3 * 3600; // 3 hours in seconds
+/// The cron checks a manga from the lower bound of the 90% prediction interval.
+const FORECAST_INTERVAL_ALPHA: f64 = 0.1;
 
+/// Checks every manga in the library for updates. Mangas from different sources are
+/// checked `concurrent_source_operations` sources at a time, while the mangas of one source
+/// are always checked one after another.
 pub async fn check_mangas_update(
     token: &CancellationToken,
     db: &Database,
     chapter_storage: &ChapterStorage,
     source_manager: &SourceManager,
+    concurrent_source_operations: usize,
 ) {
     let mangas_library = match db.get_manga_library_and_status().await {
         Ok(v) => v,
@@ -33,16 +44,122 @@ pub async fn check_mangas_update(
         }
     };
 
-    for (manga, status) in mangas_library {
-        if let Err(error) =
-            check_manga_update(token, db, chapter_storage, source_manager, &manga, &status).await
-        {
-            eprintln!("Warn[{}]: {}", manga.value(), error);
-            continue;
+    // Resume right after the last manga checked by a run that got interrupted, unless that
+    // manga has left the library since.
+    let resume_from = match db.get_check_update_cursor().await {
+        Ok(cursor) => cursor.and_then(|cursor| {
+            mangas_library
+                .iter()
+                .position(|(manga, _)| *manga == cursor)
+                .map(|position| position + 1)
+        }),
+        Err(e) => {
+            eprintln!("Failed to get check update cursor: {}", e);
+            None
         }
+    };
+    let pending = &mangas_library[resume_from.unwrap_or(0).min(mangas_library.len())..];
+
+    let mut by_source: Vec<Vec<usize>> = Vec::new();
+    let mut source_positions: HashMap<&SourceId, usize> = HashMap::new();
+    for (position, (manga, _)) in pending.iter().enumerate() {
+        let group = *source_positions
+            .entry(manga.source_id())
+            .or_insert_with(|| {
+                by_source.push(Vec::new());
+                by_source.len() - 1
+            });
+        by_source[group].push(position);
     }
+
+    let progress = Mutex::new(CheckProgress::new(pending.len()));
+    stream::iter(by_source)
+        .map(|positions| {
+            let progress = &progress;
+
+            async move {
+                let mut notified = false;
+                for &position in &positions {
+                    if token.is_cancelled() {
+                        return;
+                    }
+
+                    let (manga, status) = &pending[position];
+                    match check_manga_update(
+                        token,
+                        db,
+                        chapter_storage,
+                        source_manager,
+                        manga,
+                        status,
+                    )
+                    .await
+                    {
+                        Ok(added) => notified |= added,
+                        Err(error) => eprintln!("Warn[{}]: {}", manga.value(), error),
+                    }
+
+                    // Held while saving, so cursors are written in the order they advance.
+                    let mut progress = progress.lock().await;
+                    if let Some(checked) = progress.complete(position) {
+                        if let Err(e) = db.set_check_update_cursor(&pending[checked - 1].0).await {
+                            eprintln!("Failed to save check update cursor: {}", e);
+                        }
+                    }
+                }
+
+                if notified {
+                    let source_id = pending[positions[0]].0.source_id();
+                    handle_source_notifications(token, source_manager, source_id).await;
+                }
+            }
+        })
+        .buffer_unordered(concurrent_source_operations.max(1))
+        .collect::<Vec<_>>()
+        .await;
+
+    if token.is_cancelled() {
+        return;
+    }
+
+    if let Err(e) = db.clear_check_update_cursor().await {
+        eprintln!("Failed to clear check update cursor: {}", e);
+    }
+}
+
+/// Tracks which mangas of a run were checked. With several sources checked at once mangas
+/// finish out of order, so the saved cursor only moves past mangas that are all done.
+struct CheckProgress {
+    done: Vec<bool>,
+    /// How many mangas from the start are checked.
+    checked: usize,
 }
 
+impl CheckProgress {
+    fn new(len: usize) -> Self {
+        Self {
+            done: vec![false; len],
+            checked: 0,
+        }
+    }
+
+    /// Marks the manga at `position` as checked, returning the new number of mangas checked
+    /// from the start if it grew.
+    fn complete(&mut self, position: usize) -> Option<usize> {
+        self.done[position] = true;
+
+        let before = self.checked;
+        while self.done.get(self.checked).copied().unwrap_or(false) {
+            self.checked += 1;
+        }
+
+        (self.checked > before).then_some(self.checked)
+    }
+}
+
+/// Checks a single manga for new chapters, returning whether notifications were inserted
+/// for any. The source is told about them by [`handle_source_notifications`] once all of its
+/// mangas are checked.
 async fn check_manga_update(
     token: &CancellationToken,
     db: &Database,
@@ -51,20 +168,18 @@ async fn check_manga_update(
 
     manga: &MangaId,
     status: &PublishingStatus,
-) -> Result<()> {
-    let spec = ArimaSpec {
-        p: 1,
-        d: 1,
-        q: 1,
-        rolling_window: Some(30),
-        min_points: 6,
-    };
-
+) -> Result<bool> {
     if *status == PublishingStatus::Completed {
         db.delete_last_check_update_manga(manga).await?;
-        return Ok(());
+        return Ok(false);
     }
 
+    let spec = db
+        .find_manga_arima_spec(manga)
+        .await?
+        .map(ArimaSpec::from)
+        .unwrap_or_default();
+
     let Some(source) = source_manager.get_by_id(manga.source_id()) else {
         bail!(
             "Missing source {} – skip manga {}",
@@ -110,10 +225,14 @@ async fn check_manga_update(
     };
 
     if status != PublishingStatus::Completed {
-        let maybe_model = fit_arima_from_chapters(&new_chapters, spec);
+        let maybe_model = fit_model_from_chapters(&new_chapters, spec);
 
+        let mut interval = None;
         let next_ts_update = match maybe_model {
             Ok(model) => {
+                interval =
+                    model.forecast_interval_from_chapters(&new_chapters, FORECAST_INTERVAL_ALPHA);
+
                 let last_check_no_update = if added_chapters.is_empty() {
                     Some(chrono::Utc::now().timestamp())
                 } else {
@@ -134,13 +253,58 @@ async fn check_manga_update(
         }
         .unwrap_or_else(|| chrono::Utc::now().timestamp() + 24 * 60 * 60);
 
-        db.set_last_check_update_manga(manga, chrono::Utc::now().timestamp(), next_ts_update)
-            .await?;
+        // Never check earlier than the minimum interval allows, nor later than the forecast
+        // itself, which may have been pushed back past the last check.
+        let earliest = chrono::Utc::now().timestamp() + MIN_UPDATE_INTERVAL;
+        let interval = interval.map(|(lower, upper)| {
+            (
+                lower.clamp(earliest.min(next_ts_update), next_ts_update),
+                upper.max(next_ts_update),
+            )
+        });
+
+        db.set_last_check_update_manga(
+            manga,
+            chrono::Utc::now().timestamp(),
+            next_ts_update,
+            interval,
+        )
+        .await?;
     }
 
-    let _ = db.insert_notification(manga, &added_chapters).await;
+    let inserted = db.insert_notification(manga, &added_chapters).await.is_ok();
 
-    Ok(())
+    Ok(inserted && !added_chapters.is_empty())
+}
+
+/// Lets the source react to the notifications just inserted for its mangas, e.g. by calling
+/// a webhook.
+async fn handle_source_notifications(
+    token: &CancellationToken,
+    source_manager: &SourceManager,
+    source_id: &SourceId,
+) {
+    if token.is_cancelled() {
+        return;
+    }
+
+    let Some(source) = source_manager.get_by_id(source_id) else {
+        return;
+    };
+    if !source.1.features.has_handle_notification {
+        return;
+    }
+
+    if let Err(err) = source
+        .handle_notification_next(token.clone(), source_id.value().to_string())
+        .await
+    {
+        eprintln!(
+            "handle_notification failed for source {}: {:?}",
+            source_id.value(),
+            err
+        );
+    }
 }
 
 fn compute_new_chapters(
@@ -159,6 +323,11 @@ fn compute_new_chapters(
 // ===== cron =====
 static CRON_RUNNING: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
 
+/// Whether [`run_manga_cron`] is currently running.
+pub fn is_manga_cron_running() -> bool {
+    CRON_RUNNING.load(Ordering::SeqCst)
+}
+
 pub async fn run_manga_cron(
     db: &Database,
     chapter_storage: &ChapterStorage,
@@ -194,7 +363,14 @@ pub async fn run_manga_cron(
         if next_manga.is_none() {
             println!("Next manga not found. Re-check all mangas");
 
-            check_mangas_update(token, db, chapter_storage, source_manager).await;
+            check_mangas_update(
+                token,
+                db,
+                chapter_storage,
+                source_manager,
+                settings.concurrent_source_operations_or_default(),
+            )
+            .await;
             next_manga = match db.get_next_ts_arima_min(&skip_sources).await {
                 Ok(v) => v,
                 Err(e) => {
@@ -244,12 +420,13 @@ pub async fn run_manga_cron(
             }
         };
 
+        let mut notified_sources = HashSet::new();
         for (manga_id, status) in due_mangas {
             if skip_sources.contains(&manga_id.source_id().value().as_str()) {
                 continue;
             }
 
-            if let Err(err) = check_manga_update(
+            match check_manga_update(
                 token,
                 db,
                 chapter_storage,
@@ -259,13 +436,23 @@ pub async fn run_manga_cron(
             )
             .await
             {
-                eprintln!(
-                    "[ERROR] check_mangas_update failed for {}: {:?}",
-                    manga_id.value(),
-                    err
-                );
+                Ok(true) => {
+                    notified_sources.insert(manga_id.source_id().clone());
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    eprintln!(
+                        "[ERROR] check_mangas_update failed for {}: {:?}",
+                        manga_id.value(),
+                        err
+                    );
+                }
             }
         }
+
+        for source_id in &notified_sources {
+            handle_source_notifications(token, source_manager, source_id).await;
+        }
     }
 
     CRON_RUNNING.store(false, Ordering::SeqCst);
@@ -37,9 +37,23 @@ pub async fn fetch_manga_chapter(
         .await?
         .ok_or_else(|| anyhow!("Expected chapter to be in the database"))?;
 
+    // The source would only fail with some unhelpful error, but a copy downloaded before
+    // the chapter got locked can still be read.
+    let is_stored = chapter_storage
+        .get_stored_chapter(chapter_id, false)
+        .is_some()
+        || (use_ram
+            && chapter_storage
+                .get_stored_chapter(chapter_id, true)
+                .is_some());
+    if chapter.locked == Some(true) && !is_stored {
+        return Err(Error::ChapterLocked);
+    }
+
     match ensure_chapter_is_in_storage(
         token,
         chapter_storage,
+        database,
         source,
         &manga,
         &chapter,
@@ -59,6 +73,7 @@ pub async fn fetch_manga_chapter(
             return ensure_chapter_is_in_storage(
                 token,
                 chapter_storage,
+                database,
                 source,
                 &manga,
                 &chapter,
@@ -84,6 +99,8 @@ pub async fn fetch_manga_chapter(
 pub enum Error {
     #[error("an error occurred while downloading the chapter pages")]
     DownloadError(#[source] anyhow::Error),
+    #[error("chapter is locked")]
+    ChapterLocked,
     #[error("unknown error")]
     Other(#[from] anyhow::Error),
 }
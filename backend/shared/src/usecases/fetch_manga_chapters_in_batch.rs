@@ -68,6 +68,7 @@ pub fn fetch_manga_chapters_in_batch<'a>(
                 result = ensure_chapter_is_in_storage(
                     &cancellation_token,
                     chapter_storage,
+                    db,
                     source,
                     &manga,
                     &information,
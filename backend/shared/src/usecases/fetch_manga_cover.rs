@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::{database::Database, model::MangaId, source::Source, util::prepare_cover};
+
+/// Downloads the cover of the cached manga `id` as JPEG, building the request through
+/// `source` so that headers and signed URLs are fresh. Returns `None` if the manga isn't cached
+/// or has no cover.
+pub async fn fetch_manga_cover(
+    db: &Database,
+    source: &Source,
+    id: &MangaId,
+) -> Result<Option<Vec<u8>>> {
+    let Some(cover_url) = db
+        .find_cached_manga_information(id)
+        .await?
+        .and_then(|information| information.cover_url)
+    else {
+        return Ok(None);
+    };
+
+    let client = crate::tls::client_builder()
+        .timeout(Duration::from_secs(30))
+        .build()?;
+
+    prepare_cover(Some(cover_url), &client, source).await
+}
@@ -8,7 +8,7 @@ pub async fn find_orphan_or_read_files(
     db: &Database,
     chapter_storage: &ChapterStorage,
     invalid_mode: bool,
-) -> Result<Vec<PathBuf>> {
+) -> Result<Vec<(PathBuf, u64)>> {
     db.find_orphan_or_read_files(chapter_storage, invalid_mode)
         .await
 }
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    arima_light::{fit_model_from_chapters, timestamps_from_chapters, ArimaSpec, UpdateModel},
+    database::Database,
+    model::MangaId,
+};
+
+/// How much the update-time forecast of a manga can be trusted, judged from the
+/// standard deviation of the model residuals.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForecastConfidence {
+    /// Residuals within half a day.
+    High,
+    /// Residuals within three days.
+    Medium,
+    Low,
+}
+
+impl ForecastConfidence {
+    fn from_sigma2(sigma2: f64) -> Self {
+        let std_dev_hours = sigma2.max(0.0).sqrt() / 3600.0;
+
+        if std_dev_hours <= 12.0 {
+            Self::High
+        } else if std_dev_hours <= 72.0 {
+            Self::Medium
+        } else {
+            Self::Low
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ArimaDiagnostics {
+    pub spec: ArimaSpec,
+    /// The fitted model, or `None` when fitting failed (see `error`).
+    pub model: Option<UpdateModel>,
+    pub error: Option<String>,
+    /// The chapter upload timestamps (unix seconds, ascending) the model was fitted on.
+    pub timestamps: Vec<i64>,
+    pub next_timestamp: Option<i64>,
+    pub confidence: Option<ForecastConfidence>,
+}
+
+/// Fits the update-time model of a manga from its cached chapters, the same way the
+/// update check does, and reports everything that went into the forecast.
+pub async fn get_arima_diagnostics(db: &Database, manga_id: &MangaId) -> Result<ArimaDiagnostics> {
+    let spec = db
+        .find_manga_arima_spec(manga_id)
+        .await?
+        .map(ArimaSpec::from)
+        .unwrap_or_default();
+    let chapters = db.find_cached_chapter_informations(manga_id).await?;
+    let timestamps = timestamps_from_chapters(&chapters, spec.rolling_window).unwrap_or_default();
+
+    let (model, error) = match fit_model_from_chapters(&chapters, spec) {
+        Ok(model) => (Some(model), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    let next_timestamp = model
+        .as_ref()
+        .and_then(|model| model.forecast_1_from_chapters(&chapters, None));
+    let confidence = model
+        .as_ref()
+        .map(|model| ForecastConfidence::from_sigma2(model.sigma2()));
+
+    Ok(ArimaDiagnostics {
+        spec,
+        model,
+        error,
+        timestamps,
+        next_timestamp,
+        confidence,
+    })
+}
@@ -6,12 +6,28 @@ use crate::{
     model::{Chapter, MangaId},
 };
 
+/// Which chapters [`get_cached_manga_chapters`] returns. Unset fields don't filter.
+#[derive(Default, Clone, Copy)]
+pub struct ChapterFilter {
+    pub read: Option<bool>,
+    pub downloaded: Option<bool>,
+}
+
 pub async fn get_cached_manga_chapters(
     db: &Database,
     chapter_storage: &ChapterStorage,
     id: &MangaId,
     ram_mode_enabled: bool,
+    filter: ChapterFilter,
 ) -> Result<Vec<Chapter>> {
-    db.find_cached_chapters(id, chapter_storage, ram_mode_enabled)
-        .await
+    let mut chapters = db
+        .find_cached_chapters(id, chapter_storage, ram_mode_enabled, filter.read)
+        .await?;
+
+    // Whether a chapter is downloaded comes from the filesystem, not the database.
+    if let Some(downloaded) = filter.downloaded {
+        chapters.retain(|chapter| chapter.downloaded == downloaded);
+    }
+
+    Ok(chapters)
 }
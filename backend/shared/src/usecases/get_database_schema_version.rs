@@ -0,0 +1,6 @@
+use crate::{database::Database, model::SchemaVersion};
+use anyhow::Result;
+
+pub async fn get_database_schema_version(db: &Database) -> Result<SchemaVersion> {
+    db.get_schema_version().await
+}
@@ -0,0 +1,8 @@
+use crate::{database::Database, model::DatabaseStats};
+use anyhow::Result;
+
+/// Row counts of the user-facing tables and the size of the database file, to keep an eye on
+/// how much storage the library takes on the device.
+pub async fn get_database_stats(db: &Database) -> Result<DatabaseStats> {
+    db.get_stats().await
+}
@@ -0,0 +1,13 @@
+use crate::{
+    database::Database,
+    model::{ChapterId, DownloadProgress},
+};
+use anyhow::Result;
+
+/// The progress of the chapter's download, or `None` if it is not being downloaded.
+pub async fn get_download_progress(
+    db: &Database,
+    chapter_id: &ChapterId,
+) -> Result<Option<DownloadProgress>> {
+    db.find_download_progress(chapter_id).await
+}
@@ -0,0 +1,7 @@
+use anyhow::Result;
+
+use crate::database::Database;
+
+pub async fn get_library_tags(db: &Database) -> Result<Vec<String>> {
+    db.get_library_tags().await
+}
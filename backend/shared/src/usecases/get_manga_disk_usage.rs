@@ -0,0 +1,34 @@
+use anyhow::Result;
+use serde::Serialize;
+use size::Size;
+
+use crate::{chapter_storage::ChapterStorage, database::Database, model::MangaId};
+
+#[derive(Serialize)]
+pub struct MangaDiskUsage {
+    /// Total size of the manga's downloaded chapter files, in bytes.
+    pub bytes: u64,
+    /// `bytes` formatted for display, e.g. `12.5 MiB`.
+    pub human: String,
+}
+
+/// How much space the downloaded chapters of `manga_id` occupy. Only chapters that are
+/// known to the database are counted. The files are looked at off the async runtime.
+pub async fn get_manga_disk_usage(
+    db: &Database,
+    chapter_storage: &ChapterStorage,
+    manga_id: &MangaId,
+) -> Result<MangaDiskUsage> {
+    let chapter_ids = db.find_cached_chapter_ids(manga_id).await?;
+    let chapter_storage = chapter_storage.clone();
+    let manga_id = manga_id.clone();
+    let bytes = tokio::task::spawn_blocking(move || {
+        chapter_storage.get_chapter_disk_usage(&manga_id, chapter_ids)
+    })
+    .await?;
+
+    Ok(MangaDiskUsage {
+        bytes,
+        human: Size::from_bytes(bytes).to_string(),
+    })
+}
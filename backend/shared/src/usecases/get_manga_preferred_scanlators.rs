@@ -1,10 +1,10 @@
 use crate::{database::Database, model::MangaId};
 use anyhow::Result;
 
-pub async fn get_manga_preferred_scanlator(
+pub async fn get_manga_preferred_scanlators(
     db: &Database,
     manga_id: &MangaId,
-) -> Result<Option<String>> {
+) -> Result<Vec<String>> {
     let state = db.find_manga_state(manga_id).await?;
-    Ok(state.and_then(|s| s.preferred_scanlator))
+    Ok(state.map(|s| s.preferred_scanlators).unwrap_or_default())
 }
@@ -0,0 +1,12 @@
+use anyhow::Result;
+
+use crate::{database::Database, model::MangaId};
+
+/// The tags of the cached details of `manga_id`, empty if they aren't cached.
+pub async fn get_manga_tags(db: &Database, manga_id: &MangaId) -> Result<Vec<String>> {
+    let details = db.find_cached_manga_details(manga_id).await?;
+
+    Ok(details
+        .and_then(|(details, _)| details.tags)
+        .unwrap_or_default())
+}
@@ -1,14 +1,29 @@
 use anyhow::Result;
 
-use crate::{chapter_storage::ChapterStorage, database::Database, model::NotificationInformation};
+use crate::{chapter_storage::ChapterStorage, database::Database, model::NotificationPage};
 
 pub async fn get_notifications(
     db: &Database,
     chapter_storage: &ChapterStorage,
-) -> Result<Vec<NotificationInformation>> {
-    let mut notifications = db.get_notifications().await?;
+    before_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<NotificationPage> {
+    let mut notifications = db.get_notifications(before_id, limit).await?;
+    let next_cursor = match (limit, notifications.last()) {
+        (Some(limit), Some(last)) if notifications.len() as i64 >= limit => Some(last.id),
+        _ => None,
+    };
 
+    let ram_enabled = chapter_storage.is_ram_enabled();
     for notify in &mut notifications {
+        notify.downloaded = chapter_storage
+            .get_stored_chapter(&notify.chapter_id, false)
+            .is_some()
+            || (ram_enabled
+                && chapter_storage
+                    .get_stored_chapter(&notify.chapter_id, true)
+                    .is_some());
+
         if notify.manga_cover.is_none() {
             continue;
         }
@@ -31,5 +46,8 @@ pub async fn get_notifications(
         };
     }
 
-    Ok(notifications)
+    Ok(NotificationPage {
+        items: notifications,
+        next_cursor,
+    })
 }
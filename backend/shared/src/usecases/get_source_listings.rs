@@ -0,0 +1,13 @@
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use crate::source::Source;
+
+/// Returns the names of the listings `source` can browse, like "Popular" or "Latest". Empty
+/// when the source only has its default listing.
+pub async fn get_source_listings(
+    source: &Source,
+    cancellation_token: CancellationToken,
+) -> Result<Vec<String>> {
+    source.get_listings_next(cancellation_token).await
+}
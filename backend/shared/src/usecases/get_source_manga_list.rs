@@ -0,0 +1,71 @@
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    database::Database,
+    model::{Manga, MangaInformation, MangaState, SourceInformation},
+    source::{model::Manga as SourceManga, Source},
+};
+
+/// Browses one of the listings returned by
+/// [`get_source_listings`](super::get_source_listings), or the source's default listing when
+/// `listing` is `None`.
+pub async fn get_source_manga_list(
+    db: &Database,
+    source: &Source,
+    cancellation_token: CancellationToken,
+    listing: Option<String>,
+) -> Result<Vec<Manga>> {
+    let listing = listing
+        .map(|name| aidoku::Listing {
+            id: name.clone(),
+            name,
+            ..Default::default()
+        })
+        .unwrap_or_default();
+
+    let mangas = source.get_manga_list(cancellation_token, listing).await?;
+
+    Ok(with_library_state(db, source, mangas).await)
+}
+
+/// Caches `mangas` browsed from `source` and marks those that are in the library.
+pub(super) async fn with_library_state(
+    db: &Database,
+    source: &Source,
+    mangas: Vec<SourceManga>,
+) -> Vec<Manga> {
+    let manga_informations: Vec<_> = mangas.into_iter().map(MangaInformation::from).collect();
+
+    // Write through to the database, like searches do
+    let _ = db
+        .upsert_cached_manga_information(&manga_informations)
+        .await;
+
+    let manga_ids: Vec<_> = manga_informations.iter().map(|m| m.id.clone()).collect();
+    let unread_counts_map = db
+        .fetch_unread_chapter_counts_minimal(&manga_ids)
+        .await
+        .unwrap_or_default();
+    let source_information: SourceInformation = source.manifest().into();
+
+    manga_informations
+        .into_iter()
+        .map(|manga| {
+            let (unread_count, last_read, in_library) = unread_counts_map
+                .get(&manga.id)
+                .copied()
+                .unwrap_or((None, None, false));
+
+            Manga {
+                source_information: source_information.clone(),
+                information: manga,
+                state: MangaState::default(),
+                unread_chapters_count: unread_count,
+                last_read,
+                in_library,
+                state_viewer: false,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,20 @@
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use super::get_source_manga_list::with_library_state;
+use crate::{database::Database, model::Manga, source::Source};
+
+/// Lists the mangas of `source` tagged with `tag`, one page at a time.
+pub async fn get_source_mangas_by_tag(
+    db: &Database,
+    source: &Source,
+    cancellation_token: CancellationToken,
+    tag: String,
+    page: u32,
+) -> Result<Vec<Manga>> {
+    let mangas = source
+        .get_manga_by_tag(cancellation_token, tag, page)
+        .await?;
+
+    Ok(with_library_state(db, source, mangas).await)
+}
@@ -14,4 +14,11 @@ pub fn get_source_stored_settings(
         .get(source_id.value())
         .cloned()
         .unwrap_or_default()
+        .into_iter()
+        // The frontend edits secure values as plain strings; they're sealed again on save.
+        .map(|(key, value)| match value {
+            SourceSettingValue::SecureString(value) => (key, SourceSettingValue::String(value.0)),
+            value => (key, value),
+        })
+        .collect()
 }
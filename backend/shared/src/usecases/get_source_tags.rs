@@ -0,0 +1,13 @@
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use crate::source::Source;
+
+/// Returns the tags (genres) `source` can list mangas by, for browsing with
+/// [`get_source_mangas_by_tag`](super::get_source_mangas_by_tag).
+pub async fn get_source_tags(
+    source: &Source,
+    cancellation_token: CancellationToken,
+) -> Result<Vec<String>> {
+    source.get_tag_list(cancellation_token).await
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::database::Database;
+
+/// When a manga in the library was last checked for new chapters, and when it will be next.
+#[derive(Serialize)]
+pub struct UpdateScheduleEntry {
+    pub manga_id: String,
+    pub source_id: String,
+    pub manga_title: Option<String>,
+    pub last_check_at: i64,
+    pub next_check_at: i64,
+    /// `next_check_at` relative to now, e.g. "in 2 days" or "overdue by 1 hour".
+    pub next_check_human: String,
+}
+
+pub async fn get_update_schedule(db: &Database) -> Result<Vec<UpdateScheduleEntry>> {
+    let now = chrono::Utc::now().timestamp();
+
+    Ok(db
+        .get_update_schedule()
+        .await?
+        .into_iter()
+        .map(
+            |(manga_id, manga_title, last_check_at, next_check_at)| UpdateScheduleEntry {
+                source_id: manga_id.source_id().value().clone(),
+                manga_id: manga_id.value().clone(),
+                manga_title,
+                last_check_at,
+                next_check_at,
+                next_check_human: relative_time(next_check_at - now),
+            },
+        )
+        .collect())
+}
+
+/// Formats an offset in seconds from now, using its largest whole unit.
+fn relative_time(offset: i64) -> String {
+    let seconds = offset.unsigned_abs();
+    let (amount, unit) = match seconds {
+        0..60 => return if offset < 0 { "overdue" } else { "now" }.to_owned(),
+        60..3_600 => (seconds / 60, "minute"),
+        3_600..86_400 => (seconds / 3_600, "hour"),
+        _ => (seconds / 86_400, "day"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+
+    if offset < 0 {
+        format!("overdue by {amount} {unit}{plural}")
+    } else {
+        format!("in {amount} {unit}{plural}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_upcoming_checks() {
+        assert_eq!(relative_time(30), "now");
+        assert_eq!(relative_time(60), "in 1 minute");
+        assert_eq!(relative_time(5 * 3_600 + 59), "in 5 hours");
+        assert_eq!(relative_time(2 * 86_400 + 3_600), "in 2 days");
+    }
+
+    #[test]
+    fn formats_overdue_checks() {
+        assert_eq!(relative_time(-30), "overdue");
+        assert_eq!(relative_time(-3_600), "overdue by 1 hour");
+        assert_eq!(relative_time(-3 * 60), "overdue by 3 minutes");
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use url::Url;
+
+use crate::{
+    model::{ChapterId, MangaId},
+    source::{model::DeepLink, Source},
+};
+
+/// What a deep link points to: always a manga, and sometimes one of its chapters.
+#[derive(Debug, PartialEq)]
+pub struct DeepLinkTarget {
+    pub manga_id: MangaId,
+    pub chapter_id: Option<ChapterId>,
+}
+
+/// Asks `source` which manga (and chapter) `url` links to, so links shared from the source's
+/// website can be opened directly.
+pub async fn handle_deep_link(source: &Source, url: Url) -> Result<DeepLinkTarget> {
+    let deep_link = source.handle_deep_link(url).await?;
+
+    deep_link_target(source.manifest().info.id, deep_link)
+}
+
+fn deep_link_target(source_id: String, deep_link: DeepLink) -> Result<DeepLinkTarget> {
+    let manga = deep_link
+        .manga
+        .context("source didn't recognize the link")?;
+    let manga_id = MangaId::from_strings(source_id, manga.id);
+    let chapter_id = deep_link
+        .chapter
+        .map(|chapter| ChapterId::new(manga_id.clone(), chapter.id));
+
+    Ok(DeepLinkTarget {
+        manga_id,
+        chapter_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::model::{Chapter, Manga};
+
+    fn manga() -> Manga {
+        Manga {
+            id: "manga".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn links_to_a_manga_have_no_chapter() {
+        let deep_link = DeepLink {
+            manga: Some(manga()),
+            chapter: None,
+        };
+
+        assert_eq!(
+            deep_link_target("source".to_owned(), deep_link).unwrap(),
+            DeepLinkTarget {
+                manga_id: MangaId::from_strings("source".to_owned(), "manga".to_owned()),
+                chapter_id: None,
+            }
+        );
+    }
+
+    #[test]
+    fn links_to_a_chapter_belong_to_its_manga() {
+        let deep_link = DeepLink {
+            manga: Some(manga()),
+            chapter: Some(Chapter {
+                id: "chapter".to_owned(),
+                ..Default::default()
+            }),
+        };
+
+        let target = deep_link_target("source".to_owned(), deep_link).unwrap();
+        assert_eq!(
+            target.chapter_id,
+            Some(ChapterId::from_strings(
+                "source".to_owned(),
+                "manga".to_owned(),
+                "chapter".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn links_without_a_manga_are_rejected() {
+        let deep_link = DeepLink {
+            manga: None,
+            chapter: None,
+        };
+
+        assert!(deep_link_target("source".to_owned(), deep_link).is_err());
+    }
+}
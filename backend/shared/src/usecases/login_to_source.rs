@@ -0,0 +1,27 @@
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use crate::source::{model::SettingDefinition, Source};
+
+/// Logs in to a source with a username and password. The credentials are handed to the
+/// source under the key of its login setting, the same way Aidoku does.
+pub async fn login_to_source(
+    cancellation_token: CancellationToken,
+    source: &Source,
+    username: String,
+    password: String,
+) -> Result<bool> {
+    let key = find_login_key(&source.setting_definitions()).unwrap_or_else(|| "login".to_owned());
+
+    source
+        .handle_basic_login(cancellation_token, key, username, password)
+        .await
+}
+
+fn find_login_key(definitions: &[SettingDefinition]) -> Option<String> {
+    definitions.iter().find_map(|definition| match definition {
+        SettingDefinition::Login { key, .. } => Some(key.clone()),
+        SettingDefinition::Group { items, .. } => find_login_key(items),
+        _ => None,
+    })
+}
@@ -1,19 +1,22 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, Result};
 
 use crate::{
     chapter_storage::ChapterStorage,
     database::Database,
     model::{Chapter, ChapterId, MangaId},
-    usecases::revoke_manga_chapter::revoke_manga_chapter,
+    usecases::{
+        chapter_range::{parse_chapter_ranges, ChapterRange},
+        revoke_manga_chapter::revoke_manga_chapter,
+    },
 };
 
 /// Sets the read state of the chapters selected by `text` (a comma-separated
-/// list of chapter numbers and ranges; an empty string selects every
-/// chapter). Returns the remaining unread chapter count, when known. When
-/// marking as read and `delete_downloaded_after_read` is enabled, the
-/// selected chapters' downloaded files are deleted on a best-effort basis.
+/// list of [`ChapterRange`]s; an empty string selects every chapter).
+/// Returns the remaining unread chapter count, when known. When marking as
+/// read and `delete_downloaded_after_read` is enabled, the selected
+/// chapters' downloaded files are deleted on a best-effort basis.
 pub async fn mark_chapters_as_read(
     db: &Database,
     chapter_storage: &ChapterStorage,
@@ -22,9 +25,11 @@ pub async fn mark_chapters_as_read(
     text: &str,
     state: bool,
 ) -> Result<Option<usize>> {
-    let chapters = super::get_cached_manga_chapters(db, chapter_storage, manga_id, false).await?;
+    let chapters =
+        super::get_cached_manga_chapters(db, chapter_storage, manga_id, false, Default::default())
+            .await?;
 
-    let selected_ids = parse_chapter_ranges(&chapters, text)?;
+    let selected_ids = select_chapters(&chapters, text)?;
 
     let unread_count = db
         .set_chapters_read_state(manga_id, &selected_ids, state)
@@ -39,68 +44,35 @@ pub async fn mark_chapters_as_read(
     Ok(unread_count)
 }
 
-fn parse_chapter_ranges(chapters: &[Chapter], text: &str) -> Result<Vec<ChapterId>> {
-    if text.trim().is_empty() {
-        return Ok(chapters.iter().map(|c| c.information.id.clone()).collect());
-    }
+fn select_chapters(chapters: &[Chapter], text: &str) -> Result<Vec<ChapterId>> {
+    let ranges = parse_chapter_ranges(text)?;
 
-    let chapters: HashMap<_, _> = chapters
+    // Chapters without a number are selected by their position in the list.
+    let mut numbered: Vec<(f32, &Chapter)> = chapters
         .iter()
         .enumerate()
-        .map(|(idx, ch)| {
-            let key = ch
-                .information
-                .chapter_number
-                .map(|n| n.to_string())
-                .unwrap_or(idx.to_string());
-            (key, ch)
-        })
-        .collect();
-
-    // Build a sorted list of all numeric chapter keys (float)
-    // This allows range scanning even with decimal chapter numbers
-    let mut sorted_keys: Vec<f64> = chapters
-        .keys()
-        .filter_map(|k| k.parse::<f64>().ok())
+        .map(|(idx, ch)| (ch.information.chapter_number.unwrap_or(idx as f32), ch))
         .collect();
+    numbered.sort_by(|(a, _), (b, _)| a.total_cmp(b));
 
-    sorted_keys.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
+    let mut selected = HashSet::new();
     let mut output = Vec::<ChapterId>::new();
 
-    for part in text.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
-        // Case: range "a-b"
-        if let Some((start_raw, stop_raw)) = part.split_once('-') {
-            let start = start_raw.trim();
-            let stop = stop_raw.trim();
-
-            // Parse as float
-            let start_val: f64 = start.parse().context("Invalid float range start")?;
-            let stop_val: f64 = stop.parse().context("Invalid float range stop")?;
-
-            if start_val > stop_val {
-                bail!("Invalid range {}: start > stop", part);
-            }
-
-            // Iterate through sorted chapter keys and select those inside range
-            for key in sorted_keys.iter().copied() {
-                if key >= start_val && key <= stop_val {
-                    let key_s = key.to_string();
-
-                    if let Some(ch) = chapters.get(&key_s) {
-                        output.push(ch.information.id.clone());
-                    }
+    for range in ranges {
+        let mut found = false;
+        for (number, ch) in &numbered {
+            if range.contains(*number) {
+                found = true;
+                if selected.insert(&ch.information.id) {
+                    output.push(ch.information.id.clone());
                 }
             }
-        } else {
-            // Case: single chapter (float allowed)
-            let key = part.trim();
-
-            let ch = chapters
-                .get(key)
-                .context(format!("Can't find chapter {}", key))?;
+        }
 
-            output.push(ch.information.id.clone());
+        if let ChapterRange::Single(number) = range {
+            if !found {
+                bail!("Can't find chapter {}", number);
+            }
         }
     }
 
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use log::warn;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    chapter_storage::ChapterStorage,
+    database::Database,
+    model::{MangaId, SourceId},
+    source::Source,
+};
+
+/// Asks `source` for the current ID of every manga of it in the library, and moves everything
+/// kept about the ones it renamed, downloaded chapters included. A source's migration only runs
+/// once per version, as it may not be safe to apply twice. Returns how many mangas were
+/// migrated.
+pub async fn migrate_manga_ids(
+    db: &Database,
+    chapter_storage: &ChapterStorage,
+    source: &Source,
+    cancellation_token: CancellationToken,
+) -> Result<usize> {
+    let manifest = source.manifest();
+    let source_id = manifest.info.id;
+    let version = manifest.info.version as i64;
+    let key = SourceId::new(source_id.clone());
+    if db.find_key_migration_version(&key).await? == Some(version) {
+        return Ok(0);
+    }
+
+    let mut migrated = 0;
+    for manga_id in db.get_manga_library().await? {
+        if manga_id.source_id().value() != &source_id {
+            continue;
+        }
+
+        match migrate_manga_id(db, chapter_storage, source, &manga_id, &cancellation_token).await {
+            Ok(true) => migrated += 1,
+            Ok(false) => {}
+            Err(e) => warn!("failed to migrate the ID of {manga_id:?}: {e:?}"),
+        }
+    }
+
+    db.set_key_migration_version(&key, version).await?;
+
+    Ok(migrated)
+}
+
+async fn migrate_manga_id(
+    db: &Database,
+    chapter_storage: &ChapterStorage,
+    source: &Source,
+    manga_id: &MangaId,
+    cancellation_token: &CancellationToken,
+) -> Result<bool> {
+    let Some(new_id) = source
+        .handle_key_migration(cancellation_token.clone(), manga_id.value().clone())
+        .await?
+    else {
+        return Ok(false);
+    };
+
+    let new_manga_id = MangaId::from_strings(manga_id.source_id().value().clone(), new_id);
+    let chapter_ids = db.find_cached_chapter_ids(manga_id).await?;
+    db.migrate_manga_id(manga_id, &new_manga_id).await?;
+    chapter_storage.move_manga_chapters(manga_id, &new_manga_id, chapter_ids);
+
+    Ok(true)
+}
@@ -1,5 +1,6 @@
 pub mod add_manga_to_library;
 pub mod add_manga_to_playlist;
+pub mod chapter_range;
 pub mod check_mangas_update;
 pub mod check_update;
 pub mod clear_notifications;
@@ -8,43 +9,63 @@ pub mod delete_notification;
 pub mod delete_playlist;
 pub mod fetch_manga_chapter;
 pub mod fetch_manga_chapters_in_batch;
+pub mod fetch_manga_cover;
 pub mod find_orphan_or_read_files;
+pub mod get_arima_diagnostics;
 pub mod get_cached_manga_chapters;
 pub mod get_cached_manga_details;
 pub mod get_count_notifications;
+pub mod get_database_schema_version;
+pub mod get_database_stats;
+pub mod get_download_progress;
+pub mod get_library_tags;
+pub mod get_manga_disk_usage;
 pub mod get_manga_library;
-pub mod get_manga_preferred_scanlator;
+pub mod get_manga_preferred_scanlators;
+pub mod get_manga_tags;
 pub mod get_mangas_in_playlist;
 pub mod get_notifications;
 pub mod get_playlists;
+pub mod get_source_listings;
+pub mod get_source_manga_list;
+pub mod get_source_mangas_by_tag;
 pub mod get_source_setting_definitions;
 pub mod get_source_stored_settings;
+pub mod get_source_tags;
 pub mod get_storage_stats;
 pub mod get_tracking_user;
+pub mod get_update_schedule;
+pub mod handle_deep_link;
 pub mod install_source;
 pub mod install_update;
 pub mod link_tracking_binding;
 pub mod list_available_sources;
 pub mod list_installed_sources;
 pub mod list_tracking_bindings;
+pub mod login_to_source;
 pub mod mark_chapter_as_read;
 pub mod mark_chapters_as_read;
+pub mod migrate_manga_ids;
 pub mod oauth_bridge;
 pub mod refresh_manga_chapters;
 pub mod refresh_manga_details;
+pub mod reload_source;
 pub mod remove_manga_from_library;
 pub mod remove_manga_from_playlist;
 pub mod rename_playlist;
 pub mod revoke_manga_chapter;
 pub mod search_mangas;
 pub mod search_tracking_candidates;
-pub mod set_manga_preferred_scanlator;
+pub mod set_manga_arima_spec;
+pub mod set_manga_preferred_scanlators;
 pub mod set_manga_viewer;
 pub mod set_source_stored_settings;
+pub mod shard_chapter_storage;
 pub mod sync_database;
 pub mod sync_manga_tracking;
 pub mod uninstall_source;
 pub mod unlink_tracking_binding;
+pub mod unload_source;
 pub mod update_last_read_chapter;
 pub mod update_settings;
 pub mod validate_tracking;
@@ -52,7 +73,7 @@ pub mod validate_tracking;
 pub use add_manga_to_library::add_manga_to_library;
 pub use add_manga_to_playlist::add_manga_to_playlist;
 pub use check_mangas_update::check_mangas_update;
-pub use check_mangas_update::run_manga_cron;
+pub use check_mangas_update::{is_manga_cron_running, run_manga_cron};
 pub use check_update::check_update;
 pub use clear_notifications::clear_notifications;
 pub use create_playlist::create_playlist;
@@ -60,43 +81,63 @@ pub use delete_notification::delete_notification;
 pub use delete_playlist::delete_playlist;
 pub use fetch_manga_chapter::fetch_manga_chapter;
 pub use fetch_manga_chapters_in_batch::fetch_manga_chapters_in_batch;
+pub use fetch_manga_cover::fetch_manga_cover;
 pub use find_orphan_or_read_files::find_orphan_or_read_files;
+pub use get_arima_diagnostics::get_arima_diagnostics;
 pub use get_cached_manga_chapters::get_cached_manga_chapters;
 pub use get_cached_manga_details::get_cached_manga_details;
 pub use get_count_notifications::get_count_notifications;
+pub use get_database_schema_version::get_database_schema_version;
+pub use get_database_stats::get_database_stats;
+pub use get_download_progress::get_download_progress;
+pub use get_library_tags::get_library_tags;
+pub use get_manga_disk_usage::get_manga_disk_usage;
 pub use get_manga_library::get_manga_library;
-pub use get_manga_preferred_scanlator::get_manga_preferred_scanlator;
+pub use get_manga_preferred_scanlators::get_manga_preferred_scanlators;
+pub use get_manga_tags::get_manga_tags;
 pub use get_mangas_in_playlist::get_mangas_in_playlist;
 pub use get_notifications::get_notifications;
 pub use get_playlists::get_playlists;
+pub use get_source_listings::get_source_listings;
+pub use get_source_manga_list::get_source_manga_list;
+pub use get_source_mangas_by_tag::get_source_mangas_by_tag;
 pub use get_source_setting_definitions::get_source_setting_definitions;
 pub use get_source_stored_settings::get_source_stored_settings;
+pub use get_source_tags::get_source_tags;
 pub use get_storage_stats::get_storage_stats;
 pub use get_tracking_user::get_tracking_user_info;
+pub use get_update_schedule::get_update_schedule;
+pub use handle_deep_link::handle_deep_link;
 pub use install_source::install_source;
 pub use install_update::install_update;
 pub use link_tracking_binding::link_tracking_binding;
 pub use list_available_sources::list_available_sources;
 pub use list_installed_sources::list_installed_sources;
 pub use list_tracking_bindings::list_tracking_bindings;
+pub use login_to_source::login_to_source;
 pub use mark_chapter_as_read::mark_chapter_as_read;
 pub use mark_chapters_as_read::mark_chapters_as_read;
+pub use migrate_manga_ids::migrate_manga_ids;
 pub use oauth_bridge::{poll_oauth_status, start_oauth_session, OAuthService};
 pub use refresh_manga_chapters::refresh_manga_chapters;
 pub use refresh_manga_details::refresh_manga_details;
+pub use reload_source::reload_source;
 pub use remove_manga_from_library::remove_manga_from_library;
 pub use remove_manga_from_playlist::remove_manga_from_playlist;
 pub use rename_playlist::rename_playlist;
 pub use revoke_manga_chapter::revoke_manga_chapter;
 pub use search_mangas::search_mangas;
 pub use search_tracking_candidates::search_tracking_candidates;
-pub use set_manga_preferred_scanlator::set_manga_preferred_scanlator;
+pub use set_manga_arima_spec::set_manga_arima_spec;
+pub use set_manga_preferred_scanlators::set_manga_preferred_scanlators;
 pub use set_manga_viewer::set_manga_viewer;
 pub use set_source_stored_settings::set_source_stored_settings;
+pub use shard_chapter_storage::shard_chapter_storage;
 pub use sync_database::sync_database;
 pub use sync_manga_tracking::{sync_manga_tracking, sync_manga_tracking_push};
 pub use uninstall_source::uninstall_source;
 pub use unlink_tracking_binding::unlink_tracking_binding;
+pub use unload_source::unload_source;
 pub use update_last_read_chapter::update_last_read_chapter;
 pub use update_settings::update_settings;
 pub use validate_tracking::validate_tracking_settings;
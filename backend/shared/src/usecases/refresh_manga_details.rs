@@ -6,7 +6,10 @@ use crate::{
     chapter_storage::ChapterStorage,
     database::Database,
     model::MangaId,
-    source::{model::PublishingStatus, Source},
+    source::{
+        model::{Manga as SourceManga, PublishingStatus},
+        Source,
+    },
 };
 
 pub async fn refresh_manga_details(
@@ -21,12 +24,21 @@ pub async fn refresh_manga_details(
 
     let child_token = token.child_token();
 
-    let fetch_task = async {
-        source
-            .get_manga_details(child_token.clone(), id.value().clone())
-            .await
+    let known = match db.find_cached_manga_details(id).await? {
+        Some((manga, _)) => manga,
+        None => db
+            .find_cached_manga_information(id)
+            .await?
+            .map(SourceManga::from)
+            .unwrap_or_else(|| SourceManga {
+                source_id: id.source_id().value().clone(),
+                id: id.value().clone(),
+                ..Default::default()
+            }),
     };
 
+    let fetch_task = async { source.get_manga_details(child_token.clone(), known).await };
+
     let manga_details = match timeout(duration, fetch_task).await {
         Ok(Ok(manga)) => manga,
 
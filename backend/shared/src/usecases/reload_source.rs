@@ -0,0 +1,14 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::Result;
+use tokio::sync::Mutex;
+
+use crate::{model::SourceId, source_manager::SourceManager};
+
+pub fn reload_source(
+    source_manager: &mut SourceManager,
+    arc_manager: &Arc<Mutex<SourceManager>>,
+    path: &Path,
+) -> Result<SourceId> {
+    source_manager.reload_source(path, arc_manager)
+}
@@ -18,9 +18,9 @@ pub async fn revoke_manga_chapter(
 
     // Delete through ChapterStorage so `cached_storage_size` is decremented;
     // deleting the file directly would leave the eviction accounting stale.
-    let removed_main = match path.file_name().and_then(|name| name.to_str()) {
+    let removed_main = match chapter_storage.relative_filename(&path, use_ram) {
         Some(filename) => chapter_storage
-            .delete_filename(filename.to_string(), use_ram)
+            .delete_filename(filename, use_ram)
             .await
             .is_ok(),
         None => false,
@@ -7,12 +7,14 @@ use crate::{
 };
 use futures::{stream, StreamExt};
 use log::warn;
+use std::collections::HashSet;
 use tokio::time::timeout;
 use tokio::time::Duration;
 use tokio_util::sync::CancellationToken;
 use unicode_normalization::UnicodeNormalization;
 
 const CONCURRENT_SEARCH_REQUESTS: usize = 5;
+const DEFAULT_SOURCE_SEARCH_TIMEOUT_SECS: u64 = 15;
 const CONCURRENT_POSTER_DOWNLOADS: usize = 4;
 const POSTER_DOWNLOAD_TIMEOUT_SECS: u64 = 10;
 
@@ -31,10 +33,12 @@ pub async fn search_mangas(
     query: String,
     exclude: &Option<Vec<String>>,
     page: i32,
-    seconds: u64,
 ) -> Result<(Vec<Manga>, Vec<SearchError>, bool), Error> {
     // FIXME this looks awful
     let query = &query;
+    let seconds = settings
+        .source_search_timeout_secs
+        .unwrap_or(DEFAULT_SOURCE_SEARCH_TIMEOUT_SECS);
 
     // FIXME this kinda of works because cloning a source is cheap
     // (it has internal mutability yadda yadda).
@@ -177,7 +181,8 @@ pub async fn search_mangas(
                     )
                 }
             })
-            .buffered(CONCURRENT_SEARCH_REQUESTS)
+            // Sources answer in whatever order they finish, the results are sorted below
+            .buffer_unordered(CONCURRENT_SEARCH_REQUESTS)
             .collect::<Vec<_>>()
             .await;
 
@@ -215,20 +220,24 @@ pub async fn search_mangas(
         })
         .collect();
 
+    // A source can return the same manga on several result pages or twice on one page
+    let mut seen = HashSet::new();
+    mangas.retain(|manga| seen.insert(manga.information.id.clone()));
+
     mangas.sort_by_cached_key(|manga| {
-        manga
-            .information
-            .title
-            .clone()
-            .unwrap_or_default()
-            .nfkc()
-            .flat_map(char::to_lowercase)
-            .collect::<String>()
+        (
+            normalize_for_sorting(&manga.source_information.name),
+            normalize_for_sorting(manga.information.title.as_deref().unwrap_or_default()),
+        )
     });
 
     Ok((mangas, errors, has_next_page))
 }
 
+fn normalize_for_sorting(value: &str) -> String {
+    value.nfkc().flat_map(char::to_lowercase).collect()
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("an error occurred while fetching search results from the source")]
@@ -0,0 +1,16 @@
+use crate::{arima_light::ArimaSpecOverride, database::Database, model::MangaId};
+use anyhow::Result;
+
+pub async fn set_manga_arima_spec(
+    db: &Database,
+    manga_id: MangaId,
+    spec: Option<ArimaSpecOverride>,
+) -> Result<()> {
+    if let Some(spec) = &spec {
+        spec.validate()?;
+    }
+
+    db.set_manga_arima_spec(&manga_id, spec.as_ref()).await?;
+
+    Ok(())
+}
@@ -4,13 +4,13 @@ use crate::{
 };
 use anyhow::Result;
 
-pub async fn set_manga_preferred_scanlator(
+pub async fn set_manga_preferred_scanlators(
     db: &Database,
     manga_id: MangaId,
-    preferred_scanlator: Option<String>,
+    preferred_scanlators: Vec<String>,
 ) -> Result<()> {
     let updated_manga_state = MangaState {
-        preferred_scanlator,
+        preferred_scanlators,
     };
 
     db.upsert_manga_state(&manga_id, updated_manga_state)
@@ -6,6 +6,7 @@ use anyhow::Result;
 use crate::{
     model::SourceId,
     settings::{Settings, SourceSettingValue},
+    source_collection::SourceCollection,
     source_manager::SourceManager,
 };
 
@@ -17,6 +18,11 @@ pub fn set_source_stored_settings(
     source_id: &SourceId,
     stored_settings: HashMap<String, SourceSettingValue>,
 ) -> Result<()> {
+    let stored_settings = match source_manager.get_by_id(source_id) {
+        Some(source) => source.seal_secure_settings(stored_settings),
+        None => stored_settings,
+    };
+
     // Clone the settings and save the cloned one first, so that we only change the application settings
     // iff everything goes well
     let mut updated_settings = settings.clone();
@@ -0,0 +1,27 @@
+use anyhow::Result;
+
+use crate::{chapter_storage::ChapterStorage, database::Database};
+
+/// Moves chapters downloaded before chapter files were split into per-source folders to
+/// their new place. Does nothing when there are none left, which is almost always.
+pub async fn shard_chapter_storage(
+    db: &Database,
+    chapter_storage: &ChapterStorage,
+) -> Result<usize> {
+    let has_flat_chapters = {
+        let chapter_storage = chapter_storage.clone();
+        tokio::task::spawn_blocking(move || chapter_storage.has_flat_chapters()).await?
+    };
+    if !has_flat_chapters {
+        return Ok(0);
+    }
+
+    let chapter_ids = db.find_all_chapter_ids().await?;
+    let chapter_storage = chapter_storage.clone();
+
+    let moved =
+        tokio::task::spawn_blocking(move || chapter_storage.shard_flat_chapters(chapter_ids))
+            .await?;
+
+    Ok(moved)
+}
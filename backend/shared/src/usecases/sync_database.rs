@@ -13,11 +13,19 @@ const URL_CDN_TRACE: &str = "https://www.cloudflare.com/cdn-cgi/trace";
 #[serde(rename_all = "snake_case")]
 pub enum SyncResult {
     UpToDate,
+    /// The remote database couldn't be merged (e.g. it comes from a newer version), so one has
+    /// to replace the other.
     UpdateRequired,
     Updated,
     UpdatedToServer,
+    /// Both databases were merged, and the result uploaded.
+    Merged,
 }
 
+/// Syncs the database with the WebDAV server. By default, the remote database is merged into
+/// the local one (see [`Database::merge_from`]) and the result is uploaded.
+/// `accept_migrate_local` replaces the local database with the remote one instead, and
+/// `accept_replace_remote` uploads the local one as-is.
 pub async fn sync_database(
     db: &Database,
     settings: &mut Settings,
@@ -41,7 +49,7 @@ pub async fn sync_database(
     ensure_webdav_dir(&client, &dav_base, &user, &password).await?;
     println!("WebDAV directory ensured in {:?}", t.elapsed());
     // --- 現在のローカル DB の SHA256 を計算する ---
-    let local_sha = sha256_file(&db.filename)?;
+    let mut local_sha = sha256_file(&db.filename)?;
 
     // --- DAV から database.sha256 を読む ---
 
@@ -64,11 +72,12 @@ pub async fn sync_database(
     let now = get_current_time().await?;
     println!("Remote time fetched in {:?}", t.elapsed());
 
+    let mut merged = false;
     if let Some(remote_time_bytes) = remote_time_opt {
         let remote_time_str = String::from_utf8_lossy(&remote_time_bytes)
             .trim()
             .to_string();
-        if let Ok(remote_time) = remote_time_str.parse::<i64>() {
+        if remote_time_str.parse::<i64>().is_ok() {
             if accept_replace_remote {
                 // continue
             } else if accept_migrate_local {
@@ -82,10 +91,19 @@ pub async fn sync_database(
                 } else {
                     bail!("Remote database file not found");
                 }
-            }
-            // --- サーバーの DB が新しい場合 ---
-            else if remote_time > now {
-                return Ok(SyncResult::UpdateRequired);
+            } else if let Some(buf) =
+                dav_read(&client, &dav_base, &user, &password, "database.db").await?
+            {
+                t = std::time::Instant::now();
+                if let Err(e) = merge_remote_database(db, &buf).await {
+                    eprintln!("Failed to merge the remote database: {e:#}");
+
+                    return Ok(SyncResult::UpdateRequired);
+                }
+                println!("Remote database merged in {:?}", t.elapsed());
+
+                local_sha = sha256_file(&db.filename)?;
+                merged = true;
             }
         }
     }
@@ -130,7 +148,28 @@ pub async fn sync_database(
     .await?;
     println!("SHA256 updated in {:?}", t.elapsed());
 
-    Ok(SyncResult::UpdatedToServer)
+    Ok(if merged {
+        SyncResult::Merged
+    } else {
+        SyncResult::UpdatedToServer
+    })
+}
+
+async fn merge_remote_database(db: &Database, buf: &[u8]) -> Result<()> {
+    let remote_path = db.filename.with_extension("db.remote");
+    tokio::fs::write(&remote_path, buf).await?;
+
+    let merged = db.merge_from(&remote_path).await;
+
+    for path in [
+        remote_path.clone(),
+        remote_path.with_extension("remote-wal"),
+        remote_path.with_extension("remote-shm"),
+    ] {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+
+    merged
 }
 
 async fn get_current_time() -> Result<i64> {
@@ -78,7 +78,7 @@ pub async fn sync_manga_tracking(
     }
 
     let chapters = db
-        .find_cached_chapters(manga_id, chapter_storage, true)
+        .find_cached_chapters(manga_id, chapter_storage, true, None)
         .await?;
     for binding in bindings {
         let result = (async {
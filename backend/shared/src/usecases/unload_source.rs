@@ -0,0 +1,5 @@
+use crate::{model::SourceId, source_manager::SourceManager};
+
+pub fn unload_source(source_manager: &mut SourceManager, source_id: &SourceId) -> bool {
+    source_manager.unload_source(source_id)
+}
@@ -2,8 +2,12 @@ use anyhow::Result;
 
 use crate::{database::Database, model::ChapterId};
 
-pub async fn update_last_read_chapter(db: &Database, id: &ChapterId) -> Result<()> {
-    db.update_last_read_chapter(id).await?;
+pub async fn update_last_read_chapter(
+    db: &Database,
+    id: &ChapterId,
+    last_read_page: Option<i32>,
+) -> Result<()> {
+    db.update_last_read_chapter(id, last_read_page).await?;
 
     Ok(())
 }
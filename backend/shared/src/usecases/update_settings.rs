@@ -5,8 +5,9 @@ use serde::{Deserialize, Serialize};
 use size::{consts, Size};
 
 use crate::settings::{
-    ChapterSortingMode, ChapterTitleFormat, LibrarySortingMode, LibraryViewMode, SearchViewMode,
-    Settings, StorageSizeLimit, TrackingServiceSettings,
+    deserialize_concurrent_requests_pages, ChapterSortingMode, ChapterTitleFormat,
+    LibrarySortingMode, LibraryViewMode, SearchViewMode, Settings, StorageSizeLimit,
+    TrackingServiceSettings,
 };
 
 pub fn update_settings(
@@ -29,7 +30,10 @@ pub struct UpdateableSettings {
     library_sorting_mode: LibrarySortingMode,
     storage_size_limit_mb: usize,
     storage_path: Option<PathBuf>,
+    #[serde(default, deserialize_with = "deserialize_concurrent_requests_pages")]
     concurrent_requests_pages: Option<usize>,
+    #[serde(default)]
+    concurrent_source_operations: Option<usize>,
     api_sync: Option<String>,
     tracking_auto_sync: bool,
     anilist: TrackingServiceSettings,
@@ -71,6 +75,7 @@ impl UpdateableSettings {
             StorageSizeLimit(Size::from_megabytes(self.storage_size_limit_mb));
         settings.storage_path = self.storage_path;
         settings.concurrent_requests_pages = self.concurrent_requests_pages;
+        settings.concurrent_source_operations = self.concurrent_source_operations;
         settings.api_sync = self.api_sync;
         settings.tracking_auto_sync = self.tracking_auto_sync;
 
@@ -128,6 +133,7 @@ impl From<&Settings> for UpdateableSettings {
                 .unwrap(),
             storage_path: value.storage_path.clone(),
             concurrent_requests_pages: value.concurrent_requests_pages,
+            concurrent_source_operations: value.concurrent_source_operations,
             api_sync: value.api_sync.clone(),
             tracking_auto_sync: value.tracking_auto_sync,
             anilist: value.anilist.clone(),
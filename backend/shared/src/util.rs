@@ -9,12 +9,16 @@ use base64::Engine;
 use dom_query::Document;
 #[cfg(feature = "all")]
 use futures::{stream, StreamExt};
-use reqwest::{Client, Request};
+use reqwest::{header::HeaderMap, Client, Request};
 use tokio::net::TcpStream;
 use tokio_util::sync::CancellationToken;
 use url::Url;
 
-use crate::source::{model::Page, Source};
+use crate::source::{
+    model::Page,
+    preload_cache::{PreloadClaim, PreloadedResponse},
+    Source,
+};
 
 pub async fn has_internet_connection() -> bool {
     try_connecting_to_cloudflare().await.is_ok()
@@ -31,10 +35,22 @@ async fn try_connecting_to_cloudflare() -> Result<()> {
     Ok(())
 }
 
+/// How many hinted URLs are prefetched at once.
+const MAX_CONCURRENT_PRELOADS: usize = 5;
+
+/// Whose cache the URLs hinted by `Link: <url>; rel=preload` headers are prefetched to. The
+/// prefetches are built by `source` like its page requests, and stop once `token` is
+/// cancelled.
+pub struct Preload {
+    pub source: Source,
+    pub token: CancellationToken,
+}
+
 pub async fn request_with_forced_referer_from_request(
     client: &Client,
     mut req: Request,
     max_redirects: usize,
+    preload: Option<&Preload>,
 ) -> Result<reqwest::Response, anyhow::Error> {
     let referer = req
         .headers()
@@ -42,6 +58,14 @@ pub async fn request_with_forced_referer_from_request(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
+    if let Some(preload) = preload {
+        if req.method() == reqwest::Method::GET {
+            if let Some(preloaded) = preload.source.preload_cache().take(req.url()) {
+                return Ok(preloaded.into_response());
+            }
+        }
+    }
+
     for _ in 0..max_redirects {
         let req_method = { req.method().clone() };
         let original_headers = { req.headers().clone() };
@@ -84,6 +108,10 @@ pub async fn request_with_forced_referer_from_request(
 
         let status = resp.status();
         if !status.is_redirection() {
+            if let Some(preload) = preload {
+                spawn_preloads(client, &resp, referer.as_deref(), preload);
+            }
+
             return Ok(resp);
         }
 
@@ -120,50 +148,321 @@ pub async fn request_with_forced_referer_from_request(
     anyhow::bail!("too many redirects")
 }
 
+/// Prefetches the URLs `response` hints at in the background, at most
+/// [`MAX_CONCURRENT_PRELOADS`] at a time.
+fn spawn_preloads(
+    client: &Client,
+    response: &reqwest::Response,
+    referer: Option<&str>,
+    preload: &Preload,
+) {
+    let cache = preload.source.preload_cache();
+    // Claims that are dropped, along with the prefetches when `token` is cancelled, release
+    // their URL.
+    let claims: Vec<_> = preload_hints(response.headers(), response.url())
+        .into_iter()
+        .filter(|url| url != response.url())
+        .filter_map(|url| PreloadClaim::new(&cache, url))
+        .collect();
+    if claims.is_empty() {
+        return;
+    }
+
+    let client = client.clone();
+    let referer = referer.map(str::to_owned);
+    let source = preload.source.clone();
+    let token = preload.token.clone();
+
+    tokio::spawn(async move {
+        use futures::StreamExt as _;
+
+        let prefetches =
+            futures::stream::iter(claims).for_each_concurrent(MAX_CONCURRENT_PRELOADS, |claim| {
+                let client = &client;
+                let source = &source;
+                let referer = referer.as_deref();
+
+                async move {
+                    let response = prefetch(client, source, claim.url(), referer).await;
+                    claim.complete(response);
+                }
+            });
+
+        tokio::select! {
+            _ = token.cancelled() => {}
+            _ = prefetches => {}
+        }
+    });
+}
+
+async fn prefetch(
+    client: &Client,
+    source: &Source,
+    url: &Url,
+    referer: Option<&str>,
+) -> Option<PreloadedResponse> {
+    // Built like the page requests, so that the source gets to add its headers and cookies.
+    let mut request = source.get_image_request(url.clone(), None).await.ok()?;
+    if let Some(referer) = referer {
+        if !request.headers().contains_key(reqwest::header::REFERER) {
+            request
+                .headers_mut()
+                .insert(reqwest::header::REFERER, referer.parse().ok()?);
+        }
+    }
+
+    let response = client.execute(request).await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await.ok()?;
+    source.record_bandwidth(0, body.len());
+
+    Some(PreloadedResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// The URLs of the `Link` headers with a `preload` relation, resolved against `base`.
+pub fn preload_hints(headers: &HeaderMap, base: &Url) -> Vec<Url> {
+    let mut urls: Vec<Url> = Vec::new();
+
+    for link in headers
+        .get_all(reqwest::header::LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(split_link_header)
+    {
+        let Some((target, params)) = link
+            .trim()
+            .strip_prefix('<')
+            .and_then(|link| link.split_once('>'))
+        else {
+            continue;
+        };
+
+        let is_preload = params
+            .split(';')
+            .filter_map(|param| param.split_once('='))
+            .any(|(name, value)| {
+                name.trim().eq_ignore_ascii_case("rel")
+                    && value
+                        .trim()
+                        .trim_matches('"')
+                        .split_ascii_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("preload"))
+            });
+        if !is_preload {
+            continue;
+        }
+
+        if let Ok(url) = base.join(target.trim()) {
+            if matches!(url.scheme(), "http" | "https") && !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+
+    urls
+}
+
+/// Splits a `Link` header into its links, leaving commas inside `<...>` alone.
+fn split_link_header(value: &str) -> Vec<&str> {
+    let mut links = Vec::new();
+    let mut start = 0;
+    let mut in_target = false;
+
+    for (index, c) in value.char_indices() {
+        match c {
+            '<' => in_target = true,
+            '>' => in_target = false,
+            ',' if !in_target => {
+                links.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    links.push(&value[start..]);
+
+    links
+}
+
+/// Translates the fixed texts drawn on error images. `lang` is a BCP 47 tag like the one in
+/// `ChapterInformation::lang`; only its primary subtag is used.
+fn translate_error_text(text: &str, lang: &str) -> Option<&'static str> {
+    Some(match (text, primary_language(lang).as_str()) {
+        ("ERROR", "ja") => "エラー",
+        ("ERROR", "zh") => "错误",
+        ("ERROR", "ko") => "오류",
+        ("ERROR", "vi") => "LỖI",
+        ("Error", "ja") => "エラー",
+        ("Error", "zh") => "错误",
+        ("Error", "ko") => "오류",
+        ("Error", "vi") => "Lỗi",
+        ("Image error", "ja") => "画像エラー",
+        ("Image error", "zh") => "图片错误",
+        ("Image error", "ko") => "이미지 오류",
+        ("Image error", "vi") => "Lỗi hình ảnh",
+        ("Unknown Error", "ja") => "不明なエラー",
+        ("Unknown Error", "zh") => "未知错误",
+        ("Unknown Error", "ko") => "알 수 없는 오류",
+        ("Unknown Error", "vi") => "Lỗi không xác định",
+        _ => return None,
+    })
+}
+
+fn primary_language(lang: &str) -> String {
+    lang.split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// System fonts known to cover each language, tried in order when the embedded font lacks
+/// some of the glyphs.
+fn font_families_for_lang(lang: &str) -> &'static [&'static str] {
+    match primary_language(lang).as_str() {
+        "ja" => &[
+            "Noto Sans CJK JP",
+            "Noto Sans JP",
+            "Source Han Sans JP",
+            "IPAGothic",
+        ],
+        "zh" => &[
+            "Noto Sans CJK SC",
+            "Noto Sans SC",
+            "Source Han Sans SC",
+            "WenQuanYi Micro Hei",
+        ],
+        "ko" => &[
+            "Noto Sans CJK KR",
+            "Noto Sans KR",
+            "Source Han Sans KR",
+            "NanumGothic",
+        ],
+        _ => &["Noto Sans", "DejaVu Sans"],
+    }
+}
+
+fn covers(font: &ab_glyph::FontArc, text: &str) -> bool {
+    use ab_glyph::Font;
+
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .all(|c| font.glyph_id(c).0 != 0)
+}
+
+/// Picks a font able to draw `texts` in `lang`: the embedded one if it's enough, otherwise
+/// the first matching system font.
+fn find_font_for(lang: &str, texts: &[&str]) -> Option<ab_glyph::FontArc> {
+    use font_kit::{family_name::FamilyName, properties::Properties, source::SystemSource};
+
+    if let Some(embedded) = embedded_font() {
+        if texts.iter().all(|text| covers(&embedded, text)) {
+            return Some(embedded);
+        }
+    }
+
+    let source = SystemSource::new();
+    font_families_for_lang(lang).iter().find_map(|family| {
+        let font = source
+            .select_best_match(&[FamilyName::Title(family.to_string())], &Properties::new())
+            .ok()?
+            .load()
+            .ok()?;
+        let font = ab_glyph::FontArc::try_from_vec(font.copy_font_data()?.to_vec()).ok()?;
+
+        texts.iter().all(|text| covers(&font, text)).then_some(font)
+    })
+}
+
+#[cfg(feature = "bundled_error_font")]
+fn embedded_font() -> Option<ab_glyph::FontArc> {
+    let font_data = include_bytes!("../fonts/DejaVuSansMono.ttf") as &[u8];
+
+    Some(ab_glyph::FontArc::try_from_slice(font_data).unwrap())
+}
+
+#[cfg(not(feature = "bundled_error_font"))]
+fn embedded_font() -> Option<ab_glyph::FontArc> {
+    None
+}
+
 pub fn generate_error_image(
     status_or_code: &str,
     msg: &str,
+    lang: Option<&str>,
     width: u32,
     height: u32,
 ) -> anyhow::Result<Vec<u8>> {
-    use ab_glyph::{FontArc, PxScale};
+    use ab_glyph::PxScale;
     use image::{ImageBuffer, Rgba};
     use imageproc::drawing::draw_text_mut;
 
     let mut img = ImageBuffer::from_pixel(width, height, Rgba([255, 255, 255, 255]));
 
-    let font_data = include_bytes!("../fonts/DejaVuSansMono.ttf") as &[u8];
-    let font = FontArc::try_from_vec(font_data.to_vec()).unwrap();
-
-    let title_scale = PxScale { x: 28.0, y: 28.0 };
-    let msg_scale = PxScale { x: 20.0, y: 20.0 };
-
-    let title = format!("ERROR {}", status_or_code);
+    let localized = lang.and_then(|lang| {
+        let title = format!(
+            "{} {}",
+            translate_error_text("ERROR", lang)?,
+            translate_error_text(status_or_code, lang).unwrap_or(status_or_code)
+        );
+        let msg = translate_error_text(msg, lang).unwrap_or(msg).to_owned();
+        let font = find_font_for(lang, &[&title, &msg])?;
+
+        Some((title, msg, font))
+    });
+    // Without a font for the locale, stick to ASCII so that the embedded font can draw
+    // every character. Without an embedded font either, the image is left blank.
+    let text = localized.or_else(|| {
+        let ascii = |text: &str| {
+            text.chars()
+                .map(|c| if c.is_ascii() { c } else { '?' })
+                .collect::<String>()
+        };
 
-    draw_text_mut(
-        &mut img,
-        Rgba([0, 0, 0, 255]),
-        20,
-        20,
-        title_scale,
-        &font,
-        &title,
-    );
+        Some((
+            ascii(&format!("ERROR {}", status_or_code)),
+            ascii(msg),
+            embedded_font()?,
+        ))
+    });
 
-    let wrapped = wrap_text(msg, 46);
+    if let Some((title, msg, font)) = text {
+        let title_scale = PxScale { x: 28.0, y: 28.0 };
+        let msg_scale = PxScale { x: 20.0, y: 20.0 };
 
-    let mut y = 60;
-    for line in wrapped {
         draw_text_mut(
             &mut img,
             Rgba([0, 0, 0, 255]),
             20,
-            y,
-            msg_scale,
+            20,
+            title_scale,
             &font,
-            &line,
+            &title,
         );
-        y += 26;
+
+        let wrapped = wrap_text(&msg, 46);
+
+        let mut y = 60;
+        for line in wrapped {
+            draw_text_mut(
+                &mut img,
+                Rgba([0, 0, 0, 255]),
+                20,
+                y,
+                msg_scale,
+                &font,
+                &line,
+            );
+            y += 26;
+        }
     }
 
     for x in 0..width {
@@ -260,6 +559,148 @@ pub async fn prepare_cover(
     }
 }
 
+/// Detects the MIME type of an image from its magic numbers.
+pub fn detect_image_mime(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'B', b'M', ..] => Some("image/bmp"),
+        // RIFF container, the actual format lives in bytes 8..12.
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        // ISO BMFF container, `ftyp` box followed by the major brand.
+        [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f' | b's', ..] => {
+            Some("image/avif")
+        }
+        _ => None,
+    }
+}
+
+fn image_mime_from_extension(url: &Url) -> Option<&'static str> {
+    image_mime_for_extension(url.path().rsplit_once('.')?.1)
+}
+
+/// The MIME type of images with the `extension` file extension, if it's a known image type.
+pub fn image_mime_for_extension(extension: &str) -> Option<&'static str> {
+    match extension.to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "gif" => Some("image/gif"),
+        "bmp" => Some("image/bmp"),
+        "webp" => Some("image/webp"),
+        "avif" => Some("image/avif"),
+        _ => None,
+    }
+}
+
+fn image_extension_from_mime(mime: &str) -> &'static str {
+    match mime {
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/bmp" => "bmp",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        _ => "jpg",
+    }
+}
+
+/// Returns the file name a `Content-Disposition` header value suggests, preferring the
+/// RFC 5987 `filename*` parameter (e.g. `filename*=UTF-8''page%201.png`) over `filename`.
+pub fn content_disposition_filename(value: &str) -> Option<String> {
+    let mut filename = None;
+    let mut extended_filename = None;
+    for parameter in value.split(';') {
+        let Some((key, value)) = parameter.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+
+        match key.trim().to_ascii_lowercase().as_str() {
+            "filename" => {
+                filename = Some(
+                    value
+                        .strip_prefix('"')
+                        .and_then(|value| value.strip_suffix('"'))
+                        .unwrap_or(value)
+                        .replace("\\\"", "\""),
+                )
+            }
+            "filename*" => {
+                // charset'language'percent-encoded value
+                if let Some(encoded) = value.splitn(3, '\'').nth(2) {
+                    extended_filename = Some(
+                        percent_encoding::percent_decode_str(encoded)
+                            .decode_utf8_lossy()
+                            .into_owned(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Only the name is of use, not where the server would put it
+    extended_filename
+        .or(filename)
+        .and_then(|name| name.rsplit(['/', '\\']).next().map(str::to_owned))
+        .filter(|name| !name.is_empty())
+}
+
+/// Downloads an image embedded in a novel chapter, retrying up to 3 times.
+///
+/// The returned MIME type is detected from the image contents, falling back to the URL
+/// extension and then to `image/jpeg`.
+pub async fn fetch_novel_image(
+    url: &Url,
+    source: &Source,
+    client: &Client,
+) -> Result<(Vec<u8>, &'static str)> {
+    let mut last_err = None;
+
+    for attempt in 1..=3 {
+        let result = async {
+            let request = source
+                .get_image_request(url.clone(), None)
+                .await
+                .map_err(|err| anyhow!("failed WASM modify request {err}"))?;
+            let req_url = request.url().clone();
+
+            let response = request_with_forced_referer_from_request(client, request, 10, None)
+                .await
+                .map_err(|err| anyhow!("Request error: {err}"))?
+                .error_for_status()?;
+
+            let bytes = response
+                .bytes()
+                .await
+                .with_context(|| format!("failed to get bytes from {req_url}"))?;
+
+            Ok::<_, anyhow::Error>(bytes.to_vec())
+        }
+        .await;
+
+        match result {
+            Ok(bytes) => {
+                let mime = detect_image_mime(&bytes)
+                    .or_else(|| image_mime_from_extension(url))
+                    .unwrap_or("image/jpeg");
+
+                return Ok((bytes, mime));
+            }
+            Err(err) => {
+                last_err = Some(err);
+
+                if attempt < 3 {
+                    tokio::time::sleep(tokio::time::Duration::from_millis(200 * attempt as u64))
+                        .await;
+                }
+            }
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| anyhow!("failed to download {url}")))
+}
+
 pub async fn download_image(
     url: String,
     index: usize,
@@ -270,7 +711,7 @@ pub async fn download_image(
         bail!("Invalid URL: {}", url);
     };
 
-    let bytes_vec = if url.scheme() == "data" {
+    let (bytes_vec, mime) = if url.scheme() == "data" {
         // Parse data URI
         let s = url.as_str();
         let Some(comma_idx) = s.find(',') else {
@@ -283,49 +724,24 @@ pub async fn download_image(
             bail!("data URI is not base64 encoded: {}", url);
         }
 
-        BASE64.decode(data_part.as_bytes()).map_err(|err| {
+        let bytes_vec = BASE64.decode(data_part.as_bytes()).map_err(|err| {
             anyhow!(format!(
                 "base64 decode failed for page {}: {:?}",
                 index, err
             ))
-        })?
-    } else {
-        let request = source
-            .get_image_request(url.clone(), None)
-            .await
-            .map_err(|err| anyhow!(format!("failed WASM modify request {err}")))?;
-        let req_url = request.url().clone();
-
-        let response = request_with_forced_referer_from_request(client, request, 10)
-            .await
-            .map_err(|err| anyhow!(format!("Request error: {err}")))?;
+        })?;
+        let mime = detect_image_mime(&bytes_vec).unwrap_or("image/jpeg");
 
-        response
-            .bytes()
+        (bytes_vec, mime)
+    } else {
+        fetch_novel_image(url, source, client)
             .await
-            .map_err(|err| {
-                anyhow!(format!(
-                    "failed to get bytes for page {} from {}: {:?}",
-                    index, req_url, err
-                ))
-            })?
-            .to_vec()
+            .with_context(|| format!("failed to download image for page {index}"))?
     };
 
-    let (ext, mime) = match image::guess_format(&bytes_vec) {
-        Ok(fmt) => {
-            let ext = fmt.extensions_str()[0];
-            let mime = fmt.to_mime_type().to_string();
-            (ext.to_string(), mime)
-        }
-        Err(_) => {
-            let ext = "bin".to_string();
-            let mime = "application/octet-stream".to_string();
-            (ext, mime)
-        }
-    };
+    let ext = image_extension_from_mime(mime);
 
-    Ok((bytes_vec, ext, mime))
+    Ok((bytes_vec, ext.to_string(), mime.to_string()))
 }
 
 pub fn create_xhtml(title: &str, html: &str) -> String {
@@ -354,6 +770,20 @@ pub fn into_html(text: &str) -> String {
     }
 }
 
+/// The URL relative image sources in `document` resolve against: its `<base href>`, itself
+/// resolved against `page_url`, or `page_url` when the page has no usable base tag.
+pub fn get_base_url(document: &Document, page_url: Option<&Url>) -> Option<Url> {
+    let Some(href) = document.select("base[href]").attr("href") else {
+        return page_url.cloned();
+    };
+
+    match page_url {
+        Some(url) => url.join(&href).ok(),
+        None => Url::parse(&href).ok(),
+    }
+    .or_else(|| page_url.cloned())
+}
+
 pub fn get_image_src<F>(base_url: Option<&Url>, get: F) -> Option<String>
 where
     F: Fn(&str) -> Option<String>,
@@ -427,9 +857,12 @@ pub async fn download_all_images(
         if let Some(text) = &page.text {
             let html = into_html(text);
             let document = Document::fragment(html);
+            let page_base_url = get_base_url(&document, base_url);
 
             for img in document.select("img").iter() {
-                if let Some(src) = get_image_src(base_url, |n| img.attr(n).map(|t| t.to_string())) {
+                if let Some(src) = get_image_src(page_base_url.as_ref(), |n| {
+                    img.attr(n).map(|t| t.to_string())
+                }) {
                     if seen.insert(src.clone()) {
                         let url = src.clone();
                         let index = page.index;
@@ -509,6 +942,121 @@ pub async fn download_all_images(
 mod tests {
     use super::*;
 
+    #[test]
+    fn translates_error_texts_by_primary_language() {
+        assert_eq!(
+            translate_error_text("Image error", "ja-JP"),
+            Some("画像エラー")
+        );
+        assert_eq!(translate_error_text("Error", "ZH_hant"), Some("错误"));
+        assert_eq!(translate_error_text("Error", "fr"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "bundled_error_font")]
+    fn falls_back_to_the_embedded_font_for_ascii_text() {
+        let font = find_font_for("en", &["ERROR 404", "Not Found"]);
+
+        assert!(font.is_some());
+        assert!(generate_error_image("404", "Not Found", Some("ko"), 100, 100).is_ok());
+    }
+
+    #[test]
+    fn error_images_are_valid_jpegs() {
+        let bytes = generate_error_image("404", "Not Found", None, 120, 80).unwrap();
+
+        assert_eq!(
+            image::guess_format(&bytes).unwrap(),
+            image::ImageFormat::Jpeg
+        );
+        let img = image::load_from_memory(&bytes).unwrap().to_rgb8();
+        assert_eq!(img.dimensions(), (120, 80));
+
+        // Without the embedded font nothing can be written, so only the border is drawn.
+        #[cfg(not(feature = "bundled_error_font"))]
+        assert!(img
+            .enumerate_pixels()
+            .filter(|(x, y, _)| (2..118).contains(x) && (2..78).contains(y))
+            .all(|(_, _, pixel)| pixel.0.iter().all(|&channel| channel > 240)));
+    }
+
+    #[test]
+    fn reads_preload_hints_from_link_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            reqwest::header::LINK,
+            r#"</pages/2.jpg>; rel=preload; as=image, <https://cdn.test/a,b.jpg>; rel="preload""#
+                .parse()
+                .unwrap(),
+        );
+        headers.append(
+            reqwest::header::LINK,
+            "<https://cdn.test/next>; rel=next, </pages/2.jpg>; rel=preload"
+                .parse()
+                .unwrap(),
+        );
+        let base = Url::parse("https://cdn.test/pages/1.jpg").unwrap();
+
+        let hints: Vec<_> = preload_hints(&headers, &base)
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(
+            hints,
+            vec!["https://cdn.test/pages/2.jpg", "https://cdn.test/a,b.jpg"]
+        );
+    }
+
+    #[test]
+    fn reads_the_content_disposition_filename() {
+        assert_eq!(
+            content_disposition_filename(r#"attachment; filename="page 1.png""#).as_deref(),
+            Some("page 1.png")
+        );
+        assert_eq!(
+            content_disposition_filename("inline; filename=a.webp").as_deref(),
+            Some("a.webp")
+        );
+        assert_eq!(
+            content_disposition_filename(
+                "attachment; filename=fallback.jpg; filename*=UTF-8''%E3%83%9A%E3%83%BC%E3%82%B8.png"
+            )
+            .as_deref(),
+            Some("ページ.png")
+        );
+        assert_eq!(
+            content_disposition_filename(r#"attachment; filename="../../etc/a.gif""#).as_deref(),
+            Some("a.gif")
+        );
+        assert_eq!(content_disposition_filename("attachment"), None);
+    }
+
+    #[test]
+    fn test_detect_image_mime() {
+        assert_eq!(detect_image_mime(b"\xFF\xD8\xFF\xE0"), Some("image/jpeg"));
+        assert_eq!(detect_image_mime(b"\x89PNG\r\n\x1a\n"), Some("image/png"));
+        assert_eq!(
+            detect_image_mime(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some("image/webp")
+        );
+        assert_eq!(
+            detect_image_mime(b"\0\0\0\x1cftypavif\0\0\0\0"),
+            Some("image/avif")
+        );
+        assert_eq!(detect_image_mime(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(detect_image_mime(b""), None);
+    }
+
+    #[test]
+    fn test_image_mime_from_extension() {
+        let url = Url::parse("https://example.com/a/b.WEBP?x=1").unwrap();
+        assert_eq!(image_mime_from_extension(&url), Some("image/webp"));
+
+        let url = Url::parse("https://example.com/a/b").unwrap();
+        assert_eq!(image_mime_from_extension(&url), None);
+    }
+
     #[test]
     fn test_wrap_text_empty() {
         let result = wrap_text("", 10);
@@ -624,6 +1172,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_base_url_prefers_base_tag() {
+        let page = Url::parse("https://example.com/novel/1").unwrap();
+        let document = Document::fragment(
+            r#"<base href="https://cdn.example.com/assets/"><p><img src="a.jpg"></p>"#,
+        );
+
+        assert_eq!(
+            get_base_url(&document, Some(&page)).map(|url| url.to_string()),
+            Some("https://cdn.example.com/assets/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_base_url_resolves_relative_base_tag() {
+        let page = Url::parse("https://example.com/novel/1").unwrap();
+        let document = Document::fragment(r#"<base href="/static/"><img src="a.jpg">"#);
+
+        assert_eq!(
+            get_base_url(&document, Some(&page)).map(|url| url.to_string()),
+            Some("https://example.com/static/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_base_url_falls_back_to_page_url() {
+        let page = Url::parse("https://example.com/novel/1").unwrap();
+        let document = Document::fragment(r#"<img src="a.jpg">"#);
+
+        assert_eq!(get_base_url(&document, Some(&page)), Some(page));
+    }
+
     #[test]
     fn test_get_image_src_no_match() {
         let result = get_image_src(None, |_| None);
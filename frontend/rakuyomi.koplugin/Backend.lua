@@ -240,10 +240,12 @@ end
 --- @field volume_num number? The volume that this chapter belongs to, if known.
 --- @field read boolean If this chapter was read to its end.
 --- @field last_read number? The timestamp (in seconds since epoch) of when this chapter was last read to its end.
+--- @field last_read_page number? The page (counted from 1) this chapter was left at, if known.
 --- @field downloaded boolean If this chapter was already downloaded to the storage.
 --- @field title string? The title of this chapter, if any.
 --- @field locked boolean The locked
 --- @field lang string? The language code
+--- @field url string? The URL of this chapter on the source's website, if known.
 --- @field on_tmpfs boolean? The chapter is stored in tmpfs.
 --- @field file string? The file path of the chapter (only use in frontend).
 
@@ -252,7 +254,7 @@ end
 --- @field mangas Manga[] Found mangas.
 
 --- @class FileSummary
---- @field filenames string[] The names
+--- @field filenames string[] The paths, relative to the downloads folder
 --- @field total_size number The total size
 --- @field total_text string The total size text format kb, mb...
 
@@ -383,7 +385,7 @@ function Backend.findOrphanOrReadFiles(modeInvalid)
 end
 
 --- Delete file
---- @param filename string The name of the file to delete.
+--- @param filename string The path of the file to delete, relative to the downloads folder.
 --- @return SuccessfulResponse<nil>|ErrorResponse
 function Backend.removeFile(filename)
   return Backend.requestJson({
@@ -455,10 +457,22 @@ function Backend.searchMangas(cancel_id, search_text, exclude, page)
 end
 
 --- Lists chapters from a given manga that are already cached into the database.
+--- @param filter { read: boolean|nil, downloaded: boolean|nil }|nil Only lists chapters in these states.
 --- @return SuccessfulResponse<Chapter[]>|ErrorResponse
-function Backend.listCachedChapters(source_id, manga_id)
+function Backend.listCachedChapters(source_id, manga_id, filter)
+  local query_params = {}
+  if filter ~= nil then
+    if filter.read ~= nil then
+      query_params.read = tostring(filter.read)
+    end
+    if filter.downloaded ~= nil then
+      query_params.downloaded = tostring(filter.downloaded)
+    end
+  end
+
   return Backend.requestJson({
     path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/chapters",
+    query_params = query_params,
   })
 end
 
@@ -510,37 +524,6 @@ function Backend.markChaptersAsRead(source_id, manga_id, range, state)
   })
 end
 
---- Begins downloading all chapters from a given manga to the storage.
---- @return SuccessfulResponse<nil>|ErrorResponse
-function Backend.downloadAllChapters(source_id, manga_id)
-  return Backend.requestJson({
-    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/chapters/download-all",
-    method = "POST",
-  })
-end
-
---- @alias DownloadAllChaptersProgress { type: 'INITIALIZING' }|{ type: 'PROGRESSING', downloaded: number, total: number }|{ type: 'FINISHED' }|{ type: 'CANCELLED' }
-
---- Checks the status of a "download all chapters" operation.
---- @return SuccessfulResponse<DownloadAllChaptersProgress>|ErrorResponse
-function Backend.getDownloadAllChaptersProgress(source_id, manga_id)
-  return Backend.requestJson({
-    path = "/mangas/" ..
-        source_id .. "/" .. util.urlEncode(manga_id) .. "/chapters/download-all-progress",
-  })
-end
-
---- Requests cancellation of a "download all chapters" operation. This can only be called
---- when the operation status is `PROGRESSING`.
---- @return SuccessfulResponse<nil>|ErrorResponse
-function Backend.cancelDownloadAllChapters(source_id, manga_id)
-  return Backend.requestJson({
-    path = "/mangas/" ..
-        source_id .. "/" .. util.urlEncode(manga_id) .. "/chapters/cancel-download-all",
-    method = "POST",
-  })
-end
-
 --- @class DownloadError
 --- @field page_index number
 --- @field url string
@@ -570,6 +553,24 @@ function Backend.downloadChapter(cancel_id, source_id, manga_id, chapter_id, cha
   })
 end
 
+--- @class DownloadProgress
+--- @field pages_total number
+--- @field pages_done number
+--- @field started_at number
+--- @field status 'downloading'|'persisting'
+
+--- Returns the progress of the chapter's download, or nil if it is not being downloaded.
+--- @param source_id string
+--- @param manga_id string
+--- @param chapter_id string
+--- @return SuccessfulResponse<DownloadProgress|nil>|ErrorResponse
+function Backend.getDownloadProgress(source_id, manga_id, chapter_id)
+  return Backend.requestJson({
+    path = "/mangas/" ..
+        source_id .. "/" .. util.urlEncode(manga_id) .. "/chapters/" .. util.urlEncode(chapter_id) .. "/download-progress",
+  })
+end
+
 --- @param source_id string
 --- @param manga_id string
 --- @param chapter_id string
@@ -591,12 +592,14 @@ function Backend.revokeChapter(source_id, manga_id, chapter_id, use_ram)
 end
 
 --- Updates the last read position for the chapter.
+--- @param last_read_page number|nil The page the chapter was left at; keeps the stored one if `nil`.
 --- @return SuccessfulResponse<nil>|ErrorResponse
-function Backend.updateLastReadChapter(source_id, manga_id, chapter_id)
+function Backend.updateLastReadChapter(source_id, manga_id, chapter_id, last_read_page)
   return Backend.requestJson({
     path = "/mangas/" ..
         source_id .. "/" .. util.urlEncode(manga_id) .. "/chapters/" .. util.urlEncode(chapter_id) .. "/update-last-read",
     method = "POST",
+    body = last_read_page and { last_read_page = last_read_page } or nil,
   })
 end
 
@@ -620,6 +623,16 @@ function Backend.listInstalledSources()
   })
 end
 
+--- @class SourceDetails: { id: string, name: string, lang: string|nil, version: number, url: string|nil, min_app_version: string|nil, source_of_source: string|nil, sdk_mode: 'legacy'|'next', features: { process_page_image: boolean, has_handle_notification: boolean } }
+
+--- Lists the installed sources with their manifest metadata and the SDK mode they were loaded with.
+--- @return SuccessfulResponse<SourceDetails[]>|ErrorResponse
+function Backend.listSources()
+  return Backend.requestJson({
+    path = "/sources",
+  })
+end
+
 --- Lists information about sources available via our source lists.
 --- @return SuccessfulResponse<SourceInformation[]>|ErrorResponse
 function Backend.listAvailableSources()
@@ -638,6 +651,85 @@ function Backend.installSource(source_id, source_of_source)
   })
 end
 
+--- Logs in to a source with a username and password.
+--- @return SuccessfulResponse<{ success: boolean, message: string|nil }>|ErrorResponse
+function Backend.loginToSource(source_id, username, password)
+  return Backend.requestJson({
+    path = "/sources/" .. source_id .. "/login",
+    method = "POST",
+    body = {
+      username = username,
+      password = password,
+    },
+  })
+end
+
+--- Resolves a link to the source's website into the manga (and chapter) it points to.
+--- @return SuccessfulResponse<{ manga_id: string, chapter_id: string|nil }>|ErrorResponse
+function Backend.handleDeepLink(source_id, url)
+  return Backend.requestJson({
+    path = "/sources/" .. source_id .. "/handle-deep-link",
+    method = "POST",
+    body = {
+      url = url,
+    },
+  })
+end
+
+--- Lists the names of the listings (e.g. "Popular" or "Latest") a source offers.
+--- @return SuccessfulResponse<string[]>|ErrorResponse
+function Backend.getSourceListings(source_id)
+  return Backend.requestJson({
+    path = "/sources/" .. source_id .. "/listings",
+  })
+end
+
+--- Browses one of the source's listings, or its default one when `listing` is nil.
+--- @param listing string|nil
+--- @return SuccessfulResponse<Manga[]>|ErrorResponse
+function Backend.getSourceMangaList(source_id, listing)
+  return Backend.requestJson({
+    path = "/sources/" .. source_id .. "/mangas",
+    query_params = {
+      listing = listing,
+    },
+  })
+end
+
+--- Lists the tags (genres) a source can browse mangas by.
+--- @return SuccessfulResponse<string[]>|ErrorResponse
+function Backend.getSourceTags(source_id)
+  return Backend.requestJson({
+    path = "/sources/" .. source_id .. "/tags",
+  })
+end
+
+--- @class BandwidthStats
+--- @field bytes_sent number Bytes of request bodies sent since the source was loaded.
+--- @field bytes_received number Bytes of response bodies received since the source was loaded.
+--- @field reset_at number When counting started, as a unix timestamp.
+
+--- Gets how much a source sent and received since it was (re)loaded.
+--- @return SuccessfulResponse<BandwidthStats>|ErrorResponse
+function Backend.getSourceBandwidthStats(source_id)
+  return Backend.requestJson({
+    path = "/sources/" .. source_id .. "/bandwidth-stats",
+  })
+end
+
+--- Lists a source's mangas tagged with `tag`.
+--- @param page number|nil
+--- @return SuccessfulResponse<Manga[]>|ErrorResponse
+function Backend.getSourceMangasByTag(source_id, tag, page)
+  return Backend.requestJson({
+    path = "/sources/" .. source_id .. "/tags/mangas",
+    query_params = {
+      tag = tag,
+      page = page,
+    },
+  })
+end
+
 --- Uninstalls a source.
 --- @return SuccessfulResponse<nil>|ErrorResponse
 function Backend.uninstallSource(source_id)
@@ -655,9 +747,10 @@ end
 --- @class ButtonSettingDefinition: { type: 'button', title: string, key: string, confirmTitle: string|nil, confirmMessage: string|nil  }
 --- @class EditableListSettingDefinition: { type: 'editable-list', title: string, key: string, values: string[], titles: string[]|nil, default: string[]  }
 --- @class TextSettingDefinition: { type: 'text', placeholder: string|nil, key: string, default: string|nil }
+--- @class SecureInputSettingDefinition: { type: 'secure-input', placeholder: string|nil, key: string, default: string|nil, isSecure: boolean }
 --- @class LinkSettingDefinition: { type: 'link', title: string, url: string }
 
---- @alias SettingDefinition GroupSettingDefinition|SwitchSettingDefinition|SelectSettingDefinition|MultiSelectSettingDefinition|LoginSettingDefinition|ButtonSettingDefinition|EditableListSettingDefinition|TextSettingDefinition|LinkSettingDefinition
+--- @alias SettingDefinition GroupSettingDefinition|SwitchSettingDefinition|SelectSettingDefinition|MultiSelectSettingDefinition|LoginSettingDefinition|ButtonSettingDefinition|EditableListSettingDefinition|TextSettingDefinition|SecureInputSettingDefinition|LinkSettingDefinition
 
 --- Lists the setting definitions for a given source.
 --- @return SuccessfulResponse<SettingDefinition[]>|ErrorResponse
@@ -683,23 +776,51 @@ function Backend.setSourceStoredSettings(source_id, stored_settings)
   })
 end
 
---- Gets the preferred scanlator for a manga.
---- @return SuccessfulResponse<string|nil>|ErrorResponse
-function Backend.getPreferredScanlator(source_id, manga_id)
+--- Gets the preferred scanlators for a manga, in order of preference.
+--- @return SuccessfulResponse<string[]>|ErrorResponse
+function Backend.getPreferredScanlators(source_id, manga_id)
+  return Backend.requestJson({
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/preferred-scanlators",
+    method = "GET"
+  })
+end
+
+--- Gets the tags of a manga, from its cached details.
+--- @return SuccessfulResponse<string[]>|ErrorResponse
+function Backend.getMangaTags(source_id, manga_id)
+  return Backend.requestJson({
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/tags",
+    method = "GET"
+  })
+end
+
+--- Gets how much space the downloaded chapters of a manga take.
+--- @return SuccessfulResponse<{ bytes: number, human: string }>|ErrorResponse
+function Backend.getMangaDiskUsage(source_id, manga_id)
   return Backend.requestJson({
-    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/preferred-scanlator",
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/disk-usage",
     method = "GET"
   })
 end
 
---- Sets the preferred scanlator for a manga.
+--- Gets every tag of the mangas in the library, sorted.
+--- @return SuccessfulResponse<string[]>|ErrorResponse
+function Backend.getLibraryTags()
+  return Backend.requestJson({
+    path = "/tags",
+  })
+end
+
+--- Sets the preferred scanlators for a manga, in order of preference. Passing `nil` clears
+--- the preference, showing every scanlator.
+--- @param preferred_scanlators string[]|nil
 --- @return SuccessfulResponse<nil>|ErrorResponse
-function Backend.setPreferredScanlator(source_id, manga_id, preferred_scanlator)
+function Backend.setPreferredScanlators(source_id, manga_id, preferred_scanlators)
   return Backend.requestJson({
-    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/preferred-scanlator",
+    path = "/mangas/" .. source_id .. "/" .. util.urlEncode(manga_id) .. "/preferred-scanlators",
     method = "POST",
     body = {
-      preferred_scanlator = preferred_scanlator
+      preferred_scanlators = preferred_scanlators
     }
   })
 end
@@ -1013,6 +1134,32 @@ function Backend.requestJobCancellation(id)
   })
 end
 
+--- @class QueuedChapter: { source_id: string, manga_id: string, chapter_id: string }
+
+--- Adds chapters to the background download queue. Returns how many chapters were enqueued.
+--- @param chapters QueuedChapter[]
+--- @return SuccessfulResponse<number>|ErrorResponse
+function Backend.enqueueChapterDownloads(chapters)
+  return Backend.requestJson({
+    path = "/download-queue/enqueue",
+    method = 'POST',
+    body = chapters,
+  })
+end
+
+--- Cancels queued chapter downloads. Cancels the whole queue when `chapters` is nil.
+--- @param chapters QueuedChapter[]|nil
+--- @return SuccessfulResponse<nil>|ErrorResponse
+function Backend.cancelChapterDownloads(chapters)
+  return Backend.requestJson({
+    path = "/download-queue/cancel",
+    method = 'DELETE',
+    body = {
+      chapters = chapters,
+    },
+  })
+end
+
 --- @class UpdateInfo
 --- @field public available boolean Whether an update is available
 --- @field public current_version string The current version of rakuyomi
@@ -1075,12 +1222,39 @@ end
 --- @field chapter_title string|nil
 --- @field chapter_number number
 --- @field created_at number
+--- @field downloaded boolean
 
---- @return SuccessfulResponse<Notification[]>|ErrorResponse
-function Backend.getNotifications()
+--- @class NotificationPage
+--- @field items Notification[]
+--- @field next_cursor number|nil The `before_id` of the next page, if there is one.
+
+--- @param limit number|nil
+--- @param before_id number|nil
+--- @return SuccessfulResponse<NotificationPage>|ErrorResponse
+function Backend.getNotifications(limit, before_id)
   return Backend.requestJson({
     path = "/notifications",
-    method = 'GET'
+    method = 'GET',
+    query_params = {
+      limit = limit,
+      before_id = before_id,
+    },
+  })
+end
+
+--- @class UpdateScheduleEntry
+--- @field manga_id string
+--- @field source_id string
+--- @field manga_title string|nil
+--- @field last_check_at number
+--- @field next_check_at number
+--- @field next_check_human string e.g. "in 2 days" or "overdue by 1 hour".
+
+--- Lists when each manga will next be checked for new chapters, soonest first.
+--- @return SuccessfulResponse<UpdateScheduleEntry[]>|ErrorResponse
+function Backend.getUpdateSchedule()
+  return Backend.requestJson({
+    path = "/update-schedule",
   })
 end
 
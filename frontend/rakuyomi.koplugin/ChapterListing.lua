@@ -278,16 +278,21 @@ end
 -- Load saved scanlator preference from backend
 function ChapterListing:loadSavedScanlatorPreference()
   if self._scanlator_loaded then return end
-  local response = Backend.getPreferredScanlator(self.manga.source.id, self.manga.id)
+  local response = Backend.getPreferredScanlators(self.manga.source.id, self.manga.id)
 
   self.selected_scanlator = nil
   self._scanlator_loaded = true
 
-  if response.type == 'SUCCESS' and response.body then
+  if response.type ~= 'SUCCESS' or not response.body then
+    return
+  end
+
+  -- Fall back through the preferences until one of them has chapters for this manga
+  for _, preferred_scanlator in ipairs(response.body) do
     for _, available_scanlator in ipairs(self.available_scanlators) do
-      if available_scanlator == response.body then
-        self.selected_scanlator = response.body
-        break
+      if available_scanlator == preferred_scanlator then
+        self.selected_scanlator = preferred_scanlator
+        return
       end
     end
   end
@@ -1048,12 +1053,15 @@ function ChapterListing:openChapterOnReader(chapter, download_job, on_opened)
             true
       end,
       on_open_chapter = openChapter,
-      on_close_book_callback = function(chapter_self)
+      -- Chapters read to the end start over, the others continue where they were left.
+      start_page = not chapter.read and chapter.last_read_page or nil,
+      on_close_book_callback = function(chapter_self, current_page)
         Trapper:wrap(function()
           Backend.updateLastReadChapter(
             chapter_self.source_id,
             chapter_self.manga_id,
-            chapter_self.id
+            chapter_self.id,
+            current_page
           )
         end)
       end,
@@ -1174,6 +1182,16 @@ function ChapterListing:openMenu()
           self:onDownloadUnreadChapters()
         end
       }
+    },
+    {
+      {
+        text = Icons.FA_DOWNLOAD .. " " .. _("Download all chapters"),
+        callback = function()
+          UIManager:close(dialog)
+
+          self:onDownloadAllChapters()
+        end
+      }
     }
   }
 
@@ -1285,7 +1303,7 @@ function ChapterListing:showScanlatorDialog()
         UIManager:close(dialog)
         self.selected_scanlator = nil
 
-        Backend.setPreferredScanlator(self.manga.source.id, self.manga.id, nil)
+        Backend.setPreferredScanlators(self.manga.source.id, self.manga.id, nil)
         self._scanlator_loaded = false
 
         self:updateItems()
@@ -1306,7 +1324,7 @@ function ChapterListing:showScanlatorDialog()
           UIManager:close(dialog)
           self.selected_scanlator = scanlator
 
-          Backend.setPreferredScanlator(self.manga.source.id, self.manga.id, scanlator)
+          Backend.setPreferredScanlators(self.manga.source.id, self.manga.id, { scanlator })
           self._scanlator_loaded = false
 
           self:updateItems()
@@ -1398,134 +1416,53 @@ function ChapterListing:createDownloadJob(amount)
 end
 
 function ChapterListing:onDownloadAllChapters()
-  local downloadingMessage = InfoMessage:new {
-    text = _("Downloading all chapters, this will take a while…"),
-  }
-
-  UIManager:show(downloadingMessage)
-
-  -- FIXME when the backend functions become actually async we can get rid of this probably
-  UIManager:nextTick(function()
-    local time = require("ui/time")
-    local startTime = time.now()
-    local response = Backend.downloadAllChapters(self.manga.source.id, self.manga.id)
-
-    if response.type == 'ERROR' then
-      ErrorDialog:show(response.message)
-
-      return
-    end
-
-    local onDownloadFinished = function()
-      -- FIXME I don't think mutating the chapter list here is the way to go, but it's quicker
-      -- than making another call to list the chapters from the backend...
-      -- this also behaves wrong when the download fails but manages to download some chapters.
-      -- some possible alternatives:
-      -- - return the chapter list from the backend on the `downloadAllChapters` call
-      -- - biting the bullet and making the API call
-      for __, chapter in ipairs(self.chapters) do
-        chapter.downloaded = true
-      end
-
-      logger.info("Downloaded all chapters in ", time.to_ms(time.since(startTime)), "ms")
-
-      self:updateItems()
+  --- @type QueuedChapter[]
+  local queued_chapters = {}
+  for __, chapter in ipairs(self.chapters) do
+    if not chapter.downloaded and not chapter.locked then
+      table.insert(queued_chapters, {
+        source_id = chapter.source_id,
+        manga_id = chapter.manga_id,
+        chapter_id = chapter.id,
+      })
     end
+  end
 
-    local updateProgress = function() end
-
-    local cancellationRequested = false
-    local onCancellationRequested = function()
-      local response_s = Backend.cancelDownloadAllChapters(self.manga.source.id, self.manga.id)
-      -- FIXME is it ok to assume there are no errors here?
-      assert(response_s.type == 'SUCCESS')
-
-      cancellationRequested = true
-
-      updateProgress()
-    end
+  if #queued_chapters == 0 then
+    UIManager:show(InfoMessage:new {
+      text = _("All chapters are already downloaded."),
+      timeout = 2,
+    })
 
-    local onCancelled = function()
-      local cancelledMessage = InfoMessage:new {
-        text = _("Cancelled."),
-      }
+    return
+  end
 
-      UIManager:show(cancelledMessage)
-    end
+  -- The backend downloads queued chapters in parallel, in the background.
+  local response = Backend.enqueueChapterDownloads(queued_chapters)
+  if response.type == 'ERROR' then
+    ErrorDialog:show(response.message)
 
-    updateProgress = function()
-      -- Remove any scheduled `updateProgress` calls, because we do not want this to be
-      -- called again if not scheduled by ourselves. This may happen when `updateProgress` is called
-      -- from another place that's not from the scheduler (eg. the `onCancellationRequested` handler),
-      -- which could result in an additional `updateProgress` call that was already scheduled previously,
-      -- even if we do not schedule it at the end of the method.
-      UIManager:unschedule(updateProgress)
-      UIManager:close(downloadingMessage)
+    return
+  end
 
-      local response_s = Backend.getDownloadAllChaptersProgress(self.manga.source.id, self.manga.id)
+  local confirm_dialog
+  confirm_dialog = ConfirmBox:new {
+    text = _("Queued chapters for download") .. ": " .. response.body,
+    ok_text = _("OK"),
+    cancel_text = _("Cancel downloads"),
+    ok_callback = function()
+      UIManager:close(confirm_dialog)
+    end,
+    cancel_callback = function()
+      UIManager:close(confirm_dialog)
+
+      local response_s = Backend.cancelChapterDownloads(queued_chapters)
       if response_s.type == 'ERROR' then
         ErrorDialog:show(response_s.message)
-
-        return
-      end
-
-      local downloadProgress = response_s.body
-
-      local messageText = nil
-      local isCancellable = false
-      if downloadProgress.type == "INITIALIZING" then
-        messageText = _("Downloading all chapters, this will take a while…")
-      elseif downloadProgress.type == "FINISHED" then
-        onDownloadFinished()
-
-        return
-      elseif downloadProgress.type == "CANCELLED" then
-        onCancelled()
-
-        return
-      elseif cancellationRequested then
-        messageText = _("Waiting for download to be cancelled…")
-      elseif downloadProgress.type == "PROGRESSING" then
-        messageText = _("Downloading all chapters, this will take a while… (") ..
-            downloadProgress.downloaded .. "/" .. downloadProgress.total .. ")." ..
-            "\n\n" ..
-            _("Tap outside this message to cancel.")
-
-        isCancellable = true
-      else
-        logger.err("unexpected download progress message", downloadProgress)
-
-        error("unexpected download progress message")
       end
-
-      downloadingMessage = InfoMessage:new {
-        text = messageText,
-        dismissable = isCancellable,
-      }
-
-      -- Override the default `onTapClose`/`onAnyKeyPressed` actions
-      if isCancellable then
-        local originalOnTapClose = downloadingMessage.onTapClose
-        downloadingMessage.onTapClose = function(messageSelf)
-          onCancellationRequested()
-
-          originalOnTapClose(messageSelf)
-        end
-
-        local originalOnAnyKeyPressed = downloadingMessage.onAnyKeyPressed
-        downloadingMessage.onAnyKeyPressed = function(messageSelf)
-          onCancellationRequested()
-
-          originalOnAnyKeyPressed(messageSelf)
-        end
-      end
-      UIManager:show(downloadingMessage)
-
-      UIManager:scheduleIn(1, updateProgress)
     end
-
-    UIManager:scheduleIn(1, updateProgress)
-  end)
+  }
+  UIManager:show(confirm_dialog)
 end
 
 return ChapterListing
@@ -1010,6 +1010,8 @@ function LibraryView:openMenu()
             msg = _("Database has been synced to the server!")
           elseif response.body == 'updated' then
             msg = _("Local database has been migrated from the server!")
+          elseif response.body == 'merged' then
+            msg = _("Database has been merged with the server!")
           else
             msg = _("Sync completed!")
           end
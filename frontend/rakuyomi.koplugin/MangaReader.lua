@@ -20,7 +20,8 @@ local Testing = require('testing')
 --- @field on_open_chapter fun(chapter: Chapter): nil
 --- @field on_end_of_book_callback? fun(no_as_read: boolean): nil
 --- @field on_beginning_of_book_callback? fun(): nil
---- @field on_close_book_callback? fun(Chapter): nil
+--- @field on_close_book_callback? fun(Chapter, number|nil): nil
+--- @field start_page number|nil
 --- This is a singleton that contains a simpler interface with ReaderUI.
 local MangaReader = {
   on_return_callback = nil,
@@ -42,7 +43,8 @@ local MangaReader = {
 --- @field chapters Chapter[] List chapters filtered by ChapterListing
 --- @field viewer MangaViewer The preferred viewer mode from the source ("DefaultViewer", "Rtl", "Ltr", "Vertical", "Scroll").
 --- @field state_viewer boolean The viewer set by user?
---- @field on_close_book_callback? fun(Chapter): nil Function to be called when the user closes the manga reader.
+--- @field on_close_book_callback? fun(Chapter, number|nil): nil Function to be called with the chapter and the current page when the user closes the manga reader.
+--- @field start_page? number Page to open the file at, instead of the first one.
 
 --- Displays the file located in `path` in the KOReader's reader.
 --- If a file is already being displayed, it will be replaced.
@@ -65,6 +67,7 @@ function MangaReader:show(options)
   end
   self.state_viewer = options.state_viewer
   self.on_close_book_callback = options.on_close_book_callback
+  self.start_page = options.start_page
   local c_showing = self.is_showing
 
   -- move set self.is_showing function Rakuyomi:init call initializeFromReaderUI maybe random call sort
@@ -103,6 +106,11 @@ function MangaReader:initializeFromReaderUI(ui)
 
     ui:registerPostInitCallback(function()
       self:hookWithPriorityOntoReaderUiEvents(ui)
+
+      if self.start_page ~= nil then
+        ui:handleEvent(Event:new("GotoPage", self.start_page))
+        self.start_page = nil
+      end
     end)
   end
 end
@@ -121,7 +129,7 @@ function MangaReader:hookWithPriorityOntoReaderUiEvents(ui)
     return self:onEndOfBook()
   end
   eventListener.onCloseWidget = function()
-    self:onReaderUiCloseWidget()
+    self:onReaderUiCloseWidget(ui)
   end
   eventListener.onSetRakuViewMode = function()
     self.viewer = ui.document.configurable.rakuyomi_view_mode
@@ -227,6 +235,7 @@ function MangaReader:clean()
   self.on_end_of_book_callback = nil
   self.on_beginning_of_book_callback = nil
   self.on_close_book_callback = nil
+  self.start_page = nil
 end
 
 --- To be called when the last page of the manga is read.
@@ -249,13 +258,15 @@ function MangaReader:onBeginningOfBook()
 end
 
 --- @private
-function MangaReader:onReaderUiCloseWidget()
+--- @param ui unknown The `ReaderUI` instance being closed.
+function MangaReader:onReaderUiCloseWidget(ui)
   if self.is_switching_document then
     return
   end
 
   if self.on_close_book_callback ~= nil then
-    self.on_close_book_callback(self.chapter)
+    local current_page = ui.document ~= nil and ui:getCurrentPage() or nil
+    self.on_close_book_callback(self.chapter, current_page)
   end
 
   self.is_showing = false
@@ -10,6 +10,7 @@ local UIManager = require("ui/uimanager")
 local _ = require("gettext+")
 local ChapterListing = require("ChapterListing")
 local InfoMessage = require("ui/widget/infomessage")
+local Icons = require("Icons")
 
 local MenuItemCover = require("patch/MenuItemCover")
 local MenuCustom = require("patch/MenuCustom")
@@ -21,6 +22,7 @@ local Screen = Device.screen
 
 --- @class NotificationView : Menu
 --- @field notifications Notification[]
+--- @field next_cursor number|nil
 --- @field title_bar any
 --- @field is_borderless boolean
 --- @field no_title boolean
@@ -38,6 +40,8 @@ local NotificationView = MenuCustom:extend {
   with_context_menu = true,
 
   notifications = nil,
+  -- pagination: the `before_id` of the next page, nil once every notification is shown
+  next_cursor = nil,
   on_return_callback = nil
 }
 
@@ -58,6 +62,7 @@ function NotificationView:init()
     })
 
     self.notifications = {}
+    self.next_cursor = nil
     self:updateItems()
   end
 
@@ -83,6 +88,14 @@ function NotificationView:updateItems(select_number, no_recalculate_dimen)
         text = notify.manga_title,
         post_text = "Ch." .. (notify.chapter_number or "unknown") .. ": " .. notify.chapter_title,
         manga_cover = notify.manga_cover,
+        mandatory = notify.downloaded and Icons.FA_DOWNLOAD or nil,
+      })
+    end
+    if self.next_cursor ~= nil then
+      table.insert(item_table, {
+        text = "(" .. _("Load more") .. ")",
+        load_more = true,
+        bold = true,
       })
     end
     self.item_table = item_table
@@ -108,20 +121,21 @@ function NotificationView:generateEmptyViewItemTable()
   }
 end
 
+local NOTIFICATIONS_PAGE_SIZE = 50
+
 --- @param onReturnCallback fun()
 function NotificationView:fetchAndShow(onReturnCallback)
-  local response = Backend.getNotifications()
+  local response = Backend.getNotifications(NOTIFICATIONS_PAGE_SIZE)
   if response.type == 'ERROR' then
     ErrorDialog:show(response.message)
 
     return
   end
 
-  local notifications = response.body
-
   ---@diagnostic disable-next-line: redundant-parameter
   local widget = NotificationView:new {
-    notifications = notifications,
+    notifications = response.body.items,
+    next_cursor = response.body.next_cursor,
     covers_fullscreen = true, -- hint for UIManager:_repaint()
     page = self.page,
     on_return_callback = onReturnCallback
@@ -131,8 +145,29 @@ function NotificationView:fetchAndShow(onReturnCallback)
   UIManager:show(widget)
 end
 
+--- Appends the next page of notifications to the list.
+--- @private
+function NotificationView:loadNextPage()
+  local response = Backend.getNotifications(NOTIFICATIONS_PAGE_SIZE, self.next_cursor)
+  if response.type == 'ERROR' then
+    ErrorDialog:show(response.message)
+
+    return
+  end
+
+  for _, notification in ipairs(response.body.items) do
+    table.insert(self.notifications, notification)
+  end
+  self.next_cursor = response.body.next_cursor
+  self:updateItems()
+end
 --- @private
 function NotificationView:onMenuSelect(item)
+  if item.load_more then
+    self:loadNextPage()
+    return
+  end
+
   local onReturnCallback = function()
     self:fetchAndShow(self.on_return_callback)
   end
@@ -155,6 +190,10 @@ function NotificationView:onMenuSelect(item)
 end
 
 function NotificationView:onMenuHold(item)
+  if item.load_more then
+    return true
+  end
+
   local confirm_dialog
   confirm_dialog = ConfirmBox:new {
     text = _("Delete this notification?"),
@@ -170,14 +209,15 @@ function NotificationView:onMenuHold(item)
         return
       end
 
-      local response_n = Backend.getNotifications()
+      local response_n = Backend.getNotifications(NOTIFICATIONS_PAGE_SIZE)
       if response_n.type == 'ERROR' then
         ErrorDialog:show(response_n.message)
 
         return
       end
 
-      self.notifications = response.body
+      self.notifications = response_n.body.items
+      self.next_cursor = response_n.body.next_cursor
       self:updateItems()
     end,
     cancel_callback = function()
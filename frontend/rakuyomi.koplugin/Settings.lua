@@ -114,6 +114,10 @@ Settings.setting_value_definitions = {
         { label = _("Last read (newest)"),  value = 'last_read_desc' },
         { label = _("Source (A-Z)"),        value = 'source_asc' },
         { label = _("Source (Z-A)"),        value = 'source_desc' },
+        { label = _("Added date (oldest)"), value = 'date_added_asc' },
+        { label = _("Added date (newest)"), value = 'date_added_desc' },
+        { label = _("Last updated (oldest)"), value = 'last_updated_asc' },
+        { label = _("Last updated (newest)"), value = 'last_updated_desc' },
       }
     }
   },
@@ -265,6 +269,17 @@ Settings.setting_value_definitions = {
       default = Device.isKindle() and 4 or 5
     }
   },
+  {
+    'concurrent_source_operations',
+    {
+      type = 'integer',
+      title = _("Sources checked at once for updates"),
+      min_value = 1,
+      max_value = 16,
+      unit = 'sources',
+      default = 4
+    }
+  },
   {
     'rakuyomi_never_rtl',
     {
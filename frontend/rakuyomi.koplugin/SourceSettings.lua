@@ -91,6 +91,13 @@ local function mapSettingDefinitionToValueDefinition(setting_definition)
       title = setting_definition.title or setting_definition.placeholder or '',
       placeholder = setting_definition.placeholder or ''
     }
+  elseif setting_definition.type == 'secure-input' then
+    return {
+      type = 'string',
+      title = setting_definition.title or setting_definition.placeholder or '',
+      placeholder = setting_definition.placeholder or '',
+      is_secure = setting_definition.isSecure,
+    }
   elseif setting_definition.type == 'link' then
     return {
       type = 'label',
@@ -29,7 +29,7 @@ local SETTING_ITEM_FONT_SIZE = 18
 --- @class EnumValueDefinition: { type: 'enum', title: string, options: EnumValueDefinitionOption[], default: string|nil }
 --- @class MultiEnumValueDefinition: { type: 'multi-enum', title: string, options: EnumValueDefinitionOption[] }
 --- @class IntegerValueDefinition: { type: 'integer', title: string, min_value: number, max_value: number, unit?: string, is_local: boolean|nil, default: number|nil }
---- @class StringValueDefinition: { type: 'string', title: string, placeholder: string, validate_error?: string, validate?: fun(value: string): boolean }
+--- @class StringValueDefinition: { type: 'string', title: string, placeholder: string, is_secure?: boolean, validate_error?: string, validate?: fun(value: string): boolean }
 --- @class ListValueDefinition: { type: 'list', title: string, placeholder: string }
 --- @class LabelValueDefinition: { type: 'label', title: string, text: string }
 --- @class PathValueDefinition: { type: 'path', title: string, path_type: 'directory' }
@@ -128,6 +128,8 @@ function SettingItemValue:createValueWidget()
     local value = self:getCurrentValue()
     if value == nil or value == "" then
       value = _("Not set")
+    elseif self.value_definition.is_secure then
+      value = string.rep("•", 8)
     end
     return TextWidget:new {
       text = value,
@@ -293,6 +295,7 @@ function SettingItemValue:onTap()
       title = self.value_definition.title,
       input = self:getCurrentValue() or "",
       input_hint = self.value_definition.placeholder,
+      text_type = self.value_definition.is_secure and "password" or nil,
       buttons = {
         {
           {